@@ -0,0 +1,175 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::Deserialize;
+
+use json_walker::bench_support::{deep_nesting, long_strings, numeric_array, person_array, wide_object};
+use json_walker::json_walker::{JsonWalker, SliceReader};
+
+/// a document with every kind of value at several levels
+const CORRECT_JSON: &str = r#" {"key1":null,"key2":true,"key3":false,"key4":111,"key5":111.111,"key6":"str1 \":{}[],","key7":{  "key71" : null ,  "key72" : true ,  "key73" : false ,  "key74" : 222 ,  "key75" : 222.222 ,  "key76" : "str2 \":{}[]," ,  "key78" : [    null ,    true ,    false ,    333 ,    333.333 ,    "str3 \":{}[]," ,    {  } ,    [  ]  ] ,  "key79" : {} ,  "key710": [  ] } , "key8" : [  null ,  true ,  false ,  444 ,  444.444 ,  "str4 \":{}[]," ,  {    "key81" : null ,    "key82" : true ,    "key83" : false ,    "key84" : 555 ,
+      "key85" : 555.555 ,
+      "key86" : "str5 \":{}[]," ,    "key89" : {} ,    "key810" : [ ]  } ,  { } ,  [ ]  ] , "key9" : { } , "key10" : [ ]
+} "#;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Address {
+    street: String,
+    city: String,
+    country: String,
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Friend {
+    name: String,
+    age: u8,
+}
+
+#[derive(Deserialize)]
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+type FriendEntry = (Friend, bool, Address, Vec<String>);
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Person {
+    name: String,
+    age: i32,
+    unsigned_age: u32,
+    address: Address,
+    hobbies: Vec<String>,
+    favorite_color: Color,
+    height: f32,
+    weight: f32,
+    friends: Option<Vec<FriendEntry>>,
+    is_iranian: bool,
+}
+
+fn documents() -> Vec<(&'static str, String)> {
+    vec![
+        ("wide_object", wide_object(10_000)),
+        ("deep_nesting", deep_nesting(100)),
+        ("long_strings", long_strings(100, 10_000, false)),
+        ("long_strings_escaped", long_strings(100, 10_000, true)),
+        ("numeric_array", numeric_array(50_000)),
+        ("person_array", person_array(2_000)),
+    ]
+}
+
+fn walker(json: &str) -> JsonWalker<'_> {
+    JsonWalker::new(SliceReader::new(json.as_bytes()), 0)
+}
+
+fn next_item(c: &mut Criterion) {
+    let mut group = c.benchmark_group("next_item");
+    for (name, json) in documents() {
+        group.throughput(Throughput::Bytes(json.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &json, |b, json| {
+            b.iter(|| {
+                let mut w = walker(json);
+                while let Ok(item) = w.next_item() {
+                    black_box(item);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn skip_current_value(c: &mut Criterion) {
+    let mut group = c.benchmark_group("skip_current_value");
+    for (name, json) in documents() {
+        group.throughput(Throughput::Bytes(json.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &json, |b, json| {
+            b.iter(|| walker(json).skip_current_value().unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn current_value_content(c: &mut Criterion) {
+    let mut group = c.benchmark_group("current_value_content");
+    for (name, json) in documents() {
+        group.throughput(Throughput::Bytes(json.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &json, |b, json| {
+            b.iter(|| black_box(walker(json).current_value_content().unwrap()))
+        });
+    }
+    group.finish();
+}
+
+fn current_value(c: &mut Criterion) {
+    let mut group = c.benchmark_group("current_value");
+    let json = person_array(2_000);
+    group.throughput(Throughput::Bytes(json.len() as u64));
+    group.bench_function("person_array", |b| {
+        b.iter(|| black_box(walker(&json).current_value::<Vec<Person>>().unwrap()))
+    });
+    let json = numeric_array(50_000);
+    group.throughput(Throughput::Bytes(json.len() as u64));
+    group.bench_function("numeric_array", |b| {
+        b.iter(|| black_box(walker(&json).current_value::<Vec<f64>>().unwrap()))
+    });
+    group.finish();
+}
+
+fn many_documents(c: &mut Criterion) {
+    let mut group = c.benchmark_group("many_documents");
+    let docs: Vec<String> = (0..1_000).map(|i| format!(r#"{{"id": {i}, "name": "n{i}", "tags": ["a", "b"]}}"#)).collect();
+    group.throughput(Throughput::Bytes(docs.iter().map(|d| d.len() as u64).sum()));
+    group.bench_function("new", |b| {
+        b.iter(|| {
+            for doc in &docs {
+                black_box(walker(doc).current_value_content().unwrap());
+            }
+        })
+    });
+    group.bench_function("reset", |b| {
+        let mut w = walker("");
+        b.iter(|| {
+            for doc in &docs {
+                w.reset_with_str(doc);
+                black_box(w.current_value_content().unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn reader_dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reader_dispatch");
+    let json = format!("[{}]", vec![CORRECT_JSON; 10_000].join(","));
+    group.throughput(Throughput::Bytes(json.len() as u64));
+    group.bench_function("boxed", |b| {
+        b.iter(|| {
+            let mut w = walker(&json);
+            while let Ok(item) = w.next_item() {
+                black_box(item);
+            }
+        })
+    });
+    group.bench_function("generic", |b| {
+        b.iter(|| {
+            let mut w = JsonWalker::with_reader(json.bytes(), 0);
+            while let Ok(item) = w.next_item() {
+                black_box(item);
+            }
+        })
+    });
+    group.bench_function("from_read", |b| {
+        b.iter(|| {
+            let mut w = JsonWalker::from_read(json.as_bytes(), 0);
+            while let Ok(item) = w.next_item() {
+                black_box(item);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, next_item, skip_current_value, current_value_content, current_value, many_documents, reader_dispatch);
+criterion_main!(benches);
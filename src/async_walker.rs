@@ -0,0 +1,256 @@
+//! A walker over tokio::io::AsyncRead. The walker itself stays synchronous: bytes are read in chunks and fed to a
+//! walker of JsonWalker::new_push(), and a call which needs more bytes is retried after the next chunk arrives. Item
+//! calls go on from the token they wait for, while a value call reads the whole value ahead first, so it is buffered
+//! in memory
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{Error, ErrorKind};
+use crate::json_walker::{Content, Item, JsonWalker, ValueType};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// The async counterpart of JsonWalker for readers such as the body of an http response. Methods wait for the reader
+/// without blocking the thread. An io error of the reader is returned as Io error, while json which ends too early
+/// returns UnexpectedEos as it does for JsonWalker.
+/// The walker and its futures are not Send, as the inner walker keeps its state in Rc, e.g. the keys of the path. So a
+/// task which holds it across .await can not be started by tokio::spawn(). Walk on one thread instead: block_on() of a
+/// runtime, or tokio::task::spawn_local() inside a LocalSet, then pass the results to other tasks through a channel
+/// ### Example
+/// ```
+/// use json_walker::async_walker::AsyncJsonWalker;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let body: &[u8] = br#"{"id": 7, "tags": ["a", "b"]}"#;
+/// let mut walker = AsyncJsonWalker::new(body, 0);
+/// walker.next_key_by_name("tags").await.unwrap();
+/// assert_eq!(walker.current_value::<Vec<String>>().await.unwrap(), vec!["a", "b"]);
+/// # });
+/// ```
+/// A task which walks is spawned on a LocalSet
+/// ```
+/// use json_walker::async_walker::AsyncJsonWalker;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let local = tokio::task::LocalSet::new();
+/// let id = local.run_until(async {
+///     tokio::task::spawn_local(async {
+///         let mut walker = AsyncJsonWalker::new(&br#"{"id": 7}"#[..], 0);
+///         walker.next_key_by_name("id").await.unwrap();
+///         walker.current_value::<u32>().await.unwrap()
+///     }).await.unwrap()
+/// }).await;
+/// assert_eq!(id, 7);
+/// # });
+/// ```
+pub struct AsyncJsonWalker<R> {
+    reader: R,
+    walker: JsonWalker<'static>,
+    chunk: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncJsonWalker<R> {
+    /// **mem_size** is the same as the one of JsonWalker::new()
+    pub fn new(reader: R, mem_size: usize) -> Self {
+        AsyncJsonWalker { reader, walker: JsonWalker::new_push(mem_size), chunk: vec![0; CHUNK_SIZE] }
+    }
+
+    /// run **call** on the inner walker till it has enough bytes. A **resumable** call goes on from where it stops,
+    /// otherwise each failed try is rolled back by poll()
+    async fn drive<T>(&mut self, resumable: bool, mut call: impl FnMut(&mut JsonWalker<'static>) -> Result<T, Error>) -> Result<T, Error> {
+        loop {
+            let result = match resumable {
+                true => self.walker.poll_resumable(&mut call),
+                false => self.walker.poll(&mut call),
+            };
+            match result {
+                Err(e) if *e.kind() == ErrorKind::WouldBlock => {
+                    let n = self.reader.read(&mut self.chunk).await?;
+                    if n == 0 {
+                        self.walker.finish_feed()?;
+                    } else {
+                        self.walker.feed(&self.chunk[..n])?;
+                    }
+                }
+                r => return r,
+            }
+        }
+    }
+
+    /// check out JsonWalker::next_item()
+    pub async fn next_item(&mut self) -> Result<Item, Error> {
+        self.drive(true, |w| w.next_item()).await
+    }
+
+    /// check out JsonWalker::next_key()
+    pub async fn next_key(&mut self) -> Result<Item, Error> {
+        self.drive(true, |w| w.next_key()).await
+    }
+
+    /// check out JsonWalker::next_key_by_name()
+    pub async fn next_key_by_name(&mut self, name: &str) -> Result<Item, Error> {
+        self.drive(true, |w| w.next_key_by_name(name)).await
+    }
+
+    /// check out JsonWalker::skip_current_value(). The value is read ahead, as it is by current_value()
+    pub async fn skip_current_value(&mut self) -> Result<ValueType, Error> {
+        self.drive(true, |w| w.prefetch_value()).await?;
+        self.drive(false, |w| w.skip_current_value()).await
+    }
+
+    /// check out JsonWalker::current_value_content(). The value is read ahead, as it is by current_value()
+    pub async fn current_value_content(&mut self) -> Result<Content, Error> {
+        self.drive(true, |w| w.prefetch_value()).await?;
+        self.drive(false, |w| w.current_value_content()).await
+    }
+
+    /// check out JsonWalker::current_value(). The whole value is read ahead by prefetch_value() before it is walked, so
+    /// it is held in memory, but walked once
+    pub async fn current_value<T>(&mut self) -> Result<T, Error> where T: for<'a> serde::de::Deserialize<'a> {
+        self.drive(true, |w| w.prefetch_value()).await?;
+        self.drive(false, |w| w.current_value()).await
+    }
+
+    /// the inner walker, e.g. to read stats or the recent piece of json. Walker functions called on it directly must
+    /// go through poll()
+    pub fn walker(&mut self) -> &mut JsonWalker<'static> {
+        &mut self.walker
+    }
+}
+
+#[cfg(test)]
+mod async_walker_tests {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+
+    use serde::Deserialize;
+    use tokio::io::{AsyncRead, AsyncWriteExt, ReadBuf};
+
+    use crate::async_walker::AsyncJsonWalker;
+    use crate::ErrorKind;
+    use crate::json_walker::{JsonWalker, StringReader, ValueType};
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+
+    /// it returns its bytes one by one, then fails
+    struct Failing(Vec<u8>);
+
+    impl AsyncRead for Failing {
+        fn poll_read(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+            if self.0.is_empty() {
+                return Poll::Ready(Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset")));
+            }
+            buf.put_slice(&[self.0.remove(0)]);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// it returns its bytes one by one, then the end
+    struct Trickle(Vec<u8>, usize);
+
+    impl AsyncRead for Trickle {
+        fn poll_read(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+            let i = self.1;
+            if let Some(b) = self.0.get(i) {
+                buf.put_slice(&[*b]);
+                self.1 += 1;
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn run<F: std::future::Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(f)
+    }
+
+    /// write **json** to the other side of a duplex in pieces of **piece** bytes, with a pause after each
+    async fn slow_producer(json: &'static str, piece: usize) -> tokio::io::DuplexStream {
+        let (client, mut server) = tokio::io::duplex(piece);
+        tokio::spawn(async move {
+            for bytes in json.as_bytes().chunks(piece) {
+                server.write_all(bytes).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        });
+        client
+    }
+
+    #[test]
+    fn test_async_items() {
+        let mut expected = vec![];
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        while let Ok(item) = walker.next_item() {
+            expected.push(item);
+        }
+
+        run(async {
+            for piece in [1, 5, 64] {
+                let mut walker = AsyncJsonWalker::new(slow_producer(CORRECT_JSON, piece).await, 0);
+                let mut items = vec![];
+                let e = loop {
+                    match walker.next_item().await {
+                        Ok(item) => items.push(item),
+                        Err(e) => break e,
+                    }
+                };
+                assert_eq!(*e.kind(), ErrorKind::EOS);
+                assert_eq!(items, expected);
+                assert_eq!(walker.walker().stats().bytes_consumed, CORRECT_JSON.len() as u64);
+            }
+        });
+    }
+
+    #[test]
+    fn test_async_values() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Order {
+            id: u32,
+            items: Vec<String>,
+        }
+
+        let json = r#"{"meta": {"page": 2, "note": "a \"b\""}, "orders": [{"id": 1, "items": ["x"]}, {"id": 22, "items": []}], "n": 10}"#;
+        run(async {
+            let mut walker = AsyncJsonWalker::new(slow_producer(json, 3).await, 0);
+            assert_eq!(walker.next_key().await.unwrap(), (ValueType::Str, "meta".to_string()));
+            walker.skip_current_value().await.unwrap();
+            walker.next_key_by_name("orders").await.unwrap();
+            let orders: Vec<Order> = walker.current_value().await.unwrap();
+            assert_eq!(orders, vec![Order { id: 1, items: vec!["x".to_string()] }, Order { id: 22, items: vec![] }]);
+            assert_eq!(walker.next_key().await.unwrap().1, "n");
+            assert_eq!(walker.current_value::<u32>().await, Ok(10));
+            assert_eq!(*walker.next_item().await.unwrap_err().kind(), ErrorKind::EOS);
+
+            // the reader fails in the middle of json
+            let mut walker = AsyncJsonWalker::new(Failing(json.as_bytes()[..60].to_vec()), 0);
+            walker.next_key_by_name("orders").await.unwrap();
+            let e = walker.current_value::<Vec<Order>>().await.unwrap_err();
+            assert_eq!(*e.kind(), ErrorKind::Io(io::ErrorKind::ConnectionReset));
+
+            // a large value which arrives byte by byte is read ahead once, rather than walked again for every byte
+            let numbers: Vec<u32> = (0..50_000).collect();
+            let json = format!(r#"{{"numbers": {numbers:?}, "n": 1}}"#);
+            let mut walker = AsyncJsonWalker::new(Trickle(json.into_bytes(), 0), 0);
+            walker.next_key().await.unwrap();
+            assert_eq!(walker.current_value::<Vec<u32>>().await.unwrap(), numbers);
+            assert_eq!(walker.next_item().await.unwrap().1, "n");
+        });
+    }
+
+    #[test]
+    fn test_local_task() {
+        // the walker is not Send, so a task which holds it across .await is spawned locally
+        let json = r#"{"id": 7, "tags": ["a", "b"]}"#;
+        let local = tokio::task::LocalSet::new();
+        run(local.run_until(async {
+            let task = tokio::task::spawn_local(async {
+                let mut walker = AsyncJsonWalker::new(slow_producer(json, 2).await, 0);
+                assert_eq!(walker.next_item().await.unwrap().1, "id");
+                assert_eq!(walker.current_value::<u32>().await, Ok(7));
+                walker.next_key().await.unwrap();
+                walker.current_value::<Vec<String>>().await.unwrap()
+            });
+            assert_eq!(task.await.unwrap(), vec!["a", "b"]);
+        }));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::Error;
+
+/// Decode base64 which arrives piece by piece, so pieces do not need to be aligned to 4 chars.
+/// White-spaces are ignored and the final padding may be omitted
+pub struct Decoder {
+    quad: [u8; 4],
+    len: usize,
+    /// count of the chars seen so far, to locate errors
+    offset: u64,
+    padded: bool,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder { quad: [0; 4], len: 0, offset: 0, padded: false }
+    }
+
+    /// append the decoded bytes of **input** to **out**
+    pub fn update(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+        for &c in input {
+            let offset = self.offset;
+            self.offset += 1;
+            if c.is_ascii_whitespace() {
+                continue;
+            }
+            if self.padded {
+                // only "=" may complete the padding of the last quad
+                if c == b'=' && self.len == 3 {
+                    self.len = 0;
+                    continue;
+                }
+                return Err(invalid(offset));
+            }
+            if c == b'=' {
+                match self.len {
+                    2 | 3 => {
+                        self.flush(out);
+                        // "xx=" waits for its second "="
+                        self.len = if self.len == 2 { 3 } else { 0 };
+                        self.padded = true;
+                        continue;
+                    }
+                    _ => return Err(invalid(offset)),
+                }
+            }
+            let v = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                _ => return Err(invalid(offset)),
+            };
+            self.quad[self.len] = v;
+            self.len += 1;
+            if self.len == 4 {
+                self.flush(out);
+                self.len = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// decode what is left. An incomplete quad of 2 or 3 chars is accepted as if it was padded
+    pub fn finish(mut self, out: &mut Vec<u8>) -> Result<(), Error> {
+        match (self.padded, self.len) {
+            (false, 0) | (true, 0) => Ok(()),
+            (false, 2) | (false, 3) => {
+                self.flush(out);
+                Ok(())
+            }
+            _ => Err(invalid(self.offset)),
+        }
+    }
+
+    /// write the bytes of the first **len** chars of the quad
+    fn flush(&mut self, out: &mut Vec<u8>) {
+        let n = self.quad[..self.len].iter().enumerate().fold(0u32, |n, (i, v)| n | (*v as u32) << (18 - 6 * i));
+        for i in 0..self.len - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid(offset: u64) -> Error {
+    Error::wrong_data_type(format!("Invalid base64 at byte {offset}"))
+}
+
+#[cfg(test)]
+pub(crate) mod base64_tests {
+    use crate::base64::Decoder;
+
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    /// standard base64 with padding
+    pub fn encode(data: &[u8]) -> String {
+        let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+        for chunk in data.chunks(3) {
+            let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    fn decode(s: &str, step: usize) -> Result<Vec<u8>, crate::Error> {
+        let mut decoder = Decoder::new();
+        let mut out = Vec::new();
+        for piece in s.as_bytes().chunks(step) {
+            decoder.update(piece, &mut out)?;
+        }
+        decoder.finish(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_rfc_vectors() {
+        let cases = [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foob", "Zm9vYg=="), ("fooba", "Zm9vYmE="), ("foobar", "Zm9vYmFy")];
+        for (plain, encoded) in cases {
+            assert_eq!(encode(plain.as_bytes()), encoded);
+            for step in 1..5 {
+                assert_eq!(decode(encoded, step).unwrap(), plain.as_bytes(), "{encoded}");
+            }
+        }
+        assert_eq!(decode("Zm9vYg", 1).unwrap(), b"foob");
+        assert_eq!(decode("Zm9v\nYmFy\r\n", 3).unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(decode("Zm9v*mFy", 3).unwrap_err().msg, "Invalid base64 at byte 4");
+        assert_eq!(decode("Zg==Zg==", 5).unwrap_err().msg, "Invalid base64 at byte 4");
+        assert_eq!(decode("Z===", 2).unwrap_err().msg, "Invalid base64 at byte 1");
+        assert!(decode("Zm9vY", 2).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! Generators of synthetic json documents for benchmarks, and a simple harness to measure throughput.
+//! Documents are built by a fixed pseudo random sequence, so the same arguments always give the same text
+
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// a linear congruential generator, enough to vary the documents without a dependency
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+const SEED: u64 = 0x5eed;
+const WORDS: [&str; 8] = ["alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel"];
+
+/// an object with **keys** members of scalar values, such as {"k0": 17, "k1": "bravo", ...}
+pub fn wide_object(keys: usize) -> String {
+    let mut rng = Lcg(SEED);
+    let mut out = String::from("{");
+    for i in 0..keys {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        match rng.below(4) {
+            0 => write!(out, r#""k{i}": {}"#, rng.below(100_000)),
+            1 => write!(out, r#""k{i}": "{}""#, WORDS[rng.below(8) as usize]),
+            2 => write!(out, r#""k{i}": {}"#, rng.below(2) == 0),
+            _ => write!(out, r#""k{i}": null"#),
+        }.unwrap();
+    }
+    out.push('}');
+    out
+}
+
+/// arrays and objects nested **depth** levels deep, alternately, with a number in the middle
+pub fn deep_nesting(depth: usize) -> String {
+    let mut out = String::with_capacity(depth * 8);
+    for i in 0..depth {
+        out.push_str(if i % 2 == 0 { "[" } else { r#"{"a": "# });
+    }
+    out.push('1');
+    for i in (0..depth).rev() {
+        out.push(if i % 2 == 0 { ']' } else { '}' });
+    }
+    out
+}
+
+/// an array of **count** strings of **len** chars. With **escapes**, about every eighth char is an escape sequence
+pub fn long_strings(count: usize, len: usize, escapes: bool) -> String {
+    let mut rng = Lcg(SEED);
+    let mut out = String::from("[");
+    for i in 0..count {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        out.push('"');
+        for _ in 0..len {
+            if escapes && rng.below(8) == 0 {
+                out.push_str(["\\n", "\\\"", "\\\\", "\\u00e9"][rng.below(4) as usize]);
+            } else {
+                out.push((b'a' + rng.below(26) as u8) as char);
+            }
+        }
+        out.push('"');
+    }
+    out.push(']');
+    out
+}
+
+/// an array of **count** numbers, integers and floats mixed
+pub fn numeric_array(count: usize) -> String {
+    let mut rng = Lcg(SEED);
+    let mut out = String::from("[");
+    for i in 0..count {
+        if i > 0 {
+            out.push(',');
+        }
+        match rng.below(3) {
+            0 => write!(out, "{}", rng.below(1 << 31) as i64 - (1 << 30)),
+            1 => write!(out, "{}.{}", rng.below(10_000), rng.below(1000)),
+            _ => write!(out, "{}e-{}", rng.below(100), rng.below(10)),
+        }.unwrap();
+    }
+    out.push(']');
+    out
+}
+
+/// an array of **count** people with name, age, address, hobbies, color, sizes and friends, as in the tests of the
+/// deserializer
+pub fn person_array(count: usize) -> String {
+    let mut rng = Lcg(SEED);
+    let mut out = String::from("[");
+    for i in 0..count {
+        if i > 0 {
+            out.push(',');
+        }
+        let address = |rng: &mut Lcg| {
+            format!(r#"{{"street": "{} St", "city": "{}", "country": "{}"}}"#, rng.below(1000), WORDS[rng.below(8) as usize], WORDS[rng.below(8) as usize])
+        };
+        let friends = match rng.below(2) {
+            0 => "null".to_string(),
+            _ => {
+                let n = 1 + rng.below(3);
+                let friends: Vec<String> = (0..n).map(|_| format!(
+                    r#"[{{"name": "{}", "age": {}}}, {}, {}, ["0{}"]]"#,
+                    WORDS[rng.below(8) as usize], rng.below(90), rng.below(2) == 0, address(&mut rng), rng.below(1_000_000)
+                )).collect();
+                format!("[{}]", friends.join(", "))
+            }
+        };
+        write!(
+            out,
+            r#"{{"name": "{}", "age": {}, "unsigned_age": {}, "address": {}, "hobbies": ["{}", "{}"], "favorite_color": "{}", "height": {}.{}, "weight": -{}.{}, "friends": {}, "is_iranian": {}}}"#,
+            WORDS[rng.below(8) as usize], rng.below(90), rng.below(90), address(&mut rng), WORDS[rng.below(8) as usize], WORDS[rng.below(8) as usize],
+            ["Red", "Green", "Blue"][rng.below(3) as usize], 150 + rng.below(50), rng.below(10), 50 + rng.below(50), rng.below(10), friends, rng.below(2) == 0,
+        ).unwrap();
+    }
+    out.push(']');
+    out
+}
+
+/// The result of measure()
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Throughput {
+    /// bytes which are processed by all iterations
+    pub bytes: u64,
+    pub iterations: u32,
+    pub elapsed: Duration,
+}
+
+impl Throughput {
+    pub fn mb_per_sec(&self) -> f64 {
+        self.bytes as f64 / (1024.0 * 1024.0) / self.elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+/// Run **f** for **iterations** times, when each run processes **bytes** bytes, and measure the throughput
+/// ### Example
+/// ```
+/// use json_walker::bench_support::{measure, numeric_array};
+/// use json_walker::json_walker::{JsonWalker, StringReader};
+///
+/// let json = numeric_array(1000);
+/// let t = measure(json.len(), 3, || {
+///     let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+///     while walker.next_item().is_ok() {}
+/// });
+/// assert_eq!(t.bytes, 3 * json.len() as u64);
+/// ```
+pub fn measure(bytes: usize, iterations: u32, mut f: impl FnMut()) -> Throughput {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    Throughput { bytes: bytes as u64 * iterations as u64, iterations, elapsed: start.elapsed() }
+}
+
+#[cfg(test)]
+mod bench_support_tests {
+    use crate::bench_support::{deep_nesting, long_strings, numeric_array, person_array, wide_object};
+    use crate::json_walker::{Content, JsonWalker, StringReader};
+    use crate::tools::{LenientOptions, normalize};
+
+    #[test]
+    fn test_generators() {
+        let docs = [
+            wide_object(1000),
+            deep_nesting(100),
+            long_strings(50, 1000, false),
+            long_strings(50, 1000, true),
+            numeric_array(1000),
+            person_array(100),
+        ];
+        for doc in &docs {
+            normalize(Box::new(doc.clone().into_bytes().into_iter()), std::io::sink(), &LenientOptions::default()).unwrap();
+        }
+        assert_eq!(wide_object(1000), docs[0]);
+        assert_eq!(person_array(100), docs[5]);
+        assert_eq!(long_strings(1, 10, false).len(), 14);
+
+        let mut walker = JsonWalker::new(StringReader::new(docs[5].clone()), 0);
+        match walker.current_value_content().unwrap() {
+            Content::Array(people) => assert_eq!(people.len(), 100),
+            c => panic!("unexpected content: {:?}", c),
+        }
+    }
+
+    #[cfg(all(feature = "deserialize", feature = "serde_json"))]
+    #[test]
+    fn test_generators_de() {
+        for doc in [wide_object(100), long_strings(10, 100, true), numeric_array(100), person_array(10)] {
+            let walked = JsonWalker::new(StringReader::new(doc.clone()), 0).current_value::<serde_json::Value>().unwrap();
+            assert_eq!(walked, serde_json::from_str::<serde_json::Value>(&doc).unwrap());
+        }
+    }
+}
@@ -5,98 +5,130 @@ pub mod deserialize_mod {
     use std::str::ParseBoolError;
 
     use serde::de;
+    use serde::de::IntoDeserializer;
 
-    use crate::{Error, ErrorKind, NIL};
-    use crate::parser_core::{get_current_level, Item, Parser, TextItem, ValueType, walk_forward};
+    use crate::{Error, NIL};
+    use crate::parser_core::{begin_value, DuplicatePolicy, end_of_stream, end_value, get_path_string, is_next_element_hidden, is_repeated_key, Item, on_duplicate_key, Parser, skip_element, skip_value, TextItem, ValueType, walk_forward};
 
     //region error
-    impl de::StdError for Error {}
-
     impl de::Error for Error {
         fn custom<T>(msg: T) -> Self where T: Display {
-            Error { kind: ErrorKind::Serde, msg: msg.to_string() }
+            Error::serde(msg.to_string())
         }
     }
 
     impl From<ParseBoolError> for Error {
         fn from(value: ParseBoolError) -> Self {
-            Error { kind: ErrorKind::ParseBoolError, msg: value.to_string() }
+            Error::parse_bool(value.to_string())
         }
     }
 
     impl From<ParseIntError> for Error {
         fn from(value: ParseIntError) -> Self {
-            Error { kind: ErrorKind::ParseIntError, msg: value.to_string() }
+            Error::parse_int(value.to_string())
         }
     }
 
     impl From<ParseFloatError> for Error {
         fn from(value: ParseFloatError) -> Self {
-            Error { kind: ErrorKind::ParseFloatError, msg: value.to_string() }
+            Error::parse_float(value.to_string())
         }
     }
     //endregion
 
     //region Deserializer
-    pub struct Deserializer<'md> {
-        parser: &'md mut Parser,
+    /// Every deserialize_* function consumes exactly one value (scalar, object, array, tuple or enum).
+    /// When it returns, the cursor stands right after that value, before the trailing "," or the closing
+    /// bracket of the parent, exactly as if the value had been skipped. So next_key(), next_sibling_key() &
+    /// current_value() can be called afterward safely.
+    pub struct Deserializer<'md, 'r, R = Box<dyn Iterator<Item=u8> + 'r>> {
+        parser: &'md mut Parser<'r, R>,
     }
 
-    impl<'md> Deserializer<'md> {
-        pub fn new(parser: &'md mut Parser) -> Self {
+    impl<'md, 'r, R: Iterator<Item=u8>> Deserializer<'md, 'r, R> {
+        pub fn new(parser: &'md mut Parser<'r, R>) -> Self {
             Deserializer { parser }
         }
 
-        fn move_forward(&mut self) {
-            if self.parser.next_byte != NIL {
-                walk_forward(&mut self.parser);
+        fn begin_value(&mut self) -> usize {
+            begin_value(self.parser)
+        }
+
+        fn end_value(&mut self, stack_size: usize) {
+            end_value(self.parser, stack_size)
+        }
+
+        /// the path where **e** has happened. The deepest value which fails sets it, so outer ones keep that
+        pub(crate) fn with_path(&mut self, e: Error) -> Error {
+            match e.path {
+                Some(_) => e,
+                None => e.with_path(get_path_string(self.parser)),
             }
-            // println!("{}", get_current_status(self.parser));
         }
 
         fn next_item(&mut self) -> Result<Item, Error> {
+            let stack_size = self.begin_value();
             while self.parser.next_byte != NIL {
-                match walk_forward(&mut self.parser) {
+                match walk_forward(self.parser) {
                     TextItem::Key(i) | TextItem::Value(i) => {
-                        // println!("{}", get_current_status(self.parser));
+                        self.end_value(stack_size);
                         return Ok(i);
                     }
+                    // the rest of the container is hidden by set_filter()
+                    TextItem::None(b'}') | TextItem::None(b']') if self.parser.stack.len() < stack_size => {
+                        return Err(Error::element_not_found("The container is closed"));
+                    }
                     _ => {
                         // println!("{}", get_current_status(self.parser));
                     }
                 }
             }
-            Err(Error::new_eos())
+            Err(end_of_stream(self.parser))
         }
     }
 
     // Read de::Deserializer own doc. It has a lot of explanation and a link to a sample. At writing this code, it was https://serde.rs/impl-deserializer.html
-    impl<'de: 'md, 'md> de::Deserializer<'de> for &'md mut Deserializer<'de> {
+    impl<'de: 'md, 'md, 'r, R: Iterator<Item=u8>> de::Deserializer<'de> for &'md mut Deserializer<'de, 'r, R> {
         type Error = Error;
 
         fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            loop {
-                match walk_forward(&mut self.parser) {
-                    TextItem::Key(i) | TextItem::Value(i) => {
-                        return match i.0 {
-                            ValueType::Null => { visitor.visit_none() }
-                            ValueType::Bool => { visitor.visit_bool(i.1.parse()?) }
-                            ValueType::Int => {
-                                if i.1.starts_with('-') {
-                                    visitor.visit_i128(i.1.parse()?)
-                                } else {
-                                    visitor.visit_u128(i.1.parse()?)
-                                }
-                            }
-                            ValueType::Float => { visitor.visit_f64(i.1.parse()?) }
-                            ValueType::Str => { visitor.visit_string(i.1) }
-                            ValueType::Arr => { visitor.visit_seq(SeqAccessor::new(self)) }
-                            ValueType::Obj => { visitor.visit_map(MapAccessor::new(self)) }
-                        };
-                    }
-                    _ => {}
+            let stack_size = self.begin_value();
+            let result = match self.parser.next_byte {
+                b'{' => visitor.visit_map(MapAccessor::new(&mut *self)?),
+                b'[' => visitor.visit_seq(SeqAccessor::new(&mut *self)?),
+                _ => {
+                    let i = self.next_item()?;
+                    return match i.0 {
+                        ValueType::Null => { visitor.visit_none() }
+                        ValueType::Bool => { visitor.visit_bool(i.1.parse()?) }
+                        #[cfg(feature = "arbitrary_precision")]
+                        ValueType::Float if non_finite(&i.1).is_some() => { visitor.visit_f64(parse_f64(&i.1)?) }
+                        #[cfg(feature = "arbitrary_precision")]
+                        ValueType::UInt | ValueType::Int | ValueType::Float => {
+                            visitor.visit_map(NumberAccessor { txt: Some(i.1) })
+                        }
+                        // 128 bits are only asked for numbers which do not fit in 64 bits
+                        #[cfg(not(feature = "arbitrary_precision"))]
+                        ValueType::UInt => match i.1.parse::<u64>() {
+                            Ok(u) => visitor.visit_u64(u),
+                            Err(_) => visitor.visit_u128(i.1.parse()?),
+                        },
+                        #[cfg(not(feature = "arbitrary_precision"))]
+                        ValueType::Int => match i.1.parse::<i64>() {
+                            Ok(n) => visitor.visit_i64(n),
+                            Err(_) => visitor.visit_i128(i.1.parse()?),
+                        },
+                        #[cfg(not(feature = "arbitrary_precision"))]
+                        ValueType::Float => { visitor.visit_f64(parse_f64(&i.1)?) }
+                        ValueType::Str => { visitor.visit_string(i.1) }
+                        ValueType::Arr | ValueType::Obj => {
+                            Err(Error::wrong_data_type("Expecting a simple value"))
+                        }
+                    };
                 }
-            }
+            };
+            self.end_value(stack_size);
+            result
         }
 
         fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
@@ -136,15 +168,16 @@ pub mod deserialize_mod {
         }
 
         fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            visitor.visit_f32(self.next_item()?.1.parse()?)
+            let txt = self.next_item()?.1;
+            visitor.visit_f32(non_finite(&txt).map_or_else(|| txt.parse(), |f| Ok(f as f32))?)
         }
 
         fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            visitor.visit_f64(self.next_item()?.1.parse()?)
+            visitor.visit_f64(parse_f64(&self.next_item()?.1)?)
         }
 
         fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            visitor.visit_char(self.next_item()?.1.chars().next().ok_or(Error { kind: ErrorKind::WrongDataType, msg: "Expecting a string or a char".into() })?)
+            visitor.visit_char(self.next_item()?.1.chars().next().ok_or(Error::wrong_data_type("Expecting a string or a char"))?)
         }
 
         fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
@@ -164,11 +197,9 @@ pub mod deserialize_mod {
         }
 
         fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            if self.parser.next_byte == b':' || self.parser.next_byte == b',' {
-                self.move_forward();
-            }
+            self.begin_value();
             if self.parser.next_byte == b'n' {
-                _ = self.next_item();
+                self.next_item()?;
                 visitor.visit_none()
             } else {
                 visitor.visit_some(self)
@@ -176,11 +207,12 @@ pub mod deserialize_mod {
         }
 
         fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
+            self.next_item()?;
             visitor.visit_unit()
         }
 
         fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            visitor.visit_unit()
+            self.deserialize_unit(visitor)
         }
 
         fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
@@ -188,127 +220,180 @@ pub mod deserialize_mod {
         }
 
         fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            visitor.visit_seq(SeqAccessor::new(self))
+            let stack_size = self.begin_value();
+            let result = visitor.visit_seq(SeqAccessor::new(&mut *self)?)?;
+            self.end_value(stack_size);
+            Ok(result)
         }
 
         fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            let result = self.deserialize_seq(visitor);
-            self.move_forward();
-            result
+            self.deserialize_seq(visitor)
         }
 
         fn deserialize_tuple_struct<V>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            let result = self.deserialize_seq(visitor);
-            self.move_forward();
-            result
+            self.deserialize_seq(visitor)
         }
 
         fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            visitor.visit_map(MapAccessor::new(self))
+            let stack_size = self.begin_value();
+            let result = visitor.visit_map(MapAccessor::new(&mut *self)?)?;
+            self.end_value(stack_size);
+            Ok(result)
         }
 
         fn deserialize_struct<V>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
             self.deserialize_map(visitor)
         }
 
+        /// unit variants are plain strings, others are wrapped in an object with a single key, {"variant": value}
         fn deserialize_enum<V>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            visitor.visit_enum(VariantAccessor { de: self })
+            let stack_size = self.begin_value();
+            if self.parser.next_byte == b'{' {
+                walk_forward(self.parser);
+            }
+            let result = visitor.visit_enum(VariantAccessor { de: &mut *self })?;
+            self.end_value(stack_size);
+            Ok(result)
         }
 
         fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
             self.deserialize_string(visitor)
         }
 
+        /// the value is skipped by the fast path, which is a loop over the parser stack rather than a recursion of
+        /// visitors, so even a deeply nested unknown field can not overflow the call stack
         fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: de::Visitor<'de> {
-            self.deserialize_any(visitor)
+            skip_value(self.parser)?;
+            visitor.visit_unit()
         }
     }
 //endregion
 
     //region Accessors
-    fn handle_next_element_seed<'de, T>(de: &mut Deserializer<'de>, working_level: f32, seed: T) -> Result<Option<T::Value>, Error> where T: de::DeserializeSeed<'de> {
-        let mut current_level;
-        while de.parser.next_byte == b']' || de.parser.next_byte == b'}' {
-            current_level = get_current_level(de.parser);
-            if working_level == current_level {// cursor of parser is synced with the deserializer function calls
-                de.move_forward();
-                return Ok(None);
-            } else if working_level < current_level {// some deserializer function have returned early without any cursor move
-                loop {
-                    de.move_forward();
-                    current_level = get_current_level(de.parser);
-                    if working_level == current_level {
-                        break;
-                    }
-                }
-            } else {// some deserializer function have not returned yet byt cursor has moved extra
-                return Err(Error { kind: ErrorKind::OOPS, msg: "Strange situation".into() });
-            }
+    /// the literals which set_allow_nan() accepts. They are mapped here, rather than left to the leniency of f64 parsing
+    fn non_finite(txt: &str) -> Option<f64> {
+        match txt {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            _ => None,
         }
-        seed.deserialize(&mut *de).map(Some)
     }
 
-    fn move_to_scope(de: &mut Deserializer, desired_byte: u8) {
-        while de.parser.next_byte != b'{' && de.parser.next_byte != b'[' {
-            de.move_forward();
+    fn parse_f64(txt: &str) -> Result<f64, Error> {
+        Ok(non_finite(txt).map_or_else(|| txt.parse(), Ok)?)
+    }
+
+    /// consume the opening byte of a container and return the stack size inside it
+    fn enter_container<R: Iterator<Item=u8>>(de: &mut Deserializer<'_, '_, R>, opening_byte: u8) -> Result<usize, Error> {
+        if de.parser.next_byte == NIL {
+            return Err(end_of_stream(de.parser));
         }
-        if de.parser.next_byte == desired_byte {
-            de.move_forward();
+        if de.parser.next_byte != opening_byte {
+            return Err(Error::wrong_data_type(format!("Expecting `{}` but found `{}`", opening_byte as char, de.parser.next_byte as char)));
         }
+        walk_forward(de.parser);
+        Ok(de.parser.stack.len())
     }
 
-    struct SeqAccessor<'md, 'de: 'md> {
-        de: &'md mut Deserializer<'de>,
-        level: f32,
+    /// sync the cursor with the container, then return true if there is another element or false if the container is closed.
+    /// The stream may finish before that, e.g. by an io error
+    fn has_next_element<R: Iterator<Item=u8>>(de: &mut Deserializer<'_, '_, R>, stack_size: usize, closing_byte: u8) -> Result<bool, Error> {
+        // the previous element may be consumed partially
+        de.end_value(stack_size);
+        if de.parser.next_byte == b',' {
+            walk_forward(de.parser);
+        }
+        while is_next_element_hidden(de.parser) {
+            skip_element(de.parser);
+            if de.parser.next_byte == b',' {
+                walk_forward(de.parser);
+            }
+        }
+        match de.parser.next_byte {
+            NIL => Err(end_of_stream(de.parser)),
+            b if b == closing_byte => {
+                walk_forward(de.parser);
+                Ok(false)
+            }
+            _ => Ok(true),
+        }
+    }
+
+    struct SeqAccessor<'md, 'de: 'md, 'r, R> {
+        de: &'md mut Deserializer<'de, 'r, R>,
+        stack_size: usize,
     }
 
-    impl<'md, 'de> SeqAccessor<'md, 'de> {
-        fn new(de: &'md mut Deserializer<'de>) -> Self {
-            move_to_scope(de, b'[');
-            let level = get_current_level(de.parser);
-            SeqAccessor { de, level }
+    impl<'md, 'de, 'r, R: Iterator<Item=u8>> SeqAccessor<'md, 'de, 'r, R> {
+        fn new(de: &'md mut Deserializer<'de, 'r, R>) -> Result<Self, Error> {
+            let stack_size = enter_container(de, b'[')?;
+            Ok(SeqAccessor { de, stack_size })
         }
     }
 
 
-    impl<'md, 'de> de::SeqAccess<'de> for SeqAccessor<'md, 'de> {
+    impl<'md, 'de, 'r, R: Iterator<Item=u8>> de::SeqAccess<'de> for SeqAccessor<'md, 'de, 'r, R> {
         type Error = Error;
 
         fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> where T: de::DeserializeSeed<'de> {
-            handle_next_element_seed(self.de, self.level, seed)
+            if has_next_element(self.de, self.stack_size, b']')? {
+                seed.deserialize(&mut *self.de).map(Some).map_err(|e| self.de.with_path(e))
+            } else {
+                Ok(None)
+            }
         }
     }
 
-    struct MapAccessor<'md, 'de: 'md> {
-        de: &'md mut Deserializer<'de>,
-        level: f32,
+    struct MapAccessor<'md, 'de: 'md, 'r, R> {
+        de: &'md mut Deserializer<'de, 'r, R>,
+        stack_size: usize,
     }
 
-    impl<'md, 'de> MapAccessor<'md, 'de> {
-        fn new(de: &'md mut Deserializer<'de>) -> Self {
-            move_to_scope(de, b'{');
-            let level = get_current_level(de.parser);
-            MapAccessor { de, level }
+    impl<'md, 'de, 'r, R: Iterator<Item=u8>> MapAccessor<'md, 'de, 'r, R> {
+        fn new(de: &'md mut Deserializer<'de, 'r, R>) -> Result<Self, Error> {
+            let stack_size = enter_container(de, b'{')?;
+            Ok(MapAccessor { de, stack_size })
         }
     }
 
-    impl<'md, 'de> de::MapAccess<'de> for MapAccessor<'md, 'de> {
+    impl<'md, 'de, 'r, R: Iterator<Item=u8>> de::MapAccess<'de> for MapAccessor<'md, 'de, 'r, R> {
         type Error = Error;
 
         fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> where K: de::DeserializeSeed<'de> {
-            handle_next_element_seed(self.de, self.level, seed)
+            loop {
+                if !has_next_element(self.de, self.stack_size, b'}')? {
+                    return Ok(None);
+                }
+                if on_duplicate_key(self.de.parser) != DuplicatePolicy::FirstWins {
+                    return match seed.deserialize(&mut *self.de) {
+                        // the remaining keys are hidden by set_filter(), so the object is closed while the key is looked for
+                        Err(_) if self.de.parser.stack.len() < self.stack_size => Ok(None),
+                        result => result.map(Some).map_err(|e| self.de.with_path(e)),
+                    };
+                }
+                // the key is read apart, so a repeated one is skipped with its value before serde sees it
+                let key = match self.de.next_item() {
+                    Err(_) if self.de.parser.stack.len() < self.stack_size => return Ok(None),
+                    result => result.map_err(|e| self.de.with_path(e))?,
+                };
+                if !is_repeated_key(self.de.parser) {
+                    return seed.deserialize(key.1.into_deserializer()).map(Some).map_err(|e| self.de.with_path(e));
+                }
+                skip_value(self.de.parser)?;
+            }
         }
 
         fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error> where V: de::DeserializeSeed<'de> {
-            seed.deserialize(&mut *self.de)
+            seed.deserialize(&mut *self.de).map_err(|e| self.de.with_path(e))
         }
     }
 
-    struct VariantAccessor<'md, 'de: 'md> {
-        de: &'md mut Deserializer<'de>,
+    struct VariantAccessor<'md, 'de: 'md, 'r, R> {
+        de: &'md mut Deserializer<'de, 'r, R>,
     }
 
-    impl<'md, 'de> de::EnumAccess<'de> for VariantAccessor<'md, 'de> {
+    impl<'md, 'de, 'r, R: Iterator<Item=u8>> de::EnumAccess<'de> for VariantAccessor<'md, 'de, 'r, R> {
         type Error = Error;
         type Variant = Self;
 
@@ -318,11 +403,11 @@ pub mod deserialize_mod {
     }
 
 
-    impl<'md, 'de> de::VariantAccess<'de> for VariantAccessor<'md, 'de> {
+    impl<'md, 'de, 'r, R: Iterator<Item=u8>> de::VariantAccess<'de> for VariantAccessor<'md, 'de, 'r, R> {
         type Error = Error;
 
         fn unit_variant(self) -> Result<(), Self::Error> {
-            de::Deserialize::deserialize(self.de)
+            Ok(())
         }
 
         fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error> where T: de::DeserializeSeed<'de> {
@@ -337,5 +422,31 @@ pub mod deserialize_mod {
             de::Deserializer::deserialize_map(self.de, visitor)
         }
     }
+
+    /// the key by which serde_json::Number and the like recognize a number which is passed as its text
+    #[cfg(feature = "arbitrary_precision")]
+    const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+    /// a number of deserialize_any() as a map of one entry, NUMBER_TOKEN to the text of the number, so not a digit is lost
+    #[cfg(feature = "arbitrary_precision")]
+    struct NumberAccessor {
+        txt: Option<String>,
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    impl<'de> de::MapAccess<'de> for NumberAccessor {
+        type Error = Error;
+
+        fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> where K: de::DeserializeSeed<'de> {
+            if self.txt.is_none() {
+                return Ok(None);
+            }
+            seed.deserialize(de::value::BorrowedStrDeserializer::new(NUMBER_TOKEN)).map(Some)
+        }
+
+        fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error> where V: de::DeserializeSeed<'de> {
+            seed.deserialize(self.txt.take().unwrap_or_default().into_deserializer())
+        }
+    }
     //endregion
 }
\ No newline at end of file
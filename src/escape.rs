@@ -0,0 +1,119 @@
+use crate::Error;
+
+const HEX: &[u8] = b"0123456789abcdef";
+
+/// append the json form of **s** to **out**, surrounded by quotation marks.
+/// Only the necessary chars are escaped: quotation mark, reverse solidus and control chars. Others are kept untouched
+pub fn escape_into(s: &str, out: &mut Vec<u8>) {
+    out.reserve(s.len() + 2);
+    out.push(b'"');
+    for &b in s.as_bytes() {
+        match b {
+            b'"' => out.extend_from_slice(b"\\\""),
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            0x08 => out.extend_from_slice(b"\\b"),
+            0x0c => out.extend_from_slice(b"\\f"),
+            0..=0x1f => {
+                out.extend_from_slice(b"\\u00");
+                out.push(HEX[(b >> 4) as usize]);
+                out.push(HEX[(b & 0xf) as usize]);
+            }
+            _ => out.push(b),
+        }
+    }
+    out.push(b'"');
+}
+
+/// decode the escape sequences of a json string, given without the surrounding quotation marks.
+/// Surrogate pairs of \u escapes are joined. An unknown escape sequence or a lone surrogate is InvalidEscape error
+pub fn unescape(s: &str) -> Result<String, Error> {
+    if !s.contains('\\') {
+        return Ok(s.to_string());
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('/') => out.push('/'),
+            Some('b') => out.push('\u{8}'),
+            Some('f') => out.push('\u{c}'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('u') => {
+                let high = hex4(&mut chars, s)?;
+                let code = if (0xd800..0xdc00).contains(&high) && chars.as_str().starts_with("\\u") {
+                    let mut ahead = chars.clone();
+                    ahead.nth(1);
+                    let low = hex4(&mut ahead, s)?;
+                    if (0xdc00..0xe000).contains(&low) {
+                        chars = ahead;
+                        0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                    } else {
+                        high
+                    }
+                } else {
+                    high
+                };
+                match char::from_u32(code) {
+                    Some(c) => out.push(c),
+                    None => return Err(Error::invalid_escape(format!("Lone surrogate `\\u{code:04x}` in `{s}`"))),
+                }
+            }
+            _ => return Err(Error::invalid_escape(format!("Invalid escape sequence in `{s}`"))),
+        }
+    }
+    Ok(out)
+}
+
+fn hex4(chars: &mut std::str::Chars, s: &str) -> Result<u32, Error> {
+    let mut code = 0;
+    for _ in 0..4 {
+        match chars.next().and_then(|c| c.to_digit(16)) {
+            Some(d) => code = code * 16 + d,
+            None => return Err(Error::invalid_escape(format!("Invalid unicode escape in `{s}`"))),
+        }
+    }
+    Ok(code)
+}
+
+#[cfg(test)]
+mod escape_tests {
+    use crate::ErrorKind;
+    use crate::escape::{escape_into, unescape};
+
+    fn escape(s: &str) -> String {
+        let mut out = Vec::new();
+        escape_into(s, &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r#"str1 ":{}[],"#), r#""str1 \":{}[],""#);
+        assert_eq!(escape("a\\b\n\t\u{1}/é"), r#""a\\b\n\t\u0001/é""#);
+        assert_eq!(escape(""), r#""""#);
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r#"a\"b\\c\/\n\t\u0041\u00e9"#).unwrap(), "a\"b\\c/\n\tAé");
+        assert_eq!(unescape(r#"\ud83d\ude00 \uD83D\uDE00"#).unwrap(), "😀 😀");
+        assert_eq!(unescape("plain").unwrap(), "plain");
+        assert_eq!(unescape(r#"\x"#).unwrap_err().kind, ErrorKind::InvalidEscape);
+        assert_eq!(unescape(r#"\u12"#).unwrap_err().kind, ErrorKind::InvalidEscape);
+        let e = unescape(r#"\ud83d x"#).unwrap_err();
+        assert_eq!((e.kind, e.msg.as_str()), (ErrorKind::InvalidEscape, r#"Lone surrogate `\ud83d` in `\ud83d x`"#));
+        assert!(unescape(r#"\ude00\ud83d"#).is_err());
+        assert!(unescape(r#"\ud83d\u0041"#).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{Error, ErrorKind};
+use crate::json_walker::JsonWalker;
+use crate::tools::write_content;
+use crate::writer::JsonWriter;
+
+fn reader(bytes: &[u8]) -> Box<dyn Iterator<Item=u8>> {
+    Box::new(Vec::from(bytes).into_iter())
+}
+
+/// Walk all items of **bytes**. Invalid json returns Err and never panics
+/// ### Example
+/// ```
+/// use json_walker::fuzz_targets::check_walk;
+///
+/// assert!(check_walk(br#"{"a": [1, "x", null]}"#).is_ok());
+/// assert!(check_walk(b"tru").is_err());
+/// ```
+pub fn check_walk(bytes: &[u8]) -> Result<(), Error> {
+    let mut walker = JsonWalker::new(reader(bytes), 0);
+    loop {
+        match walker.next_item() {
+            Ok(_) => {}
+            Err(e) if e.kind == ErrorKind::EOS => break,
+            Err(e) => return Err(e),
+        }
+    }
+    let stats = walker.stats();
+    assert_eq!(stats.objects_opened, stats.objects_closed);
+    assert_eq!(stats.arrays_opened, stats.arrays_closed);
+    Ok(())
+}
+
+/// Build the content of **bytes**, write it back as json and parse it again. Both contents must be equal
+pub fn check_content(bytes: &[u8]) -> Result<(), Error> {
+    let content = JsonWalker::new(reader(bytes), 0).current_value_content()?;
+    let mut w = JsonWriter::new(Vec::new());
+    write_content(&content, &mut w)?;
+    let text = w.finish()?;
+    let again = JsonWalker::new(reader(&text), 0).current_value_content()?;
+    assert_eq!(content, again, "content changed after writing: {}", String::from_utf8_lossy(&text));
+    Ok(())
+}
+
+/// Deserialize **bytes** by current_value() and by serde_json. Where both succeed, their values must be equal
+#[cfg(all(feature = "deserialize", feature = "serde_json"))]
+pub fn check_deserialize_value(bytes: &[u8]) -> Result<(), Error> {
+    let walked = JsonWalker::new(reader(bytes), 0).current_value::<serde_json::Value>()?;
+    if let Ok(parsed) = serde_json::from_slice::<serde_json::Value>(bytes) {
+        assert!(same_value(&walked, &parsed), "left: {}\nright: {}", walked, parsed);
+    }
+    Ok(())
+}
+
+/// serde_json parses floats by a fast approximation and "-0" as a float, so numbers are compared by their f64 values
+/// and one ulp of difference is accepted, unless they are equal as they are, such as out of the f64 range with
+/// arbitrary_precision
+#[cfg(all(feature = "deserialize", feature = "serde_json"))]
+fn same_value(a: &serde_json::Value, b: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x == y || match (x.as_f64(), y.as_f64()) {
+            (Some(x), Some(y)) => x == y || (x - y).abs() <= f64::EPSILON * x.abs().max(y.abs()),
+            _ => false,
+        },
+        (Value::Array(x), Value::Array(y)) => x.len() == y.len() && x.iter().zip(y).all(|(x, y)| same_value(x, y)),
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len() && x.iter().all(|(k, v)| y.get(k).is_some_and(|w| same_value(v, w)))
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod fuzz_targets_tests {
+    use crate::ErrorKind;
+    use crate::fuzz_targets::{check_content, check_walk};
+
+    const CRASHERS: [&[u8]; 9] = [b"\0", b"[\0]", b"tru", b"[nul", b"{\"a\": fals}", b"-", b"[-]", b"-.5", b"[[[[[[["];
+
+    #[test]
+    fn test_crashers() {
+        for bytes in CRASHERS {
+            assert!(check_walk(bytes).is_err(), "{:?}", bytes);
+            assert!(check_content(bytes).is_err(), "{:?}", bytes);
+        }
+        assert!(check_walk(&[b'['; 200]).is_err());
+        assert!(check_walk(b"\"\xff\"").is_err());
+    }
+
+    #[test]
+    fn test_raw_bytes() {
+        // nothing checks bytes before the walker, so it tells malformed json by itself
+        for (bytes, kind) in [(&b"[1 2]"[..], ErrorKind::InvalidToken), (b"{\"a\" 1}", ErrorKind::UnexpectedChar), (b"\"\\ud800\"", ErrorKind::InvalidEscape), (b"//\n1", ErrorKind::UnexpectedChar), (b"[\"a\0\"]", ErrorKind::UnexpectedChar)] {
+            assert_eq!(check_walk(bytes).unwrap_err().kind(), &kind, "{:?}", bytes);
+            assert_eq!(check_content(bytes).unwrap_err().kind(), &kind, "{:?}", bytes);
+        }
+        assert_eq!(check_walk(b"[1]]").unwrap_err().kind(), &ErrorKind::TrailingCharacters);
+        check_walk(b"\xef\xbb\xbf[]").unwrap();
+    }
+
+    #[test]
+    fn test_valid() {
+        let json = br#" {"a": [1, -2.5e3, "x\"\u00e9", null, true, {}], "b": {"c": []}, "a": false} "#;
+        check_walk(json).unwrap();
+        check_content(json).unwrap();
+        check_walk(b"-0").unwrap();
+        check_content(br#"[1e5, -2.5E-3, "a\n\u00e9\/"]"#).unwrap();
+        check_content(b"\"\\ud83d\\ude00\"").unwrap();
+    }
+
+    #[cfg(all(feature = "deserialize", feature = "serde_json"))]
+    #[test]
+    fn test_deserialize_value() {
+        use crate::fuzz_targets::check_deserialize_value;
+
+        for bytes in CRASHERS {
+            assert!(check_deserialize_value(bytes).is_err(), "{:?}", bytes);
+        }
+        check_deserialize_value(br#"{"a": [1, -2, 3.5, "s\n\u00e9", null, true], "b": {"c": 18446744073709551615}}"#).unwrap();
+        check_deserialize_value(b"[-0, 3e23, 1.5E-3]").unwrap();
+        check_deserialize_value(b"[1e400, -12e4567]").unwrap();
+    }
+}
@@ -1,28 +1,562 @@
+use std::io;
+#[cfg(feature = "deserialize")]
+use std::ops::ControlFlow;
+use std::time::Instant;
+
 use crate::*;
 #[cfg(feature = "deserialize")]
 use crate::deserializer::deserialize_mod::Deserializer;
+#[cfg(feature = "deserialize")]
+use crate::parser_core::{add_stats, get_allow_nan, get_filter_below, on_duplicate_key, start_capture, stop_capture};
+#[cfg(feature = "deserialize")]
+use crate::tools::write_content;
+#[cfg(feature = "deserialize")]
+use crate::writer::JsonWriter;
 pub use crate::Error;
-use crate::parser_core::{extract_current_value, get_stack_top_index, Parser, walk_forward, get_current_level, get_path, get_recent_piece, seek_by_level_offset};
-pub use crate::parser_core::{Content, Item, Parser as JsonWalker, PathItem, TextItem, ValueType};
+use crate::escape::unescape;
+use crate::parser_core::{begin_value, current_key, get_current_status, check_deadline, feed, finish_feed, is_poisoned, prefetch_value, probe, same_key, is_next_element_hidden, skip_element, end_of_stream, end_value, poll, poll_resumable, set_allow_comments, set_allow_nan, set_lenient_syntax, set_multi_document, next_document, set_max_consecutive_whitespace, set_max_input_bytes, set_max_depth, finish, set_keep_escapes, set_lossy_utf8, set_on_duplicate_key, set_recovery, take_errors, extract_current_value, get_keep_escapes, set_deadline, set_filter, set_max_content_bytes, get_stack_top_index, Parser, walk_forward, get_current_level, get_path, get_path_string, next_token, get_recent_piece, get_recent_piece_marked, get_recent_piece_window, seek_by_level_offset, seek_pointer, skip_value, stream_string, get_stats, reset_stats, byte_offset, last_token_offset, position};
+use crate::path_matcher::{escape_token, PathMatcher};
+#[cfg(feature = "unicode-normalization")]
+use crate::parser_core::set_normalize_keys;
+#[cfg(feature = "unicode-normalization")]
+use crate::path_matcher::KeyNormalization;
+use crate::base64;
+pub use crate::parser_core::{Checkpoint, Content, DuplicatePolicy, Entry, Item, JsonEvent, Parser as JsonWalker, Path, PathItem, Stats, TextItem, Token, TokenKind, ValueType};
 pub use crate::readers::*;
+use crate::tools::HashOptions;
+use crate::tools::structural_hash::hash_value;
+
+impl<'r, R: Iterator<Item=u8>> Parser<'r, R> {
+    /// Run a walker function on a walker of new_nonblocking(). While the reader is pending, WouldBlock error is returned
+    /// and the walker is left as it was before the call, so the same call can be retried once more data arrives. Bytes
+    /// which are read before the reader gets pending are kept and read again by the retry, which only runs the call
+    /// once the token that stopped it is complete. The call is walked again from its start though, so a call which
+    /// takes a large value should be preceded by prefetch_value(). On other walkers, **call** just runs
+    /// ### Example
+    /// ```
+    /// use json_walker::ErrorKind;
+    /// use json_walker::json_walker::{JsonWalker, ReadSignal, SignalReader};
+    ///
+    /// struct Socket(Vec<ReadSignal>);
+    ///
+    /// impl SignalReader for Socket {
+    ///     fn read_signal(&mut self) -> ReadSignal {
+    ///         if self.0.is_empty() { ReadSignal::Eof } else { self.0.remove(0) }
+    ///     }
+    /// }
+    ///
+    /// let socket = Socket(vec![ReadSignal::Byte(b'['), ReadSignal::Byte(b'1'), ReadSignal::Pending, ReadSignal::Byte(b'2'), ReadSignal::Byte(b']')]);
+    /// let mut walker = JsonWalker::new_nonblocking(Box::new(socket), 0);
+    /// // the number may go on, so it is not returned before the reader gets data
+    /// assert_eq!(walker.poll(|w| w.next_item()).unwrap_err().kind(), &ErrorKind::WouldBlock);
+    /// assert_eq!(walker.poll(|w| w.next_item()).unwrap().1, "12");
+    /// ```
+    pub fn poll<T>(&mut self, call: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        poll(self, call)
+    }
+
+    /// poll() for calls which go on from wherever they stop, such as next_item(), so a retry goes on from the token
+    /// which has stopped the call
+    pub(crate) fn poll_resumable<T>(&mut self, call: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        poll_resumable(self, call)
+    }
+
+    /// On a walker of new_nonblocking() or new_push(), read the whole value in front of the cursor ahead, through
+    /// poll(), so the walker function which takes the value next does not wait in the middle of it. The value is kept
+    /// in memory till it is walked. On other walkers, nothing is done
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, ValueType};
+    ///
+    /// let mut walker = JsonWalker::new_push(0);
+    /// walker.feed(br#"{"a": [1, 2"#).unwrap();
+    /// walker.poll(|w| w.next_key()).unwrap();
+    /// assert!(walker.poll(|w| w.prefetch_value()).is_err());
+    /// walker.feed(b"]}").unwrap();
+    /// walker.poll(|w| w.prefetch_value()).unwrap();
+    /// assert_eq!(walker.poll(|w| w.skip_current_value()), Ok(ValueType::Arr));
+    /// ```
+    pub fn prefetch_value(&mut self) -> Result<(), Error> {
+        prefetch_value(self)
+    }
+
+    /// Hand **bytes** to a walker of new_push(). They may cut json anywhere, even in the middle of a string or number.
+    /// Io error of InvalidInput kind is returned by other walkers, or once finish_feed() is called
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, ValueType};
+    ///
+    /// let mut walker = JsonWalker::new_push(0);
+    /// walker.feed(br#"{"name": "Jo"#).unwrap();
+    /// assert_eq!(walker.next_item_nonblocking(), Ok(Some((ValueType::Str, "name".to_string()))));
+    /// assert_eq!(walker.next_item_nonblocking(), Ok(None));
+    /// walker.feed(br#"hn", "age": 4"#).unwrap();
+    /// assert_eq!(walker.next_item_nonblocking(), Ok(Some((ValueType::Str, "John".to_string()))));
+    /// walker.next_item_nonblocking().unwrap();
+    /// // the number may go on
+    /// assert_eq!(walker.next_item_nonblocking(), Ok(None));
+    /// walker.feed(b"2}").unwrap();
+    /// walker.finish_feed().unwrap();
+    /// assert_eq!(walker.next_item_nonblocking(), Ok(Some((ValueType::UInt, "42".to_string()))));
+    /// assert!(walker.next_item_nonblocking().is_err());
+    /// assert!(walker.feed(b" ").is_err());
+    /// ```
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        feed(self, bytes)
+    }
+
+    /// Tell a walker of new_push() that all of json is fed. Other walkers return Io error of InvalidInput kind
+    pub fn finish_feed(&mut self) -> Result<(), Error> {
+        finish_feed(self)
+    }
+
+    /// next_item() through poll(). None means more bytes are needed, then the call can be retried after feed(). The
+    /// retry goes on from where the last call stopped, so a string or number which is fed in many pieces is not read
+    /// again for each piece
+    pub fn next_item_nonblocking(&mut self) -> Result<Option<Item>, Error> {
+        match self.poll_resumable(|w| w.next_item()) {
+            Ok(item) => Ok(Some(item)),
+            Err(e) if e.kind == ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
 
-impl Parser {
     /// return the level of current position in json string.
     /// for more information check out next_item_by_level() doc
     pub fn get_current_level(&mut self) -> f32 {
         get_current_level(self)
     }
 
+    /// Return the most recent key of the current object, or the key whose value is the current array, without moving
+    /// the cursor. An array in an array takes the key of the outer one. None is returned before the first key of an
+    /// object, in arrays which belong to no key and at the root, so `#` of the root is never reported
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": {"b": 1}, "c": [2]}"#.to_string()), 0);
+    /// assert_eq!(walker.current_key(), None);
+    /// walker.next_key_by_name("b").unwrap();
+    /// assert_eq!(walker.current_key(), Some("b"));
+    /// walker.next_key_by_name("c").unwrap();
+    /// walker.next_value().unwrap();
+    /// assert_eq!(walker.current_key(), Some("c"));
+    /// ```
+    pub fn current_key(&self) -> Option<&str> {
+        current_key(self)
+    }
+
+    /// current_key() as an owned String
+    pub fn current_key_owned(&self) -> Option<String> {
+        current_key(self).map(String::from)
+    }
+
     /// json has tree structure. this function returns that path to the current position with some details
     pub fn get_path(&mut self) -> Vec<PathItem> {
         get_path(self)
     }
 
-    /// if mem_size is set in new() function, this function will return the latest piece of json, so you can apply a regex operation for example
+    /// if mem_size is set in new() function, this function will return the latest piece of json, so you can apply a regex operation for example.
+    /// Early in the stream it holds only the bytes read so far
     pub fn get_recent_piece(&mut self) -> String {
         get_recent_piece(self)
     }
 
+    /// same as get_recent_piece(), but only the last **before** bytes are returned, even if mem_size is larger.
+    /// Early in the stream, only the bytes read so far are returned
+    pub fn get_recent_piece_window(&mut self, before: usize) -> String {
+        get_recent_piece_window(self, before)
+    }
+
+    /// return the bytes read so far from memory, where **marker** is inserted at the cursor, which is just before the
+    /// byte about to be processed. At the end of json, the marker comes last
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"key": "value"}"#.to_string()), 50);
+    /// walker.next_key().unwrap();
+    /// assert_eq!(walker.get_recent_piece_marked("|"), r#"{"key"|:"#);
+    /// ```
+    pub fn get_recent_piece_marked(&mut self, marker: &str) -> String {
+        get_recent_piece_marked(self, marker)
+    }
+
+    /// Whether the walker is stopped by an error which leaves nothing to walk, such as WhitespaceLimitExceeded,
+    /// InvalidToken or UnexpectedChar on malformed json. The first call after the error returns it, then every call
+    /// which walks returns Poisoned error, which holds the original one. Only reset() with a new reader makes the
+    /// walker usable again, since the reader is dropped by the error
+    /// ### Example
+    /// ```
+    /// use json_walker::ErrorKind;
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new("[1x]".to_string()), 0);
+    /// assert_eq!(walker.next_item().unwrap_err().kind(), &ErrorKind::InvalidToken);
+    /// assert!(walker.is_poisoned());
+    /// assert!(matches!(walker.next_item().unwrap_err().kind(), ErrorKind::Poisoned { .. }));
+    /// walker.reset(StringReader::new("[2]".to_string()));
+    /// assert_eq!(walker.next_item().unwrap().1, "2");
+    /// ```
+    pub fn is_poisoned(&self) -> bool {
+        is_poisoned(self)
+    }
+
+    /// counters of what the walker has processed so far. Check out Stats doc
+    pub fn stats(&self) -> &Stats {
+        get_stats(self)
+    }
+
+    /// set all counters of stats() to zero
+    pub fn reset_stats(&mut self) {
+        reset_stats(self)
+    }
+
+    /// The line and column of the byte in front of the cursor, both from 1, where "\n" and "\r\n" end a line and a
+    /// column counts bytes. Error messages tell it as "line:column"
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new("{\r\n  \"a\": 1,\n  \"b\": 2\n}".to_string()), 0);
+    /// walker.next_key_by_name("b").unwrap();
+    /// assert_eq!(walker.position(), (3, 6));
+    /// ```
+    pub fn position(&self) -> (u64, u64) {
+        position(self)
+    }
+
+    /// Bytes which are read from the reader so far. The walker reads one byte ahead, so it counts the byte in front of
+    /// the cursor too. Unlike stats().bytes_consumed, reset_stats() does not change it
+    pub fn byte_offset(&self) -> u64 {
+        byte_offset(self)
+    }
+
+    /// The byte offset of the token which the last call has walked over, where a string starts with its quotation
+    /// mark. After next_item(), next_key() and the like, it is where the returned item starts in the stream. None
+    /// before the first token
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let json = r#"{"a": [1, 22], "b": "x"}"#;
+    /// let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+    /// walker.next_key_by_name("b").unwrap();
+    /// assert_eq!(walker.last_token_offset(), Some(15));
+    /// walker.next_item().unwrap();
+    /// assert_eq!(&json[walker.last_token_offset().unwrap() as usize..], r#""x"}"#);
+    /// ```
+    pub fn last_token_offset(&self) -> Option<u64> {
+        last_token_offset(self)
+    }
+
+    /// Bound the time of walk operations. Once **deadline** is passed, the loops of next_item(), next_key() and
+    /// the other seeking functions, skip_current_value(), walk_with(), take_sample() and find_element() stop with
+    /// DeadlineExceeded error, which tells the current path and offset. The clock is read every 64 bytes, so a slow
+    /// reader is noticed after a few bytes at most, while a single huge token is still read to its end.
+    /// The walker stays usable, and the error is returned again till the deadline is moved or cleared. So is it when the
+    /// deadline passes in a run of more than 64 white-spaces: the walk stops in the middle of the run as it does at the
+    /// end of stream, but with DeadlineExceeded error, then moving or clearing the deadline goes on with the run
+    /// ### Example
+    /// ```
+    /// use std::time::Instant;
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1}"#.to_string()), 0);
+    /// walker.set_deadline(Instant::now());
+    /// assert!(walker.next_key().is_err());
+    /// walker.clear_deadline();
+    /// assert_eq!(walker.next_key().unwrap().1, "a");
+    /// ```
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        set_deadline(self, Some(deadline))
+    }
+
+    pub fn clear_deadline(&mut self) {
+        set_deadline(self, None)
+    }
+
+    /// Limit a run of white-spaces between tokens, so a hostile stream of endless spaces can not keep the walker busy.
+    /// The default is 1MB and None means no limit. Once a run gets longer, the walk is finished and functions which
+    /// would return EOS error return WhitespaceLimitExceeded error with the offset of the run
+    /// ### Example
+    /// ```
+    /// use json_walker::ErrorKind;
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(format!("[1,{}2]", " ".repeat(100))), 0);
+    /// walker.set_max_consecutive_whitespace(Some(10));
+    /// assert_eq!(walker.next_item().unwrap().1, "1");
+    /// assert_eq!(walker.next_item().unwrap_err().kind(), &ErrorKind::WhitespaceLimitExceeded);
+    /// ```
+    pub fn set_max_consecutive_whitespace(&mut self, max: Option<usize>) {
+        set_max_consecutive_whitespace(self, max)
+    }
+
+    /// Skip `// line` and `/* block */` comments wherever white-spaces are allowed, as JSON5 and config files have
+    /// them. It is off by default, so comments are rejected by UnexpectedChar error. An unterminated block comment
+    /// finishes the walk with UnexpectedEos error
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new("// config\n{\"a\" /* key */ : 1}".to_string()), 0);
+    /// walker.set_allow_comments(true);
+    /// assert_eq!(walker.next_item().unwrap().1, "a");
+    /// assert_eq!(walker.next_item().unwrap().1, "1");
+    /// ```
+    pub fn set_allow_comments(&mut self, allow_comments: bool) {
+        set_allow_comments(self, allow_comments)
+    }
+
+    /// Accept the syntax of JavaScript object literals: strings which are delimited by `'`, in which `\'` stands for
+    /// `'`, and keys which are identifiers such as `name` or `$id` without quotation marks. Both quote styles may be
+    /// mixed in one json. It is off by default, so only RFC 8259 is accepted
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new("{key: 'it\\'s', \"b\": \"c\"}".to_string()), 0);
+    /// walker.set_lenient_syntax(true);
+    /// assert_eq!(walker.next_item().unwrap().1, "key");
+    /// assert_eq!(walker.next_item().unwrap().1, "it's");
+    /// assert_eq!(walker.next_key_by_name("b").unwrap().1, "b");
+    /// ```
+    pub fn set_lenient_syntax(&mut self, lenient_syntax: bool) {
+        set_lenient_syntax(self, lenient_syntax)
+    }
+
+    /// Accept `NaN`, `Infinity` and `-Infinity`, as Python's json.dumps() writes them, as Float values with their text
+    /// as it is. Other spellings such as `nan` or `inf` are still rejected. current_value() maps them to f64::NAN and
+    /// the infinities, while to_value() fails as serde_json::Value has no place for them. It is off by default
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader, ValueType};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new("[NaN, -Infinity]".to_string()), 0);
+    /// walker.set_allow_nan(true);
+    /// assert_eq!(walker.next_item().unwrap(), (ValueType::Float, "NaN".to_string()));
+    /// assert_eq!(walker.next_item().unwrap(), (ValueType::Float, "-Infinity".to_string()));
+    /// ```
+    pub fn set_allow_nan(&mut self, allow_nan: bool) {
+        set_allow_nan(self, allow_nan)
+    }
+
+    /// Limit the bytes which are read from the start of json, so an untrusted upload does not have to be cut by the
+    /// caller. None means no limit, which is the default. Once the walker reads more, the walk is finished, even in the
+    /// middle of a value, and functions which would return EOS error return LimitExceeded error. The limit is kept by
+    /// reset() and reset_stats()
+    /// ### Example
+    /// ```
+    /// use json_walker::ErrorKind;
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"[1, 2, "a long string"]"#.to_string()), 0);
+    /// walker.set_max_input_bytes(Some(10));
+    /// assert_eq!(walker.next_item().unwrap().1, "1");
+    /// assert_eq!(walker.next_item().unwrap().1, "2");
+    /// assert_eq!(walker.next_item().unwrap_err().kind(), &ErrorKind::LimitExceeded);
+    /// ```
+    pub fn set_max_input_bytes(&mut self, max: Option<u64>) {
+        set_max_input_bytes(self, max)
+    }
+
+    /// Limit containers which are nested in each other, so a hostile document such as a million of "[" can not grow the
+    /// stack of the walker, nor the recursion of current_value_content() and the deserializer. The default is 128 and
+    /// None means no limit. Once a container gets deeper, the walk is finished and functions which would return EOS error
+    /// return MaxDepthExceeded error
+    /// ### Example
+    /// ```
+    /// use json_walker::ErrorKind;
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new("[[[1]]]".to_string()), 0);
+    /// walker.set_max_depth(Some(2));
+    /// assert_eq!(walker.next_item().unwrap_err().kind(), &ErrorKind::MaxDepthExceeded);
+    /// ```
+    pub fn set_max_depth(&mut self, max: Option<usize>) {
+        set_max_depth(self, max)
+    }
+
+    /// Decide what is done with a key which is repeated in one object, which is Allow by default. Keys of each object
+    /// are tracked until it is closed, unless the policy is Allow. Error finishes the walk by DuplicateKey error at the
+    /// repeated key, while FirstWins and LastWins tell current_value_content(), to_value() and current_value() which
+    /// member to keep. next_item() and other walking functions still return every member
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{DuplicatePolicy, JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1, "a": 2}"#.to_string()), 0);
+    /// walker.set_on_duplicate_key(DuplicatePolicy::FirstWins);
+    /// let content = walker.current_value_content().unwrap();
+    /// assert_eq!(format!("{content:?}"), r#"Object({"a": Simple((UInt, "1"))})"#);
+    /// ```
+    pub fn set_on_duplicate_key(&mut self, policy: DuplicatePolicy) {
+        set_on_duplicate_key(self, policy)
+    }
+
+    /// Walk over malformed json instead of being stopped by it, which is off by default. When a syntax error is met
+    /// inside a container, the rest of that container is skipped by tracking brackets and strings only, then the walk
+    /// goes on after its closing byte as if it was closed normally. Items which are returned before the error stay
+    /// returned. The errors are kept for take_errors() with is_recovered() set. An error which can not be skipped, such
+    /// as the end of stream, still finishes the walk
+    /// ### Example
+    /// ```
+    /// use json_walker::ErrorKind;
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"[{"a": 1}, {"a": x}, {"a": 3}]"#.to_string()), 0);
+    /// walker.set_recovery(true);
+    /// let mut items = vec![];
+    /// while let Ok(item) = walker.next_item() {
+    ///     items.push(item.1);
+    /// }
+    /// assert_eq!(items, vec!["a", "1", "a", "a", "3"]);
+    /// let errors = walker.take_errors();
+    /// assert_eq!(errors[0].kind(), &ErrorKind::UnexpectedChar);
+    /// ```
+    pub fn set_recovery(&mut self, recovery: bool) {
+        set_recovery(self, recovery)
+    }
+
+    /// Return strings and keys in raw mode, exactly as they are in json text without the quotation marks, which is off
+    /// by default. Then escape sequences such as "\n" and "\u00e9" are neither decoded nor verified. Lookups such as
+    /// next_key_by_name() still compare decoded keys
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let json = r#"["line\nbreak", "\u0041"]"#;
+    /// let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+    /// assert_eq!(walker.next_item().unwrap().1, "line\nbreak");
+    /// walker.set_keep_escapes(true);
+    /// assert_eq!(walker.next_item().unwrap().1, r"\u0041");
+    /// ```
+    pub fn set_keep_escapes(&mut self, keep_escapes: bool) {
+        set_keep_escapes(self, keep_escapes)
+    }
+
+    /// Replace bytes of strings and keys which are not utf8 by U+FFFD and go on, which is off by default, so such bytes
+    /// finish the walk by InvalidUtf8 error
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::JsonWalker;
+    ///
+    /// let mut walker = JsonWalker::with_reader(b"[\"a\xFFb\"]".iter().copied(), 0);
+    /// walker.set_lossy_utf8(true);
+    /// assert_eq!(walker.next_item().unwrap().1, "a\u{FFFD}b");
+    /// ```
+    pub fn set_lossy_utf8(&mut self, lossy: bool) {
+        set_lossy_utf8(self, lossy)
+    }
+
+    /// Check that json is over: the root value is walked over to its end, e.g. by current_value(), and nothing but
+    /// white-spaces follows it. Closing brackets in front of the cursor, as the last values of containers leave it, are
+    /// walked over. Otherwise TrailingCharacters error is returned, or InvalidStructure if the root value is
+    /// not finished yet. An error which has stopped the walk earlier is returned as well
+    /// ### Example
+    /// ```
+    /// use json_walker::ErrorKind;
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1} x"#.to_string()), 0);
+    /// walker.skip_current_value().unwrap();
+    /// assert_eq!(walker.finish().unwrap_err().kind(), &ErrorKind::TrailingCharacters);
+    /// ```
+    pub fn finish(&mut self) -> Result<(), Error> {
+        finish(self)
+    }
+
+    /// Let another root value follow the last one, as NDJSON or a stream of concatenated documents does, instead of
+    /// TrailingCharacters error. Documents may be separated by any white-spaces, while words such as numbers need one
+    /// to be told apart. new_multi() sets it for new()
+    pub fn set_multi_document(&mut self, multi_document: bool) {
+        set_multi_document(self, multi_document)
+    }
+
+    /// Move to the start of the next document of a multi-document json, check out set_multi_document(). What is left
+    /// from the current document is skipped, unless it is not started yet, so the first call stands before the first
+    /// document. The result is false at the end of the stream, and an error if the last document is not complete
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new_multi(StringReader::new("[1, 2] [3] 4".to_string()), 0);
+    /// let mut firsts = vec![];
+    /// while walker.next_document().unwrap() {
+    ///     firsts.push(walker.next_item().unwrap().1);
+    /// }
+    /// assert_eq!(firsts, ["1", "3", "4"]);
+    /// ```
+    pub fn next_document(&mut self) -> Result<bool, Error> {
+        next_document(self)
+    }
+
+    /// Errors which are skipped since the last call, check out set_recovery()
+    pub fn take_errors(&mut self) -> Vec<Error> {
+        take_errors(self)
+    }
+
+    /// Limit the memory of values which are built by current_value_content(), to_value(), take_sample() and query(),
+    /// None for no limit which is the default. The size is approximate: the length of each string, key and number
+    /// plus the size of a Content node for each of them. Once a value gets larger, LimitExceeded error is returned and
+    /// the rest of the value is skipped without being built, so the cursor stands right after the value, exactly as
+    /// after skip_current_value(), and walking can go on
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"[[1, 2, 3, 4, 5, 6, 7, 8], "next"]"#.to_string()), 0);
+    /// walker.set_max_content_bytes(Some(100));
+    /// walker.move_n_element_forward(1);
+    /// assert!(walker.current_value_content().is_err());
+    /// assert_eq!(walker.next_item().unwrap().1, "next");
+    /// ```
+    pub fn set_max_content_bytes(&mut self, max_content_bytes: Option<usize>) {
+        set_max_content_bytes(self, max_content_bytes)
+    }
+
+    /// Hide nodes from every function of the walker, so they can not be read by mistake. A node is hidden if **exclude**
+    /// matches it or one of its ancestors. If **include** is given, only the nodes it matches, their descendants and the
+    /// containers on the way to them are left. Hidden keys are passed with their values by the fast path, hidden elements of arrays as
+    /// a whole, so they are absent from current_value_content(), to_value() and current_value() too. Levels and get_path()
+    /// are not affected
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    /// use json_walker::path_matcher::PathMatcher;
+    ///
+    /// let json = r#"{"users": [{"name": "a", "password": "x"}, {"name": "b", "password": "y"}]}"#;
+    /// let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+    /// walker.set_filter(None, Some(PathMatcher::new("/users/*/password").unwrap()));
+    /// let mut items = vec![];
+    /// while let Ok(item) = walker.next_item() {
+    ///     items.push(item.1);
+    /// }
+    /// assert_eq!(items, vec!["users", "name", "a", "name", "b"]);
+    /// ```
+    pub fn set_filter(&mut self, include: Option<PathMatcher>, exclude: Option<PathMatcher>) {
+        set_filter(self, include, exclude)
+    }
+
+    /// Normalize keys and names before comparing them in lookups, such as next_key_by_name(), deserialize_key() and
+    /// functions which take a json pointer. Escape sequences of keys are decoded anyway, so "caf\u00e9" matches
+    /// "café" without it. Matchers of set_filter() have their own setting, check out PathMatcher::with_normalize_keys()
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    /// use json_walker::path_matcher::KeyNormalization;
+    ///
+    /// // the key is "e" followed by a combining acute accent
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"cafe\u0301": 1}"#.to_string()), 0);
+    /// walker.set_normalize_keys(Some(KeyNormalization::Nfc));
+    /// assert!(walker.next_key_by_name("caf\u{e9}").is_ok());
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    pub fn set_normalize_keys(&mut self, normalize_keys: Option<KeyNormalization>) {
+        set_normalize_keys(self, normalize_keys)
+    }
+
     /// Parse json until the position at which, node level reaches the target_level_offset
     /// ## Sample json with level in different positions after parsing each element:
     /// <pre>
@@ -66,18 +600,13 @@ impl Parser {
     ///  #/{#,0}/
     /// </pre>
     pub fn get_path_string(&mut self) -> String {
-        let p = self.get_path();
-        let mut s = String::with_capacity(p.len() * 10);
-        for x in p.iter() {
-            s.push_str(&x.to_string());
-            s.push('/');
-        }
-        s
+        get_path_string(self)
     }
 
     /// Return next key or value in json. No matter if the item belongs to the child node or parent. If  no item exists, None will be returned
     pub fn next_item(&mut self) -> Result<Item, Error> {
         while self.next_byte != NIL {
+            check_deadline(self)?;
             match walk_forward(self) {
                 TextItem::Key(t) | TextItem::Value(t) => {
                     return Ok(t);
@@ -87,12 +616,145 @@ impl Parser {
                 }
             }
         }
-        Err(Error::new_eos())
+        Err(end_of_stream(self))
+    }
+
+    /// Same as next_item(), but the result tells whether the item is a key or a value
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{Entry, JsonWalker, StringReader, ValueType};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": "b"}"#.to_string()), 0);
+    /// assert_eq!(walker.next_entry(), Ok(Entry::Key((ValueType::Str, "a".to_string()))));
+    /// assert_eq!(walker.next_entry(), Ok(Entry::Value((ValueType::Str, "b".to_string()))));
+    /// ```
+    pub fn next_entry(&mut self) -> Result<Entry, Error> {
+        while self.next_byte != NIL {
+            check_deadline(self)?;
+            match walk_forward(self) {
+                TextItem::Key(t) => return Ok(Entry::Key(t)),
+                TextItem::Value(t) => return Ok(Entry::Value(t)),
+                _ => continue,
+            }
+        }
+        Err(end_of_stream(self))
+    }
+
+    /// Return the next event of json: the start or end of an object or array, a key or a value. Commas and colons are
+    /// consumed silently. At the end of json, the result is None, unless the walk is stopped by an error. Unlike
+    /// next_token(), nodes which are hidden by set_filter() have no event
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonEvent, JsonWalker, StringReader, ValueType};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1]}"#.to_string()), 0);
+    /// let events: Vec<JsonEvent> = std::iter::from_fn(|| walker.next_event().unwrap()).collect();
+    /// assert_eq!(events, vec![
+    ///     JsonEvent::StartObject,
+    ///     JsonEvent::Key((ValueType::Str, "a".to_string())),
+    ///     JsonEvent::StartArray,
+    ///     JsonEvent::Value((ValueType::UInt, "1".to_string())),
+    ///     JsonEvent::EndArray,
+    ///     JsonEvent::EndObject,
+    /// ]);
+    /// ```
+    pub fn next_event(&mut self) -> Result<Option<JsonEvent>, Error> {
+        while self.next_byte != NIL {
+            check_deadline(self)?;
+            let event = match walk_forward(self) {
+                TextItem::Key(t) => JsonEvent::Key(t),
+                TextItem::Value(t) => JsonEvent::Value(t),
+                TextItem::None(b'{') => JsonEvent::StartObject,
+                TextItem::None(b'}') => JsonEvent::EndObject,
+                TextItem::None(b'[') => JsonEvent::StartArray,
+                TextItem::None(b']') => JsonEvent::EndArray,
+                TextItem::None(_) => continue,
+            };
+            return Ok(Some(event));
+        }
+        if is_poisoned(self) {
+            return Err(end_of_stream(self));
+        }
+        Ok(None)
+    }
+
+    /// Return the next token of json text, such as "{", ":" or a string, with its byte range in the stream. It is meant
+    /// for tools which care about the text itself, like formatters and highlighters. Tokens are validated as other
+    /// functions do, but set_filter() does not hide them. Tokens and items can be mixed, since both move the same cursor
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader, TokenKind};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 12}"#.to_string()), 0);
+    /// let kinds: Vec<TokenKind> = std::iter::from_fn(|| walker.next_token().ok()).map(|t| t.kind).collect();
+    /// assert_eq!(kinds, vec![TokenKind::LBrace, TokenKind::String, TokenKind::Colon, TokenKind::Number, TokenKind::RBrace]);
+    /// ```
+    pub fn next_token(&mut self) -> Result<Token, Error> {
+        check_deadline(self)?;
+        next_token(self)
+    }
+
+    /// Iterate over the rest of keys and values by next_entry(). The iteration ends at the end of json or after an error
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1, {"b": 2}]}"#.to_string()), 0);
+    /// let keys: Vec<String> = walker.entries().filter_map(|e| e.ok()).filter(|e| e.is_key()).map(|e| e.item().1.clone()).collect();
+    /// assert_eq!(keys, vec!["a", "b"]);
+    /// ```
+    pub fn entries(&mut self) -> Entries<'_, 'r, R> {
+        Entries { walker: self, failed: false }
+    }
+
+    /// Step over the elements of the array in front of the cursor, or the one which holds it, one by one. Check out
+    /// ElementsIter::next_element(). ElementsIter is not an Iterator, as each element is read by the walker which it
+    /// lends to the body, so a `while let` loop takes the place of `for`
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1, {"b": 2}, 3]}"#.to_string()), 0);
+    /// walker.next_key().unwrap();
+    /// let mut elements = walker.elements();
+    /// let mut values = vec![];
+    /// while let Some((index, walker)) = elements.next_element().unwrap() {
+    ///     if index != 1 {
+    ///         values.push(walker.next_value().unwrap().1);
+    ///     }
+    /// }
+    /// assert_eq!(values, vec!["1", "3"]);
+    /// ```
+    pub fn elements(&mut self) -> ElementsIter<'_, 'r, R> {
+        ElementsIter { walker: self, stack_size: None, index: 0, finished: false }
+    }
+
+    /// Step over the members of the object in front of the cursor, or the one which holds it, one by one. It is the
+    /// same as elements() for objects. Check out MembersIter::next_member(). The name entries() is taken by the
+    /// iterator of next_entry(), and like ElementsIter, MembersIter lends the walker to the body rather than being an
+    /// Iterator, so `for (key, entry) in walker.entries()` is written as the `while let` loop below
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"id": 7, "tags": ["a"], "name": "b"}"#.to_string()), 0);
+    /// let mut members = walker.members();
+    /// let mut name = None;
+    /// while let Some((key, walker)) = members.next_member().unwrap() {
+    ///     if key == "name" {
+    ///         name = Some(walker.next_string().unwrap());
+    ///     }
+    /// }
+    /// assert_eq!(name, Some("b".to_string()));
+    /// ```
+    pub fn members(&mut self) -> MembersIter<'_, 'r, R> {
+        MembersIter { walker: self, stack_size: None, finished: false }
     }
 
     /// Next key will be returned and values will be ignored. No matter if it belongs to child or parent node. If there is no more key, None would be the result
     pub fn next_key(&mut self) -> Result<Item, Error> {
         while self.next_byte != NIL {
+            check_deadline(self)?;
             match walk_forward(self) {
                 TextItem::Key(t) => {
                     return Ok(t);
@@ -102,82 +764,362 @@ impl Parser {
                 }
             }
         }
-        Err(Error::new_eos())
+        Err(end_of_stream(self))
     }
 
-    /// The json will be parsed till the mentioned key. If key does not exist or it is already passed,
-    /// parsing will continue to the end of stream.
-    pub fn next_key_by_name(&mut self, name: &str) -> Result<Item, Error> {
-        let mut key;
-        loop {
-            key = self.next_key();
-            match key {
-                Ok(t) if !t.1.eq(name) => {
+    /// Next value will be returned and keys will be ignored. No matter if it belongs to child or parent node, so after a
+    /// key whose value is a container, the first scalar inside it is returned. If there is no more value, EOS error is the
+    /// result
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader, ValueType};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1, "b": [true]}"#.to_string()), 0);
+    /// walker.next_key_by_name("b").unwrap();
+    /// assert_eq!(walker.next_value().unwrap(), (ValueType::Bool, "true".to_string()));
+    /// ```
+    pub fn next_value(&mut self) -> Result<Item, Error> {
+        while self.next_byte != NIL {
+            check_deadline(self)?;
+            match walk_forward(self) {
+                TextItem::Value(t) => {
+                    return Ok(t);
+                }
+                _ => {
                     continue;
                 }
-                _ => {}
             }
-            break;
         }
-        key
+        Err(end_of_stream(self))
     }
 
-    /// The json will be parsed till the next sibling key.
-    /// At the end of current element (object or array), None will be returned and cursor will not move any further by this function
-    pub fn next_sibling_key(&mut self) -> Result<Item, Error> {
-        if self.next_byte != NIL {
-            let top_index = get_stack_top_index(self);
-            let top_stack_level = self.stack[top_index].level;
-            let diff = top_stack_level - top_stack_level.floor();
-            if seek_by_level_offset(self, diff) {
-                return self.next_key();
-            }
+    /// The json will be parsed till the mentioned key, then its value is returned, just as next_key_by_name() and
+    /// next_value() do. If the value is an object or array, WrongDataType error is returned and the cursor stands
+    /// before it, so current_value_content() or skip_current_value() can take it
+    /// ### Example
+    /// ```
+    /// use json_walker::ErrorKind;
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1], "b": "x"}"#.to_string()), 0);
+    /// assert_eq!(walker.next_value_by_key("a").unwrap_err().kind(), &ErrorKind::WrongDataType);
+    /// assert_eq!(walker.next_value_by_key("b").unwrap().1, "x");
+    /// ```
+    pub fn next_value_by_key(&mut self, name: &str) -> Result<Item, Error> {
+        self.next_key_by_name(name)?;
+        begin_value(self);
+        if self.next_byte == b'{' || self.next_byte == b'[' {
+            return Err(Error::wrong_data_type(format!("Expecting a simple value.{}", get_current_status(self))));
         }
-        Err(Error::new_eos())
+        self.next_value()
     }
 
-    /// Return next child key.
-    /// The key must be only one level lower than the current node, so grand children will not count in.
-    pub fn next_child_key(&mut self) -> Result<Item, Error> {
-        if self.next_byte != NIL {
-            let top_index = get_stack_top_index(self);
-            let top_stack_level = self.stack[top_index].level;
-            let diff = (top_stack_level + 1.0).floor() - top_stack_level;
-            if seek_by_level_offset(self, diff) {
-                return self.next_key();
-            }
+    /// next_value() if its type is one of **types**, otherwise WrongDataType error which tells **expected**
+    fn next_value_of(&mut self, types: &[ValueType], expected: &str) -> Result<String, Error> {
+        let (value_type, txt) = self.next_value()?;
+        if !types.contains(&value_type) {
+            return Err(Error::wrong_data_type(format!("Expecting {expected} but found {value_type:?} `{txt}`.{}", get_current_status(self))));
         }
-        Err(Error::new_eos())
+        Ok(txt)
     }
 
-    /// Return next key of parent (1 level up) or None if parent has no more key
-    pub fn next_key_from_parent(&mut self) -> Result<Item, Error> {
-        if self.next_byte != NIL {
-            let top_index = get_stack_top_index(self);
-            let top_stack_level = self.stack[top_index].level;
-            let diff = (top_stack_level - 1.0).ceil() - top_stack_level;
-            if seek_by_level_offset(self, diff) {
-                return self.next_key();
-            }
+    /// The next value as bool, keys are ignored as next_value() does. If it is another type, WrongDataType error is
+    /// returned and the value is passed anyway, so are the following typed functions
+    /// ### Example
+    /// ```
+    /// use json_walker::ErrorKind;
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"on": true, "n": -3, "f": 0.5, "s": "x", "z": null, "u": 7}"#.to_string()), 0);
+    /// assert_eq!(walker.next_bool(), Ok(true));
+    /// assert_eq!(walker.next_i64(), Ok(-3));
+    /// assert_eq!(walker.next_f64(), Ok(0.5));
+    /// assert_eq!(walker.next_string(), Ok("x".to_string()));
+    /// assert_eq!(walker.expect_null(), Ok(()));
+    /// assert_eq!(walker.next_string().unwrap_err().kind(), &ErrorKind::WrongDataType);
+    /// ```
+    pub fn next_bool(&mut self) -> Result<bool, Error> {
+        Ok(self.next_value_of(&[ValueType::Bool], "a bool")? == "true")
+    }
+
+    /// The next value as i64. A number which does not fit is ParseIntError error, while one with a fraction or an
+    /// exponent is WrongDataType error, as it is not an integer
+    pub fn next_i64(&mut self) -> Result<i64, Error> {
+        let txt = self.next_value_of(&[ValueType::UInt, ValueType::Int], "an integer")?;
+        txt.parse().map_err(|e| Error::parse_int(format!("`{txt}` is not an i64: {e}")))
+    }
+
+    /// The next value as u64. A negative number, or one which does not fit, is ParseIntError error. A fraction is
+    /// WrongDataType error as it is for next_i64()
+    pub fn next_u64(&mut self) -> Result<u64, Error> {
+        let txt = self.next_value_of(&[ValueType::UInt, ValueType::Int], "an integer")?;
+        txt.parse().map_err(|e| Error::parse_int(format!("`{txt}` is not a u64: {e}")))
+    }
+
+    /// The next number as f64, integers included. A number which is too big for f64, such as 1e400, is ParseFloatError
+    /// error rather than infinity, while a tiny one such as 1e-400 is rounded to 0. Infinity of set_allow_nan() is kept
+    pub fn next_f64(&mut self) -> Result<f64, Error> {
+        let txt = self.next_value_of(&[ValueType::UInt, ValueType::Int, ValueType::Float], "a number")?;
+        match txt.parse::<f64>() {
+            Ok(f) if f.is_infinite() && !txt.ends_with("Infinity") => Err(Error::parse_float(format!("`{txt}` is out of the range of f64"))),
+            r => r.map_err(|e| Error::parse_float(format!("`{txt}` is not an f64: {e}"))),
         }
-        Err(Error::new_eos())
     }
 
-    /// Parse json until the position in which, node level reaches the target_level.
-    /// - For short, consider each "[" and "{" one level increase and "]" and "}" one level decrease
-    /// - ":" is used for accessing simple values
-    /// ## Sample json with node level in different positions:
-    /// <pre>
-    /// 0 1       1.5   1      1.5 2     2 3        3.5    2 1 0
-    /// <span style="color:yellow">
-    /// ↓ ↓        ↓    ↓        ↓ ↓     ↓ ↓         ↓     ↓ ↓ ↓
-    /// </span>
+    /// The next value as String. Escape sequences are decoded, unless set_keep_escapes() is on
+    pub fn next_string(&mut self) -> Result<String, Error> {
+        self.next_value_of(&[ValueType::Str], "a string")
+    }
+
+    /// Pass the next value, which must be null
+    pub fn expect_null(&mut self) -> Result<(), Error> {
+        self.next_value_of(&[ValueType::Null], "null").map(|_| ())
+    }
+
+    /// The json will be parsed till the mentioned key. If key does not exist or it is already passed,
+    /// parsing will continue to the end of stream. Keys are compared in their decoded form, so **name** "é" matches the
+    /// key "\u00e9", even if set_keep_escapes() is on
+    pub fn next_key_by_name(&mut self, name: &str) -> Result<Item, Error> {
+        self.next_key_by_name_with(name, &SearchOptions::default())
+    }
+
+    /// The same as next_key_by_name(), but the scan stops with BudgetExhausted error when the budget of **options** is
+    /// spent. The walker stays usable, so the search can be continued by another call
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, SearchOptions, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1, "b": 2, "c": 3}"#.to_string()), 0);
+    /// assert!(walker.next_key_by_name_with("c", &SearchOptions::default().with_max_items(2)).is_err());
+    /// assert_eq!(walker.next_key_by_name("c").unwrap().1, "c");
+    /// ```
+    pub fn next_key_by_name_with(&mut self, name: &str, options: &SearchOptions) -> Result<Item, Error> {
+        let start_bytes = self.stats().bytes_consumed;
+        let mut items = 0;
+        while self.next_byte != NIL {
+            check_deadline(self)?;
+            self.check_budget(options, items, start_bytes)?;
+            match walk_forward(self) {
+                TextItem::Key(t) if same_key(self, &t.1, name) => return Ok(t),
+                TextItem::Key(_) | TextItem::Value(_) => items += 1,
+                _ => {}
+            }
+        }
+        Err(end_of_stream(self))
+    }
+
+    /// BudgetExhausted error if a scan which has passed **items** since **start_bytes** is out of budget
+    fn check_budget(&self, options: &SearchOptions, items: u64, start_bytes: u64) -> Result<(), Error> {
+        let bytes = self.stats().bytes_consumed - start_bytes;
+        if options.max_items.is_some_and(|m| items >= m) || options.max_bytes.is_some_and(|m| bytes >= m) {
+            return Err(Error::budget_exhausted(format!("Search budget exhausted after {items} items and {bytes} bytes")));
+        }
+        Ok(())
+    }
+
+    /// The json will be parsed till the next key which matches **pattern**, no matter if the key belongs to a child or
+    /// parent node. If there is no such key, parsing will continue to the end of stream, like next_key_by_name()
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, KeyPattern, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"id": 1, "item-7-id": 2}"#.to_string()), 0);
+    /// assert_eq!(walker.next_key_matching(&KeyPattern::glob("item-*-id")).unwrap().1, "item-7-id");
+    /// ```
+    pub fn next_key_matching(&mut self, pattern: &KeyPattern) -> Result<Item, Error> {
+        self.next_key_where(|key| pattern.matches(key))
+    }
+
+    /// The same as next_key_matching(), but keys are checked by **pred**. Like next_key_by_name(), keys are passed in
+    /// their decoded form, even if set_keep_escapes() is on
+    pub fn next_key_where(&mut self, mut pred: impl FnMut(&str) -> bool) -> Result<Item, Error> {
+        while self.next_byte != NIL {
+            check_deadline(self)?;
+            if let TextItem::Key(t) = walk_forward(self) {
+                let decoded = if get_keep_escapes(self) && t.1.contains('\\') { unescape(&t.1).ok() } else { None };
+                if pred(decoded.as_deref().unwrap_or(&t.1)) {
+                    return Ok(t);
+                }
+            }
+        }
+        Err(end_of_stream(self))
+    }
+
+    /// The inverse of get_path_string(): the json will be parsed till the node of **path**, such as `#/{key8}/[6]/{key81}`.
+    /// Check out PathMatcher::from_path_string() for the syntax. The result is the key of a member, or the first item of an
+    /// element, which is the value itself for a scalar, so an empty element is never reached. With `*` the first matching
+    /// node after the cursor is taken and the next call takes the one after it. If the node does not exist or it is
+    /// already entered or passed, parsing will continue to the end of stream, like next_key_by_name()
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let json = r#"{"a": [{"b": 1}, {"b": 2, "c": [3, 4]}]}"#;
+    /// let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+    /// assert_eq!(walker.seek_to_path("#/{a}/[1]/{c}/[1]").unwrap().1, "4");
+    /// ```
+    pub fn seek_to_path(&mut self, path: &str) -> Result<Item, Error> {
+        let matcher = PathMatcher::from_path_string(path)?;
+        while self.next_byte != NIL {
+            check_deadline(self)?;
+            match walk_forward(self) {
+                TextItem::Key(t) if matcher.reaches(&get_path(self), true) => return Ok(t),
+                TextItem::Value(t) if matcher.reaches(&get_path(self), false) => return Ok(t),
+                _ => {}
+            }
+        }
+        Err(end_of_stream(self))
+    }
+
+    /// The json will be parsed till the next sibling key.
+    /// At the end of current element (object or array), None will be returned and cursor will not move any further by this function
+    pub fn next_sibling_key(&mut self) -> Result<Item, Error> {
+        if self.next_byte != NIL {
+            let top_index = get_stack_top_index(self);
+            let top_stack_level = self.stack[top_index].level;
+            let diff = top_stack_level - top_stack_level.floor();
+            if seek_by_level_offset(self, diff) {
+                return self.next_key();
+            }
+            check_deadline(self)?;
+        }
+        Err(end_of_stream(self))
+    }
+
+    /// The same as next_key_by_name(), but only keys of the object which holds the cursor are checked, so keys of its
+    /// children or parents never match. At the end of that object, EOS error is returned and the cursor stands before
+    /// its closing bracket, so next_key_from_parent() can go on from there
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"user": {"id": 1}, "id": 2}"#.to_string()), 0);
+    /// walker.next_sibling_key_by_name("id").unwrap();
+    /// assert_eq!(walker.next_value().unwrap().1, "2");
+    /// ```
+    pub fn next_sibling_key_by_name(&mut self, name: &str) -> Result<Item, Error> {
+        self.next_key_by_name_within(name, 0)
+    }
+
+    /// The same as next_sibling_key_by_name(), but keys which are up to **max_extra_depth** levels deeper than the
+    /// object which holds the cursor are checked too. Each object or array counts as a level, so the keys of
+    /// `{"a": [{"id": 1}]}` are 2 levels deeper than "a"
+    pub fn next_key_by_name_within(&mut self, name: &str, max_extra_depth: usize) -> Result<Item, Error> {
+        // before the root value, its object is the current one
+        if self.stack.len() == 1 && self.next_byte == b'{' {
+            walk_forward(self);
+        }
+        let index = match self.stack.iter().rposition(|s| s.symbol == '{') {
+            Some(index) => index,
+            None if is_poisoned(self) || self.next_byte == NIL => return Err(end_of_stream(self)),
+            None => return Err(Error::invalid_structure(format!("The cursor is not in an object.{}", get_current_status(self)))),
+        };
+        let level = self.stack[index].level;
+        while self.next_byte != NIL {
+            check_deadline(self)?;
+            // right after a key or a value of the object, its level is on top
+            if self.next_byte == b'}' && self.stack.len() == index + 1 {
+                break;
+            }
+            if let TextItem::Key(t) = walk_forward(self) {
+                if get_current_level(self) - level <= max_extra_depth as f32 && same_key(self, &t.1, name) {
+                    return Ok(t);
+                }
+            }
+        }
+        Err(end_of_stream(self))
+    }
+
+    /// Return next child key.
+    /// The key must be only one level lower than the current node, so grand children will not count in.
+    pub fn next_child_key(&mut self) -> Result<Item, Error> {
+        if self.next_byte != NIL {
+            let top_index = get_stack_top_index(self);
+            let top_stack_level = self.stack[top_index].level;
+            let diff = (top_stack_level + 1.0).floor() - top_stack_level;
+            if seek_by_level_offset(self, diff) {
+                return self.next_key();
+            }
+            check_deadline(self)?;
+        }
+        Err(end_of_stream(self))
+    }
+
+    /// Step into the current value, which must be an object or array, so the cursor stands right after its opening
+    /// bracket and next_key() or next_item() returns its first item. If the value is a scalar, WrongDataType error is
+    /// returned and the cursor stays before it
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{ContainerKind, JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": {"b": [1, 2], "c": 3}, "d": 4}"#.to_string()), 0);
+    /// assert_eq!(walker.enter(), Ok(ContainerKind::Object));
+    /// walker.next_key().unwrap();
+    /// assert_eq!(walker.enter(), Ok(ContainerKind::Object));
+    /// walker.next_key().unwrap();
+    /// assert_eq!(walker.enter(), Ok(ContainerKind::Array));
+    /// walker.exit_current().unwrap();
+    /// walker.exit_current().unwrap();
+    /// assert_eq!(walker.next_sibling_key().unwrap().1, "d");
+    /// ```
+    pub fn enter(&mut self) -> Result<ContainerKind, Error> {
+        self.walk_before_value();
+        let kind = match self.next_byte {
+            b'{' => ContainerKind::Object,
+            b'[' => ContainerKind::Array,
+            NIL => return Err(end_of_stream(self)),
+            _ => return Err(Error::wrong_data_type(format!("Expecting an object or array.{}", get_current_status(self)))),
+        };
+        walk_forward(self);
+        Ok(kind)
+    }
+
+    /// Walk over the rest of the object or array which holds the cursor by the fast path, including its closing
+    /// bracket, so the cursor stands right after it, as if the container was skipped by skip_current_value(). At the
+    /// root, out of any container, InvalidStructure error is returned
+    pub fn exit_current(&mut self) -> Result<(), Error> {
+        let index = match self.stack.iter().rposition(|s| s.symbol == '{' || s.symbol == '[') {
+            Some(index) => index,
+            None if is_poisoned(self) => return Err(end_of_stream(self)),
+            None => return Err(Error::invalid_structure(format!("The cursor is not in an object or array.{}", get_current_status(self)))),
+        };
+        end_value(self, index);
+        if self.stack.len() > index {
+            return Err(end_of_stream(self));
+        }
+        check_deadline(self)
+    }
+
+    /// Return next key of parent (1 level up) or None if parent has no more key
+    pub fn next_key_from_parent(&mut self) -> Result<Item, Error> {
+        if self.next_byte != NIL {
+            let top_index = get_stack_top_index(self);
+            let top_stack_level = self.stack[top_index].level;
+            let diff = (top_stack_level - 1.0).ceil() - top_stack_level;
+            if seek_by_level_offset(self, diff) {
+                return self.next_key();
+            }
+            check_deadline(self)?;
+        }
+        Err(end_of_stream(self))
+    }
+
+    /// Parse json until the position in which, node level reaches the target_level.
+    /// - For short, consider each "[" and "{" one level increase and "]" and "}" one level decrease
+    /// - ":" is used for accessing simple values
+    /// ## Sample json with node level in different positions:
+    /// <pre>
+    /// 0 1       1.5   1      1.5 2     2 3        3.5    2 1 0
+    /// <span style="color:yellow">
+    /// ↓ ↓        ↓    ↓        ↓ ↓     ↓ ↓         ↓     ↓ ↓ ↓
+    /// </span>
     ///  { "key1" : 123, "key2" : [ true, { "key21" : 2.5 } ] }
     /// </pre>
     pub fn next_item_by_level(&mut self, target_level: f32) -> Result<Item, Error> {
         let mut ti;
         let mut stack_top;
         while self.next_byte != NIL {
+            check_deadline(self)?;
             ti = walk_forward(self);
             stack_top = self.stack.last().unwrap();
             if stack_top.level == target_level {
@@ -191,7 +1133,7 @@ impl Parser {
                 }
             }
         }
-        Err(Error::new_eos())
+        Err(end_of_stream(self))
     }
 
     /// To jump to the desired item (key or value), use this function.
@@ -203,20 +1145,18 @@ impl Parser {
     ///
     /// # Example
     ///```
-    /// fn main(){
-    ///    use json_walker::json_walker::{CurrentState, Item, JsonWalker, StringReader, ValueType};
-    ///
-    ///    let json = r#"[{"key1":{"key4":100},"key2":10},[{"key1":{"key4":300}, "key3":100}],"key1"]"#;
-    ///    let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 50);
-    ///    let patterns = vec![
-    ///       |cs: &CurrentState| -> bool{ cs.level == 2.0 && cs.nth_occurrence == 0 },
-    ///       |cs: &CurrentState| -> bool{ cs.latest_key.eq("key1") && cs.level == 3.0 },
-    ///       |cs: &CurrentState| -> bool{cs.latest_key.eq("key4") },
-    ///    ];
-    ///
-    ///    let item = walker.next_item_by_pattern(&patterns);
-    ///    assert_eq!(item, Ok((ValueType::Str, String::from("key4"))));
-    /// }
+    /// use json_walker::json_walker::{CurrentState, Item, JsonWalker, StringReader, ValueType};
+    ///
+    /// let json = r#"[{"key1":{"key4":100},"key2":10},[{"key1":{"key4":300}, "key3":100}],"key1"]"#;
+    /// let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 50);
+    /// let patterns = vec![
+    ///    |cs: &CurrentState| -> bool{ cs.level == 2.0 && cs.nth_occurrence == 0 },
+    ///    |cs: &CurrentState| -> bool{ cs.latest_key.eq("key1") && cs.level == 3.0 },
+    ///    |cs: &CurrentState| -> bool{cs.latest_key.eq("key4") },
+    /// ];
+    ///
+    /// let item = walker.next_item_by_pattern(&patterns);
+    /// assert_eq!(item, Ok((ValueType::Str, String::from("key4"))));
     /// ```
     /// In the above example 3 patterns are hired p0, p1 & p2 and we want to find the second key4.
     ///
@@ -257,7 +1197,15 @@ impl Parser {
     /// |   | #/[#, 1]/<span style="color:teal">[#, 0]</span>/{key1, 0}/{key4, 0}/ | p0:  level == 2.0 && nth_occurrence == 0 🟢
     /// | 7 | #/[#, 1]/[#, 0]/<span style="color:teal">{key1, 0}</span>/{key4, 0}/ | p1: latest_key.eq("key1") && level == 3.0 🟢
     /// |   | #/[#, 1]/[#, 0]/{key1, 0}/<span style="color:teal">{key4, 0}</span>/ | p2: latest_key.eq("key4") 🟢
-    pub fn next_item_by_pattern(&mut self, pattern: &Vec<impl Fn(&CurrentState) -> bool>) -> Result<Item, Error> {
+    pub fn next_item_by_pattern(&mut self, pattern: &[impl Fn(&CurrentState) -> bool]) -> Result<Item, Error> {
+        self.next_item_by_pattern_with(pattern, &SearchOptions::default())
+    }
+
+    /// The same as next_item_by_pattern(), but the scan stops with BudgetExhausted error when the budget of **options**
+    /// is spent. The walker stays usable, so the search can be continued by another call
+    pub fn next_item_by_pattern_with(&mut self, pattern: &[impl Fn(&CurrentState) -> bool], options: &SearchOptions) -> Result<Item, Error> {
+        let start_bytes = self.stats().bytes_consumed;
+        let mut items = 0;
         let pat_top = pattern.len() - 1;
         let mut pat_index;
         let mut stack_item;
@@ -265,6 +1213,8 @@ impl Parser {
         let mut is_key;
         let mut item;
         'next_item: while self.next_byte != NIL {
+            check_deadline(self)?;
+            self.check_budget(options, items, start_bytes)?;
             match walk_forward(self) {
                 TextItem::Key(m) => {
                     item = m;
@@ -278,6 +1228,7 @@ impl Parser {
                     continue;
                 }
             }
+            items += 1;
             pat_index = pat_top;
             for si in (1..=self.stack.len() - 1).rev() {
                 stack_item = &self.stack[si];
@@ -298,152 +1249,2926 @@ impl Parser {
                 }
             }
         }
-        Err(Error::new_eos())
+        Err(end_of_stream(self))
     }
 
     fn walk_before_value(&mut self) {
-        while self.next_byte == b':' || self.next_byte == b',' || self.stack.last().is_some_and(|s| s.symbol == '{') {
+        while self.next_byte != NIL && (self.next_byte == b':' || self.next_byte == b',' || self.stack.last().is_some_and(|s| s.symbol == '{')) {
             walk_forward(self);
         }
     }
 
     /// Based on cursor location, the value of current key will be returned.
     /// Value can be a single string, integer, float, boolean, null, object or array.
-    /// If there is no progress, the whole object will be returned
+    /// If there is no progress, the whole object will be returned.
+    /// If the value is larger than set_max_content_bytes(), LimitExceeded error is returned
     pub fn current_value_content(&mut self) -> Result<Content, Error> {
         self.walk_before_value();
         if self.next_byte != NIL {
             let top_index = get_stack_top_index(self);
-            return Ok(extract_current_value(self, top_index));
+            return extract_current_value(self, top_index);
         }
-        Err(Error::new_eos())
+        Err(end_of_stream(self))
+    }
+
+    /// Based on cursor location, the value of current key will be skipped without building any string or Content,
+    /// then the type of the skipped value is returned.
+    /// The cursor stands exactly where it would be after current_value(), so next_sibling_key() & next_key() work as expected.
+    pub fn skip_current_value(&mut self) -> Result<ValueType, Error> {
+        self.walk_before_value();
+        skip_value(self)
     }
 
     /// Based on cursor location, the value of current key will be deserialize.
+    ///
+    /// When the result is Ok, the whole value (scalar, object, array, tuple or enum) is consumed and the cursor stands
+    /// right after it, before the trailing "," or the closing bracket of the parent. It is exactly where the cursor would be
+    /// if the value had been skipped, so next_key(), next_sibling_key() or another current_value() call can follow.
+    ///
+    /// When the result is Err, the rest of the value is skipped by the fast path, so the cursor gets the same place and
+    /// error.is_recovered() is true. It is false if walking is stopped before the end of the value, e.g. by
+    /// set_max_consecutive_whitespace(). error.path() tells the innermost value which has failed
+    ///
+    /// With DuplicatePolicy::LastWins, the value is read as current_value_content() first, since the last member of a
+    /// repeated key is not known before its object is closed. Then an error of serde has no position, and its path counts
+    /// the members of an object in the order of their keys, without the repeated ones
     #[cfg(feature = "deserialize")]
     pub fn current_value<V>(&mut self) -> Result<V, Error> where V: for<'a> serde::de::Deserialize<'a>, {
         self.walk_before_value();
-        if self.next_byte != NIL {
-            let mut de = Deserializer::new(self);
-            return V::deserialize(&mut de);
+        if self.next_byte == NIL {
+            return Err(end_of_stream(self));
+        }
+        if on_duplicate_key(self) == DuplicatePolicy::LastWins {
+            return self.current_value_of_content();
         }
-        Err(Error::new_eos())
+        let stack_size = begin_value(self);
+        let mut de = Deserializer::new(self);
+        let result = V::deserialize(&mut de).map_err(|e| de.with_path(e));
+        result.map_err(|mut e| {
+            e.recovered = self.recover_value(stack_size);
+            e
+        })
     }
 
-    /// move n item including key, value or other none white space char such as "{", "[", "}", "]", ":" or ","
-    pub fn move_n_element_forward(&mut self, n: usize) {
-        for _ in 0..n {
-            walk_forward(self);
+    /// deserialize current_value_content(), which keeps the last member of each repeated key
+    #[cfg(feature = "deserialize")]
+    fn current_value_of_content<V>(&mut self) -> Result<V, Error> where V: for<'a> serde::de::Deserialize<'a>, {
+        let path = get_path_string(self);
+        let content = self.current_value_content()?;
+        let mut w = JsonWriter::new(Vec::new());
+        write_content(&content, &mut w)?;
+        let mut walker = JsonWalker::with_reader(w.finish()?.into_iter(), 0);
+        walker.set_allow_nan(get_allow_nan(self));
+        // the content is taken whole, so the cursor is after the value anyway, while the path of an error goes on from
+        // the path of the value
+        walker.current_value().map_err(|e| {
+            let inner = e.path.as_deref().and_then(|p| p.split_once('/')).map_or("", |(_, p)| p);
+            let path = format!("{path}{inner}");
+            Error { position: None, recovered: true, ..e.with_path(path) }
+        })
+    }
+
+    /// move the cursor to the end of a value which is partly consumed by a failed deserialization
+    #[cfg(feature = "deserialize")]
+    fn recover_value(&mut self, stack_size: usize) -> bool {
+        if self.stack.len() > stack_size {
+            end_value(self, stack_size);
+        } else if !matches!(self.next_byte, NIL | b',' | b']' | b'}') {
+            // the value is rejected before its first byte
+            return skip_value(self).is_ok();
         }
+        self.stack.len() <= stack_size
     }
-}
 
-pub struct CurrentState<'a> {
-    /// **latest_key** is the latest key seen in the current position
-    pub latest_key: &'a str,
+    /// Based on cursor location, the value of current key will be returned as serde_json::Value.
+    /// It is built directly from the json text, not through Content. Integers become i64 (or u64 if they do not fit) and
+    /// other numbers become f64. Keys keep their order if "preserve_order" feature of serde_json is enabled in your project.
+    /// The cursor postcondition is the same as current_value(). set_max_content_bytes() applies to it too
+    #[cfg(feature = "serde_json")]
+    pub fn to_value(&mut self) -> Result<serde_json::Value, Error> {
+        self.walk_before_value();
+        crate::value::build_value(self)
+    }
 
-    /// **nth_occurrence** is the index of the current item (key or value) in json
-    pub nth_occurrence: usize,
+    /// Based on cursor location, the sha-256 digest of the structure of current value is returned. Check out
+    /// tools::structural_hash() doc. The cursor postcondition is the same as current_value()
+    pub fn hash_current_value(&mut self, options: &HashOptions) -> Result<[u8; 32], Error> {
+        self.walk_before_value();
+        hash_value(self, options)
+    }
 
-    /// **level: f32** is the level of the current position. Please check out next_item_by_level() docs
-    pub level: f32,
+    /// Walk over the value in front of the cursor and pass each of its items to **observer** with the json pointer of
+    /// the item, relative to the value. Keys get the pointer of their member, brackets get the pointer of their
+    /// container, "," and ":" are not passed. Nothing is built, so observers such as tools::StreamValidator and your own
+    /// processing can share one pass. An error of **observer** stops walking. The cursor postcondition is the same as
+    /// current_value()
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader, TextItem};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1, {"b/c": true}]}"#.to_string()), 0);
+    /// let mut pointers = vec![];
+    /// walker.walk_with(&mut |pointer, item| {
+    ///     if let TextItem::Value(_) = item {
+    ///         pointers.push(pointer.to_string());
+    ///     }
+    ///     Ok(())
+    /// }).unwrap();
+    /// assert_eq!(pointers, vec!["/a/0", "/a/1/b~1c"]);
+    /// ```
+    pub fn walk_with(&mut self, observer: &mut dyn FnMut(&str, &TextItem) -> Result<(), Error>) -> Result<(), Error> {
+        self.walk_before_value();
+        let mut pointer = String::new();
+        // open containers: length of their own pointer and the index of the next element, None for objects
+        let mut containers: Vec<(usize, Option<usize>)> = vec![];
+        loop {
+            check_deadline(self)?;
+            if self.next_byte == NIL {
+                return Err(end_of_stream(self));
+            }
+            let item = walk_forward(self);
+            match &item {
+                TextItem::Key(k) => {
+                    let len = containers.last().map_or(0, |c| c.0);
+                    pointer.truncate(len);
+                    pointer.push('/');
+                    let key = if get_keep_escapes(self) { unescape(&k.1)? } else { k.1.clone() };
+                    pointer.push_str(&escape_token(&key));
+                }
+                TextItem::Value(_) | TextItem::None(b'{') | TextItem::None(b'[') => {
+                    if let Some((len, Some(index))) = containers.last_mut() {
+                        pointer.truncate(*len);
+                        pointer.push_str(&format!("/{index}"));
+                        *index += 1;
+                    }
+                    if let TextItem::None(b) = item {
+                        containers.push((pointer.len(), if b == b'[' { Some(0) } else { None }));
+                    }
+                }
+                TextItem::None(b'}') | TextItem::None(b']') => {
+                    if let Some((len, _)) = containers.pop() {
+                        pointer.truncate(len);
+                    }
+                }
+                TextItem::None(_) => continue,
+            }
+            observer(&pointer, &item)?;
+            if containers.is_empty() && !matches!(item, TextItem::Key(_)) {
+                return Ok(());
+            }
+        }
+    }
 
-    /// **current_item** is the current scanned which is Item(ValueType, value as string)
-    pub current_item: &'a Item,
+    /// Evaluate a jq-style path such as `.items[].name` in one streaming pass. Matched values are built as Content lazily,
+    /// one per call of next(), and everything else is walked over without being built. Paths are absolute, so call it
+    /// before the cursor passes the nodes of interest.
+    /// Only a subset of jq is supported, which is a chain of these steps:
+    /// - `.` alone for the root
+    /// - `.key` for a member, where key is an identifier of ascii letters, digits and "_" which does not start by a digit
+    /// - `[n]` for the element at index n, counted from 0
+    /// - `[]` for every element of an array or every member of an object
+    ///
+    /// Anything else, such as negative indices `[-1]`, slices `[1:2]`, quoted keys `["a b"]`, recursion `..`, `?`, pipes
+    /// `|` and functions, is rejected by InvalidPath error which tells the position of the offending char. Check out
+    /// PathMatcher::from_query()
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{Content, JsonWalker, StringReader, ValueType};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"items": [{"name": "a"}, {"name": "b"}]}"#.to_string()), 0);
+    /// let names: Vec<Content> = walker.query(".items[].name").unwrap().collect::<Result<_, _>>().unwrap();
+    /// assert_eq!(names, vec![Content::Simple((ValueType::Str, "a".to_string())), Content::Simple((ValueType::Str, "b".to_string()))]);
+    /// assert!(walker.query(".items[-1]").is_err());
+    /// assert!(walker.query(".items[] | .name").is_err());
+    /// ```
+    pub fn query(&mut self, expr: &str) -> Result<QueryResults<'_, 'r, R>, Error> {
+        let matcher = PathMatcher::from_query(expr)?;
+        let before_value = match self.next_byte {
+            NIL | b',' | b':' | b'}' | b']' => false,
+            _ => self.stack.last().is_none_or(|s| s.symbol != '{'),
+        };
+        Ok(QueryResults { walker: self, matcher, before_value })
+    }
 
-    /// **is_key: bool**, determines if the current item is a key or value
-    pub is_key: bool,
-}
+    /// Run **f** as a probe: the bytes it reads are walked again afterward, so the cursor stays where it was, and so do
+    /// stats. If **f** reads more than **limit** bytes, LimitExceeded error is returned and the cursor is still in place.
+    /// Only an error which stops the walker, e.g. an io error, is returned without rewinding
+    pub fn probe<T>(&mut self, limit: usize, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        check_deadline(self)?;
+        probe(self, limit, f)
+    }
 
-#[cfg(test)]
-mod walker_tests {
-    use std::collections::BTreeMap;
+    /// Whether the object which holds the cursor has the key among the keys after the cursor. It is a probe(), so
+    /// the cursor does not move and at most **limit** bytes are read ahead
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"id": 7, "data": [1, 2], "error": "x"}"#.to_string()), 0);
+    /// walker.next_key().unwrap();
+    /// assert_eq!(walker.has_key_in_current("error", 1024), Ok(true));
+    /// assert_eq!(walker.has_key_in_current("warning", 1024), Ok(false));
+    /// assert_eq!(walker.next_key().unwrap().1, "data");
+    /// ```
+    pub fn has_key_in_current(&mut self, name: &str, limit: usize) -> Result<bool, Error> {
+        self.probe(limit, |walker| Ok(walker.seek_key_in_current_object(name)))
+    }
 
-    use crate::Error;
-    use crate::json_walker::{CurrentState, JsonWalker};
-    use crate::parser_core::{Content, ValueType};
-    use crate::readers::StringReader;
+    /// Whether **pointer** (relative to the value in front of the cursor) exists. It is a probe(), so the cursor does
+    /// not move and at most **limit** bytes are read ahead
+    pub fn has_pointer(&mut self, pointer: &str, limit: usize) -> Result<bool, Error> {
+        let pointer = PathMatcher::new(pointer)?;
+        self.probe(limit, |walker| {
+            walker.walk_before_value();
+            match seek_pointer(walker, &pointer) {
+                Ok(()) => Ok(true),
+                Err(e) if e.kind == ErrorKind::KeyNotFound => Ok(false),
+                Err(e) => Err(e),
+            }
+        })
+    }
 
-    const CORRECT_JSON: &str = r#" {"key1":null,"key2":true,"key3":false,"key4":111,"key5":111.111,"key6":"str1 \":{}[],","key7":{  "key71" : null ,  "key72" : true ,  "key73" : false ,  "key74" : 222 ,  "key75" : 222.222 ,  "key76" : "str2 \":{}[]," ,  "key78" : [    null ,    true ,    false ,    333 ,    333.333 ,    "str3 \":{}[]," ,    {  } ,    [  ]  ] ,  "key79" : {} ,  "key710": [  ] } , "key8" : [  null ,  true ,  false ,  444 ,  444.444 ,  "str4 \":{}[]," ,  {    "key81" : null ,    "key82" : true ,    "key83" : false ,    "key84" : 555 ,
-      "key85" : 555.555 ,
-      "key86" : "str5 \":{}[]," ,    "key89" : {} ,    "key810" : [ ]  } ,  { } ,  [ ]  ] , "key9" : { } , "key10" : [ ]
-} "#;
+    /// Seek the key and deserialize its value in one call. The cursor postcondition is the same as current_value().
+    /// - **scope** determines where the key is looked for. Check out KeyScope doc
+    /// - If the key is not found, the error kind is KeyNotFound, even if the stream is finished
+    #[cfg(feature = "deserialize")]
+    pub fn deserialize_key<V>(&mut self, name: &str, scope: KeyScope) -> Result<V, Error> where V: for<'a> serde::de::Deserialize<'a>, {
+        let found = match scope {
+            KeyScope::Global => self.next_key_by_name(name).is_ok(),
+            KeyScope::CurrentObject => self.seek_key_in_current_object(name),
+        };
+        if !found {
+            return Err(Error::key_not_found(format!(r#"Key "{name}" not found"#)));
+        }
+        self.current_value()
+    }
 
-    #[test]
-    fn test_next_item() {
-        // keys and values must be retrieved in order
-        let words = [
-            "key1",
-            "null",
-            "key2",
-            "true",
-            "key3",
-            "false",
-            "key4",
-            "111",
-            "key5",
-            "111.111",
-            "key6",
-            "str1 \":{}[],",
-            "key7",
-            "key71",
-            "null",
-            "key72",
-            "true",
-            "key73",
-            "false",
-            "key74",
-            "222",
-            "key75",
-            "222.222",
-            "key76",
-            "str2 \":{}[],",
-            "key78",
-            "null",
-            "true",
-            "false",
-            "333",
-            "333.333",
-            "str3 \":{}[],",
-            "key79",
-            "key710",
-            "key8",
-            "null",
-            "true",
-            "false",
-            "444",
-            "444.444",
-            "str4 \":{}[],",
-            "key81",
-            "null",
-            "key82",
-            "true",
-            "key83",
-            "false",
-            "key84",
-            "555",
-            "key85",
-            "555.555",
-            "key86",
-            "str5 \":{}[],",
-            "key89",
-            "key810",
-            "key9",
-            "key10",
-        ];
+    /// walk forward till the key in the object which holds the cursor. It stops as soon as that object is closed
+    fn seek_key_in_current_object(&mut self, name: &str) -> bool {
+        let level = match self.stack.last() {
+            Some(s) if s.symbol == '{' || s.symbol == ':' => s.level.floor(),
+            _ => return false,
+        };
+        while self.next_byte != NIL {
+            match walk_forward(self) {
+                TextItem::Key(t) if get_current_level(self) == level && same_key(self, &t.1, name) => return true,
+                _ if get_current_level(self) < level => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Find the first element of the current array (the one in front of the cursor or the one which holds it) which is an
+    /// object whose **field** satisfies **pred**, and deserialize only that element. The result is its index in the
+    /// array and the element. Elements are scanned just as far as the field and the rest of them is skipped fast,
+    /// although their bytes are kept till the end of each element, since the field may come after others.
+    /// Fields which are objects or arrays never satisfy **pred**. If no element matches, the error kind is ElementNotFound
+    /// and the cursor stands right after the array. Otherwise the cursor stands right after the matched element
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let json = r#"{"orders": [{"id": 1, "total": 10}, {"total": 25, "id": 2}]}"#;
+    /// let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+    /// walker.next_key_by_name("orders").unwrap();
+    /// let (index, order) = walker.find_element::<serde_json::Value>("id", |id| id.1 == "2").unwrap();
+    /// assert_eq!((index, order["total"].as_i64()), (1, Some(25)));
+    /// ```
+    #[cfg(feature = "deserialize")]
+    pub fn find_element<V>(&mut self, field: &str, pred: impl Fn(&Item) -> bool) -> Result<(usize, V), Error> where V: for<'a> serde::de::Deserialize<'a>, {
+        self.walk_before_value();
+        if self.next_byte == b'[' {
+            walk_forward(self);
+        } else if self.stack.last().is_none_or(|s| s.symbol != '[') {
+            return Err(Error::wrong_data_type(format!("Expecting an array.{}", get_current_status(self))));
+        }
+        let mut index = 0;
+        loop {
+            check_deadline(self)?;
+            if self.next_byte == b',' {
+                walk_forward(self);
+            }
+            if is_next_element_hidden(self) {
+                skip_element(self);
+                index += 1;
+                continue;
+            }
+            match self.next_byte {
+                NIL => return Err(end_of_stream(self)),
+                b']' => {
+                    walk_forward(self);
+                    return Err(Error::element_not_found(format!("No element matched after scanning {index} elements")));
+                }
+                b'{' => {}
+                _ => {
+                    skip_value(self)?;
+                    index += 1;
+                    continue;
+                }
+            }
+
+            let path = get_path(self);
+            start_capture(self);
+            let stack_size = begin_value(self);
+            walk_forward(self);
+            let mut matched = false;
+            loop {
+                check_deadline(self)?;
+                match walk_forward(self) {
+                    TextItem::Key(k) if same_key(self, &k.1, field) => {
+                        begin_value(self);
+                        if self.next_byte == b'{' || self.next_byte == b'[' {
+                            skip_value(self)?;
+                        } else if let TextItem::Value(v) = walk_forward(self) {
+                            matched = pred(&v);
+                        }
+                        break;
+                    }
+                    TextItem::Key(_) => {
+                        skip_value(self)?;
+                    }
+                    TextItem::None(b'}') => break,
+                    TextItem::None(_) if self.next_byte != NIL => {}
+                    _ => return Err(end_of_stream(self)),
+                }
+            }
+            end_value(self, stack_size);
+            let element = stop_capture(self);
+            if matched {
+                let mut parser = Parser::new(Box::new(element.into_iter()), 0);
+                let (include, exclude) = get_filter_below(self, &path);
+                set_filter(&mut parser, include, exclude);
+                let value = parser.current_value()?;
+                add_stats(self, parser.stats());
+                return Ok((index, value));
+            }
+            index += 1;
+        }
+    }
+
+    /// Seek the array at **pointer** (relative to the value in front of the cursor) and return its first **n** elements.
+    /// Reading stops right after the nth element, so the rest of the stream is never touched. If the array has fewer
+    /// elements, all of them are returned and the cursor stands right after the array
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{Content, JsonWalker, StringReader, ValueType};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"results": [1, 2, 3]}"#.to_string()), 0);
+    /// let sample = walker.take_sample("/results", 2).unwrap();
+    /// assert_eq!(sample, vec![Content::Simple((ValueType::UInt, "1".to_string())), Content::Simple((ValueType::UInt, "2".to_string()))]);
+    /// ```
+    pub fn take_sample(&mut self, pointer: &str, n: usize) -> Result<Vec<Content>, Error> {
+        self.take_elements(pointer, n, |walker| walker.current_value_content())
+    }
+
+    /// The same as take_sample(), but elements are deserialized
+    #[cfg(feature = "deserialize")]
+    pub fn take_sample_as<V>(&mut self, pointer: &str, n: usize) -> Result<Vec<V>, Error> where V: for<'a> serde::de::Deserialize<'a>, {
+        self.take_elements(pointer, n, |walker| walker.current_value())
+    }
+
+    /// Seek the array at **pointer** (relative to the value in front of the cursor) and pass its elements to **f** one by
+    /// one, deserialized. When **f** returns Break, walking stops right after that element, so the rest of the array can
+    /// be processed later by resume_elements() with the returned state. A passed deadline of set_deadline() stops walking
+    /// the same way, but by DeadlineExceeded error
+    /// ### Example
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"ids": [1, 2, 3, 4]}"#.to_string()), 0);
+    /// let mut ids = vec![];
+    /// let state = walker.for_each_element("/ids", |id: i32| {
+    ///     ids.push(id);
+    ///     if ids.len() == 2 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+    /// }).unwrap();
+    /// assert_eq!((ids.len(), state.finished), (2, false));
+    /// let state = walker.resume_elements(&state, |id: i32| {
+    ///     ids.push(id);
+    ///     ControlFlow::Continue(())
+    /// }).unwrap();
+    /// assert_eq!((ids, state.processed, state.finished), (vec![1, 2, 3, 4], 4, true));
+    /// ```
+    #[cfg(feature = "deserialize")]
+    pub fn for_each_element<T>(&mut self, pointer: &str, f: impl FnMut(T) -> ControlFlow<()>) -> Result<ElementsState, Error> where T: for<'a> serde::de::Deserialize<'a>, {
+        self.walk_before_value();
+        seek_pointer(self, &PathMatcher::new(pointer)?)?;
+        if self.next_byte != b'[' {
+            return Err(Error::wrong_data_type(format!("The value of `{pointer}` is not an array")));
+        }
+        walk_forward(self);
+        let state = ElementsState { stack_size: self.stack.len(), processed: 0, finished: false };
+        self.each_element(state, f)
+    }
+
+    /// Go on with the elements of an array which for_each_element() or resume_elements() stopped in, while the cursor
+    /// stands where they left it. Otherwise the error kind is InvalidStructure
+    #[cfg(feature = "deserialize")]
+    pub fn resume_elements<T>(&mut self, state: &ElementsState, f: impl FnMut(T) -> ControlFlow<()>) -> Result<ElementsState, Error> where T: for<'a> serde::de::Deserialize<'a>, {
+        if state.finished {
+            return Ok(*state);
+        }
+        if self.stack.len() != state.stack_size || self.stack.last().is_none_or(|s| s.symbol != '[') {
+            return Err(Error::invalid_structure(format!("The cursor is not in the array of the state.{}", get_current_status(self))));
+        }
+        self.each_element(*state, f)
+    }
+
+    #[cfg(feature = "deserialize")]
+    fn each_element<T>(&mut self, mut state: ElementsState, mut f: impl FnMut(T) -> ControlFlow<()>) -> Result<ElementsState, Error> where T: for<'a> serde::de::Deserialize<'a>, {
+        loop {
+            check_deadline(self)?;
+            if self.next_byte == b',' {
+                walk_forward(self);
+            }
+            match self.next_byte {
+                NIL => return Err(end_of_stream(self)),
+                b']' => {
+                    walk_forward(self);
+                    state.finished = true;
+                    return Ok(state);
+                }
+                _ => {}
+            }
+            if is_next_element_hidden(self) {
+                skip_element(self);
+                continue;
+            }
+            let element = self.current_value()?;
+            state.processed += 1;
+            if f(element).is_break() {
+                return Ok(state);
+            }
+        }
+    }
+
+    fn take_elements<T>(&mut self, pointer: &str, n: usize, mut take: impl FnMut(&mut Self) -> Result<T, Error>) -> Result<Vec<T>, Error> {
+        self.walk_before_value();
+        seek_pointer(self, &PathMatcher::new(pointer)?)?;
+        if self.next_byte != b'[' {
+            return Err(Error::wrong_data_type(format!("The value of `{pointer}` is not an array")));
+        }
+        walk_forward(self);
+        let mut elements = Vec::with_capacity(n);
+        while elements.len() < n {
+            check_deadline(self)?;
+            if self.next_byte == b',' {
+                walk_forward(self);
+            }
+            if self.next_byte == b']' {
+                walk_forward(self);
+                break;
+            }
+            if is_next_element_hidden(self) {
+                skip_element(self);
+                continue;
+            }
+            elements.push(take(self)?);
+        }
+        Ok(elements)
+    }
+
+    /// Check the order of keys of the object at **pointer**, relative to the value in front of the cursor, so "" selects
+    /// that value itself. Values are skipped by the fast path and nothing is built. Check out KeyOrder doc for the policies.
+    /// A violation is KeyOrder error, which names the first out-of-place key and its position, starting from 0.
+    /// Either way, the cursor stands right after the object
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, KeyOrder, StringReader};
+    ///
+    /// let json = r#"{"header": {"id": 1, "len": 2, "crc": 3}}"#;
+    /// let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+    /// assert!(walker.assert_key_order("/header", &["id", "crc"], KeyOrder::Subsequence).is_ok());
+    /// let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+    /// // Key `len` at position 1 is out of order, expecting `crc`
+    /// assert!(walker.assert_key_order("/header", &["id", "crc"], KeyOrder::Prefix).is_err());
+    /// ```
+    pub fn assert_key_order(&mut self, pointer: &str, expected: &[&str], policy: KeyOrder) -> Result<(), Error> {
+        self.walk_before_value();
+        seek_pointer(self, &PathMatcher::new(pointer)?)?;
+        if self.next_byte != b'{' {
+            return Err(Error::wrong_data_type(format!("The value of `{pointer}` is not an object")));
+        }
+        let stack_size = begin_value(self);
+        walk_forward(self);
+        let result = self.check_key_order(expected, policy);
+        end_value(self, stack_size);
+        result
+    }
+
+    /// the cursor stands right after "{"
+    fn check_key_order(&mut self, expected: &[&str], policy: KeyOrder) -> Result<(), Error> {
+        let out_of_order = |key: &str, position: usize, want: &str| Error::key_order(format!("Key `{key}` at position {position} is out of order, expecting `{want}`"));
+        // count of expected keys seen so far
+        let mut matched = 0;
+        let mut position = 0;
+        while self.next_byte != b'}' && self.next_byte != NIL {
+            check_deadline(self)?;
+            if self.next_byte == b',' {
+                walk_forward(self);
+            }
+            let key = match walk_forward(self) {
+                TextItem::Key(k) => k.1,
+                _ => return Err(Error::invalid_structure(format!("Expecting a key at position {position}"))),
+            };
+            match expected.get(matched) {
+                Some(want) if same_key(self, &key, want) => matched += 1,
+                Some(want) if policy != KeyOrder::Subsequence || expected[matched..].iter().any(|e| same_key(self, &key, e)) => {
+                    return Err(out_of_order(&key, position, want));
+                }
+                None if policy == KeyOrder::Exact => {
+                    return Err(Error::key_order(format!("Key `{key}` at position {position} is not expected")));
+                }
+                _ => {}
+            }
+            skip_value(self)?;
+            position += 1;
+        }
+        match expected.get(matched) {
+            Some(want) => Err(Error::key_order(format!("Key `{want}` is missing, the object has {position} keys"))),
+            None => Ok(()),
+        }
+    }
+
+    /// Based on cursor location, the string value is written to **sink** piece by piece, so even a huge string is never
+    /// held in memory. **decode** determines what is written, check out StringSink doc. The result is the count of
+    /// written bytes and the cursor postcondition is the same as current_value()
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader, StringSink};
+    ///
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"blob": "aGVsbG8=", "name": "a\tb"}"#.to_string()), 0);
+    /// walker.next_key_by_name("blob").unwrap();
+    /// let mut out = Vec::new();
+    /// assert_eq!(walker.stream_string_value(&mut out, StringSink::Base64), Ok(5));
+    /// assert_eq!(out, b"hello");
+    /// ```
+    pub fn stream_string_value(&mut self, mut sink: impl io::Write, decode: StringSink) -> Result<u64, Error> {
+        self.walk_before_value();
+        let mut written = 0u64;
+        match decode {
+            StringSink::Raw => stream_string(self, &mut |s| {
+                sink.write_all(s.as_bytes())?;
+                written += s.len() as u64;
+                Ok(())
+            })?,
+            StringSink::Base64 => {
+                let mut decoder = base64::Decoder::new();
+                let mut out = Vec::with_capacity(8192);
+                stream_string(self, &mut |s| {
+                    decoder.update(s.as_bytes(), &mut out)?;
+                    sink.write_all(&out)?;
+                    written += out.len() as u64;
+                    out.clear();
+                    Ok(())
+                })?;
+                decoder.finish(&mut out)?;
+                sink.write_all(&out)?;
+                written += out.len() as u64;
+            }
+        }
+        sink.flush()?;
+        Ok(written)
+    }
+
+    /// move n item including key, value or other none white space char such as "{", "[", "}", "]", ":" or ","
+    pub fn move_n_element_forward(&mut self, n: usize) {
+        for _ in 0..n {
+            walk_forward(self);
+        }
+    }
+}
+
+/// Keys and values returned by JsonWalker::entries()
+pub struct Entries<'a, 'r, R = Box<dyn Iterator<Item=u8> + 'r>> {
+    walker: &'a mut Parser<'r, R>,
+    failed: bool,
+}
+
+impl<R: Iterator<Item=u8>> Iterator for Entries<'_, '_, R> {
+    type Item = Result<Entry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.walker.next_entry() {
+            Err(e) if e.kind == ErrorKind::EOS => None,
+            r => {
+                self.failed = r.is_err();
+                Some(r)
+            }
+        }
+    }
+}
+
+/// Elements of an array, returned by JsonWalker::elements(). It is a lending iterator: the walker of an element is
+/// borrowed from it till the next call of next_element()
+pub struct ElementsIter<'a, 'r, R = Box<dyn Iterator<Item=u8> + 'r>> {
+    walker: &'a mut Parser<'r, R>,
+    /// stack size in the array, None till the array is entered
+    stack_size: Option<usize>,
+    /// index of the current element in the array
+    index: usize,
+    finished: bool,
+}
+
+impl<'r, R: Iterator<Item=u8>> ElementsIter<'_, 'r, R> {
+    /// Move the cursor before the next element and return its index in the array, along with the walker to read it by
+    /// current_value_content(), current_value(), skip_current_value() or any other function. Whatever the body leaves
+    /// from the element is skipped by the fast path, so it may read the element partly, or not at all. After the
+    /// closing bracket, the result is None and the cursor stands right after the array. If the cursor is in the array
+    /// already, elements are counted from there. If the value in front of the cursor is not an array, and the cursor is not in one, WrongDataType error is returned
+    pub fn next_element(&mut self) -> Result<Option<(usize, &mut Parser<'r, R>)>, Error> {
+        if self.finished {
+            return Ok(None);
+        }
+        let walker = &mut *self.walker;
+        match self.stack_size {
+            Some(stack_size) => {
+                // the body has walked out of the array
+                if walker.stack.len() < stack_size {
+                    self.finished = true;
+                    return Ok(None);
+                }
+                end_value(walker, stack_size);
+                if !matches!(walker.next_byte, b',' | b']' | NIL) {
+                    skip_element(walker);
+                }
+                self.index += 1;
+            }
+            // between elements of an array, no value is in front of the cursor
+            None if walker.stack.last().is_some_and(|s| s.symbol == '[') && matches!(walker.next_byte, b',' | b']') => {
+                self.stack_size = Some(walker.stack.len());
+            }
+            None => {
+                walker.walk_before_value();
+                if walker.next_byte == b'[' {
+                    walk_forward(walker);
+                } else if walker.stack.last().is_none_or(|s| s.symbol != '[') {
+                    self.finished = true;
+                    return Err(Error::wrong_data_type(format!("Expecting an array.{}", get_current_status(walker))));
+                }
+                self.stack_size = Some(walker.stack.len());
+            }
+        }
+        loop {
+            check_deadline(walker)?;
+            if walker.next_byte == b',' {
+                walk_forward(walker);
+            }
+            match walker.next_byte {
+                NIL => {
+                    self.finished = true;
+                    return Err(end_of_stream(walker));
+                }
+                b']' => {
+                    walk_forward(walker);
+                    self.finished = true;
+                    return Ok(None);
+                }
+                _ => {}
+            }
+            if is_next_element_hidden(walker) {
+                skip_element(walker);
+                self.index += 1;
+                continue;
+            }
+            return Ok(Some((self.index, walker)));
+        }
+    }
+}
+
+/// Members of an object, returned by JsonWalker::members(). It is a lending iterator: the walker of a value is borrowed
+/// from it till the next call of next_member()
+pub struct MembersIter<'a, 'r, R = Box<dyn Iterator<Item=u8> + 'r>> {
+    walker: &'a mut Parser<'r, R>,
+    /// stack size in the object, None till the object is entered
+    stack_size: Option<usize>,
+    finished: bool,
+}
+
+impl<'r, R: Iterator<Item=u8>> MembersIter<'_, 'r, R> {
+    /// Move the cursor after the next key and return the key, along with the walker to read its value by
+    /// current_value_content(), current_value(), skip_current_value() or any other function. Whatever the body leaves
+    /// from the value is skipped by the fast path, so it may read the value partly, or not at all. After the closing
+    /// bracket, the result is None and the cursor stands right after the object. If the value in front of the cursor
+    /// is not an object, and the cursor is not in one, WrongDataType error is returned
+    pub fn next_member(&mut self) -> Result<Option<(String, &mut Parser<'r, R>)>, Error> {
+        if self.finished {
+            return Ok(None);
+        }
+        let walker = &mut *self.walker;
+        let stack_size = match self.stack_size {
+            Some(stack_size) => {
+                // the body has walked out of the object
+                if walker.stack.len() < stack_size {
+                    self.finished = true;
+                    return Ok(None);
+                }
+                end_value(walker, stack_size);
+                if walker.next_byte == b':' {
+                    skip_value(walker)?;
+                }
+                stack_size
+            }
+            // before a key, or right after a value of the object
+            None if walker.stack.last().is_some_and(|s| s.symbol == '{') && walker.next_byte != b':' => walker.stack.len(),
+            None => {
+                begin_value(walker);
+                if walker.next_byte != b'{' {
+                    self.finished = true;
+                    return Err(Error::wrong_data_type(format!("Expecting an object.{}", get_current_status(walker))));
+                }
+                walk_forward(walker);
+                walker.stack.len()
+            }
+        };
+        self.stack_size = Some(stack_size);
+        while walker.stack.len() >= stack_size {
+            check_deadline(walker)?;
+            match walker.next_byte {
+                NIL => {
+                    self.finished = true;
+                    return Err(end_of_stream(walker));
+                }
+                b'}' if walker.stack.len() == stack_size => {
+                    walk_forward(walker);
+                    break;
+                }
+                _ => {}
+            }
+            // a hidden member is skipped by the step
+            if let TextItem::Key(t) = walk_forward(walker) {
+                return Ok(Some((t.1, walker)));
+            }
+        }
+        self.finished = true;
+        Ok(None)
+    }
+}
+
+/// Values matched by JsonWalker::query(), in the order they appear in json. A value which is larger than
+/// set_max_content_bytes() is an error, then the iteration goes on with the next matches
+pub struct QueryResults<'a, 'r, R = Box<dyn Iterator<Item=u8> + 'r>> {
+    walker: &'a mut Parser<'r, R>,
+    matcher: PathMatcher,
+    before_value: bool,
+}
+
+impl<R: Iterator<Item=u8>> Iterator for QueryResults<'_, '_, R> {
+    type Item = Result<Content, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.before_value && self.walker.next_byte != NIL && self.matcher.matches(&get_path(self.walker)) {
+                self.before_value = false;
+                let top_index = get_stack_top_index(self.walker);
+                return Some(extract_current_value(self.walker, top_index));
+            }
+            if self.walker.next_byte == NIL {
+                return None;
+            }
+            self.before_value = match walk_forward(self.walker) {
+                TextItem::None(b':') => true,
+                TextItem::None(b'[') | TextItem::None(b',') => self.walker.stack.last().is_some_and(|s| s.symbol == '[') && self.walker.next_byte != b']',
+                _ => false,
+            };
+        }
+    }
+}
+
+/// Budget of a search such as next_key_by_name_with(). Both limits are off by default
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchOptions {
+    /// count of keys and values which may be passed over
+    pub max_items: Option<u64>,
+    /// count of bytes which may be read
+    pub max_bytes: Option<u64>,
+}
+
+impl SearchOptions {
+    pub fn with_max_items(mut self, max_items: u64) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+}
+
+/// Which keys next_key_matching() looks for. Chars are compared one by one, and case-insensitively after
+/// with_ignore_case(true), so nothing is allocated for a comparison
+/// ### Example
+/// ```
+/// use json_walker::json_walker::KeyPattern;
+///
+/// assert!(KeyPattern::prefix("user_").matches("user_name"));
+/// assert!(KeyPattern::glob("item-?-*").matches("item-7-id"));
+/// assert!(KeyPattern::exact("ID").with_ignore_case(true).matches("id"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyPattern {
+    kind: KeyPatternKind,
+    text: String,
+    ignore_case: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum KeyPatternKind {
+    Exact,
+    Prefix,
+    Suffix,
+    Contains,
+    Glob,
+}
+
+impl KeyPattern {
+    pub fn exact(text: &str) -> Self {
+        Self::new(KeyPatternKind::Exact, text)
+    }
+
+    pub fn prefix(text: &str) -> Self {
+        Self::new(KeyPatternKind::Prefix, text)
+    }
+
+    pub fn suffix(text: &str) -> Self {
+        Self::new(KeyPatternKind::Suffix, text)
+    }
+
+    pub fn contains(text: &str) -> Self {
+        Self::new(KeyPatternKind::Contains, text)
+    }
+
+    /// `*` matches any run of chars, even an empty one, and `?` matches exactly one char. Other chars match themselves
+    pub fn glob(pattern: &str) -> Self {
+        Self::new(KeyPatternKind::Glob, pattern)
+    }
+
+    fn new(kind: KeyPatternKind, text: &str) -> Self {
+        KeyPattern { kind, text: text.to_string(), ignore_case: false }
+    }
+
+    pub fn with_ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    pub fn matches(&self, key: &str) -> bool {
+        let strip = |key| strip_text(key, &self.text, self.ignore_case);
+        match self.kind {
+            KeyPatternKind::Exact => strip(key).is_some_and(str::is_empty),
+            KeyPatternKind::Prefix => strip(key).is_some(),
+            KeyPatternKind::Suffix => tails(key).any(|tail| strip(tail).is_some_and(str::is_empty)),
+            KeyPatternKind::Contains => tails(key).any(|tail| strip(tail).is_some()),
+            KeyPatternKind::Glob => glob(key, &self.text, self.ignore_case),
+        }
+    }
+}
+
+/// **key** and every tail of it, from the longest to the empty one
+fn tails(key: &str) -> impl Iterator<Item=&str> {
+    key.char_indices().map(|(i, _)| &key[i..]).chain(std::iter::once(""))
+}
+
+fn same_char(a: char, b: char, ignore_case: bool) -> bool {
+    a == b || ignore_case && a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// the rest of **key** if it starts with **text**
+fn strip_text<'k>(key: &'k str, text: &str, ignore_case: bool) -> Option<&'k str> {
+    let mut chars = key.chars();
+    for t in text.chars() {
+        if !same_char(chars.next()?, t, ignore_case) {
+            return None;
+        }
+    }
+    Some(chars.as_str())
+}
+
+/// match **key** with a glob **pattern**. On a mismatch, the latest `*` takes one more char and matching goes on from there
+fn glob(mut key: &str, mut pattern: &str, ignore_case: bool) -> bool {
+    // the pattern after the latest "*" and the key which is left for it
+    let mut star: Option<(&str, &str)> = None;
+    loop {
+        let mut p = pattern.chars();
+        let mut k = key.chars();
+        match (p.next(), k.next()) {
+            (Some('*'), _) => {
+                pattern = p.as_str();
+                star = Some((pattern, key));
+                continue;
+            }
+            (None, None) => return true,
+            (Some(pc), Some(kc)) if pc == '?' || same_char(kc, pc, ignore_case) => {
+                pattern = p.as_str();
+                key = k.as_str();
+                continue;
+            }
+            _ => {}
+        }
+        let Some((star_pattern, star_key)) = star else {
+            return false;
+        };
+        let mut k = star_key.chars();
+        if k.next().is_none() {
+            return false;
+        }
+        pattern = star_pattern;
+        key = k.as_str();
+        star = Some((pattern, key));
+    }
+}
+
+/// What stream_string_value() writes
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum StringSink {
+    /// the text of the string with escape sequences decoded
+    Raw,
+    /// the bytes which the string holds in base64. Invalid base64 is reported by its byte offset in the string
+    Base64,
+}
+
+/// The container which JsonWalker::enter() steps into
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ContainerKind {
+    Object,
+    Array,
+}
+
+/// Where to look for a key
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum KeyScope {
+    /// the key may belong to the current object, its children or any node after that, like next_key_by_name()
+    Global,
+    /// only keys of the object which holds the cursor are checked
+    CurrentObject,
+}
+
+/// How JsonWalker::assert_key_order() compares keys of an object with the expected ones
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum KeyOrder {
+    /// the object has exactly the expected keys in the same order
+    Exact,
+    /// the object starts with the expected keys in the same order, then any key may come
+    Prefix,
+    /// the expected keys appear in the same order, while other keys may come between them
+    Subsequence,
+}
+
+/// Where JsonWalker::for_each_element() stopped, so resume_elements() can go on from there
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ElementsState {
+    /// depth of the array, to check the cursor is still in it
+    stack_size: usize,
+    /// count of elements which are passed to the callback so far
+    pub processed: usize,
+    /// true if the array is closed and the cursor stands right after it
+    pub finished: bool,
+}
+
+pub struct CurrentState<'a> {
+    /// **latest_key** is the latest key seen in the current position
+    pub latest_key: &'a str,
+
+    /// **nth_occurrence** is the index of the current item (key or value) in json
+    pub nth_occurrence: usize,
+
+    /// **level: f32** is the level of the current position. Please check out next_item_by_level() docs
+    pub level: f32,
+
+    /// **current_item** is the current scanned which is Item(ValueType, value as string)
+    pub current_item: &'a Item,
+
+    /// **is_key: bool**, determines if the current item is a key or value
+    pub is_key: bool,
+}
+
+#[cfg(test)]
+pub(crate) mod walker_tests {
+    use std::cell::Cell;
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+
+    use crate::{Error, ErrorKind, NIL, Position};
+    use crate::json_walker::{ContainerKind, CurrentState, JsonEvent, JsonWalker, KeyOrder, KeyPattern, SearchOptions, StringSink};
+    use crate::parser_core::{Content, DuplicatePolicy, Entry, Stats, TextItem, ValueType, walk_forward};
+    use crate::path_matcher::PathMatcher;
+    use crate::readers::{DetectingReader, FileReader, ReadSignal, SignalReader, SliceReader, StringReader};
+    use crate::tools::write_content;
+    use crate::writer::JsonWriter;
+
+    pub(crate) const CORRECT_JSON: &str = r#" {"key1":null,"key2":true,"key3":false,"key4":111,"key5":111.111,"key6":"str1 \":{}[],","key7":{  "key71" : null ,  "key72" : true ,  "key73" : false ,  "key74" : 222 ,  "key75" : 222.222 ,  "key76" : "str2 \":{}[]," ,  "key78" : [    null ,    true ,    false ,    333 ,    333.333 ,    "str3 \":{}[]," ,    {  } ,    [  ]  ] ,  "key79" : {} ,  "key710": [  ] } , "key8" : [  null ,  true ,  false ,  444 ,  444.444 ,  "str4 \":{}[]," ,  {    "key81" : null ,    "key82" : true ,    "key83" : false ,    "key84" : 555 ,
+      "key85" : 555.555 ,
+      "key86" : "str5 \":{}[]," ,    "key89" : {} ,    "key810" : [ ]  } ,  { } ,  [ ]  ] , "key9" : { } , "key10" : [ ]
+} "#;
+
+    #[test]
+    fn test_next_item() {
+        // keys and values must be retrieved in order
+        let words = [
+            "key1",
+            "null",
+            "key2",
+            "true",
+            "key3",
+            "false",
+            "key4",
+            "111",
+            "key5",
+            "111.111",
+            "key6",
+            "str1 \":{}[],",
+            "key7",
+            "key71",
+            "null",
+            "key72",
+            "true",
+            "key73",
+            "false",
+            "key74",
+            "222",
+            "key75",
+            "222.222",
+            "key76",
+            "str2 \":{}[],",
+            "key78",
+            "null",
+            "true",
+            "false",
+            "333",
+            "333.333",
+            "str3 \":{}[],",
+            "key79",
+            "key710",
+            "key8",
+            "null",
+            "true",
+            "false",
+            "444",
+            "444.444",
+            "str4 \":{}[],",
+            "key81",
+            "null",
+            "key82",
+            "true",
+            "key83",
+            "false",
+            "key84",
+            "555",
+            "key85",
+            "555.555",
+            "key86",
+            "str5 \":{}[],",
+            "key89",
+            "key810",
+            "key9",
+            "key10",
+        ];
         let mut word_index = 0;
         let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 50);
         loop {
             match walker.next_item() {
-                Err(_) => {
-                    break;
+                Err(_) => {
+                    break;
+                }
+                Ok(t) => {
+                    assert!(t.1.eq(words[word_index]));
+                    word_index += 1;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_next_entry() {
+        // the classification must be the same as walk_forward()
+        let mut parser = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        let mut expected = vec![];
+        while parser.next_byte != NIL {
+            match walk_forward(&mut parser) {
+                TextItem::Key(t) => expected.push(Entry::Key(t)),
+                TextItem::Value(t) => expected.push(Entry::Value(t)),
+                TextItem::None(_) => {}
+            }
+        }
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 50);
+        let mut entries = vec![];
+        while let Ok(e) = walker.next_entry() {
+            entries.push(e);
+        }
+        assert_eq!(entries, expected);
+        assert_eq!(entries.iter().filter(|e| e.is_key()).count(), 27);
+        assert_eq!(entries[12], Entry::Key((ValueType::Str, "key7".to_string())));
+        // the first value after a nested container which closes
+        let i = entries.iter().position(|e| e.item().1 == "key79").unwrap();
+        assert!(entries[i].is_key() && entries[i - 1] == Entry::Value((ValueType::Str, "str3 \":{}[],".to_string())));
+
+        let walker = &mut JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        let collected: Vec<Entry> = walker.entries().collect::<Result<_, _>>().unwrap();
+        assert_eq!(collected, expected);
+        assert_eq!(walker.next_entry().unwrap_err().kind, ErrorKind::EOS);
+        assert!(walker.entries().next().is_none());
+    }
+
+    #[test]
+    fn test_recent_piece_before_buffer_fills() {
+        // the piece of json in an error message, without colors
+        let snippet = |e: Error| e.msg.split("\t\t").nth(1).unwrap().replace("\x1b[91m", "").replace("\x1b[32m", "").replace("\x1b[0m", "");
+        let expecting_string = |json: &str, n: usize| {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 11);
+            walker.move_n_element_forward(n);
+            let e = walker.stream_string_value(Vec::new(), StringSink::Raw).unwrap_err();
+            (snippet(e), walker.stats().bytes_consumed as usize)
+        };
+
+        // the error is at byte 3, so the piece is short
+        assert_eq!(expecting_string("  [1]", 0), ("  [...".to_string(), 3));
+        let mut walker = JsonWalker::new(StringReader::new("[1]".to_string()), 11);
+        assert_eq!(walker.get_recent_piece(), "[");
+        walker.next_item().unwrap();
+        assert_eq!(walker.get_recent_piece(), "[1]");
+
+        // exactly mem_size bytes
+        let json = format!("{}[1]", " ".repeat(10));
+        assert_eq!(expecting_string(&json, 0), (format!("{}[...", " ".repeat(10)), 11));
+
+        // after wrap around, the last mem_size bytes in order
+        let json = "[1, 2, 3, 4, 5, 6, 7, 8]";
+        let (piece, consumed) = expecting_string(json, 12);
+        assert_eq!(piece, format!("{}...", &json[consumed - 11..consumed]));
+        assert_eq!(piece, " 4, 5, 6, 7...");
+    }
+
+    #[test]
+    fn test_recent_piece_window_and_marker() {
+        // at the very start, only the first byte is read
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1, 2, 3], "b": "xyz"}"#.to_string()), 10);
+        assert_eq!(walker.get_recent_piece_marked("|"), "|{");
+        assert_eq!(walker.get_recent_piece_window(5), "{");
+        assert_eq!(walker.get_recent_piece(), "{");
+
+        // mid-document, before the buffer wraps
+        walker.next_key().unwrap();
+        assert_eq!(walker.get_recent_piece_marked("|"), r#"{"a"|:"#);
+        assert_eq!(walker.get_recent_piece_window(3), r#"a":"#);
+        assert_eq!(walker.get_recent_piece_window(100), r#"{"a":"#);
+
+        // after wrap around, the window is bound by mem_size
+        walker.next_key().unwrap();
+        assert_eq!(walker.get_recent_piece_marked("<>"), r#", 3], "b"<>:"#);
+        assert_eq!(walker.get_recent_piece_window(4), r#""b":"#);
+        assert_eq!(walker.get_recent_piece_window(100), walker.get_recent_piece());
+
+        // the end of json
+        while walker.next_item().is_ok() {}
+        assert_eq!(walker.get_recent_piece_marked("|"), r#"b": "xyz"}|"#);
+
+        // without memory
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1}"#.to_string()), 0);
+        walker.next_key().unwrap();
+        assert_eq!(walker.get_recent_piece_marked("|"), "|");
+        assert_eq!(walker.get_recent_piece_window(5), "");
+    }
+
+    #[test]
+    fn test_assert_key_order() {
+        let check = |expected: &[&str], policy: KeyOrder| -> Result<(), Error> {
+            let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+            let result = walker.assert_key_order("/key7", expected, policy);
+            // the rest of the object is skipped anyway
+            assert_eq!(walker.next_key().unwrap().1, "key8");
+            assert_eq!(walker.stats().str_values, 0);
+            result
+        };
+        let msg = |expected: &[&str], policy: KeyOrder| check(expected, policy).unwrap_err().msg;
+        let all = ["key71", "key72", "key73", "key74", "key75", "key76", "key78", "key79", "key710"];
+
+        assert_eq!(check(&all, KeyOrder::Exact), Ok(()));
+        assert_eq!(msg(&["key71", "key73"], KeyOrder::Exact), "Key `key72` at position 1 is out of order, expecting `key73`");
+        assert_eq!(msg(&all[..8], KeyOrder::Exact), "Key `key710` at position 8 is not expected");
+        assert_eq!(msg(&[&all[..], &["key711"]].concat(), KeyOrder::Exact), "Key `key711` is missing, the object has 9 keys");
+
+        assert_eq!(check(&all[..3], KeyOrder::Prefix), Ok(()));
+        assert_eq!(check(&[], KeyOrder::Prefix), Ok(()));
+        assert_eq!(msg(&["key71", "key72", "key74"], KeyOrder::Prefix), "Key `key73` at position 2 is out of order, expecting `key74`");
+
+        assert_eq!(check(&["key72", "key76", "key710"], KeyOrder::Subsequence), Ok(()));
+        assert_eq!(msg(&["key72", "key710", "key76"], KeyOrder::Subsequence), "Key `key76` at position 5 is out of order, expecting `key710`");
+        assert_eq!(msg(&["key72", "key0"], KeyOrder::Subsequence), "Key `key0` is missing, the object has 9 keys");
+        assert_eq!(check(&["key72", "key0"], KeyOrder::Subsequence).unwrap_err().kind, ErrorKind::KeyOrder);
+
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.next_key_by_name("key8").unwrap();
+        assert_eq!(walker.assert_key_order("", &[], KeyOrder::Prefix).unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(walker.assert_key_order("/6", &["key81", "key82"], KeyOrder::Prefix), Ok(()));
+        assert_eq!(walker.next_item().unwrap().1, "key9");
+    }
+
+    struct Scripted(std::collections::VecDeque<ReadSignal>);
+
+    impl SignalReader for Scripted {
+        fn read_signal(&mut self) -> ReadSignal {
+            self.0.pop_front().unwrap_or(ReadSignal::Eof)
+        }
+    }
+
+    /// CORRECT_JSON with **pendings** Pending signals before each byte which **at** selects
+    fn scripted(at: impl Fn(usize) -> bool, pendings: usize) -> Box<Scripted> {
+        let mut script = std::collections::VecDeque::new();
+        for (i, b) in CORRECT_JSON.bytes().enumerate() {
+            if at(i) {
+                script.extend(std::iter::repeat_n(ReadSignal::Pending, pendings));
+            }
+            script.push_back(ReadSignal::Byte(b));
+        }
+        Box::new(Scripted(script))
+    }
+
+    #[test]
+    fn test_probe() {
+        let expected: Vec<Entry> = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0).entries().map(|e| e.unwrap()).collect();
+
+        // probes at every key leave the walk unchanged
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 30);
+        let mut entries = vec![];
+        while let Ok(entry) = walker.next_entry() {
+            if entry.is_key() {
+                let (stats, piece) = (walker.stats().clone(), walker.get_recent_piece());
+                assert_eq!(walker.has_key_in_current("absent", 1000), Ok(false));
+                walker.has_key_in_current(&entry.item().1, 1000).unwrap();
+                let after = walker.get_recent_piece();
+                assert_eq!((walker.stats(), after), (&stats, piece));
+            }
+            entries.push(entry);
+        }
+        assert_eq!(entries, expected);
+
+        let json = r#"{"id": 1, "data": {"items": [10, 20, {"x": null}]}, "error": {"code": 3}}"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.next_key().unwrap();
+        assert_eq!(walker.has_key_in_current("error", 1000), Ok(true));
+        assert_eq!(walker.has_key_in_current("id", 1000), Ok(false));
+        // the value of "error" is further than 20 bytes
+        assert_eq!(walker.has_key_in_current("error", 20).unwrap_err().kind, ErrorKind::LimitExceeded);
+        assert_eq!(walker.next_item(), Ok((ValueType::UInt, "1".to_string())));
+        assert_eq!(walker.next_key().unwrap().1, "data");
+
+        // pointers are relative to the value in front of the cursor
+        assert_eq!(walker.has_pointer("/items/2/x", 1000), Ok(true));
+        assert_eq!(walker.has_pointer("/items/3", 1000), Ok(false));
+        assert_eq!(walker.has_pointer("/items/0/x", 1000), Ok(false));
+        assert_eq!(walker.has_pointer("/items/2/x", 5).unwrap_err().kind, ErrorKind::LimitExceeded);
+        // a probe right after another one reads the replayed bytes
+        assert_eq!(walker.has_pointer("/items/1", 1000), Ok(true));
+        assert_eq!(walker.next_key().unwrap().1, "items");
+        assert_eq!(walker.probe(1000, |w| w.next_key_by_name("code")), Ok((ValueType::Str, "code".to_string())));
+        // a probe inside another one
+        let nested = walker.probe(1000, |w| {
+            assert_eq!(w.has_pointer("/2/x", 1000), Ok(true));
+            w.next_key_by_name("x")
+        });
+        assert_eq!(nested, Ok((ValueType::Str, "x".to_string())));
+        assert_eq!(walker.next_item(), Ok((ValueType::UInt, "10".to_string())));
+        assert_eq!(walker.current_value_content().unwrap(), Content::Simple((ValueType::UInt, "20".to_string())));
+        assert_eq!(walker.next_key_by_name("code").unwrap().1, "code");
+
+        // through poll()
+        let mut walker = JsonWalker::new_nonblocking(scripted(|i| i % 5 == 0, 1), 0);
+        let mut entries = vec![];
+        loop {
+            match walker.poll(|w| {
+                let has = w.probe(100, |w| w.next_item().map(|_| true))?;
+                assert!(has);
+                w.next_entry()
+            }) {
+                Ok(e) => entries.push(e),
+                Err(e) if e.kind == ErrorKind::WouldBlock => {}
+                Err(_) => break,
+            }
+        }
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn test_poll() {
+        let mut expected = vec![];
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        while let Ok(item) = walker.next_item() {
+            expected.push(item);
+        }
+
+        // inside keywords, numbers, strings and escape sequences
+        let awkward: Vec<usize> = ["ull", "rue", "alse", "11.1", "11,", r#"\":"#, "tr1", "ey7", r#"" :"#]
+            .iter()
+            .map(|p| CORRECT_JSON.find(p).unwrap())
+            .collect();
+        type At = Box<dyn Fn(usize) -> bool>;
+        let cases: [(At, usize); 3] = [
+            (Box::new(move |i| awkward.contains(&i)), 2),
+            (Box::new(|i| i % 7 == 3), 1),
+            (Box::new(|_| true), 1),
+        ];
+        for (at, pendings) in cases {
+            let mut walker = JsonWalker::new_nonblocking(scripted(at, pendings), 20);
+            let mut items = vec![];
+            let mut blocked = 0;
+            loop {
+                match walker.poll(|w| w.next_item()) {
+                    Ok(item) => items.push(item),
+                    Err(e) if e.kind == ErrorKind::WouldBlock => blocked += 1,
+                    Err(e) => {
+                        assert_eq!(e.kind, ErrorKind::EOS);
+                        break;
+                    }
+                }
+            }
+            assert_eq!(items, expected);
+            assert!(blocked > 0);
+            assert_eq!(walker.stats().bytes_consumed, CORRECT_JSON.len() as u64);
+        }
+
+        // a value is built from the bytes of several retries
+        let mut walker = JsonWalker::new_nonblocking(scripted(|_| true, 1), 0);
+        let key7 = loop {
+            match walker.poll(|w| w.next_key_by_name("key7").and_then(|_| w.current_value_content())) {
+                Err(e) if e.kind == ErrorKind::WouldBlock => continue,
+                r => break r.unwrap(),
+            }
+        };
+        let mut plain = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        plain.next_key_by_name("key7").unwrap();
+        assert_eq!(key7, plain.current_value_content().unwrap());
+    }
+
+    #[test]
+    fn test_bom() {
+        fn items(mut walker: JsonWalker) -> Vec<crate::json_walker::Item> {
+            let mut items = vec![];
+            while let Ok(item) = walker.next_item() {
+                items.push(item);
+            }
+            items
+        }
+
+        for json in [CORRECT_JSON, CORRECT_JSON.trim(), "[1, 2.5]", "7", r#""a""#] {
+            let expected = items(JsonWalker::new(StringReader::new(json.to_string()), 0));
+            let with_bom = format!("\u{feff}{json}");
+            assert_eq!(items(JsonWalker::new(StringReader::new(with_bom.clone()), 0)), expected);
+            assert_eq!(items(JsonWalker::new(StringReader::new(with_bom.clone()), 20)), expected);
+            assert_eq!(items(JsonWalker::new_fallible(Box::new(with_bom.clone().into_bytes().into_iter().map(Ok)), 0)), expected);
+
+            let mut walker = JsonWalker::new_push(0);
+            walker.feed(&with_bom.as_bytes()[..2]).unwrap();
+            assert_eq!(walker.next_item_nonblocking(), Ok(None));
+            walker.feed(&with_bom.as_bytes()[2..]).unwrap();
+            walker.finish_feed().unwrap();
+            let mut pushed = vec![];
+            while let Ok(Some(item)) = walker.next_item_nonblocking() {
+                pushed.push(item);
+            }
+            assert_eq!(pushed, expected);
+        }
+        let mut walker = JsonWalker::new(StringReader::new(format!("\u{feff}{CORRECT_JSON}")), 0);
+        walker.next_key_by_name("key8").unwrap();
+        let mut plain = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        plain.next_key_by_name("key8").unwrap();
+        // offsets count the BOM
+        assert_eq!(walker.stats().bytes_consumed, plain.stats().bytes_consumed + 3);
+
+        // json in UTF-16LE
+        let utf16: Vec<u8> = "\u{feff}{\"a\": 1}".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let mut walker = JsonWalker::new(Box::new(utf16.clone().into_iter()), 0);
+        let e = walker.next_item().unwrap_err();
+        assert_eq!(e.kind, ErrorKind::UnsupportedEncoding);
+        assert!(matches!(walker.next_item().unwrap_err().kind, ErrorKind::Poisoned { .. }));
+        let mut walker = JsonWalker::new(DetectingReader::new(Box::new(utf16.into_iter())).unwrap(), 0);
+        assert_eq!(walker.next_key().unwrap().1, "a");
+
+        let mut walker = JsonWalker::new(Box::new([0xEF, 0xBB, b'[', b']'].into_iter()), 0);
+        assert_eq!(walker.current_value_content().unwrap_err().kind, ErrorKind::UnsupportedEncoding);
+    }
+
+    #[test]
+    fn test_push() {
+        let mut expected = vec![];
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        while let Ok(item) = walker.next_item() {
+            expected.push(item);
+        }
+
+        for chunk in [1, 2, 7, 64] {
+            let mut walker = JsonWalker::new_push(20);
+            let mut items = vec![];
+            let mut waits = 0;
+            for bytes in CORRECT_JSON.as_bytes().chunks(chunk) {
+                walker.feed(bytes).unwrap();
+                while let Some(item) = walker.next_item_nonblocking().unwrap() {
+                    items.push(item);
+                }
+                waits += 1;
+            }
+            walker.finish_feed().unwrap();
+            loop {
+                match walker.next_item_nonblocking() {
+                    Ok(Some(item)) => items.push(item),
+                    Ok(None) => panic!("the feed is finished"),
+                    Err(e) => {
+                        assert_eq!(e.kind, ErrorKind::EOS);
+                        break;
+                    }
+                }
+            }
+            assert_eq!(items, expected);
+            assert_eq!(waits, CORRECT_JSON.len().div_ceil(chunk));
+            assert_eq!(walker.stats().bytes_consumed, CORRECT_JSON.len() as u64);
+        }
+
+        // suspended in the middle of an escape sequence, a keyword and a number at the root
+        let mut walker = JsonWalker::new_push(0);
+        assert_eq!(walker.next_item_nonblocking(), Ok(None));
+        walker.feed(br#"["a\"#).unwrap();
+        assert_eq!(walker.next_item_nonblocking(), Ok(None));
+        walker.feed(br#"u00e9", tr"#).unwrap();
+        assert_eq!(walker.next_item_nonblocking(), Ok(Some((ValueType::Str, "a\u{e9}".to_string()))));
+        assert_eq!(walker.next_item_nonblocking(), Ok(None));
+        walker.feed(b"ue]").unwrap();
+        assert_eq!(walker.next_item_nonblocking(), Ok(Some((ValueType::Bool, "true".to_string()))));
+
+        let mut walker = JsonWalker::new_push(0);
+        walker.feed(b" -12").unwrap();
+        assert_eq!(walker.next_item_nonblocking(), Ok(None));
+        walker.feed(b".5").unwrap();
+        walker.finish_feed().unwrap();
+        assert_eq!(walker.next_item_nonblocking(), Ok(Some((ValueType::Float, "-12.5".to_string()))));
+
+        // only a parser of new_push() is fed, till finish_feed()
+        let mut walker = JsonWalker::new(StringReader::new("[]".to_string()), 0);
+        assert_eq!(walker.feed(b"1").unwrap_err().kind, ErrorKind::Io(io::ErrorKind::InvalidInput));
+        assert_eq!(walker.finish_feed().unwrap_err().kind, ErrorKind::Io(io::ErrorKind::InvalidInput));
+        let mut walker = JsonWalker::new_push(0);
+        walker.finish_feed().unwrap();
+        assert_eq!(walker.feed(b"1").unwrap_err().kind, ErrorKind::Io(io::ErrorKind::InvalidInput));
+    }
+
+    #[test]
+    fn test_push_resumes_token() {
+        // a long string fed byte by byte is scanned once, rather than walked again for every byte
+        let json = format!(r#"["{}", 1]"#, "ab".repeat(100_000));
+        let mut walker = JsonWalker::new_push(0);
+        let mut items = vec![];
+        for b in json.as_bytes() {
+            walker.feed(&[*b]).unwrap();
+            while let Some(item) = walker.next_item_nonblocking().unwrap() {
+                items.push(item.1.len());
+            }
+        }
+        walker.finish_feed().unwrap();
+        assert_eq!(walker.next_item_nonblocking().unwrap_err().kind, ErrorKind::EOS);
+        assert_eq!(items, vec![200_000, 1]);
+
+        // so is a value which is read ahead by prefetch_value()
+        let json = format!(r#"{{"a": [{}0], "b": true}}"#, "1, ".repeat(50_000));
+        let mut walker = JsonWalker::new_push(0);
+        walker.feed(&json.as_bytes()[..5]).unwrap();
+        assert_eq!(walker.poll(|w| w.next_key()).unwrap().1, "a");
+        let mut fed = 5;
+        while walker.poll(|w| w.prefetch_value()).is_err() {
+            walker.feed(&json.as_bytes()[fed..fed + 1]).unwrap();
+            fed += 1;
+        }
+        // the value and the byte after it
+        assert!(json[..fed].ends_with("0],"));
+        walker.feed(&json.as_bytes()[fed..]).unwrap();
+        assert_eq!(walker.poll(|w| w.skip_current_value()), Ok(ValueType::Arr));
+        assert_eq!(walker.poll(|w| w.next_item()).unwrap().1, "b");
+    }
+
+    #[test]
+    fn test_next_token() {
+        let json = "{\"a\" : [1, -2.5e3,\n  \"x\\n\u{e9}\"], \"b\":{\"c\":null}, \"d\": [true, false]} ";
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        let mut tokens = vec![];
+        while let Ok(token) = walker.next_token() {
+            tokens.push((token.kind, &json[token.span.start as usize..token.span.end as usize], token.text));
+        }
+        use crate::json_walker::TokenKind as K;
+        let s = |t: &str| Some(t.to_string());
+        assert_eq!(tokens, vec![
+            (K::LBrace, "{", None), (K::String, "\"a\"", s("a")), (K::Colon, ":", None), (K::LBracket, "[", None), (K::Number, "1", s("1")),
+            (K::Comma, ",", None), (K::Number, "-2.5e3", s("-2.5e3")), (K::Comma, ",", None), (K::String, "\"x\\n\u{e9}\"", s("x\n\u{e9}")),
+            (K::RBracket, "]", None), (K::Comma, ",", None), (K::String, "\"b\"", s("b")), (K::Colon, ":", None), (K::LBrace, "{", None),
+            (K::String, "\"c\"", s("c")), (K::Colon, ":", None), (K::Null, "null", None), (K::RBrace, "}", None), (K::Comma, ",", None),
+            (K::String, "\"d\"", s("d")), (K::Colon, ":", None), (K::LBracket, "[", None), (K::True, "true", None), (K::Comma, ",", None),
+            (K::False, "false", None), (K::RBracket, "]", None), (K::RBrace, "}", None),
+        ]);
+        assert_eq!(walker.next_token(), Err(Error::new_eos()));
+
+        // tokens and items share the cursor and the filter applies only to items
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_filter(None, Some(PathMatcher::new("/b").unwrap()));
+        assert_eq!(walker.next_token().unwrap().kind, K::LBrace);
+        assert_eq!(walker.next_item(), Ok((ValueType::Str, "a".to_string())));
+        assert_eq!(walker.next_token().unwrap().kind, K::Colon);
+        assert_eq!(walker.next_token().unwrap().kind, K::LBracket);
+        assert_eq!(walker.next_item(), Ok((ValueType::UInt, "1".to_string())));
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "d".to_string())));
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_filter(None, Some(PathMatcher::new("/b").unwrap()));
+        assert_eq!(std::iter::from_fn(|| walker.next_token().ok()).count(), tokens.len());
+    }
+
+    #[test]
+    fn test_next_event() {
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        let mut events = vec![];
+        while let Some(event) = walker.next_event().unwrap() {
+            events.push(event);
+        }
+        // every start has its matching end
+        let mut open = vec![];
+        for event in &events {
+            match event {
+                JsonEvent::StartObject => open.push(JsonEvent::EndObject),
+                JsonEvent::StartArray => open.push(JsonEvent::EndArray),
+                JsonEvent::EndObject | JsonEvent::EndArray => assert_eq!(open.pop().as_ref(), Some(event)),
+                _ => {}
+            }
+        }
+        assert!(open.is_empty());
+        let count = |f: fn(&JsonEvent) -> bool| events.iter().filter(|e| f(e)).count();
+        assert_eq!(count(|e| matches!(e, JsonEvent::StartObject)), 8);
+        assert_eq!(count(|e| matches!(e, JsonEvent::StartArray)), 7);
+        assert_eq!(count(|e| matches!(e, JsonEvent::Key(_))), 27);
+        assert_eq!(count(|e| matches!(e, JsonEvent::Value(_))), 30);
+        assert_eq!(walker.next_event(), Ok(None));
+
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1, {"b": null}, []], "c": "x"}"#.to_string()), 0);
+        let events: Vec<JsonEvent> = std::iter::from_fn(|| walker.next_event().unwrap()).collect();
+        let item = |value_type: ValueType, txt: &str| (value_type, txt.to_string());
+        assert_eq!(events, vec![
+            JsonEvent::StartObject,
+            JsonEvent::Key(item(ValueType::Str, "a")),
+            JsonEvent::StartArray,
+            JsonEvent::Value(item(ValueType::UInt, "1")),
+            JsonEvent::StartObject,
+            JsonEvent::Key(item(ValueType::Str, "b")),
+            JsonEvent::Value(item(ValueType::Null, "null")),
+            JsonEvent::EndObject,
+            JsonEvent::StartArray,
+            JsonEvent::EndArray,
+            JsonEvent::EndArray,
+            JsonEvent::Key(item(ValueType::Str, "c")),
+            JsonEvent::Value(item(ValueType::Str, "x")),
+            JsonEvent::EndObject,
+        ]);
+
+        let mut walker = JsonWalker::new(StringReader::new("7".to_string()), 0);
+        assert_eq!(walker.next_event(), Ok(Some(JsonEvent::Value(item(ValueType::UInt, "7")))));
+        assert_eq!(walker.next_event(), Ok(None));
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1"#.to_string()), 0);
+        let mut result = walker.next_event();
+        while let Ok(Some(_)) = result {
+            result = walker.next_event();
+        }
+        assert_eq!(result.unwrap_err().kind, ErrorKind::UnexpectedEos);
+    }
+
+    #[test]
+    fn test_scalar_root() {
+        let scalars = [
+            ("123", (ValueType::UInt, "123")),
+            ("0", (ValueType::UInt, "0")),
+            ("-7", (ValueType::Int, "-7")),
+            ("-1.5e3", (ValueType::Float, "-1.5e3")),
+            ("true", (ValueType::Bool, "true")),
+            ("false", (ValueType::Bool, "false")),
+            ("null", (ValueType::Null, "null")),
+            (r#""hello""#, (ValueType::Str, "hello")),
+        ];
+        for (scalar, (value_type, txt)) in scalars {
+            let item = (value_type, txt.to_string());
+            for json in [scalar.to_string(), format!(" {scalar}"), format!("{scalar}\n"), format!("\r\n\t{scalar}  ")] {
+                let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+                assert_eq!(walker.next_item(), Ok(item.clone()), "{json:?}");
+                assert_eq!(walker.next_item().unwrap_err().kind, ErrorKind::EOS, "{json:?}");
+                assert!(walker.finish().is_ok(), "{json:?}");
+
+                let mut walker = JsonWalker::with_reader(json.bytes(), 0);
+                assert_eq!(walker.current_value_content(), Ok(Content::Simple(item.clone())), "{json:?}");
+                assert!(walker.finish().is_ok(), "{json:?}");
+
+                let mut walker = JsonWalker::from_read(std::io::Cursor::new(json.clone().into_bytes()), 0);
+                assert_eq!(walker.skip_current_value(), Ok(value_type), "{json:?}");
+                assert!(walker.finish().is_ok(), "{json:?}");
+            }
+        }
+
+        // a scalar which is cut by the end of stream
+        let cases = [
+            ("tru", ErrorKind::UnexpectedEos, "Unexpected end of stream in `tru` at byte 3 (1:4)"),
+            ("-", ErrorKind::InvalidNumber, "Invalid number `-` at byte 1 (1:2)"),
+            ("1.", ErrorKind::InvalidNumber, "Invalid number `1.` at byte 2 (1:3)"),
+            ("1e", ErrorKind::InvalidNumber, "Invalid number `1e` at byte 2 (1:3)"),
+            ("\"ab", ErrorKind::UnexpectedEos, "Unexpected end of stream in a string at byte 3 (1:4)"),
+        ];
+        for (json, kind, msg) in cases {
+            let e = JsonWalker::new(StringReader::new(json.to_string()), 0).next_item().unwrap_err();
+            assert_eq!((e.kind, e.msg.as_str()), (kind, msg), "{json}");
+        }
+    }
+
+    #[test]
+    fn test_number_types() {
+        let json = "[0, -0, 18446744073709551615, 18446744073709551616, -9223372036854775808, -1, 1.5, -1e3, +7]";
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        let types: Vec<ValueType> = std::iter::from_fn(|| walker.next_item().ok().map(|i| i.0)).collect();
+        use ValueType::*;
+        assert_eq!(types, [UInt, Int, UInt, UInt, Int, Int, Float, Float, UInt]);
+        assert_eq!((walker.stats().values(UInt), walker.stats().values(Int)), (4, 3));
+    }
+
+    #[test]
+    fn test_allow_nan() {
+        let json = "[NaN, Infinity, -Infinity, {\"a\": NaN}]";
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_allow_nan(true);
+        let items: Vec<(ValueType, String)> = std::iter::from_fn(|| walker.next_item().ok()).collect();
+        let float = |s: &str| (ValueType::Float, s.to_string());
+        assert_eq!(items, [float("NaN"), float("Infinity"), float("-Infinity"), (ValueType::Str, "a".to_string()), float("NaN")]);
+
+        // texts are compared, so NaN equals NaN in Content
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_allow_nan(true);
+        let nan = Content::Simple(float("NaN"));
+        assert_eq!(walker.current_value_content().unwrap(), Content::Array(vec![
+            nan.clone(), Content::Simple(float("Infinity")), Content::Simple(float("-Infinity")), Content::Object([("a".to_string(), nan)].into()),
+        ]));
+
+        // the spelling of Python only
+        for json in ["[nan]", "[NAN]", "[infinity]", "[-inf]", "[+Infinity]", "[Infinit]", "[NaNa]", "[-Infinityx]"] {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            walker.set_allow_nan(true);
+            assert!(walker.current_value_content().is_err(), "{json}");
+        }
+        // it is off by default
+        for (json, kind) in [("[NaN]", ErrorKind::UnexpectedChar), ("[Infinity]", ErrorKind::UnexpectedChar), ("[-Infinity]", ErrorKind::InvalidNumber)] {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            assert_eq!(walker.next_item().unwrap_err().kind, kind, "{json}");
+        }
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_number_types_value() {
+        let mut walker = JsonWalker::new(StringReader::new("[18446744073709551615, -9223372036854775808, 9223372036854775808]".to_string()), 0);
+        let value = walker.to_value().unwrap();
+        assert_eq!(value[0].as_u64(), Some(u64::MAX));
+        assert_eq!(value[1].as_i64(), Some(i64::MIN));
+        assert_eq!(value[2].as_u64(), Some(i64::MAX as u64 + 1));
+    }
+
+    #[test]
+    fn test_escaped_strings() {
+        let json = r#"["line\nbreak", "\"\\\/\b\f\n\r\t", "\u0041", "\uD83D\uDE00", "\u00e9t\u00E9"]"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        let items: Vec<String> = std::iter::from_fn(|| walker.next_item().ok().map(|i| i.1)).collect();
+        assert_eq!(items, ["line\nbreak", "\"\\/\u{8}\u{c}\n\r\t", "A", "😀", "été"]);
+        // raw mode
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_keep_escapes(true);
+        let items: Vec<String> = std::iter::from_fn(|| walker.next_item().ok().map(|i| i.1)).collect();
+        assert_eq!(items, [r"line\nbreak", r#"\"\\\/\b\f\n\r\t"#, r"\u0041", r"\uD83D\uDE00", r"\u00e9t\u00E9"]);
+
+        let first_error = |json: &str| {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            loop {
+                if let Err(e) = walker.next_item() {
+                    return e;
+                }
+            }
+        };
+        let e = first_error(r#"[1, "a\qb"]"#);
+        assert_eq!(e.kind(), &ErrorKind::InvalidEscape);
+        assert_eq!(e.message(), r"Invalid escape sequence in `a\qb` at byte 4 (1:5)");
+        assert_eq!(first_error(r#"{"a": "\ud83d"}"#).kind(), &ErrorKind::InvalidEscape);
+        assert_eq!(first_error(r#"{"a": "\ude00\ud83d"}"#).kind(), &ErrorKind::InvalidEscape);
+        assert_eq!(first_error(r#"{"a": "\u12x4"}"#).kind(), &ErrorKind::InvalidEscape);
+        // they are not verified in raw mode
+        let mut walker = JsonWalker::new(StringReader::new(r#"["a\qb", "\ud83d"]"#.to_string()), 0);
+        walker.set_keep_escapes(true);
+        assert_eq!(walker.next_item().unwrap().1, r"a\qb");
+        assert_eq!(walker.next_item().unwrap().1, r"\ud83d");
+    }
+
+    #[test]
+    fn test_escaped_keys() {
+        let json = r#"{"caf\u00e9": {"\ud83d\ude00": [1, 2]}, "café": 3}"#;
+        for keep_escapes in [false, true] {
+            let walker = || {
+                let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+                walker.set_keep_escapes(keep_escapes);
+                walker
+            };
+            let mut w = walker();
+            assert!(w.next_key_by_name("café").is_ok());
+            assert!(w.next_key_by_name("\u{1f600}").is_ok());
+            assert!(PathMatcher::new("/café/\u{1f600}").unwrap().matches(&w.get_path()));
+            assert!(w.next_key_by_name("café").is_ok());
+            assert_eq!(w.current_value_content(), Ok(Content::Simple((ValueType::UInt, "3".to_string()))));
+            assert!(walker().take_sample("/café/\u{1f600}", 1).is_ok_and(|s| s.len() == 1));
+            assert!(walker().assert_key_order("", &["café", "café"], KeyOrder::Exact).is_ok());
+            // an escaped backslash is not an escape sequence
+            let mut w = JsonWalker::new(StringReader::new(r#"{"a\\u00e9": 1}"#.to_string()), 0);
+            w.set_keep_escapes(keep_escapes);
+            assert!(w.next_key_by_name("a\u{e9}").is_err());
+        }
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn test_normalize_keys() {
+        use crate::path_matcher::KeyNormalization;
+
+        // the composed and decomposed forms of "é"
+        let json = "{\"caf\u{e9}\": 1, \"cafe\u{301}\": 2}";
+        for (composed, normalize_keys) in [(true, None), (false, None), (true, Some(KeyNormalization::Nfc)), (false, Some(KeyNormalization::Nfc))] {
+            let name = if composed { "cafe\u{301}" } else { "caf\u{e9}" };
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            walker.set_normalize_keys(normalize_keys);
+            // the first key matches only when keys are normalized
+            let value = walker.next_key_by_name(name).and_then(|_| walker.current_value_content());
+            let expected = if normalize_keys.is_some() || !composed { "1" } else { "2" };
+            assert_eq!(value, Ok(Content::Simple((ValueType::UInt, expected.to_string()))), "{name} {normalize_keys:?}");
+
+            let m = PathMatcher::new(&format!("/{name}")).unwrap().with_normalize_keys(normalize_keys);
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            walker.next_key().unwrap();
+            assert_eq!(m.matches(&walker.get_path()), normalize_keys.is_some() || !composed);
+        }
+    }
+
+    #[test]
+    fn test_word_adjacency() {
+        let cases = [
+            ("[truefalse]", "true", "Unexpected `f` at byte 5 (1:6) after `true`"),
+            ("[123abc]", "123", "Unexpected `a` at byte 4 (1:5) after `123`"),
+            ("[1, null null]", "null", "Unexpected `n` at byte 9 (1:10) after `null`"),
+            (r#"{"a": nullnull}"#, "null", "Unexpected `n` at byte 10 (1:11) after `null`"),
+            (r#"{"a": 1.5x, "b": 2}"#, "1.5", "Unexpected `x` at byte 9 (1:10) after `1.5`"),
+            (r#"{"a": false "b": 2}"#, "false", "Unexpected `\"` at byte 12 (1:13) after `false`"),
+            ("truefalse", "true", "Unexpected `f` at byte 4 (1:5) after `true`"),
+            ("123abc", "123", "Unexpected `a` at byte 3 (1:4) after `123`"),
+            ("nullnull", "null", "Unexpected `n` at byte 4 (1:5) after `null`"),
+            ("1 ]", "1", "Unexpected `]` at byte 2 (1:3) after `1`"),
+            ("[1 2]", "1", "Unexpected `2` at byte 3 (1:4) after `1`"),
+            ("-7\t\u{1}", "-7", "Unexpected 0x01 at byte 3 (1:4) after `-7`"),
+        ];
+        for (json, word, msg) in cases {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            let mut last = None;
+            let e = loop {
+                match walker.next_item() {
+                    Ok(item) => last = Some(item.1),
+                    Err(e) => break e,
+                }
+            };
+            // the call which reads the word returns the error instead of it
+            assert_ne!(last.as_deref(), Some(word), "{json}");
+            assert_eq!((e.kind, e.msg.as_str()), (ErrorKind::InvalidToken, msg), "{json}");
+            assert!(matches!(walker.next_item().unwrap_err().kind, ErrorKind::Poisoned { .. }));
+        }
+
+        for json in ["[true, 1,2 ,-3.5e2]", r#"{"a":null}"#, " 12 ", "false"] {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            while walker.next_item().is_ok() {}
+            assert_eq!(walker.next_item(), Err(Error::new_eos()), "{json}");
+        }
+    }
+
+    #[test]
+    fn test_position() {
+        let json = "{\n  \"id\": 12,\r\n  \"tags\": [\"a\",\n    \"b\"],\n\n\t\"deep\": {\"x\": null}\r\n}";
+        let expected = [("id", (2, 7)), ("tags", (3, 9)), ("deep", (6, 8)), ("x", (6, 14))];
+        for mem_size in [0, 20] {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), mem_size);
+            assert_eq!(walker.position(), (1, 1));
+            for (key, position) in expected {
+                // the cursor stands on ":" after the key
+                assert_eq!(walker.next_key().unwrap().1, key);
+                assert_eq!(walker.position(), position, "{key}");
+            }
+            assert_eq!(walker.next_item().unwrap().1, "null");
+            assert_eq!(walker.position(), (6, 20));
+            walker.skip_current_value().ok();
+            assert!(walker.next_item().is_err());
+            assert_eq!(walker.position(), (7, 2));
+        }
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.next_key_by_name("tags").unwrap();
+        walker.has_key_in_current("deep", 1000).unwrap();
+        assert_eq!(walker.position(), (3, 9));
+
+        // in errors and panics
+        let e = JsonWalker::new(StringReader::new("[\n1, 2,\n  3x]".to_string()), 0).current_value_content().unwrap_err();
+        assert_eq!(e.msg, "Unexpected `x` at byte 11 (3:4) after `3`");
+        let mut walker = JsonWalker::new(StringReader::new("{\n \"a\": 1\n}".to_string()), 0);
+        walker.next_key().unwrap();
+        let e = walker.stream_string_value(Vec::new(), StringSink::Raw).unwrap_err();
+        assert!(e.msg.contains("position: 2:7"), "{}", e.msg);
+        let mut walker = JsonWalker::new(StringReader::new("[1,\n  2,\n  ]]".to_string()), 0);
+        while walker.next_item().is_ok() {}
+        let e = walker.current_value_content().unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::Poisoned { .. }));
+        assert_eq!(e.position(), Some(Position { byte_offset: 12, line: 3, column: 4 }));
+        // the bad byte ends a line, so the look-ahead new-line is not where it is
+        for (json, msg, position) in [
+            ("[x\n]", "Unexpected `x` at byte 1 (1:2), near `[x`", (1, 1, 2)),
+            ("[1,\n  tx\r\n]", "Expecting null, true or false but found `tx` at byte 7 (2:4), near `[1,\n  tx`", (7, 2, 4)),
+            ("{\n\"a\" 1\n}", "Unexpected `1` at byte 6 (2:5), near `{\n\"a\" 1`", (6, 2, 5)),
+        ] {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 16);
+            let e = loop {
+                if let Err(e) = walker.next_item() {
+                    break e;
+                }
+            };
+            assert_eq!(e.kind, ErrorKind::UnexpectedChar, "{json}");
+            assert_eq!(e.msg, msg);
+            let (byte_offset, line, column) = position;
+            assert_eq!(e.position(), Some(Position { byte_offset, line, column }), "{json}");
+        }
+    }
+
+    #[test]
+    fn test_malformed() {
+        let first_error = |json: &str| {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 16);
+            let e = loop {
+                if let Err(e) = walker.next_item() {
+                    break e;
+                }
+            };
+            assert!(walker.is_poisoned(), "{json}");
+            e
+        };
+        let e = first_error("[1,}");
+        assert_eq!(e.kind, ErrorKind::UnexpectedChar);
+        assert_eq!(e.msg, "Unexpected `}` at byte 3 (1:4), near `[1,}`");
+        let e = first_error("[1.2.3]");
+        assert_eq!(e.kind, ErrorKind::InvalidNumber);
+        assert_eq!(e.msg, "Invalid number `1.2.` at byte 4 (1:5), near `[1.2.`");
+        let e = first_error(r#"{"a": [1"#);
+        assert_eq!(e.kind, ErrorKind::UnexpectedEos);
+        assert!(e.msg.starts_with("Unexpected end of stream at byte 8"), "{}", e.msg);
+        assert_eq!(first_error(r#"["ab"#).kind, ErrorKind::UnexpectedEos);
+        assert_eq!(first_error("[nul]").kind, ErrorKind::UnexpectedChar);
+        assert_eq!(first_error("{} {}").kind, ErrorKind::TrailingCharacters);
+
+        // by other functions
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1, 2}}"#.to_string()), 0);
+        assert_eq!(walker.current_value_content().unwrap_err().kind, ErrorKind::UnexpectedChar);
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1, {"b" 2}]}"#.to_string()), 0);
+        walker.next_key().unwrap();
+        assert_eq!(walker.skip_current_value().unwrap_err().kind, ErrorKind::UnexpectedChar);
+    }
+
+    #[test]
+    fn test_error_accessors() {
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1, {"b": x}]}"#.to_string()), 0);
+        let e = loop {
+            if let Err(e) = walker.next_item() {
+                break e;
+            }
+        };
+        assert_eq!(e.kind(), &ErrorKind::UnexpectedChar);
+        assert_eq!(e.message(), "Unexpected `x` at byte 16 (1:17)");
+        assert_eq!(e.position(), Some(Position { byte_offset: 16, line: 1, column: 17 }));
+        assert_eq!(e.path(), Some("#/{a,0}/[a,1]/{b,0}/"));
+        assert_eq!(e.to_string(), "UnexpectedChar: Unexpected `x` at byte 16 (1:17), path: #/{a,0}/[a,1]/{b,0}/");
+        // Poisoned tells where the original error has happened
+        let poisoned = walker.next_item().unwrap_err();
+        assert!(matches!(poisoned.kind(), ErrorKind::Poisoned { original } if **original == e));
+        assert_eq!((poisoned.position(), poisoned.path()), (e.position(), e.path()));
+        assert!(poisoned.to_string().starts_with("Poisoned: The walker is stopped by an earlier error: Unexpected `x`"));
+
+        // errors which do not stop the walker have no position
+        let e = JsonWalker::new(StringReader::new("[1]".to_string()), 0).next_key_by_name("a").unwrap_err();
+        assert_eq!((e.position(), e.path()), (None, None));
+        assert_eq!(Error::key_not_found("gone"), Error::new(ErrorKind::KeyNotFound, "gone"));
+        assert_eq!(Error::new_eos().to_string(), "EOS: End of stream");
+        let boxed: Box<dyn std::error::Error> = Box::new(Error::invalid_number("1.2.3"));
+        assert_eq!(boxed.to_string(), "InvalidNumber: 1.2.3");
+    }
+
+    #[test]
+    fn test_recovery() {
+        let items = |walker: &mut JsonWalker| {
+            let mut items = vec![];
+            while let Ok(item) = walker.next_item() {
+                items.push(item.1);
+            }
+            items
+        };
+        let json = r#"[{"id": 1}, {"id": 2}, {"id": 3, "key":}, {"id": 4}, {"id": 5}]"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_recovery(true);
+        assert_eq!(items(&mut walker), ["id", "1", "id", "2", "id", "3", "key", "id", "4", "id", "5"]);
+        assert!(!walker.is_poisoned());
+        let errors = walker.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind(), &ErrorKind::UnexpectedChar);
+        assert_eq!(errors[0].position().unwrap().byte_offset, 39);
+        assert!(errors[0].is_recovered());
+        assert!(walker.take_errors().is_empty());
+        // it is off by default
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        assert_eq!(items(&mut walker).len(), 7);
+        assert!(walker.is_poisoned());
+
+        // brackets in strings and nested containers of the skipped part, and a number which is broken
+        let json = r#"{"a": [1, 2 x "]", {"b": [3]}, 4], "c": [1.2.3, {"d": "}"}], "e": true}"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_recovery(true);
+        assert_eq!(items(&mut walker), ["a", "1", "2", "c", "e", "true"]);
+        let kinds: Vec<ErrorKind> = walker.take_errors().into_iter().map(|e| e.kind().clone()).collect();
+        assert_eq!(kinds, [ErrorKind::UnexpectedChar, ErrorKind::InvalidNumber]);
+        let stats = walker.stats();
+        assert_eq!((stats.objects_opened, stats.objects_closed, stats.arrays_opened, stats.arrays_closed), (1, 1, 2, 2));
+
+        // the end of stream can not be skipped
+        let mut walker = JsonWalker::new(StringReader::new(r#"[{"a": x, "b": [1"#.to_string()), 0);
+        walker.set_recovery(true);
+        assert_eq!(items(&mut walker), ["a"]);
+        assert!(walker.take_errors().is_empty());
+        assert!(walker.is_poisoned());
+    }
+
+    #[test]
+    fn test_duplicate_key() {
+        let json = r#"{"a": 1, "b": {"a": 0}, "a": 2}"#;
+        let content = |policy: DuplicatePolicy| {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            walker.set_on_duplicate_key(policy);
+            walker.current_value_content()
+        };
+        let a = |content: Content| match content {
+            Content::Object(o) => o["a"].clone(),
+            _ => panic!(),
+        };
+        assert_eq!(a(content(DuplicatePolicy::Allow).unwrap()), Content::Simple((ValueType::UInt, "2".into())));
+        assert_eq!(a(content(DuplicatePolicy::LastWins).unwrap()), Content::Simple((ValueType::UInt, "2".into())));
+        assert_eq!(a(content(DuplicatePolicy::FirstWins).unwrap()), Content::Simple((ValueType::UInt, "1".into())));
+        let e = content(DuplicatePolicy::Error).unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::DuplicateKey);
+        assert_eq!(e.message(), "Duplicate key `a` at byte 24 (1:25)");
+
+        // the walk goes on until the repeated key, which keys of another object do not make
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_on_duplicate_key(DuplicatePolicy::Error);
+        let mut items = vec![];
+        while let Ok(item) = walker.next_item() {
+            items.push(item.1);
+        }
+        assert_eq!(items, ["a", "1", "b", "a", "0"]);
+        assert_eq!(walker.next_item().unwrap_err().kind(), &ErrorKind::Poisoned { original: Box::new(e) });
+
+        // escape sequences are decoded before keys are compared
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1, "\u0061": 2}"#.to_string()), 0);
+        walker.set_on_duplicate_key(DuplicatePolicy::Error);
+        assert_eq!(walker.current_value_content().unwrap_err().kind(), &ErrorKind::DuplicateKey);
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_duplicate_key_value() {
+        for (policy, expected) in [(DuplicatePolicy::Allow, 2), (DuplicatePolicy::FirstWins, 1), (DuplicatePolicy::LastWins, 2)] {
+            let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1, "a": 2}"#.to_string()), 0);
+            walker.set_on_duplicate_key(policy);
+            assert_eq!(walker.to_value().unwrap(), serde_json::json!({"a": expected}));
+        }
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let json = b"{\"a\": \"x\xFFy\", \"b\": 2}";
+        let mut walker = JsonWalker::with_reader(json.iter().copied(), 0);
+        assert_eq!(walker.next_item().unwrap().1, "a");
+        let e = walker.next_item().unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::InvalidUtf8);
+        assert_eq!(e.message(), "Invalid utf8 at byte 8 (1:9)");
+        assert!(walker.is_poisoned());
+
+        let mut walker = JsonWalker::with_reader(json.iter().copied(), 0);
+        walker.set_lossy_utf8(true);
+        let items: Vec<String> = std::iter::from_fn(|| walker.next_item().ok().map(|i| i.1)).collect();
+        assert_eq!(items, ["a", "x\u{FFFD}y", "b", "2"]);
+
+        // a key and a streamed string
+        let mut walker = JsonWalker::with_reader(b"{\"\xFF\": 1}".iter().copied(), 0);
+        assert_eq!(walker.next_key().unwrap_err().kind(), &ErrorKind::InvalidUtf8);
+        let stream = |lossy: bool| {
+            let mut walker = JsonWalker::with_reader(b"\"x\xFFy\"".iter().copied(), 0);
+            walker.set_lossy_utf8(lossy);
+            let mut out = Vec::new();
+            walker.stream_string_value(&mut out, StringSink::Raw).map(|_| out)
+        };
+        assert_eq!(stream(false).unwrap_err().kind(), &ErrorKind::InvalidUtf8);
+        assert_eq!(stream(true).unwrap(), "x\u{FFFD}y".as_bytes());
+
+        // the root string is read by stream_string_value(), then finish() checks the rest
+        let mut walker = JsonWalker::with_reader(b"\"ab\" c".iter().copied(), 0);
+        walker.stream_string_value(Vec::new(), StringSink::Raw).unwrap();
+        assert_eq!(walker.finish().unwrap_err().kind(), &ErrorKind::TrailingCharacters);
+    }
+
+    #[test]
+    fn test_finish() {
+        let finish = |json: &str| {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            walker.skip_current_value().unwrap();
+            walker.finish()
+        };
+        assert!(finish(r#"{"a": [1, 2]}"#).is_ok());
+        assert!(finish("{\"a\": 1} \n\t \r\n").is_ok());
+        assert!(finish("[1] ").is_ok());
+        let e = finish(r#"{"a": 1}]"#).unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(e.message(), "Unexpected `]` after the end of json at byte 8 (1:9)");
+        assert_eq!(finish(r#"{"a": 1} ,"#).unwrap_err().kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(finish("[1]\n[2]").unwrap_err().kind(), &ErrorKind::TrailingCharacters);
+
+        // the root value is not finished
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1, "b": 2}"#.to_string()), 0);
+        walker.next_key().unwrap();
+        let e = walker.finish().unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::InvalidStructure);
+        assert_eq!(e.path(), Some("#/{a,0}/"));
+        walker.skip_current_value().unwrap();
+        walker.next_key().unwrap();
+        walker.skip_current_value().unwrap();
+        assert!(walker.finish().is_ok());
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_allow_nan_de() {
+        #[derive(serde::Deserialize, Debug)]
+        struct Sample {
+            max: f64,
+            min: f32,
+            mean: f64,
+        }
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"max": Infinity, "min": -Infinity, "mean": NaN}"#.to_string()), 0);
+        walker.set_allow_nan(true);
+        let sample = walker.current_value::<Sample>().unwrap();
+        assert_eq!(sample.max, f64::INFINITY);
+        assert_eq!(sample.min, f32::NEG_INFINITY);
+        assert!(sample.mean.is_nan());
+
+        #[cfg(feature = "serde_json")]
+        {
+            let mut walker = JsonWalker::new(StringReader::new("[NaN, 1.5]".to_string()), 0);
+            walker.set_allow_nan(true);
+            // serde_json::Value turns a float which is not finite into null
+            assert_eq!(walker.current_value::<serde_json::Value>().unwrap(), serde_json::json!([null, 1.5]));
+        }
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_multi_document_de() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Line {
+            id: u32,
+            tags: Vec<String>,
+        }
+        let ndjson = "{\"id\": 1, \"tags\": []}\n{\"id\": 2, \"tags\": [\"a\"]}\n{\"id\": 3, \"tags\": [\"b\", \"c\"]}\n";
+        let mut walker = JsonWalker::new_multi(StringReader::new(ndjson.to_string()), 0);
+        let mut lines = vec![];
+        while walker.next_document().unwrap() {
+            lines.push(walker.current_value::<Line>().unwrap());
+        }
+        assert_eq!(lines.iter().map(|l| l.id).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(lines[2], Line { id: 3, tags: vec!["b".to_string(), "c".to_string()] });
+
+        let mut walker = JsonWalker::new_multi(StringReader::new("1 2.5 3".to_string()), 0);
+        let numbers: Vec<f64> = std::iter::from_fn(|| walker.next_document().unwrap().then(|| walker.current_value().unwrap())).collect();
+        assert_eq!(numbers, [1.0, 2.5, 3.0]);
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_scalar_root_de() {
+        fn value<T: serde::de::DeserializeOwned>(json: &str) -> T {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            let value = walker.current_value::<T>().unwrap();
+            assert!(walker.finish().is_ok(), "{json:?}");
+            value
+        }
+        for padding in ["", " ", "\n", " \t\n"] {
+            assert_eq!(value::<u64>(&format!("{padding}123{padding}")), 123);
+            assert_eq!(value::<i8>(&format!("{padding}-7{padding}")), -7);
+            assert_eq!(value::<f64>(&format!("{padding}2.5{padding}")), 2.5);
+            assert!(value::<bool>(&format!("{padding}true{padding}")));
+            assert_eq!(value::<Option<u8>>(&format!("{padding}null{padding}")), None);
+            assert_eq!(value::<String>(&format!("{padding}\"hello\"{padding}")), "hello");
+        }
+        #[cfg(feature = "serde_json")]
+        {
+            assert_eq!(value::<serde_json::Value>("4"), serde_json::json!(4));
+            assert_eq!(value::<serde_json::Value>(" false"), serde_json::json!(false));
+        }
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_number_types_de() {
+        let value = |json: &str| JsonWalker::new(StringReader::new(json.to_string()), 0).current_value::<serde_json::Value>().unwrap();
+        assert_eq!(value("18446744073709551615").as_u64(), Some(u64::MAX));
+        assert_eq!(value("-9223372036854775808").as_i64(), Some(i64::MIN));
+        assert_eq!(value("[1, -1]"), serde_json::json!([1, -1]));
+        let mut walker = JsonWalker::new(StringReader::new("[18446744073709551615, -9223372036854775808]".to_string()), 0);
+        assert_eq!(walker.current_value::<(u64, i64)>().unwrap(), (u64::MAX, i64::MIN));
+    }
+
+    #[cfg(all(feature = "arbitrary_precision", feature = "serde_json"))]
+    #[test]
+    fn test_arbitrary_precision_de() {
+        // 40 digits, keys are sorted as serde_json::Value writes them
+        let json = r#"{"big":-1234567890123456789012345678901234567890,"pi":3.141592653589793238462643383279502884197,"price":0.1000000000000000055}"#;
+        let value = JsonWalker::new(StringReader::new(json.to_string()), 0).current_value::<serde_json::Value>().unwrap();
+        assert_eq!(value.to_string(), json);
+        assert_eq!(JsonWalker::new(StringReader::new(json.to_string()), 0).to_value().unwrap().to_string(), json);
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Prices {
+            price: serde_json::Number,
+            pi: serde_json::Number,
+        }
+        let prices = JsonWalker::new(StringReader::new(json.to_string()), 0).current_value::<Prices>().unwrap();
+        assert_eq!(prices.price.to_string(), "0.1000000000000000055");
+        assert_eq!(prices.pi.to_string(), "3.141592653589793238462643383279502884197");
+        // numbers which are asked for by their type are parsed as usual
+        let mut walker = JsonWalker::new(StringReader::new("[1.5, -2]".to_string()), 0);
+        assert_eq!(walker.current_value::<(f64, i8)>().unwrap(), (1.5, -2));
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_invalid_utf8_de() {
+        let value = |lossy: bool| {
+            let mut walker = JsonWalker::with_reader(b"{\"a\": [\"x\xFFy\"]}".iter().copied(), 0);
+            walker.set_lossy_utf8(lossy);
+            walker.current_value::<BTreeMap<String, Vec<String>>>()
+        };
+        let e = value(false).unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::InvalidUtf8);
+        assert_eq!(e.path(), Some("#/{a,0}/[a,0]/"));
+        assert_eq!(value(true).unwrap()["a"], ["x\u{FFFD}y"]);
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_finish_de() {
+        let finish = |json: &str| {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            assert_eq!(walker.current_value::<BTreeMap<String, i32>>().unwrap()["a"], 1);
+            walker.finish()
+        };
+        assert!(finish(r#"{"a": 1}"#).is_ok());
+        assert!(finish("{\"a\": 1}  \n").is_ok());
+        assert_eq!(finish(r#"{"a": 1}]"#).unwrap_err().kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(finish(r#"{"a": 1},"#).unwrap_err().kind(), &ErrorKind::TrailingCharacters);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let truncated = [r#""unterminated"#, r#"["ab"#, r#"{"key": "a\"#, r#"{"key""#, "[12", "{\"a\": 1.5", r#"{"key":"#, r#"{"a": {"key": "#];
+        for json in truncated {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            let e = loop {
+                if let Err(e) = walker.next_item() {
+                    break e;
+                }
+            };
+            assert_eq!(e.kind, ErrorKind::UnexpectedEos, "{json}");
+            assert!(e.msg.starts_with("Unexpected end of stream") && e.msg.contains(&format!("at byte {}", json.len())), "{}", e.msg);
+            let e = JsonWalker::new(StringReader::new(json.to_string()), 0).current_value_content().unwrap_err();
+            assert_eq!(e.kind, ErrorKind::UnexpectedEos, "{json}");
+        }
+        // the items before the end are walked
+        let mut walker = JsonWalker::new(StringReader::new("[1, 12".to_string()), 0);
+        assert_eq!(walker.next_item().unwrap().1, "1");
+        assert_eq!(walker.next_item().unwrap().1, "12");
+        assert_eq!(walker.next_item().unwrap_err().kind, ErrorKind::UnexpectedEos);
+        // a complete root value
+        for json in ["12", "\"a\"", "[1]", "{} "] {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            while walker.next_item().is_ok() {}
+            assert!(!walker.is_poisoned(), "{json}");
+        }
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_truncated_de() {
+        let value = |json: &str| JsonWalker::new(StringReader::new(json.to_string()), 0).current_value::<BTreeMap<String, f64>>().unwrap_err().kind;
+        assert_eq!(value(r#"{"a": 1.5"#), ErrorKind::UnexpectedEos);
+        assert_eq!(value(r#"{"a":"#), ErrorKind::UnexpectedEos);
+        assert_eq!(value(r#"{"a"#), ErrorKind::UnexpectedEos);
+        assert_eq!(JsonWalker::new(StringReader::new(r#""ab"#.to_string()), 0).current_value::<String>().unwrap_err().kind, ErrorKind::UnexpectedEos);
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_malformed_de() {
+        let value = |json: &str| JsonWalker::new(StringReader::new(json.to_string()), 0).current_value::<Vec<Vec<f64>>>().unwrap_err().kind;
+        assert_eq!(value("[[1,}]"), ErrorKind::UnexpectedChar);
+        assert_eq!(value("[[1, -]]"), ErrorKind::InvalidNumber);
+        assert_eq!(value("[[1, 2"), ErrorKind::UnexpectedEos);
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_duplicate_key_de() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct A {
+            a: i32,
+            b: i32,
+        }
+        let walker = |policy: DuplicatePolicy| {
+            let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1, "b": 3, "a": {"c": [2]}}"#.to_string()), 0);
+            walker.set_on_duplicate_key(policy);
+            walker
+        };
+        assert_eq!(walker(DuplicatePolicy::FirstWins).current_value::<A>().unwrap(), A { a: 1, b: 3 });
+        assert_eq!(walker(DuplicatePolicy::Error).current_value::<A>().unwrap_err().kind(), &ErrorKind::DuplicateKey);
+        let e = walker(DuplicatePolicy::LastWins).current_value::<A>().unwrap_err();
+        assert_eq!((e.kind(), e.path(), e.is_recovered()), (&ErrorKind::ParseIntError, Some("#/{a,0}/"), true));
+
+        let mut walker = JsonWalker::new(StringReader::new(r#"[{"a": 1, "b": 3, "a": 2}, {"b": 4, "a": 5, "b": 6}]"#.to_string()), 0);
+        walker.set_on_duplicate_key(DuplicatePolicy::LastWins);
+        assert_eq!(walker.current_value::<Vec<A>>().unwrap(), vec![A { a: 2, b: 3 }, A { a: 5, b: 6 }]);
+
+        let map = |policy: DuplicatePolicy| {
+            let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1, "a": 2}"#.to_string()), 0);
+            walker.set_on_duplicate_key(policy);
+            walker.current_value::<BTreeMap<String, i32>>()
+        };
+        assert_eq!(map(DuplicatePolicy::Allow).unwrap()["a"], 2);
+        assert_eq!(map(DuplicatePolicy::FirstWins).unwrap()["a"], 1);
+        assert_eq!(map(DuplicatePolicy::LastWins).unwrap()["a"], 2);
+        assert_eq!(map(DuplicatePolicy::Error).unwrap_err().kind(), &ErrorKind::DuplicateKey);
+    }
+
+    #[test]
+    fn test_offsets() {
+        for mem_size in [0, 20] {
+            let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), mem_size);
+            assert_eq!((walker.last_token_offset(), walker.byte_offset()), (None, 2));
+            let mut count = 0;
+            while let Ok(item) = walker.next_item() {
+                let at = &CORRECT_JSON[walker.last_token_offset().unwrap() as usize..];
+                match item.0 {
+                    // escapes are decoded in the item
+                    ValueType::Str => assert!(at.starts_with('"') && at[1..].starts_with(&item.1[..4]), "{at}"),
+                    _ => assert!(at.starts_with(&item.1), "{at}"),
+                }
+                assert!(walker.byte_offset() > walker.last_token_offset().unwrap() + item.1.len() as u64);
+                count += 1;
+            }
+            assert_eq!(count, 57);
+            assert_eq!(walker.byte_offset(), CORRECT_JSON.len() as u64);
+        }
+
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.next_key_by_name("key5").unwrap();
+        assert_eq!(walker.last_token_offset(), Some(50));
+        // the look-ahead byte is ":"
+        assert_eq!(walker.byte_offset(), 57);
+        walker.reset_stats();
+        assert_eq!(walker.next_item().unwrap().1, "111.111");
+        assert_eq!((walker.last_token_offset(), walker.byte_offset(), walker.stats().bytes_consumed), (Some(57), 65, 8));
+        walker.next_key_by_name("key84").unwrap();
+        assert_eq!(walker.last_token_offset().map(|o| o as usize), CORRECT_JSON.find(r#""key84""#));
+        // the same offsets after a failed poll() or a probe()
+        walker.has_key_in_current("absent", 10_000).unwrap();
+        assert_eq!(walker.last_token_offset().map(|o| o as usize), CORRECT_JSON.find(r#""key84""#));
+        assert_eq!(walker.next_item().unwrap().1, "555");
+        assert_eq!(walker.last_token_offset().map(|o| o as usize), CORRECT_JSON.find("555"));
+
+        let mut walker = JsonWalker::new(StringReader::new(format!("\u{feff} {CORRECT_JSON}")), 0);
+        walker.next_key().unwrap();
+        assert_eq!(walker.last_token_offset(), Some(6));
+    }
+
+    #[test]
+    fn test_reset() {
+        fn walk(walker: &mut JsonWalker) -> (Vec<Entry>, String, Stats) {
+            let entries = walker.entries().map(|e| e.unwrap()).collect();
+            (entries, walker.get_recent_piece(), walker.stats().clone())
+        }
+
+        let docs = [CORRECT_JSON, r#"{"id": 1, "tags": ["a", "b"]}"#, "[]", " 12 ", r#""text""#, r#"[{"a": {"b": [[1], {"c": null}]}}]"#];
+        let mut walker = JsonWalker::new(StringReader::new(String::new()), 20);
+        walker.current_value_content().unwrap_err();
+        let capacity = walker.stack.capacity();
+        for (i, json) in docs.iter().cycle().take(3 * docs.len()).enumerate() {
+            let expected = walk(&mut JsonWalker::new(StringReader::new(json.to_string()), 20));
+            if i % 2 == 0 {
+                walker.reset(StringReader::new(json.to_string()));
+            } else {
+                walker.reset_with_str(json);
+            }
+            assert_eq!(walk(&mut walker), expected);
+
+            // a walk which is left in the middle
+            walker.reset_with_str(json);
+            walker.next_item().ok();
+            walker.reset_with_str(json);
+            assert_eq!(walk(&mut walker), expected);
+        }
+        assert_eq!(walker.stack.capacity(), capacity);
+
+        // a walker of new_push() becomes a plain one
+        let mut walker = JsonWalker::new_push(0);
+        walker.feed(b"[1").unwrap();
+        assert_eq!(walker.next_item_nonblocking(), Ok(None));
+        walker.reset_with_str("[2]");
+        assert_eq!(walker.next_item().unwrap().1, "2");
+    }
+
+    #[test]
+    fn test_with_reader() {
+        let mut boxed = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 20);
+        let mut generic = JsonWalker::with_reader(CORRECT_JSON.bytes(), 20);
+        loop {
+            let item = boxed.next_item();
+            assert_eq!(generic.next_item(), item);
+            assert_eq!(generic.get_recent_piece(), boxed.get_recent_piece());
+            if item.is_err() {
+                break;
+            }
+        }
+        assert_eq!(generic.stats(), boxed.stats());
+
+        // a probe reads its bytes again, also when the reader is not boxed
+        let mut walker = JsonWalker::with_reader(CORRECT_JSON.bytes(), 0);
+        walker.next_key().unwrap();
+        assert_eq!(walker.has_key_in_current("key9", 10_000), Ok(true));
+        assert_eq!(walker.next_key_by_name("key7").unwrap().1, "key7");
+        walker.reset(CORRECT_JSON.bytes());
+        assert_eq!(walker.entries().count(), 57);
+
+        // a halted walker stops reading
+        let mut walker = JsonWalker::with_reader(b"\xFE\xFF[1]".iter().copied(), 0);
+        assert_eq!(walker.current_value_content().unwrap_err().kind, ErrorKind::UnsupportedEncoding);
+        assert!(matches!(walker.next_item().unwrap_err().kind, ErrorKind::Poisoned { .. }));
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut walker = JsonWalker::with_reader(StringReader::new(CORRECT_JSON.to_string()), 30);
+        walker.next_key_by_name("key7").unwrap();
+        walker.skip_current_value().unwrap();
+        let mut fork = walker.fork();
+        assert_eq!(fork.get_recent_piece(), walker.get_recent_piece());
+        assert_eq!(fork.stats(), walker.stats());
+
+        assert_eq!(walker.next_key().unwrap().1, "key8");
+        assert_eq!(walker.next_item(), Ok((ValueType::Null, "null".to_string())));
+        assert_eq!(walker.next_item(), Ok((ValueType::Bool, "true".to_string())));
+        let mut fork_of_fork = walker.fork();
+        assert_eq!(fork.next_key().unwrap().1, "key8");
+        assert_eq!(fork.next_item(), Ok((ValueType::Null, "null".to_string())));
+        assert_eq!(walker.next_item(), Ok((ValueType::Bool, "false".to_string())));
+        assert_eq!(fork_of_fork.next_item(), Ok((ValueType::Bool, "false".to_string())));
+
+        // both walks go on to the end independently
+        let rest: Vec<_> = std::iter::from_fn(|| walker.next_item().ok()).collect();
+        assert_eq!(std::iter::from_fn(|| fork_of_fork.next_item().ok()).collect::<Vec<_>>(), rest);
+        assert_eq!(fork.next_item(), Ok((ValueType::Bool, "true".to_string())));
+
+        // bytes which a probe has read are read again by the fork as well
+        let mut walker = JsonWalker::with_reader(SliceReader::new(CORRECT_JSON.as_bytes()), 0);
+        walker.next_key().unwrap();
+        assert_eq!(walker.has_key_in_current("key9", 10_000), Ok(true));
+        let mut fork = walker.fork();
+        assert_eq!(fork.next_key_by_name("key7").unwrap().1, "key7");
+        assert_eq!(walker.next_key_by_name("key9").unwrap().1, "key9");
+    }
+
+    #[test]
+    fn test_from_read() {
+        struct CountingRead {
+            inner: io::Cursor<Vec<u8>>,
+            calls: Rc<Cell<usize>>,
+        }
+
+        impl io::Read for CountingRead {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.calls.set(self.calls.get() + 1);
+                self.inner.read(buf)
+            }
+        }
+
+        let json = format!("[{}]", vec![CORRECT_JSON; 100].join(","));
+        let counting = |calls: &Rc<Cell<usize>>| CountingRead { inner: io::Cursor::new(json.clone().into_bytes()), calls: calls.clone() };
+        let walk = |walker: &mut JsonWalker| {
+            let mut items = vec![];
+            while let Ok(item) = walker.next_item() {
+                items.push((item, walker.get_recent_piece()));
+            }
+            items
+        };
+
+        let per_byte = Rc::new(Cell::new(0));
+        let mut walker = JsonWalker::new_fallible(Box::new(io::Read::bytes(counting(&per_byte))), 40);
+        let expected = walk(&mut walker);
+        let buffered = Rc::new(Cell::new(0));
+        let mut walker = JsonWalker::from_read(counting(&buffered), 40);
+        assert_eq!(walk(&mut walker), expected);
+        assert_eq!(walker.byte_offset(), json.len() as u64);
+        assert!(per_byte.get() > json.len());
+        assert_eq!(buffered.get(), json.len().div_ceil(8 * 1024) + 1);
+
+        // the buffer is not longer than the capacity
+        let buffered = Rc::new(Cell::new(0));
+        let mut walker = JsonWalker::from_read_with_capacity(counting(&buffered), 100, 0);
+        walker.skip_current_value().unwrap();
+        assert_eq!(buffered.get(), json.len().div_ceil(100) + 1);
+
+        // a probe reads buffered bytes again
+        let mut walker = JsonWalker::from_read(io::Cursor::new(CORRECT_JSON), 0);
+        walker.next_key().unwrap();
+        assert_eq!(walker.has_key_in_current("key9", 10_000), Ok(true));
+        assert_eq!(walker.next_key_by_name("key7").unwrap().1, "key7");
+
+        struct FailingRead;
+
+        impl io::Read for FailingRead {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))
+            }
+        }
+
+        let source = io::Read::chain(io::Cursor::new(br#"{"a": [1, 2"#.to_vec()), FailingRead);
+        let mut walker = JsonWalker::from_read(source, 0);
+        assert_eq!(walker.current_value_content().unwrap_err().kind, ErrorKind::Io(io::ErrorKind::ConnectionReset));
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_with_reader_de() {
+        let mut walker = JsonWalker::with_reader(br#"{"a": [1, 2], "b": "x"}"#.iter().copied(), 0);
+        assert_eq!(walker.next_key_by_name("a").unwrap().1, "a");
+        assert_eq!(walker.current_value::<Vec<u8>>().unwrap(), vec![1, 2]);
+        assert_eq!(walker.next_key().unwrap().1, "b");
+        assert_eq!(walker.current_value::<String>().unwrap(), "x");
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let mut walker = JsonWalker::with_reader(StringReader::new(CORRECT_JSON.to_string()), 30);
+        walker.next_key_by_name("key7").unwrap();
+        let checkpoint = walker.checkpoint();
+        let (stats, piece, offset) = (walker.stats().clone(), walker.get_recent_piece(), walker.byte_offset());
+        let expected: Vec<_> = std::iter::from_fn(|| walker.next_item().ok()).collect();
+        assert_eq!(walker.next_item().unwrap_err().kind, ErrorKind::EOS);
+
+        // a walk which has reached the end goes back as well
+        for _ in 0..2 {
+            walker.restore(&checkpoint).unwrap();
+            assert_eq!((walker.get_recent_piece(), walker.stats(), walker.byte_offset()), (piece.clone(), &stats, offset));
+            assert_eq!(std::iter::from_fn(|| walker.next_item().ok()).collect::<Vec<_>>(), expected);
+        }
+
+        // bytes which a probe has read are read again after the restore
+        let mut walker = JsonWalker::with_reader(SliceReader::new(CORRECT_JSON.as_bytes()), 0);
+        walker.next_key().unwrap();
+        let checkpoint = walker.checkpoint();
+        assert_eq!(walker.has_key_in_current("key9", 10_000), Ok(true));
+        assert_eq!(walker.next_key_by_name("key9").unwrap().1, "key9");
+        walker.restore(&checkpoint).unwrap();
+        assert_eq!(walker.next_item(), Ok((ValueType::Null, "null".to_string())));
+        assert_eq!(walker.next_key().unwrap().1, "key2");
+
+        let json = format!("[{}]", vec![CORRECT_JSON; 100].join(","));
+        let path = std::env::temp_dir().join(format!("json_walker_{}_checkpoint.json", std::process::id()));
+        std::fs::write(&path, &json).unwrap();
+        let mut walker = JsonWalker::with_reader(FileReader::with_capacity(&path, 100).unwrap(), 0);
+        walker.next_key_by_name("key8").unwrap();
+        let checkpoint = walker.checkpoint();
+        while walker.next_item().is_ok() {}
+        walker.restore(&checkpoint).unwrap();
+        assert_eq!(walker.next_item(), Ok((ValueType::Null, "null".to_string())));
+        assert_eq!(walker.next_key().unwrap().1, "key81");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_max_input_bytes_de() {
+        let json = format!("[{}]", vec!["12345"; 200_000].join(","));
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        walker.set_max_input_bytes(Some(64 * 1024));
+        assert_eq!(walker.current_value::<Vec<u32>>().unwrap_err().kind, ErrorKind::LimitExceeded);
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_max_depth_de() {
+        let hostile = format!("{}1{}", "[".repeat(10_000), "]".repeat(10_000));
+        let mut walker = JsonWalker::new(StringReader::new(hostile), 0);
+        assert_eq!(walker.current_value::<Vec<Vec<u8>>>().unwrap_err().kind, ErrorKind::MaxDepthExceeded);
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_checkpoint_de() {
+        use crate::json_walker::walker_test_de::data1::Point;
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Tagged<T> {
+            payload: T,
+            #[serde(rename = "type")]
+            kind: String,
+        }
+
+        let json = r#"{"a": {"payload": {"x": 1, "y": 2}, "type": "point"}, "b": {"payload": [1, 2, 3], "type": "list"}}"#;
+        let mut walker = JsonWalker::with_reader(SliceReader::new(json.as_bytes()), 0);
+        let mut found = vec![];
+        while walker.next_key().is_ok() {
+            let checkpoint = walker.checkpoint();
+            walker.next_key_by_name("type").unwrap();
+            let kind: String = walker.current_value().unwrap();
+            walker.restore(&checkpoint).unwrap();
+            match kind.as_str() {
+                "point" => found.push(format!("{:?}", walker.current_value::<Tagged<Point>>().unwrap())),
+                _ => found.push(format!("{:?}", walker.current_value::<Tagged<Vec<u8>>>().unwrap())),
+            }
+        }
+        assert_eq!(found, [
+            r#"Tagged { payload: Point { x: 1, y: 2 }, kind: "point" }"#,
+            r#"Tagged { payload: [1, 2, 3], kind: "list" }"#,
+        ]);
+    }
+
+    #[test]
+    fn test_poisoned() {
+        let poisoned_by = |walker: &mut JsonWalker| match walker.next_item().map_err(|e| e.kind) {
+            Err(ErrorKind::Poisoned { original }) => Some(original.kind),
+            _ => None,
+        };
+        let check_reset = |walker: &mut JsonWalker| {
+            assert!(walker.is_poisoned());
+            walker.reset(StringReader::new(r#"{"a": [1]}"#.to_string()));
+            assert!(!walker.is_poisoned());
+            assert_eq!(walker.next_key_by_name("a").unwrap().1, "a");
+            assert_eq!(walker.current_value_content().unwrap(), Content::Array(vec![Content::Simple((ValueType::UInt, "1".to_string()))]));
+        };
+
+        // a limit
+        let mut walker = JsonWalker::new(StringReader::new(format!("[1,{}2]", " ".repeat(100))), 0);
+        walker.set_max_consecutive_whitespace(Some(10));
+        walker.next_item().unwrap();
+        assert_eq!(walker.next_item().unwrap_err().kind, ErrorKind::WhitespaceLimitExceeded);
+        assert_eq!(poisoned_by(&mut walker), Some(ErrorKind::WhitespaceLimitExceeded));
+        assert!(matches!(walker.next_key_by_name("a").unwrap_err().kind, ErrorKind::Poisoned { .. }));
+        check_reset(&mut walker);
+
+        // an invalid token
+        let mut walker = JsonWalker::new(StringReader::new("[nullx, 2]".to_string()), 0);
+        assert_eq!(walker.skip_current_value().unwrap_err().kind, ErrorKind::InvalidToken);
+        assert_eq!(poisoned_by(&mut walker), Some(ErrorKind::InvalidToken));
+        check_reset(&mut walker);
+
+        // malformed json
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1, }, "b": 3}"#.to_string()), 0);
+        walker.next_item().unwrap();
+        assert_eq!(walker.next_key().unwrap_err().kind, ErrorKind::UnexpectedChar);
+        assert!(walker.is_poisoned());
+        assert_eq!(poisoned_by(&mut walker), Some(ErrorKind::UnexpectedChar));
+        assert!(matches!(walker.current_value_content().unwrap_err().kind, ErrorKind::Poisoned { .. }));
+        check_reset(&mut walker);
+    }
+
+    /// the bytes of **json** up to **n**, then a ConnectionReset error
+    pub(crate) fn failing_reader(json: &str, n: usize) -> Box<dyn Iterator<Item=std::io::Result<u8>>> {
+        let bytes: Vec<u8> = json.bytes().take(n).collect();
+        let failure = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset");
+        Box::new(bytes.into_iter().map(Ok).chain(std::iter::once(Err(failure))))
+    }
+
+    #[test]
+    fn test_fallible_reader() {
+        let reset = ErrorKind::Io(std::io::ErrorKind::ConnectionReset);
+        let expected: Vec<_> = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0).entries().map(|e| e.unwrap()).collect();
+        for n in 0..=CORRECT_JSON.len() {
+            // items before the failure are the same, then the failure is returned once instead of EOS
+            let mut walker = JsonWalker::new_fallible(failing_reader(CORRECT_JSON, n), 10);
+            let mut entries = vec![];
+            let error = loop {
+                match walker.next_entry() {
+                    Ok(e) => entries.push(e),
+                    Err(e) => break e,
+                }
+            };
+            assert_eq!(error.kind, reset, "n: {n}");
+            assert_eq!(error.msg, "connection reset");
+            assert_eq!(entries[..], expected[..entries.len()]);
+            assert!(walker.is_poisoned());
+            assert!(matches!(walker.next_item().unwrap_err().kind, ErrorKind::Poisoned { original } if original.kind == reset));
+
+            // a value is complete once its closing bracket is read
+            let mut walker = JsonWalker::new_fallible(failing_reader(CORRECT_JSON, n), 0);
+            let content = walker.current_value_content();
+            if n < CORRECT_JSON.trim_end().len() {
+                assert_eq!(content.unwrap_err().kind, reset, "n: {n}");
+            } else {
+                assert!(content.is_ok());
+                assert_eq!(walker.next_item().unwrap_err().kind, reset);
+            }
+        }
+
+        // in the middle of a string which is streamed
+        let json = r#"{"a": "some long text"}"#;
+        let mut walker = JsonWalker::new_fallible(failing_reader(json, 12), 0);
+        walker.next_key().unwrap();
+        assert_eq!(walker.stream_string_value(&mut Vec::new(), StringSink::Raw).unwrap_err().kind, reset);
+
+        // interrupted reads are retried
+        let interrupted = json.bytes().flat_map(|b| [Err(std::io::ErrorKind::Interrupted.into()), Ok(b)]);
+        let mut walker = JsonWalker::new_fallible(Box::new(interrupted), 0);
+        assert_eq!(walker.next_key_by_name("a").unwrap().1, "a");
+        assert_eq!(walker.next_item().unwrap().1, "some long text");
+        assert_eq!(walker.next_item().unwrap_err().kind, ErrorKind::EOS);
+
+        // reset() with an infallible reader
+        let mut walker = JsonWalker::new_fallible(failing_reader(json, 3), 0);
+        assert_eq!(walker.next_item().unwrap_err().kind, reset);
+        walker.reset(StringReader::new(json.to_string()));
+        assert_eq!(walker.next_key().unwrap().1, "a");
+    }
+
+    #[test]
+    fn test_max_input_bytes() {
+        let json = format!("[{}]", vec![r#"{"id": 12345, "name": "a name"}"#; 32 * 1024].join(","));
+        assert!(json.len() > 1024 * 1024);
+        let limited = |mem_size| {
+            let mut walker = JsonWalker::new(StringReader::new(json.clone()), mem_size);
+            walker.set_max_input_bytes(Some(64 * 1024));
+            walker
+        };
+
+        let mut walker = limited(0);
+        let mut count = 0;
+        let e = loop {
+            match walker.next_item() {
+                Ok(_) => count += 1,
+                Err(e) => break e,
+            }
+        };
+        assert_eq!(e.kind, ErrorKind::LimitExceeded);
+        assert_eq!(walker.byte_offset(), 64 * 1024);
+        assert!(count > 8000 && count <= 8192, "{count}");
+        assert!(matches!(walker.next_item().unwrap_err().kind, ErrorKind::Poisoned { .. }));
+
+        // a value which is being built is cut as well
+        let mut walker = limited(20);
+        assert_eq!(walker.current_value_content().unwrap_err().kind, ErrorKind::LimitExceeded);
+        assert_eq!(limited(0).skip_current_value().unwrap_err().kind, ErrorKind::LimitExceeded);
+
+        // json of the same size as the limit is read to the end
+        let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+        walker.set_max_input_bytes(Some(json.len() as u64));
+        assert_eq!(walker.skip_current_value(), Ok(ValueType::Arr));
+        walker.reset(StringReader::new(format!("{json} ")));
+        assert_eq!(walker.skip_current_value().unwrap_err().kind, ErrorKind::LimitExceeded);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let nested = |depth: usize| format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+        let hostile = nested(10_000);
+        fn kind<T>(r: Result<T, Error>) -> ErrorKind {
+            r.err().unwrap().kind
+        }
+
+        assert_eq!(kind(JsonWalker::new(StringReader::new(hostile.clone()), 0).next_item()), ErrorKind::MaxDepthExceeded);
+        assert_eq!(kind(JsonWalker::new(StringReader::new(hostile.clone()), 0).current_value_content()), ErrorKind::MaxDepthExceeded);
+        assert_eq!(kind(JsonWalker::new(StringReader::new(hostile.clone()), 0).skip_current_value()), ErrorKind::MaxDepthExceeded);
+        let mut walker = JsonWalker::new(StringReader::new(hostile.clone()), 0);
+        let e = walker.next_item().unwrap_err();
+        assert_eq!(e.msg, "More than 128 nested containers at byte 128 (1:129)");
+        assert_eq!(walker.stack.len(), 129);
+        assert!(matches!(walker.next_item().unwrap_err().kind, ErrorKind::Poisoned { .. }));
+
+        // objects are containers too
+        let json = format!(r#"{}{{"a": 1}}{}"#, "[".repeat(127), "]".repeat(127));
+        assert_eq!(JsonWalker::new(StringReader::new(json), 0).skip_current_value(), Ok(ValueType::Arr));
+        let json = format!(r#"{}{{"a": [1]}}{}"#, "[".repeat(127), "]".repeat(127));
+        assert_eq!(kind(JsonWalker::new(StringReader::new(json), 0).skip_current_value()), ErrorKind::MaxDepthExceeded);
+
+        let mut walker = JsonWalker::new(StringReader::new(nested(128)), 0);
+        assert!(walker.current_value_content().is_ok());
+        let mut walker = JsonWalker::new(StringReader::new(nested(500)), 0);
+        walker.set_max_depth(None);
+        assert_eq!(walker.next_item(), Ok((ValueType::UInt, "1".to_string())));
+        walker.set_max_depth(Some(2));
+        walker.reset(StringReader::new(nested(3)));
+        assert_eq!(kind(walker.current_value_content()), ErrorKind::MaxDepthExceeded);
+    }
+
+    #[test]
+    fn test_max_consecutive_whitespace() {
+        let gap = " ".repeat(2 << 20);
+        let json = format!(r#"{{"a": 1,{gap}"b": 2}}"#);
+
+        let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+        walker.set_max_consecutive_whitespace(Some(1 << 20));
+        walker.next_item().unwrap();
+        assert_eq!(walker.next_item(), Ok((ValueType::UInt, "1".to_string())));
+        let e = walker.next_key().unwrap_err();
+        assert_eq!(e.kind, ErrorKind::WhitespaceLimitExceeded);
+        assert_eq!(e.msg, "More than 1048576 consecutive white-spaces at byte 8, cut at 1:1048585");
+        // reading stops at the limit
+        assert_eq!(walker.stats().bytes_consumed, 8 + (1 << 20) + 1);
+        // later calls tell the original error
+        for e in [walker.next_item().unwrap_err(), walker.skip_current_value().unwrap_err()] {
+            assert!(matches!(e.kind, ErrorKind::Poisoned { original } if original.kind == ErrorKind::WhitespaceLimitExceeded));
+        }
+
+        // the default limit is 1MB
+        let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+        assert_eq!(walker.next_key_by_name("b").unwrap_err().kind, ErrorKind::WhitespaceLimitExceeded);
+
+        // without a limit, a passed deadline interrupts the run
+        let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+        walker.set_max_consecutive_whitespace(None);
+        walker.next_item().unwrap();
+        walker.next_item().unwrap();
+        walker.set_deadline(Instant::now());
+        // it walks over "," and the run after that
+        walker.move_n_element_forward(1);
+        assert_eq!(walker.stats().bytes_consumed, 8 + 65);
+        // the error is returned till the deadline is moved, and the walker is not poisoned by it
+        for _ in 0..2 {
+            let e = walker.next_key().unwrap_err();
+            assert_eq!(e.kind, ErrorKind::DeadlineExceeded);
+            assert_eq!(e.msg, "Deadline exceeded in 65 consecutive white-spaces at byte 8, cut at 1:73");
+        }
+        assert!(!walker.is_poisoned());
+        walker.set_deadline(Instant::now());
+        assert_eq!(walker.next_key().unwrap_err().kind, ErrorKind::DeadlineExceeded);
+        // the look-ahead byte which is put aside is the first one of the next run
+        assert_eq!(walker.stats().bytes_consumed, 8 + 65 + 64);
+        walker.clear_deadline();
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "b".to_string())));
+        assert_eq!(walker.next_item(), Ok((ValueType::UInt, "2".to_string())));
+
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        walker.set_max_consecutive_whitespace(None);
+        assert_eq!(walker.next_key_by_name("b").unwrap().1, "b");
+    }
+
+    #[test]
+    fn test_allow_comments() {
+        fn items(json: &str, allow_comments: bool) -> Result<Vec<(ValueType, String)>, Error> {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            walker.set_allow_comments(allow_comments);
+            let mut items = vec![];
+            loop {
+                match walker.next_item() {
+                    Ok(item) => items.push(item),
+                    Err(e) if e.kind == ErrorKind::EOS => return Ok(items),
+                    Err(e) => return Err(e),
                 }
-                Ok(t) => {
-                    assert!(t.1.eq(words[word_index]));
-                    word_index += 1;
+            }
+        }
+
+        // between a key and `:`, between elements, at the start and before EOF
+        let commented = format!(
+            "// head\n{}/* tail */ // end",
+            CORRECT_JSON.replace("\" :", "\" /* key */ :").replace(" , ", " // next\n, ").replace("[  ]", "[ /* [] */ ]")
+        );
+        assert_ne!(commented, CORRECT_JSON);
+        assert_eq!(items(&commented, true).unwrap(), items(CORRECT_JSON, false).unwrap());
+        assert_eq!(items("[1/**/,/***/2/*\n*/]//", true).unwrap().len(), 2);
+        assert_eq!(items("\"a\"// end", true).unwrap(), vec![(ValueType::Str, "a".to_string())]);
+
+        // comments are rejected by default
+        for json in [commented.as_str(), "[1, /* c */ 2]"] {
+            assert_eq!(items(json, false).unwrap_err().kind, ErrorKind::UnexpectedChar, "{json}");
+        }
+        assert_eq!(items("{\"a\": 1} // end", false).unwrap_err().kind, ErrorKind::TrailingCharacters);
+
+        // an unterminated block comment
+        for json in ["[1, /* c", "[1, /* c *", "{\"a\": 1} /*/"] {
+            assert_eq!(items(json, true).unwrap_err().kind, ErrorKind::UnexpectedEos, "{json}");
+        }
+        // a lone slash is not a comment
+        assert_eq!(items("[1, / 2]", true).unwrap_err().kind, ErrorKind::UnexpectedChar);
+    }
+
+    #[test]
+    fn test_lenient_syntax() {
+        fn items(json: &str, lenient_syntax: bool) -> Result<Vec<(ValueType, String)>, Error> {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            walker.set_lenient_syntax(lenient_syntax);
+            let mut items = vec![];
+            loop {
+                match walker.next_item() {
+                    Ok(item) => items.push(item),
+                    Err(e) if e.kind == ErrorKind::EOS => return Ok(items),
+                    Err(e) => return Err(e),
                 }
             }
         }
+        let str = |s: &str| (ValueType::Str, s.to_string());
+
+        // escaped quotation marks, and a " which needs no escape
+        let json = r#"{a: 'it\'s', "b": ['x\\\'y', "'", '"', '\"\u0041'], c_1 : {$d:'' , _e: "q\"q"}}"#;
+        assert_eq!(items(json, true).unwrap(), vec![
+            str("a"), str("it's"), str("b"), str("x\\'y"), str("'"), str("\""), str("\"A"), str("c_1"), str("$d"), str(""), str("_e"), str("q\"q"),
+        ]);
+        // keys which look like words are keys
+        assert_eq!(items("{true: null, null: false, nullx: 1}", true).unwrap(), vec![
+            str("true"), (ValueType::Null, "null".to_string()), str("null"), (ValueType::Bool, "false".to_string()), str("nullx"), (ValueType::UInt, "1".to_string()),
+        ]);
+        assert_eq!(items("'root'", true).unwrap(), vec![str("root")]);
+
+        // an identifier is not a value and a key does not start with a digit
+        for json in ["{a: b}", "{1a: 1}", "{a b: 1}", "{a-b: 1}"] {
+            assert!(items(json, true).is_err(), "{json}");
+        }
+        // the walker is strict by default
+        for json in ["{a: 1}", "['a']", "{\"a\": 'b'}", "{'a': 1}"] {
+            assert_eq!(items(json, false).unwrap_err().kind, ErrorKind::UnexpectedChar, "{json}");
+        }
+
+        // a deferred string and a skipped one
+        let mut walker = JsonWalker::new(StringReader::new(r#"[{k: 'a\'b"c'}, 'x\']', 2]"#.to_string()), 0);
+        walker.set_lenient_syntax(true);
+        walker.next_key_by_name("k").unwrap();
+        let mut streamed = Vec::new();
+        walker.stream_string_value(&mut streamed, StringSink::Raw).unwrap();
+        assert_eq!(streamed, b"a'b\"c");
+        walker.skip_current_value().unwrap();
+        assert_eq!(walker.next_item().unwrap(), (ValueType::UInt, "2".to_string()));
+    }
+
+    #[test]
+    fn test_multi_document() {
+        for json in ["{\"a\": 1}\n{\"a\": 2}\n{\"a\": 3}", "{\"a\": 1}   {\"a\": 2} {\"a\": 3}", "\n{\"a\": 1}\n{\"a\": 2}\r\n{\"a\": 3}\n", "{\"a\": 1}{\"a\": 2}{\"a\": 3}"] {
+            let mut walker = JsonWalker::new_multi(StringReader::new(json.to_string()), 0);
+            let mut values = vec![];
+            while walker.next_document().unwrap() {
+                values.push(walker.current_value_content().unwrap());
+            }
+            let object = |n: &str| Content::Object([("a".to_string(), Content::Simple((ValueType::UInt, n.to_string())))].into());
+            assert_eq!(values, [object("1"), object("2"), object("3")], "{json:?}");
+            assert!(walker.finish().is_ok());
+            assert_eq!(walker.next_document(), Ok(false));
+        }
+
+        // items go on from one document to the next one
+        let mut walker = JsonWalker::new_multi(StringReader::new("1 -2\n\"a\" [true] null".to_string()), 0);
+        let items: Vec<String> = std::iter::from_fn(|| walker.next_item().ok().map(|i| i.1)).collect();
+        assert_eq!(items, ["1", "-2", "a", "true", "null"]);
+
+        // the rest of a document is skipped
+        let mut walker = JsonWalker::new_multi(StringReader::new(r#"{"a": [1, 2], "b": 3} {"c": 4}"#.to_string()), 0);
+        assert!(walker.next_document().unwrap());
+        assert_eq!(walker.next_item().unwrap().1, "a");
+        assert_eq!(walker.next_item().unwrap().1, "1");
+        assert!(walker.next_document().unwrap());
+        assert_eq!(walker.next_key().unwrap().1, "c");
+        assert!(!walker.next_document().unwrap());
+
+        // an incomplete document
+        let mut walker = JsonWalker::new_multi(StringReader::new(r#"{"a": 1} {"b": "#.to_string()), 0);
+        walker.skip_current_value().unwrap();
+        assert!(walker.next_document().unwrap());
+        assert!(walker.next_key().is_ok());
+        assert!(walker.next_document().is_err());
+
+        // words need a white-space in between and a blank stream has no documents
+        let mut walker = JsonWalker::new_multi(StringReader::new("truefalse".to_string()), 0);
+        assert_eq!(walker.next_item().unwrap_err().kind, ErrorKind::InvalidToken);
+        assert_eq!(JsonWalker::new_multi(StringReader::new(" \n ".to_string()), 0).next_document(), Ok(false));
+
+        // a single document is expected by default
+        let mut walker = JsonWalker::new(StringReader::new("{} {}".to_string()), 0);
+        assert!(walker.next_document().unwrap());
+        walker.skip_current_value().unwrap();
+        assert_eq!(walker.next_document().unwrap_err().kind, ErrorKind::TrailingCharacters);
     }
 
     #[test]
@@ -635,7 +4360,7 @@ mod walker_tests {
                 if is_str {
                     ValueType::Str
                 } else {
-                    ValueType::Int
+                    ValueType::UInt
                 },
                 String::from(v),
             ))
@@ -690,19 +4415,800 @@ mod walker_tests {
         assert_eq!(a, Ok(item("10", false)));
     }
 
+    #[test]
+    fn test_stats_full_walk() {
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 50);
+        while walker.next_item().is_ok() {}
+        let stats = walker.stats();
+        assert_eq!(stats.keys, 27);
+        assert_eq!(stats.values(ValueType::Null), 5);
+        assert_eq!(stats.values(ValueType::Bool), 10);
+        assert_eq!(stats.values(ValueType::UInt), 5);
+        assert_eq!(stats.values(ValueType::Int), 0);
+        assert_eq!(stats.values(ValueType::Float), 5);
+        assert_eq!(stats.values(ValueType::Str), 5);
+        assert_eq!((stats.objects_opened, stats.objects_closed), (8, 8));
+        assert_eq!((stats.arrays_opened, stats.arrays_closed), (7, 7));
+        assert_eq!(stats.max_depth, 4);
+        assert_eq!(stats.bytes_consumed, CORRECT_JSON.len() as u64);
+        assert_eq!(stats.skipped_items, 0);
+
+        walker.reset_stats();
+        assert_eq!(walker.stats(), &Stats::default());
+    }
+
+    #[test]
+    fn test_stats_with_skip_current_value() {
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 50);
+        let _ = walker.next_key_by_name("key7");
+        assert_eq!(walker.skip_current_value(), Ok(ValueType::Obj));
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "key8".to_string())));
+        let stats = walker.stats();
+        assert_eq!(stats.keys, 8);
+        assert_eq!(stats.values(ValueType::Null), 1);
+        // 9 keys and 12 values of key7 are skipped
+        assert_eq!(stats.skipped_items, 21);
+        assert_eq!((stats.objects_opened, stats.objects_closed), (4, 3));
+        assert_eq!((stats.arrays_opened, stats.arrays_closed), (3, 3));
+        assert_eq!(stats.max_depth, 4);
+    }
+
+    #[test]
+    fn test_next_value() {
+        // only values must be retrieved in order
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        let values: Vec<String> = std::iter::from_fn(|| walker.next_value().ok().map(|v| v.1)).collect();
+        assert_eq!(values.len(), 30);
+        assert_eq!(values[..4], ["null", "true", "false", "111"]);
+        assert_eq!(values.last().map(|v| v.as_str()), Some("str5 \":{}[],"));
+
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.next_key_by_name("key4").unwrap();
+        assert_eq!(walker.next_value(), Ok((ValueType::UInt, "111".to_string())));
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "key5".to_string())));
+        // the first value inside an object
+        walker.next_key_by_name("key7").unwrap();
+        assert_eq!(walker.next_value(), Ok((ValueType::Null, "null".to_string())));
+        assert_eq!(walker.get_path_string(), "#/{key7,6}/{key71,0}/");
+
+        // a flat array of scalars
+        let mut walker = JsonWalker::new(StringReader::new("[1, \"a\", null]".to_string()), 0);
+        let values: Vec<String> = std::iter::from_fn(|| walker.next_value().ok().map(|v| v.1)).collect();
+        assert_eq!(values, ["1", "a", "null"]);
+        assert_eq!(walker.next_value().unwrap_err().kind, ErrorKind::EOS);
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.expect_null().unwrap();
+        assert_eq!(walker.next_bool(), Ok(true));
+        assert_eq!(walker.next_bool(), Ok(false));
+        assert_eq!(walker.next_i64(), Ok(111));
+        assert_eq!(walker.next_f64(), Ok(111.111));
+        assert_eq!(walker.next_string(), Ok("str1 \":{}[],".to_string()));
+        // the first value of key7
+        walker.expect_null().unwrap();
+
+        let json = r#"[18446744073709551615, 18446744073709551616, -9223372036854775808, -9223372036854775809, 9223372036854775808, -1, 1.5, 7, 1e400]"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        assert_eq!(walker.next_u64(), Ok(u64::MAX));
+        assert_eq!(walker.next_u64().unwrap_err().kind, ErrorKind::ParseIntError);
+        assert_eq!(walker.next_i64(), Ok(i64::MIN));
+        assert_eq!(walker.next_i64().unwrap_err().kind, ErrorKind::ParseIntError);
+        assert_eq!(walker.next_i64().unwrap_err().kind, ErrorKind::ParseIntError);
+        assert_eq!(walker.next_u64().unwrap_err().kind, ErrorKind::ParseIntError);
+        // a fraction is not an integer, while an integer is a number
+        assert_eq!(walker.next_i64().unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(walker.next_f64(), Ok(7.0));
+        assert_eq!(walker.next_f64().unwrap_err().kind, ErrorKind::ParseFloatError);
+
+        // types are checked before parsing, and the value is passed anyway
+        let mut walker = JsonWalker::new(StringReader::new(r#"["1", 1, null, "true", true]"#.to_string()), 0);
+        let e = walker.next_i64().unwrap_err();
+        assert_eq!(e.kind, ErrorKind::WrongDataType);
+        assert!(e.msg.starts_with("Expecting an integer but found Str `1`"), "{}", e.msg);
+        assert_eq!(walker.next_string().unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(walker.next_bool().unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(walker.expect_null().unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(walker.next_bool(), Ok(true));
+        assert_eq!(walker.next_bool().unwrap_err().kind, ErrorKind::EOS);
+    }
+
+    #[test]
+    fn test_current_key() {
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        assert_eq!(walker.current_key(), None);
+        walker.next_key_by_name("key78").unwrap();
+        assert_eq!(walker.current_key(), Some("key78"));
+        // in its array
+        walker.next_value().unwrap();
+        assert_eq!(walker.get_path_string(), "#/{key7,6}/{key78,6}/[key78,0]/");
+        assert_eq!(walker.current_key(), Some("key78"));
+        walker.next_key_by_name("key84").unwrap();
+        assert_eq!(walker.current_key(), Some("key84"));
+        assert_eq!(walker.current_key_owned(), Some("key84".to_string()));
+        // the value does not change it
+        walker.next_value().unwrap();
+        assert_eq!(walker.current_key(), Some("key84"));
+
+        // an object in an array has no key before its first one
+        let mut walker = JsonWalker::new(StringReader::new(r##"[{"#": [[1]]}, [2], {}]"##.to_string()), 0);
+        walk_forward(&mut walker);
+        assert_eq!(walker.current_key(), None);
+        walk_forward(&mut walker);
+        assert_eq!(walker.current_key(), None);
+        // `#` is a key as well
+        assert_eq!(walker.next_key().unwrap().1, "#");
+        assert_eq!(walker.current_key(), Some("#"));
+        // an array in an array takes the key of the outer one
+        walker.next_value().unwrap();
+        assert_eq!(walker.get_path_string(), "#/[#,0]/{#,0}/[#,0]/[#,0]/");
+        assert_eq!(walker.current_key(), Some("#"));
+        walker.next_value().unwrap();
+        assert_eq!(walker.current_key(), None);
+        // at the end of json
+        assert!(walker.next_item().is_err());
+        assert_eq!(walker.current_key(), None);
+    }
+
+    #[test]
+    fn test_next_value_by_key() {
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        assert_eq!(walker.next_value_by_key("key4"), Ok((ValueType::UInt, "111".to_string())));
+        assert_eq!(walker.next_value_by_key("key6"), Ok((ValueType::Str, "str1 \":{}[],".to_string())));
+        // the cursor stays before a container
+        assert_eq!(walker.next_value_by_key("key7").unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(walker.skip_current_value(), Ok(ValueType::Obj));
+        assert_eq!(walker.next_value_by_key("key8").unwrap_err().kind, ErrorKind::WrongDataType);
+        assert!(matches!(walker.current_value_content(), Ok(Content::Array(a)) if a.len() == 9));
+        assert_eq!(walker.next_value_by_key("key10").unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(walker.next_value_by_key("key1").unwrap_err().kind, ErrorKind::EOS);
+    }
+
+    #[test]
+    fn test_key_pattern() {
+        assert!(KeyPattern::exact("key7").matches("key7"));
+        assert!(!KeyPattern::exact("key7").matches("key71"));
+        assert!(KeyPattern::suffix("_id").matches("user_id"));
+        assert!(!KeyPattern::suffix("_id").matches("user_idx"));
+        assert!(KeyPattern::suffix("").matches(""));
+        assert!(KeyPattern::contains("ey7").matches("key71"));
+        assert!(!KeyPattern::contains("ey8").matches("key71"));
+        assert!(KeyPattern::prefix("é").matches("équipe"));
+
+        let glob = |pattern: &str, key: &str| KeyPattern::glob(pattern).matches(key);
+        assert!(glob("item-*-id", "item-7-id"));
+        assert!(glob("item-*-id", "item--id"));
+        assert!(glob("item-*-id", "item-a-id-b-id"));
+        assert!(!glob("item-*-id", "item-7-idx"));
+        assert!(glob("*", ""));
+        assert!(glob("a*b*c", "aXbYbZc"));
+        assert!(!glob("a*b*c", "aXbYbZ"));
+        assert!(glob("k?y?", "key7"));
+        assert!(!glob("k?y?", "key71"));
+        assert!(glob("?é", "xé"));
+
+        assert!(!KeyPattern::prefix("KEY").matches("key1"));
+        assert!(KeyPattern::prefix("KEY").with_ignore_case(true).matches("key1"));
+        assert!(KeyPattern::glob("*ÉTÉ").with_ignore_case(true).matches("l'été"));
+    }
+
+    #[test]
+    fn test_elements() {
+        let integers = |walker: &mut JsonWalker| {
+            let mut elements = walker.elements();
+            let mut sum = 0;
+            while let Some((_, walker)) = elements.next_element().unwrap() {
+                if let Ok(Content::Simple((ValueType::Int | ValueType::UInt, n))) = walker.current_value_content() {
+                    sum += n.parse::<i64>().unwrap();
+                }
+            }
+            sum
+        };
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.next_key_by_name("key78").unwrap();
+        assert_eq!(integers(&mut walker), 333);
+        // the cursor stands right after the array
+        assert_eq!(walker.next_key().unwrap().1, "key79");
+        let mut walker = JsonWalker::new(StringReader::new("[1, [2, 3], 4, {\"a\": 5}, -6]".to_string()), 0);
+        assert_eq!(integers(&mut walker), -1);
+
+        // elements are skipped by skip_current_value(), by leaving them untouched, or partly read
+        let json = r#"{"a": [0, {"b": [1]}, [2, [3]], 4, {"c": 5}, 6], "d": 7}"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.next_key().unwrap();
+        let mut elements = walker.elements();
+        let mut items = vec![];
+        while let Some((index, walker)) = elements.next_element().unwrap() {
+            match index {
+                0 | 4 => items.push(walker.current_value_content().unwrap()),
+                1 => assert!(walker.skip_current_value().is_ok()),
+                // only the first item of the element is read
+                2 => items.push(Content::Simple(walker.next_item().unwrap())),
+                _ => {}
+            }
+        }
+        assert_eq!(items, vec![
+            Content::Simple((ValueType::UInt, "0".to_string())),
+            Content::Simple((ValueType::UInt, "2".to_string())),
+            Content::Object(BTreeMap::from([("c".to_string(), Content::Simple((ValueType::UInt, "5".to_string())))])),
+        ]);
+        assert_eq!(elements.next_element().unwrap().map(|e| e.0), None);
+        assert_eq!(walker.next_key().unwrap().1, "d");
+
+        // the cursor may be in the array already, then elements are counted from there
+        let mut walker = JsonWalker::new(StringReader::new("[1, [], 2]".to_string()), 0);
+        walker.next_item().unwrap();
+        let mut elements = walker.elements();
+        assert_eq!(elements.next_element().unwrap().map(|e| e.0), Some(0));
+        let (index, walker) = elements.next_element().unwrap().unwrap();
+        assert_eq!((index, walker.next_value().unwrap().1), (1, "2".to_string()));
+        assert_eq!(elements.next_element().unwrap().map(|e| e.0), None);
+
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1}"#.to_string()), 0);
+        walker.next_key().unwrap();
+        assert_eq!(walker.elements().next_element().map(|e| e.is_some()).unwrap_err().kind, ErrorKind::WrongDataType);
+        let mut walker = JsonWalker::new(StringReader::new("[1, 2".to_string()), 0);
+        let mut elements = walker.elements();
+        assert!(elements.next_element().unwrap().is_some());
+        assert!(elements.next_element().unwrap().is_some());
+        assert_eq!(elements.next_element().map(|e| e.is_some()).unwrap_err().kind, ErrorKind::UnexpectedEos);
+    }
+
+    #[cfg(feature = "deserialize")]
+    #[test]
+    fn test_elements_de() {
+        #[derive(serde::Deserialize, Debug, PartialEq, Default)]
+        #[serde(default)]
+        struct Sample {
+            key82: bool,
+            key84: u32,
+        }
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.next_key_by_name("key8").unwrap();
+        let mut elements = walker.elements();
+        let mut samples = vec![];
+        while let Some((index, walker)) = elements.next_element().unwrap() {
+            // the 7th and 8th elements are objects
+            if index == 6 || index == 7 {
+                samples.push(walker.current_value::<Sample>().unwrap());
+            }
+        }
+        assert_eq!(samples, vec![Sample { key82: true, key84: 555 }, Sample::default()]);
+        assert_eq!(walker.next_key().unwrap().1, "key9");
+    }
+
+    #[test]
+    fn test_members() {
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.next_key_by_name("key7").unwrap();
+        let mut members = walker.members();
+        let mut keys = vec![];
+        while let Some((key, _)) = members.next_member().unwrap() {
+            keys.push(key);
+        }
+        assert_eq!(keys, ["key71", "key72", "key73", "key74", "key75", "key76", "key78", "key79", "key710"]);
+        // values which are left untouched are skipped
+        assert_eq!(walker.next_key().unwrap().1, "key8");
+
+        let json = r#"{"a": {"b": [1, {"c": 2}], "d": {"e": 3}, "f": [4], "g": 5, "h": {}}, "i": 6}"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.next_key().unwrap();
+        let mut members = walker.members();
+        let mut items = vec![];
+        while let Some((key, walker)) = members.next_member().unwrap() {
+            match key.as_str() {
+                // only the first item of the value is read
+                "b" => items.push(walker.next_item().unwrap().1),
+                "d" => items.push(format!("{:?}", walker.current_value_content().unwrap())),
+                "f" => assert_eq!(walker.skip_current_value(), Ok(ValueType::Arr)),
+                "g" => items.push(walker.next_value().unwrap().1),
+                _ => items.push(key),
+            }
+        }
+        assert_eq!(items, vec!["1".to_string(), r#"Object({"e": Simple((UInt, "3"))})"#.to_string(), "5".to_string(), "h".to_string()]);
+        assert!(members.next_member().unwrap().is_none());
+        assert_eq!(walker.next_key().unwrap().1, "i");
+
+        // the cursor may be in the object already
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": {"x": 1}, "b": 2, "c": 3}"#.to_string()), 0);
+        walker.next_key().unwrap();
+        walker.skip_current_value().unwrap();
+        let mut members = walker.members();
+        assert_eq!(members.next_member().unwrap().map(|m| m.0), Some("b".to_string()));
+        assert_eq!(members.next_member().unwrap().map(|m| m.0), Some("c".to_string()));
+        assert_eq!(members.next_member().unwrap().map(|m| m.0), None);
+        assert_eq!(walker.next_byte, NIL);
+
+        let mut walker = JsonWalker::new(StringReader::new("[{}]".to_string()), 0);
+        assert_eq!(walker.members().next_member().map(|m| m.is_some()).unwrap_err().kind, ErrorKind::WrongDataType);
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1"#.to_string()), 0);
+        let mut members = walker.members();
+        assert!(members.next_member().unwrap().is_some());
+        assert_eq!(members.next_member().map(|m| m.is_some()).unwrap_err().kind, ErrorKind::UnexpectedEos);
+    }
+
+    #[test]
+    fn test_next_sibling_key_by_name() {
+        let json = r#"{"user": {"name": "a", "order": {"id": 7}, "tags": [{"id": 8}], "id": 3}, "id": 9}"#;
+        let walker = || JsonWalker::new(StringReader::new(json.to_string()), 0);
+        // ids of children are not matched
+        let mut w = walker();
+        assert_eq!(w.next_sibling_key_by_name("id").unwrap().1, "id");
+        assert_eq!(w.next_value().unwrap().1, "9");
+        let mut w = walker();
+        w.next_key_by_name("name").unwrap();
+        assert_eq!(w.next_sibling_key_by_name("id").unwrap().1, "id");
+        assert_eq!(w.next_value().unwrap().1, "3");
+        let mut w = walker();
+        w.next_key_by_name("user").unwrap();
+        w.next_sibling_key_by_name("id").unwrap();
+        assert_eq!(w.next_value().unwrap().1, "9");
+
+        // the search stops at the end of the object, before its closing bracket
+        let mut w = walker();
+        w.next_key_by_name("order").unwrap();
+        assert_eq!(w.next_sibling_key_by_name("name"), Err(Error::new_eos()));
+        assert_eq!(w.next_byte, b'}');
+        assert_eq!(w.next_sibling_key_by_name("id"), Err(Error::new_eos()));
+        assert_eq!(w.next_key_from_parent().unwrap().1, "id");
+        assert_eq!(w.next_value().unwrap().1, "9");
+
+        let mut w = walker();
+        w.next_key_by_name("name").unwrap();
+        assert_eq!(w.next_key_by_name_within("id", 1).unwrap().1, "id");
+        assert_eq!(w.next_value().unwrap().1, "7");
+        // the cursor is in "order" now
+        assert_eq!(w.next_key_by_name_within("id", 1), Err(Error::new_eos()));
+        assert_eq!(w.next_key_from_parent().unwrap().1, "tags");
+        // the ids of elements of an array are 2 levels deeper
+        assert_eq!(w.next_key_by_name_within("id", 1).unwrap().1, "id");
+        assert_eq!(w.next_value().unwrap().1, "3");
+        let mut w = walker();
+        w.next_key_by_name("tags").unwrap();
+        assert_eq!(w.next_key_by_name_within("id", 2).unwrap().1, "id");
+        assert_eq!(w.next_value().unwrap().1, "8");
+
+        let mut w = JsonWalker::new(StringReader::new("[1, 2]".to_string()), 0);
+        w.next_item().unwrap();
+        assert_eq!(w.next_sibling_key_by_name("id").unwrap_err().kind, ErrorKind::InvalidStructure);
+    }
+
+    #[test]
+    fn test_next_key_matching() {
+        // the prefix matches keys of nested objects too
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        let mut keys = vec![];
+        while let Ok(key) = walker.next_key_matching(&KeyPattern::prefix("key7")) {
+            keys.push(key.1);
+        }
+        assert_eq!(keys, ["key7", "key71", "key72", "key73", "key74", "key75", "key76", "key78", "key79", "key710"]);
+
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        assert_eq!(walker.next_key_matching(&KeyPattern::glob("key8?")).unwrap().1, "key81");
+        assert_eq!(walker.next_key_matching(&KeyPattern::suffix("10")).unwrap().1, "key810");
+        assert_eq!(walker.next_key_matching(&KeyPattern::contains("x")), Err(Error::new_eos()));
+        assert_eq!(walker.next_byte, NIL);
+
+        let json = r#"{"Name": 1, "nAmE": 2, "name": 3}"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        assert_eq!(walker.next_key_matching(&KeyPattern::exact("name")).unwrap().1, "name");
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        assert_eq!(walker.next_key_matching(&KeyPattern::exact("name").with_ignore_case(true)).unwrap().1, "Name");
+        assert_eq!(walker.next_key_matching(&KeyPattern::exact("name").with_ignore_case(true)).unwrap().1, "nAmE");
+
+        // keys are checked in their decoded form
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1, "caf\u00e9": 2}"#.to_string()), 0);
+        walker.set_keep_escapes(true);
+        assert_eq!(walker.next_key_where(|key| key.ends_with('é')).unwrap().1, "caf\\u00e9");
+    }
+
+    #[test]
+    fn test_seek_to_path() {
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        assert_eq!(walker.seek_to_path("#/{key4}"), Ok((ValueType::Str, "key4".to_string())));
+        // the node of key4 is behind the cursor now
+        let mut walker2 = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker2.seek_to_path("#/{key4}").unwrap();
+        assert_eq!(walker2.seek_to_path("#/{key4}"), Err(Error::new_eos()));
+        assert_eq!(walker.seek_to_path("#/{key7}/{key78}/[3]"), Ok((ValueType::UInt, "333".to_string())));
+        assert_eq!(walker.get_path_string(), "#/{key7,6}/{key78,6}/[key78,3]/");
+        // the first item of an object element is its first key
+        assert_eq!(walker.seek_to_path("#/{key8}/[6]").unwrap().1, "key81");
+        assert_eq!(walker.seek_to_path("#/{key8}/[6]/{key84}").unwrap().1, "key84");
+        assert_eq!(walker.get_path_string(), "#/{key8,7}/[key8,6]/{key84,3}/");
+        assert_eq!(walker.seek_to_path("#/{key8}/[6]/{key81}"), Err(Error::new_eos()));
+        assert_eq!(walker.next_byte, NIL);
+
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        assert_eq!(walker.seek_to_path("#/{key8}/[6]/{key81}/").unwrap().1, "key81");
+        assert_eq!(walker.seek_to_path("#/{key8}/[20]"), Err(Error::new_eos()));
+
+        // wildcards take the matching nodes one by one
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        assert_eq!(walker.seek_to_path("#/{key7}/{key78}/[*]").unwrap().1, "null");
+        assert_eq!(walker.seek_to_path("#/{key7}/{key78}/[*]").unwrap().1, "true");
+        assert_eq!(walker.seek_to_path("#/*/[*]/{key82}").unwrap().1, "key82");
+        assert_eq!(walker.seek_to_path("#/{*}").unwrap().1, "key9");
+
+        let mut walker = JsonWalker::new(StringReader::new("[[1, 2], [3, 4]]".to_string()), 0);
+        assert_eq!(walker.seek_to_path("#/[*]/[1]").unwrap().1, "2");
+        assert_eq!(walker.seek_to_path("#/[*]/[1]").unwrap().1, "4");
+        assert_eq!(walker.seek_to_path("#/[1]").unwrap_err().kind, ErrorKind::EOS);
+        assert_eq!(walker.seek_to_path("[1]").unwrap_err().kind, ErrorKind::InvalidPath);
+    }
+
+    #[test]
+    fn test_enter_exit() {
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        assert_eq!(walker.enter(), Ok(ContainerKind::Object));
+        assert_eq!(walker.get_path_string(), "#/{,0}/");
+        assert_eq!(walker.next_key().unwrap().1, "key1");
+        while walker.next_sibling_key().unwrap().1 != "key7" {}
+        assert_eq!(walker.enter(), Ok(ContainerKind::Object));
+        assert_eq!(walker.get_path_string(), "#/{key7,6}/{,0}/");
+        assert_eq!(walker.next_key().unwrap().1, "key71");
+        while walker.next_sibling_key().unwrap().1 != "key78" {}
+        assert_eq!(walker.enter(), Ok(ContainerKind::Array));
+        assert_eq!(walker.get_path_string(), "#/{key7,6}/{key78,6}/[key78,0]/");
+        // a scalar can not be entered and the cursor stays before it
+        assert_eq!(walker.enter().unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(walker.next_item().unwrap().1, "null");
+        walker.exit_current().unwrap();
+        assert_eq!(walker.next_sibling_key().unwrap().1, "key79");
+        walker.exit_current().unwrap();
+        assert_eq!(walker.next_sibling_key().unwrap().1, "key8");
+        assert_eq!(walker.enter(), Ok(ContainerKind::Array));
+        // the 7th element is an object
+        for _ in 0..6 {
+            walker.skip_current_value().unwrap();
+        }
+        assert_eq!(walker.enter(), Ok(ContainerKind::Object));
+        assert_eq!(walker.next_key().unwrap().1, "key81");
+        assert_eq!(walker.get_path_string(), "#/{key8,7}/[key8,6]/{key81,0}/");
+        walker.exit_current().unwrap();
+        assert_eq!(walker.enter(), Ok(ContainerKind::Object));
+        assert_eq!(walker.get_path_string(), "#/{key8,7}/[key8,7]/{,0}/");
+        // an empty container is closed right away
+        walker.exit_current().unwrap();
+        assert_eq!(walker.enter(), Ok(ContainerKind::Array));
+        walker.exit_current().unwrap();
+        walker.exit_current().unwrap();
+        assert_eq!(walker.next_sibling_key().unwrap().1, "key9");
+        // the root is the last container
+        walker.exit_current().unwrap();
+        assert!(walker.finish().is_ok());
+        assert_eq!(walker.exit_current().unwrap_err().kind, ErrorKind::InvalidStructure);
+        assert_eq!(walker.enter().unwrap_err().kind, ErrorKind::EOS);
+
+        // the stream is cut before the end of the container
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1, 2"#.to_string()), 0);
+        walker.next_key().unwrap();
+        assert_eq!(walker.enter(), Ok(ContainerKind::Array));
+        assert_eq!(walker.exit_current().unwrap_err().kind, ErrorKind::UnexpectedEos);
+    }
+
+    #[test]
+    fn test_skip_current_value() {
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 50);
+        let _ = walker.next_key_by_name("key4");
+        assert_eq!(walker.skip_current_value(), Ok(ValueType::UInt));
+        assert_eq!(walker.next_sibling_key(), Ok((ValueType::Str, "key5".to_string())));
+        let _ = walker.next_key_by_name("key710");
+        assert_eq!(walker.skip_current_value(), Ok(ValueType::Arr));
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "key8".to_string())));
+
+        // the whole object of key7, whose nested keys are not returned as items
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.next_key_by_name("key7").unwrap();
+        let before = walker.stats().keys;
+        assert_eq!(walker.skip_current_value(), Ok(ValueType::Obj));
+        assert_eq!(walker.next_sibling_key(), Ok((ValueType::Str, "key8".to_string())));
+        assert_eq!(walker.stats().keys, before + 1);
+        assert_eq!(walker.get_path_string(), "#/{key8,7}/");
+
+        // containers inside an array
+        let mut walker = JsonWalker::new(StringReader::new(r#"[1,{"a":[1]},[2,{}],3]"#.to_string()), 50);
+        assert_eq!(walker.next_item(), Ok((ValueType::UInt, "1".to_string())));
+        assert_eq!(walker.skip_current_value(), Ok(ValueType::Obj));
+        assert_eq!(walker.skip_current_value(), Ok(ValueType::Arr));
+        assert_eq!(walker.next_item(), Ok((ValueType::UInt, "3".to_string())));
+    }
+
+    #[test]
+    fn test_take_sample() {
+        let elements: Vec<String> = (0..50000).map(|i| format!(r#"{{"id": {i}, "tags": ["t{i}"]}}"#)).collect();
+        let json = format!(r#"{{"meta": {{"count": 50000}}, "results": [{}]}}"#, elements.join(", "));
+        let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+        let sample = walker.take_sample("/results", 5).unwrap();
+        assert_eq!(sample.len(), 5);
+        for (i, c) in sample.iter().enumerate() {
+            match c {
+                Content::Object(m) => assert_eq!(m["id"], Content::Simple((ValueType::UInt, i.to_string()))),
+                _ => panic!("{c:?}"),
+            }
+        }
+        // reading stops right after the 5th element
+        let consumed = walker.stats().bytes_consumed as usize;
+        assert!(consumed < 250, "{consumed}");
+        assert!(json[..consumed].ends_with(r#"{"id": 4, "tags": ["t4"]},"#) || json[..consumed].ends_with(r#"{"id": 4, "tags": ["t4"]}"#));
+
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [1, [2]], "b": 1}"#.to_string()), 0);
+        assert_eq!(walker.take_sample("/a", 10).unwrap().len(), 2);
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "b".to_string())));
+
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        assert_eq!(walker.take_sample("/meta", 1).unwrap_err().kind, crate::ErrorKind::WrongDataType);
+    }
+
+    #[test]
+    fn test_stream_string_value() {
+        // 1MB of pseudo-random bytes, while json writers may escape "/" as "\/"
+        let mut seed = 7u32;
+        let blob: Vec<u8> = (0..1 << 20).map(|_| {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            (seed >> 24) as u8
+        }).collect();
+        let encoded = crate::base64::base64_tests::encode(&blob).replace('/', "\\/");
+        let json = format!(r#"{{"name": "x\ty", "blob": "{encoded}" , "after": 1}}"#);
+        // the reader hands over the json in uneven chunks
+        let chunks: Vec<Vec<u8>> = json.as_bytes().chunks(1000 + 7).map(|c| c.to_vec()).collect();
+        let mut walker = JsonWalker::new(Box::new(chunks.into_iter().flatten()), 0);
+
+        walker.next_key_by_name("name").unwrap();
+        let mut name = Vec::new();
+        assert_eq!(walker.stream_string_value(&mut name, StringSink::Raw), Ok(3));
+        assert_eq!(name, b"x\ty");
+
+        walker.next_key_by_name("blob").unwrap();
+        let mut out = Vec::new();
+        assert_eq!(walker.stream_string_value(&mut out, StringSink::Base64), Ok(blob.len() as u64));
+        assert!(out == blob);
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "after".to_string())));
+
+        let mut walker = JsonWalker::new(StringReader::new(r#"["\ud83d\ude00 é", "aGV*sbG8=", 1]"#.to_string()), 0);
+        walker.move_n_element_forward(1);
+        let mut out = Vec::new();
+        walker.stream_string_value(&mut out, StringSink::Raw).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "😀 é");
+        let e = walker.stream_string_value(Vec::new(), StringSink::Base64).unwrap_err();
+        assert_eq!(e.msg, "Invalid base64 at byte 3");
+        assert_eq!(walker.stream_string_value(Vec::new(), StringSink::Raw).unwrap_err().kind, crate::ErrorKind::WrongDataType);
+    }
+
+    #[test]
+    fn test_query() {
+        let query = |expr: &str| -> Vec<Content> {
+            JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0).query(expr).unwrap().collect::<Result<_, _>>().unwrap()
+        };
+        let simple = |t: ValueType, s: &str| Content::Simple((t, s.to_string()));
+
+        assert_eq!(query(".key4"), vec![simple(ValueType::UInt, "111")]);
+        assert_eq!(query(".key7.key74"), vec![simple(ValueType::UInt, "222")]);
+        assert_eq!(query(".key7.key78[5]"), vec![simple(ValueType::Str, "str3 \":{}[],")]);
+        assert_eq!(query(".key8[3]"), vec![simple(ValueType::UInt, "444")]);
+        assert_eq!(query(".key8[]").len(), 9);
+        assert_eq!(query(".key8[].key84"), vec![simple(ValueType::UInt, "555")]);
+        assert_eq!(query(".key8[6].key810"), vec![Content::Array(vec![])]);
+        assert_eq!(query(".key7[]").len(), 9);
+        assert_eq!(query(".key8[9]"), vec![]);
+        assert_eq!(query(".nothing"), vec![]);
+        match &query(".")[..] {
+            [Content::Object(m)] => assert_eq!(m.len(), 10),
+            r => panic!("{r:?}"),
+        }
+
+        // the iterator is lazy, so the walker goes on from the first match
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        assert_eq!(walker.query(".key7.key71").unwrap().next(), Some(Ok(simple(ValueType::Null, "null"))));
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "key72".to_string())));
+        assert_eq!(walker.query(".key7.key78[]").unwrap().take(8).count(), 8);
+        assert_eq!(walker.query(".key8[0]").unwrap().next(), Some(Ok(simple(ValueType::Null, "null"))));
+
+        let e = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0).query(".key8[] | .key84").err().unwrap();
+        assert_eq!(e.kind, crate::ErrorKind::InvalidPath);
+    }
+
+    #[test]
+    fn test_search_budget() {
+        // about 1MB without the key
+        let items: Vec<String> = (0..40000).map(|i| format!(r#"{{"id": {i}, "name": "n{i}"}}"#)).collect();
+        let json = format!(r#"{{"items": [{}], "last": true}}"#, items.join(","));
+        assert!(json.len() > 1 << 20);
+        let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+
+        let options = SearchOptions::default().with_max_bytes(1024);
+        let e = walker.next_key_by_name_with("missing", &options).unwrap_err();
+        assert_eq!(e.kind, crate::ErrorKind::BudgetExhausted);
+        let consumed = walker.stats().bytes_consumed;
+        assert!(consumed < 1100, "{consumed}");
+        assert!(e.msg.ends_with("and 1024 bytes"), "{}", e.msg);
+
+        // the walker is still usable and another budget starts from where the previous one stopped
+        let e = walker.next_key_by_name_with("missing", &SearchOptions::default().with_max_items(10)).unwrap_err();
+        assert!(e.msg.starts_with("Search budget exhausted after 10 items"), "{}", e.msg);
+        assert_eq!(walker.next_key_by_name("last"), Ok((ValueType::Str, "last".to_string())));
+
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        let pattern = [|cs: &CurrentState| cs.is_key && cs.current_item.1 == "name" && cs.level == 3.0];
+        assert_eq!(walker.next_item_by_pattern_with(&pattern, &SearchOptions::default().with_max_items(3)).unwrap_err().kind, crate::ErrorKind::BudgetExhausted);
+        assert_eq!(walker.next_item_by_pattern_with(&pattern, &SearchOptions::default().with_max_items(4)), Ok((ValueType::Str, "name".to_string())));
+    }
+
+    #[test]
+    fn test_deadline() {
+        // 1ms per byte, so reading the whole json takes more than 10 seconds
+        let slow_reader = || {
+            let json = format!(r#"{{"items": [{}], "last": 1}}"#, vec!["1"; 5000].join(","));
+            Box::new(json.into_bytes().into_iter().inspect(|_| std::thread::sleep(Duration::from_millis(1))))
+        };
+
+        let mut walker = JsonWalker::new(slow_reader(), 0);
+        let start = Instant::now();
+        walker.set_deadline(start + Duration::from_millis(100));
+        let e = walker.next_key_by_name("last").unwrap_err();
+        let elapsed = start.elapsed();
+        assert_eq!(e.kind, crate::ErrorKind::DeadlineExceeded);
+        assert!(elapsed < Duration::from_millis(1000), "{elapsed:?}");
+        assert!(e.msg.starts_with("Deadline exceeded at byte "), "{}", e.msg);
+        assert!(e.msg.contains("path: #/{items,0}/[items,"), "{}", e.msg);
+        // the error is returned again till the deadline is moved
+        assert_eq!(walker.next_item().unwrap_err().kind, crate::ErrorKind::DeadlineExceeded);
+
+        let mut walker = JsonWalker::new(slow_reader(), 0);
+        walker.next_key().unwrap();
+        let start = Instant::now();
+        walker.set_deadline(start + Duration::from_millis(100));
+        assert_eq!(walker.skip_current_value().unwrap_err().kind, crate::ErrorKind::DeadlineExceeded);
+        assert!(start.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_max_content_bytes() {
+        // about 10MB
+        let items: Vec<String> = (0..200_000).map(|i| format!(r#"{{"id": {i}, "text": "{}"}}"#, "x".repeat(30))).collect();
+        let json = format!(r#"{{"items": [{}], "small": [1, "a"], "last": 1}}"#, items.join(","));
+        assert!(json.len() > 10_000_000);
+
+        let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+        walker.set_max_content_bytes(Some(1 << 20));
+        walker.next_key_by_name("items").unwrap();
+        let e = walker.current_value_content().unwrap_err();
+        assert_eq!(e.kind, crate::ErrorKind::LimitExceeded);
+        // the rest of the array is skipped, not built
+        assert!(walker.stats().skipped_items > 500_000);
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "small".to_string())));
+        assert_eq!(walker.current_value_content().unwrap(), Content::Array(vec![
+            Content::Simple((ValueType::UInt, "1".to_string())),
+            Content::Simple((ValueType::Str, "a".to_string())),
+        ]));
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "last".to_string())));
+
+        // the error can be followed by skip_current_value() of the next value as well
+        let mut walker = JsonWalker::new(StringReader::new(r#"[["aaaaaaaaaaaaaaaaaaaa"], {"k": "v"}, 1]"#.to_string()), 0);
+        walker.set_max_content_bytes(Some(64));
+        walker.move_n_element_forward(1);
+        assert_eq!(walker.current_value_content().unwrap_err().kind, crate::ErrorKind::LimitExceeded);
+        assert_eq!(walker.skip_current_value(), Ok(ValueType::Obj));
+        assert_eq!(walker.current_value_content(), Ok(Content::Simple((ValueType::UInt, "1".to_string()))));
+
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        walker.set_max_content_bytes(Some(1 << 20));
+        let results: Vec<Result<Content, crate::Error>> = walker.query(".[]").unwrap().collect();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap_err().kind, crate::ErrorKind::LimitExceeded);
+        assert!(results[1].is_ok() && results[2].is_ok());
+    }
+
+    #[test]
+    fn test_walk_with() {
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": {"b": [1, {}, [true]]}, "c~": null, "d": 2}"#.to_string()), 0);
+        walker.next_key_by_name("a").unwrap();
+        let mut events = vec![];
+        walker.walk_with(&mut |pointer, item| {
+            events.push(format!("{pointer} {}", match item {
+                TextItem::Key(k) => format!("key {}", k.1),
+                TextItem::Value(v) => v.1.clone(),
+                TextItem::None(b) => (*b as char).to_string(),
+            }));
+            Ok(())
+        }).unwrap();
+        assert_eq!(events, vec![" {", "/b key b", "/b [", "/b/0 1", "/b/1 {", "/b/1 }", "/b/2 [", "/b/2/0 true", "/b/2 ]", "/b ]", " }"]);
+        // the cursor stands right after the value
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "c~".to_string())));
+
+        let mut pointers = vec![];
+        walker.walk_with(&mut |pointer, _| {
+            pointers.push(pointer.to_string());
+            Ok(())
+        }).unwrap();
+        assert_eq!(pointers, vec![""]);
+
+        let mut walker = JsonWalker::new(StringReader::new(r#"[{"c~/": 1}, 2]"#.to_string()), 0);
+        let mut count = 0;
+        let e = walker.walk_with(&mut |pointer, _| {
+            count += 1;
+            if pointer == "/0/c~0~1" { Err(crate::Error::test_failed(String::new())) } else { Ok(()) }
+        });
+        assert_eq!(e.unwrap_err().kind, crate::ErrorKind::TestFailed);
+        assert_eq!(count, 3);
+        let e = JsonWalker::new(StringReader::new("[1, ".to_string()), 0).walk_with(&mut |_, _| Ok(())).unwrap_err();
+        assert_eq!(e.kind, crate::ErrorKind::UnexpectedEos);
+    }
+
+    #[test]
+    fn test_filter() {
+        // items with their paths and levels, without the ones under key8
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        let mut expected = vec![];
+        while let Ok(item) = walker.next_item() {
+            let path = walker.get_path_string();
+            if !path.starts_with("#/{key8,") {
+                expected.push((item, path, walker.get_current_level()));
+            }
+        }
+        assert_eq!(expected.len(), 36);
+
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.set_filter(None, Some(PathMatcher::new("/key8").unwrap()));
+        let mut items = vec![];
+        while let Ok(item) = walker.next_item() {
+            items.push((item, walker.get_path_string(), walker.get_current_level()));
+        }
+        assert_eq!(items, expected);
+
+        // hidden members are absent from the built values
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.set_filter(None, Some(PathMatcher::new("/key7/*").unwrap()));
+        let mut content = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0).current_value_content().unwrap();
+        if let Content::Object(m) = &mut content {
+            m.insert("key7".to_string(), Content::Object(BTreeMap::new()));
+        }
+        assert_eq!(walker.current_value_content().unwrap(), content);
+
+        let json = r#"{"a": [1, {"b": 2, "c": 3}, [4], 5], "d": {"b": 6}, "e": 7}"#;
+        let filtered = |include: Option<&str>, exclude: Option<&str>| {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            walker.set_filter(include.map(|p| PathMatcher::new(p).unwrap()), exclude.map(|p| PathMatcher::new(p).unwrap()));
+            let content = walker.current_value_content().unwrap();
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            walker.set_filter(include.map(|p| PathMatcher::new(p).unwrap()), exclude.map(|p| PathMatcher::new(p).unwrap()));
+            let mut items = vec![];
+            while let Ok(item) = walker.next_item() {
+                items.push(item.1);
+            }
+            let mut w = JsonWriter::new(Vec::new());
+            write_content(&content, &mut w).unwrap();
+            (String::from_utf8(w.finish().unwrap()).unwrap(), items.join(" "))
+        };
+        assert_eq!(filtered(None, Some("/a/1")), (r#"{"a":[1,[4],5],"d":{"b":6},"e":7}"#.to_string(), "a 1 4 5 d b 6 e 7".to_string()));
+        assert_eq!(filtered(None, Some("/a/3")), (r#"{"a":[1,{"b":2,"c":3},[4]],"d":{"b":6},"e":7}"#.to_string(), "a 1 b 2 c 3 4 d b 6 e 7".to_string()));
+        assert_eq!(filtered(None, Some("/*/b")), (r#"{"a":[1,{"b":2,"c":3},[4],5],"d":{},"e":7}"#.to_string(), "a 1 b 2 c 3 4 5 d e 7".to_string()));
+        assert_eq!(filtered(Some("/a/*/c"), None), (r#"{"a":[{"c":3},[]]}"#.to_string(), "a c 3".to_string()));
+        assert_eq!(filtered(Some("/a"), Some("/a/*/b")), (r#"{"a":[1,{"c":3},[4],5]}"#.to_string(), "a 1 c 3 4 5".to_string()));
+        assert_eq!(filtered(Some("/d/b"), None), (r#"{"d":{"b":6}}"#.to_string(), "d b 6".to_string()));
+    }
+
     #[test]
     fn test_json_file() {}
 }
 
 #[cfg(test)]
 #[cfg(feature = "deserialize")]
-mod walker_test_de {
-    use crate::json_walker::JsonWalker;
-    use crate::json_walker::walker_test_de::data1::MixedDataTypes;
+pub(crate) mod walker_test_de {
+    use std::collections::BTreeMap;
+    use std::ops::ControlFlow;
+
+    use crate::{Error, ErrorKind};
+    use crate::json_walker::{JsonWalker, KeyScope, ValueType};
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::json_walker::walker_test_de::data1::{Color, Message, MixedDataTypes, Point};
     use crate::json_walker::walker_test_de::data2::Person;
+    use crate::path_matcher::PathMatcher;
     use crate::readers::StringReader;
 
-    mod data1 {
+    pub(crate) mod data1 {
         use serde::{Deserialize, Serialize};
 
         pub fn create_data() -> MixedDataTypes {
@@ -768,7 +5274,7 @@ mod walker_test_de {
         }
     }
 
-    mod data2 {
+    pub(crate) mod data2 {
         use serde::{Deserialize, Serialize};
 
         pub fn create_data() -> Vec<Person> {
@@ -782,7 +5288,7 @@ mod walker_test_de {
                         city: "New York".to_string(),
                         country: "USA".to_string(),
                     },
-                    hobbies: vec!["reading", "painting", "hiking"].iter().map(|s| s.to_string()).collect(),
+                    hobbies: ["reading", "painting", "hiking"].iter().map(|s| s.to_string()).collect(),
                     favorite_color: Color::Blue,
                     height: 1.75,
                     weight: -65.5,
@@ -812,7 +5318,7 @@ mod walker_test_de {
                         city: "Hamedan-Hamedan".to_string(),
                         country: "Iran".to_string(),
                     },
-                    hobbies: vec!["gaming", "mount climbing", "bicycle"].iter().map(|s| s.to_string()).collect(),
+                    hobbies: ["gaming", "mount climbing", "bicycle"].iter().map(|s| s.to_string()).collect(),
                     favorite_color: Color::Green,
                     height: 164.1,
                     weight: -82.3,
@@ -829,6 +5335,8 @@ mod walker_test_de {
             Blue,
         }
 
+        type FriendEntry = (Friend, bool, Address, Vec<String>);
+
         #[derive(Debug, Serialize, Deserialize, PartialEq)]
         pub struct Person {
             name: String,
@@ -840,7 +5348,7 @@ mod walker_test_de {
             height: f64,
             weight: f64,
             // Option<Vec<(Friend, is_close, Address, Vec<phones>)>>
-            friends: Option<Vec<(Friend, bool, Address, Vec<String>)>>,
+            friends: Option<Vec<FriendEntry>>,
             is_iranian: bool,
 
         }
@@ -868,6 +5376,313 @@ mod walker_test_de {
         assert_eq!(de, data);
     }
 
+    #[test]
+    fn test_error_path_de() {
+        let json = serde_json::to_string(&data2::create_data()).unwrap().replace(r#""age":-32"#, r#""age":"old""#);
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        let e = walker.current_value::<Vec<Person>>().unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::ParseIntError);
+        assert_eq!(e.path(), Some("#/[#,0]/{friends,8}/[friends,1]/[friends,0]/{age,1}/"));
+        assert!(e.is_recovered());
+
+        // errors of de::Error::custom()
+        let json = r#"{"a": {"b": [{"c": "Red"}, {"c": "Green", "d": "Purple"}]}}"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        let e = walker.current_value::<BTreeMap<String, BTreeMap<String, Vec<BTreeMap<String, Color>>>>>().unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::Serde);
+        assert_eq!(e.path(), Some("#/{a,0}/{b,0}/[b,1]/{d,1}/"));
+    }
+
+    fn next_field<T>(walker: &mut JsonWalker, name: &str) -> T where T: for<'a> serde::de::Deserialize<'a> {
+        assert_eq!(walker.next_key().unwrap().1, name);
+        walker.current_value::<T>().unwrap()
+    }
+
+    #[test]
+    fn test_current_value_postcondition_with_next_key() {
+        // each field gets deserialized separately, then next_key() must return the next field of the same object
+        let data = data1::create_data();
+        let json = serde_json::to_string(&data).unwrap();
+        let mut walker = JsonWalker::new(StringReader::new(json), 50);
+        assert_eq!(next_field::<Option<usize>>(&mut walker, "null"), data.null);
+        assert_eq!(next_field::<Option<usize>>(&mut walker, "unsigned"), data.unsigned);
+        assert_eq!(next_field::<[i32; 2]>(&mut walker, "int"), data.int);
+        assert_eq!(next_field::<[f32; 2]>(&mut walker, "float1"), data.float1);
+        assert_eq!(next_field::<char>(&mut walker, "character"), data.character);
+        assert_eq!(next_field::<bool>(&mut walker, "boolean"), data.boolean);
+        assert_eq!(next_field::<String>(&mut walker, "string"), data.string);
+        assert_eq!(next_field::<Vec<u8>>(&mut walker, "bytes"), data.bytes);
+        assert_eq!(next_field::<(i32, f32, char, Option<String>, Vec<i32>, Color, Point)>(&mut walker, "tuple"), data.tuple);
+        assert_eq!(next_field::<Vec<(char, String)>>(&mut walker, "array1"), data.array1);
+        assert_eq!(next_field::<Vec<Point>>(&mut walker, "array2"), data.array2);
+        assert_eq!(next_field::<Vec<Color>>(&mut walker, "array3"), data.array3);
+        assert_eq!(next_field::<Message>(&mut walker, "enum1"), data.enum1);
+        assert_eq!(next_field::<Message>(&mut walker, "enum2"), data.enum2);
+        assert_eq!(next_field::<Message>(&mut walker, "enum3"), data.enum3);
+        assert_eq!(next_field::<Message>(&mut walker, "enum4"), data.enum4);
+        assert_eq!(walker.next_key(), Err(Error::new_eos()));
+    }
+
+    #[test]
+    fn test_current_value_postcondition_with_next_sibling_key() {
+        // container values are deserialized, then the rest of siblings must be reachable by next_sibling_key()
+        let data = data1::create_data();
+        let json = serde_json::to_string(&data).unwrap();
+        let mut walker = JsonWalker::new(StringReader::new(json), 50);
+        let _ = walker.next_key_by_name("tuple");
+        assert_eq!(walker.current_value::<(i32, f32, char, Option<String>, Vec<i32>, Color, Point)>().unwrap(), data.tuple);
+        assert_eq!(walker.next_sibling_key().unwrap().1, "array1");
+        let _ = walker.next_sibling_key();
+        assert_eq!(walker.current_value::<Vec<Point>>().unwrap(), data.array2);
+        assert_eq!(walker.next_sibling_key().unwrap().1, "array3");
+        let _ = walker.next_sibling_key();
+        assert_eq!(walker.current_value::<Message>().unwrap(), data.enum1);
+        let _ = walker.next_sibling_key();
+        assert_eq!(walker.current_value::<Message>().unwrap(), data.enum2);
+        assert_eq!(walker.next_sibling_key().unwrap().1, "enum3");
+        assert_eq!(walker.current_value::<Message>().unwrap(), data.enum3);
+        assert_eq!(walker.next_sibling_key().unwrap().1, "enum4");
+        assert_eq!(walker.current_value::<Message>().unwrap(), data.enum4);
+        assert_eq!(walker.next_sibling_key(), Err(Error::new_eos()));
+    }
+
+    #[test]
+    fn test_current_value_postcondition_on_array_elements() {
+        // elements of an array can be deserialized one by one
+        let data = data2::create_data();
+        let json = serde_json::to_string(&data).unwrap();
+        let mut walker = JsonWalker::new(StringReader::new(json), 50);
+        walker.move_n_element_forward(1);
+        assert_eq!(walker.current_value::<Person>().unwrap(), data[0]);
+        assert_eq!(walker.current_value::<Person>().unwrap(), data[1]);
+        assert_eq!(walker.next_item(), Err(Error::new_eos()));
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Key7 {
+        key71: Option<i32>,
+        key72: bool,
+        key73: bool,
+        key74: u32,
+        key75: f64,
+        key76: String,
+        key78: Vec<serde_json::Value>,
+        key79: BTreeMap<String, i32>,
+        key710: Vec<i32>,
+    }
+
+    #[test]
+    fn test_deserialize_key() {
+        let expected: serde_json::Value = serde_json::from_str(CORRECT_JSON).unwrap();
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 50);
+
+        let key7 = walker.deserialize_key::<Key7>("key7", KeyScope::Global).unwrap();
+        assert_eq!(key7.key71, None);
+        assert_eq!(key7.key76, "str2 \":{}[],");
+        assert_eq!(key7.key78, expected["key7"]["key78"].as_array().unwrap().clone());
+        assert!(key7.key79.is_empty() && key7.key710.is_empty());
+
+        let key8 = walker.deserialize_key::<Vec<serde_json::Value>>("key8", KeyScope::CurrentObject).unwrap();
+        assert_eq!(key8, expected["key8"].as_array().unwrap().clone());
+
+        assert_eq!(walker.next_key().unwrap().1, "key9");
+    }
+
+    #[test]
+    fn test_deserialize_key_not_found() {
+        // key71 belongs to a child of root, so it is not reachable by CurrentObject scope
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 50);
+        let _ = walker.next_key();
+        let result = walker.deserialize_key::<i32>("key71", KeyScope::CurrentObject);
+        assert_eq!(result.unwrap_err().kind, ErrorKind::KeyNotFound);
+
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 50);
+        let result = walker.deserialize_key::<i32>("key0", KeyScope::Global);
+        assert_eq!(result.unwrap_err().kind, ErrorKind::KeyNotFound);
+    }
+
+    #[test]
+    fn test_take_sample_as() {
+        let data = data2::create_data();
+        let json = format!(r#"{{"people": {}}}"#, serde_json::to_string(&data).unwrap());
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        let sample = walker.take_sample_as::<Person>("/people", 1).unwrap();
+        assert_eq!(sample.len(), 1);
+        assert_eq!(sample[0], data[0]);
+    }
+
+    #[test]
+    fn test_find_element() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Order {
+            name: String,
+            tags: Vec<String>,
+            id: u32,
+        }
+
+        // the id comes last, so the whole element is passed before the predicate is tested
+        let orders: Vec<String> = (0..1000).map(|i| format!(r#"{{"name": "order {i}", "tags": ["t{i}", "x"], "id": {}}}"#, 10000 + i)).collect();
+        let json = format!(r#"{{"orders": [{}], "after": 1}}"#, orders.join(", "));
+
+        let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+        walker.next_key_by_name("orders").unwrap();
+        let (index, order) = walker.find_element::<Order>("id", |id| id.1 == "10741").unwrap();
+        assert_eq!(index, 741);
+        assert_eq!(order, Order { name: "order 741".to_string(), tags: vec!["t741".to_string(), "x".to_string()], id: 10741 });
+        // strings are only built for the matched element, while the fields before "id" are skipped in every scanned one
+        assert_eq!(walker.stats().str_values, 3);
+        assert_eq!(walker.stats().skipped_items, 742 * 3);
+        assert_eq!(walker.next_key_by_name("name").unwrap().1, "name");
+        assert_eq!(walker.next_item().unwrap().1, "order 742");
+
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        walker.next_key_by_name("orders").unwrap();
+        let e = walker.find_element::<Order>("id", |id| id.1 == "1").unwrap_err();
+        assert_eq!(e.kind, ErrorKind::ElementNotFound);
+        assert_eq!(e.msg, "No element matched after scanning 1000 elements");
+        assert_eq!(walker.next_key().unwrap().1, "after");
+
+        let mut walker = JsonWalker::new(StringReader::new(r#"[1, {"id": [2]}, {"x": 1}, {"id": 2, "v": {"id": 3}}]"#.to_string()), 0);
+        assert_eq!(walker.find_element::<serde_json::Value>("id", |id| id.1 == "2").unwrap().0, 3);
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": 1}"#.to_string()), 0);
+        assert_eq!(walker.find_element::<serde_json::Value>("id", |_| true).unwrap_err().kind, ErrorKind::WrongDataType);
+    }
+
+    #[test]
+    fn test_fallible_reader_de() {
+        use crate::json_walker::walker_tests::failing_reader;
+
+        let reset = ErrorKind::Io(std::io::ErrorKind::ConnectionReset);
+        let json = CORRECT_JSON.trim_end();
+        for n in 0..json.len() {
+            let mut walker = JsonWalker::new_fallible(failing_reader(json, n), 0);
+            assert_eq!(walker.current_value::<serde_json::Value>().map_err(|e| e.kind), Err(reset.clone()), "n: {n}");
+            assert!(walker.is_poisoned());
+        }
+        let mut walker = JsonWalker::new_fallible(failing_reader(json, json.len()), 0);
+        assert!(walker.current_value::<serde_json::Value>().is_ok());
+    }
+
+    #[test]
+    fn test_current_value_recovery() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct User {
+            name: String,
+            age: u8,
+            tags: Vec<String>,
+        }
+
+        let json = r#"[
+            {"name": "a", "age": 1, "tags": []},
+            {"name": "b", "age": "two", "tags": ["x", {"deep": [1, 2]}]},
+            {"name": "c", "age": 3, "tags": ["y"]},
+            {"name": "d", "age": 4, "tags": []}
+        ]"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.move_n_element_forward(1);
+        assert_eq!(walker.current_value::<User>().unwrap().name, "a");
+        let e = walker.current_value::<User>().unwrap_err();
+        assert_eq!(e.kind, ErrorKind::ParseIntError);
+        assert!(e.is_recovered());
+        assert_eq!(walker.current_value::<User>().unwrap(), User { name: "c".to_string(), age: 3, tags: vec!["y".to_string()] });
+        assert_eq!(walker.current_value::<User>().unwrap().name, "d");
+        assert_eq!(walker.next_item(), Err(Error::new_eos()));
+
+        // rejected at the first byte, the value is skipped as a whole
+        let mut walker = JsonWalker::new(StringReader::new(r#"{"a": [[1], {"b": 2}], "c": 3}"#.to_string()), 0);
+        walker.next_key().unwrap();
+        assert!(walker.current_value::<User>().unwrap_err().is_recovered());
+        assert_eq!(walker.next_sibling_key().unwrap().1, "c");
+    }
+
+    #[test]
+    fn test_filter_de() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct User {
+            name: String,
+            password: Option<String>,
+            tags: Vec<i32>,
+        }
+
+        // the hidden key is the first and the last one of an object, and the first, middle and last element of an array
+        let json = r#"[{"password": "x", "name": "a", "tags": [1, 2, 3]}, {"tags": [4], "name": "b", "password": "y"}]"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_filter(None, Some(PathMatcher::new("/*/password").unwrap()));
+        assert_eq!(walker.current_value::<Vec<User>>().unwrap(), vec![
+            User { name: "a".to_string(), password: None, tags: vec![1, 2, 3] },
+            User { name: "b".to_string(), password: None, tags: vec![4] },
+        ]);
+
+        for (pointer, tags) in [("/0/tags/0", [2, 3]), ("/0/tags/1", [1, 3]), ("/0/tags/2", [1, 2])] {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            walker.set_filter(None, Some(PathMatcher::new(pointer).unwrap()));
+            assert_eq!(walker.current_value::<Vec<User>>().unwrap()[0].tags, tags);
+        }
+
+        // hidden elements and keys are absent from found elements and samples
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_filter(None, Some(PathMatcher::new("/*/password").unwrap()));
+        let (index, user) = walker.find_element::<User>("name", |name| name.1 == "b").unwrap();
+        assert_eq!((index, user.password), (1, None));
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_filter(None, Some(PathMatcher::new("/0").unwrap()));
+        assert_eq!(walker.find_element::<User>("name", |name| name.1 == "a").unwrap_err().kind, ErrorKind::ElementNotFound);
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.set_filter(None, Some(PathMatcher::new("/0/tags/1").unwrap()));
+        assert_eq!(walker.take_sample_as::<i32>("/0/tags", 5).unwrap(), vec![1, 3]);
+
+        #[cfg(feature = "serde_json")]
+        {
+            let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+            walker.set_filter(None, Some(PathMatcher::new("/*/password").unwrap()));
+            let mut expected: serde_json::Value = serde_json::from_str(json).unwrap();
+            for user in expected.as_array_mut().unwrap() {
+                user.as_object_mut().unwrap().remove("password");
+            }
+            assert_eq!(walker.to_value().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_for_each_element() {
+        let json = format!(r#"{{"ids": [{}], "next": true}}"#, (0..10).map(|i| i.to_string()).collect::<Vec<_>>().join(", "));
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        let mut ids = vec![];
+        let state = walker.for_each_element("/ids", |id: i32| {
+            ids.push(id);
+            if ids.len() == 5 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        }).unwrap();
+        assert_eq!((ids.clone(), state.processed, state.finished), ((0..5).collect(), 5, false));
+
+        let snapshot = state;
+        let mut rest = vec![];
+        let state = walker.resume_elements(&snapshot, |id: i32| {
+            rest.push(id);
+            ControlFlow::Continue(())
+        }).unwrap();
+        assert_eq!((rest, state.processed, state.finished), ((5..10).collect(), 10, true));
+        assert_eq!(walker.resume_elements(&state, |_: i32| ControlFlow::Continue(())), Ok(state));
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "next".to_string())));
+        assert_eq!(walker.resume_elements(&snapshot, |_: i32| ControlFlow::Continue(())).unwrap_err().kind, ErrorKind::InvalidStructure);
+    }
+
+    #[test]
+    fn test_ignore_deep_field() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Small {
+            a: i32,
+        }
+        let depth = 100_000;
+        let json = format!(r#"{{"junk": {}{}, "a": 1}}"#, "[".repeat(depth), "]".repeat(depth));
+        let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+        assert_eq!(walker.current_value::<Small>().unwrap_err().kind, ErrorKind::MaxDepthExceeded);
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        walker.set_max_depth(None);
+        assert_eq!(walker.current_value::<Small>().unwrap(), Small { a: 1 });
+        assert_eq!(walker.stats().max_depth, depth as u64 + 1);
+        assert_eq!(walker.next_item(), Err(Error::new_eos()));
+    }
+
     #[test]
     fn test_data2_de() {
         let data = data2::create_data();
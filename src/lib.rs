@@ -1,27 +1,150 @@
 use std::fmt::{Display, Formatter};
 
 pub mod json_walker;
+pub mod writer;
+pub mod tools;
+pub mod path_matcher;
 mod parser_core;
 mod readers;
 mod deserializer;
+mod serializer;
+mod escape;
+mod sha256;
+mod base64;
+#[cfg(feature = "serde_json")]
+mod value;
+#[doc(hidden)]
+pub mod fuzz_targets;
+#[cfg(feature = "bench-support")]
+pub mod bench_support;
+#[cfg(feature = "async")]
+pub mod async_walker;
+
+#[cfg(feature = "serialize")]
+pub use serializer::serialize_mod::{Serializer, to_string, to_writer};
 
 const NIL: u8 = 0;
 const ROOT: char = '#';
 
 //region error
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Error {
     kind: ErrorKind,
     msg: String,
+    recovered: bool,
+    position: Option<Position>,
+    path: Option<String>,
+}
+
+/// where in json text an error has happened. Line and column start from 1 and columns count bytes, as
+/// JsonWalker::position() does
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Position {
+    pub byte_offset: u64,
+    pub line: u64,
+    pub column: u64,
+}
+
+/// a constructor for each ErrorKind which has no data
+macro_rules! error_constructors {
+    ($($name:ident => $kind:ident),* $(,)?) => {
+        impl Error {
+            $(
+                #[doc = concat!("Error of ErrorKind::", stringify!($kind))]
+                pub fn $name(msg: impl Into<String>) -> Self {
+                    Error::new(ErrorKind::$kind, msg)
+                }
+            )*
+        }
+    };
+}
+
+error_constructors! {
+    serde => Serde,
+    parse_bool => ParseBoolError,
+    parse_int => ParseIntError,
+    parse_float => ParseFloatError,
+    wrong_data_type => WrongDataType,
+    key_not_found => KeyNotFound,
+    element_not_found => ElementNotFound,
+    invalid_structure => InvalidStructure,
+    invalid_path => InvalidPath,
+    test_failed => TestFailed,
+    key_order => KeyOrder,
+    budget_exhausted => BudgetExhausted,
+    deadline_exceeded => DeadlineExceeded,
+    limit_exceeded => LimitExceeded,
+    max_depth_exceeded => MaxDepthExceeded,
+    unsupported_encoding => UnsupportedEncoding,
+    would_block => WouldBlock,
+    whitespace_limit_exceeded => WhitespaceLimitExceeded,
+    invalid_token => InvalidToken,
+    unexpected_char => UnexpectedChar,
+    unexpected_eos => UnexpectedEos,
+    invalid_number => InvalidNumber,
+    invalid_utf8 => InvalidUtf8,
+    invalid_escape => InvalidEscape,
+    oops => OOPS,
 }
 
 impl Error {
+    pub fn new(kind: ErrorKind, msg: impl Into<String>) -> Self {
+        Error { kind, msg: msg.into(), recovered: false, position: None, path: None }
+    }
+
     pub fn new_eos() -> Self {
-        Error { kind: ErrorKind::EOS, msg: "End of stream".to_string() }
+        Error::new(ErrorKind::EOS, "End of stream")
+    }
+
+    /// Error of ErrorKind::Io, check out From<std::io::Error> as well
+    pub fn io(kind: std::io::ErrorKind, msg: impl Into<String>) -> Self {
+        Error::new(ErrorKind::Io(kind), msg)
+    }
+
+    /// Error of ErrorKind::Poisoned, which holds **original** and takes its position and path
+    pub fn poisoned(original: Error) -> Self {
+        let msg = format!("The walker is stopped by an earlier error: {}", original.msg);
+        let (position, path) = (original.position, original.path.clone());
+        Error { position, path, ..Error::new(ErrorKind::Poisoned { original: Box::new(original) }, msg) }
+    }
+
+    /// the same error, which has happened at **position** and **path**
+    pub(crate) fn at(self, position: Position, path: String) -> Self {
+        Error { position: Some(position), ..self.with_path(path) }
+    }
+
+    /// the same error, which has happened at **path**
+    pub(crate) fn with_path(self, path: String) -> Self {
+        Error { path: Some(path), ..self }
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.msg
+    }
+
+    /// where json text is broken, for errors which stop the walker such as UnexpectedChar or LimitExceeded
+    pub fn position(&self) -> Option<Position> {
+        self.position
+    }
+
+    /// JsonWalker::get_path_string() at the time of the error, for errors which stop the walker and for errors of
+    /// deserialization by JsonWalker::current_value()
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// true if the walker skipped the rest of the failed value, so walking can go on right after it.
+    /// Check out JsonWalker::current_value()
+    pub fn is_recovered(&self) -> bool {
+        self.recovered
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum ErrorKind {
     EOS,
     Serde,
@@ -29,12 +152,63 @@ pub enum ErrorKind {
     ParseIntError,
     ParseFloatError,
     WrongDataType,
+    KeyNotFound,
+    ElementNotFound,
+    InvalidStructure,
+    InvalidPath,
+    TestFailed,
+    /// keys of an object are not in the expected order, check out JsonWalker::assert_key_order()
+    KeyOrder,
+    BudgetExhausted,
+    DeadlineExceeded,
+    LimitExceeded,
+    /// containers are nested deeper than JsonWalker::set_max_depth() allows
+    MaxDepthExceeded,
+    UnsupportedEncoding,
+    WouldBlock,
+    WhitespaceLimitExceeded,
+    InvalidToken,
+    /// malformed json, a byte which can not be at its place. The message tells the position and the recent piece
+    UnexpectedChar,
+    /// malformed json, the stream is finished in the middle of a value
+    UnexpectedEos,
+    /// malformed json, a number such as "1.2.3" or "1e"
+    InvalidNumber,
+    /// a string holds bytes which are not utf8, check out JsonWalker::set_lossy_utf8()
+    InvalidUtf8,
+    /// malformed json, an unknown escape sequence such as "\x" or a lone surrogate such as "\ud83d"
+    InvalidEscape,
+    /// malformed json, there are more bytes than white-spaces after the root value, check out JsonWalker::finish()
+    TrailingCharacters,
+    /// a key is repeated in one object, check out JsonWalker::set_on_duplicate_key()
+    DuplicateKey,
+    /// an earlier error has stopped the walker and only reset() makes it usable again
+    Poisoned { original: Box<Error> },
+    /// the reader has failed with this kind of io error. The message of Error is the one of the io error
+    Io(std::io::ErrorKind),
     OOPS,
 }
 
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::io(value.kind(), value.to_string())
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// e.g. "UnexpectedChar: Unexpected `}` at byte 3 (1:4), path: #/[#,0]/"
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&format!("Deserialization error: {:?}", self))
+        match &self.kind {
+            ErrorKind::Poisoned { .. } => f.write_str("Poisoned")?,
+            kind => write!(f, "{kind:?}")?,
+        }
+        write!(f, ": {}", self.msg)?;
+        if let Some(path) = &self.path {
+            write!(f, ", path: {path}")?;
+        }
+        Ok(())
     }
 }
 //endregion
@@ -0,0 +1,437 @@
+use crate::Error;
+use crate::parser_core::PathItem;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Segment {
+    /// a key, or an index if it is a number, as a token of json pointer is
+    Name(String),
+    /// a key, even if it is a number
+    Key(String),
+    Index(usize),
+    Any,
+    AnyKey,
+    AnyIndex,
+}
+
+impl Segment {
+    /// how the segment is written in error messages
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            Segment::Name(name) | Segment::Key(name) => name.clone(),
+            Segment::Index(i) => i.to_string(),
+            Segment::Any => "*".to_string(),
+            Segment::AnyKey => "{*}".to_string(),
+            Segment::AnyIndex => "[*]".to_string(),
+        }
+    }
+}
+
+/// Select nodes by a json pointer such as "/users/*/email". "*" matches any key or index, "~0" and "~1" stand for "~" and "/".
+/// The empty pointer selects the root. Matching is done against get_path(), so no extra state is needed while walking
+/// ### Example
+/// ```
+/// use json_walker::json_walker::{JsonWalker, StringReader};
+/// use json_walker::path_matcher::PathMatcher;
+///
+/// let m = PathMatcher::new("/users/*/email").unwrap();
+/// let mut walker = JsonWalker::new(StringReader::new(r#"{"users":[{"email":"a@b.c"}]}"#.to_string()), 0);
+/// walker.next_key_by_name("email").unwrap();
+/// assert!(m.matches(&walker.get_path()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathMatcher {
+    segments: Vec<Segment>,
+    normalize_keys: Option<KeyNormalization>,
+}
+
+/// How keys are normalized before they are compared with a name, in addition to decoding escape sequences, so
+/// "caf\u00e9" always matches "café". Normalization needs "unicode-normalization" feature
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyNormalization {
+    /// Unicode canonical composition, so a composed char matches its decomposed form
+    Nfc,
+}
+
+impl PathMatcher {
+    pub fn new(pointer: &str) -> Result<Self, Error> {
+        let segments = split_pointer(pointer)?
+            .into_iter()
+            .map(|s| if s == "*" { Segment::Any } else { Segment::Name(s) })
+            .collect();
+        Ok(PathMatcher { segments, normalize_keys: None })
+    }
+
+    /// Compile a jq-style path such as `.items[].name`. Supported steps are `.key`, `[n]` and `[]`, which iterates over
+    /// elements or members; `.` alone selects the root. Other jq features such as pipes, slices or functions are rejected.
+    /// Errors tell the byte position of the offending char, starting from 0
+    /// ### Example
+    /// ```
+    /// use json_walker::path_matcher::PathMatcher;
+    ///
+    /// assert_eq!(PathMatcher::from_query(".items[].name").unwrap(), PathMatcher::new("/items/*/name").unwrap());
+    /// assert!(PathMatcher::from_query(".items | length").is_err());
+    /// ```
+    pub fn from_query(expr: &str) -> Result<Self, Error> {
+        let b = expr.as_bytes();
+        let error = |msg: String, i: usize| Error::invalid_path(format!("{msg} at position {i} of `{expr}`"));
+        let unexpected = |i: usize| match b.get(i) {
+            None => error("Unexpected end".to_string(), i),
+            Some(c) if b"|,?()+-*/=<>$@:; ".contains(c) => error(format!("`{}` is not supported", *c as char), i),
+            Some(c) => error(format!("Unexpected `{}`", *c as char), i),
+        };
+        if b.first() != Some(&b'.') {
+            return Err(unexpected(0));
+        }
+        let mut segments = vec![];
+        let mut i = 0;
+        while i < b.len() {
+            match b[i] {
+                // the first "." may stand alone or be followed by "["
+                b'.' if i == 0 && b.get(1).is_none_or(|c| *c == b'[') => i += 1,
+                b'.' => {
+                    i += 1;
+                    let start = i;
+                    if b.get(i).is_some_and(|c| c.is_ascii_alphabetic() || *c == b'_') {
+                        while b.get(i).is_some_and(|c| c.is_ascii_alphanumeric() || *c == b'_') {
+                            i += 1;
+                        }
+                    }
+                    if i == start {
+                        return Err(match b.get(i) {
+                            Some(b'.') => error("`..` is not supported".to_string(), i - 1),
+                            _ => unexpected(i),
+                        });
+                    }
+                    segments.push(Segment::Name(expr[start..i].to_string()));
+                }
+                b'[' => {
+                    i += 1;
+                    let start = i;
+                    while b.get(i).is_some_and(|c| c.is_ascii_digit()) {
+                        i += 1;
+                    }
+                    if b.get(i) != Some(&b']') {
+                        return Err(unexpected(i));
+                    }
+                    segments.push(if i == start { Segment::Any } else { Segment::Name(expr[start..i].to_string()) });
+                    i += 1;
+                }
+                _ => return Err(unexpected(i)),
+            }
+        }
+        Ok(PathMatcher { segments, normalize_keys: None })
+    }
+
+    /// Compile a path in the shape of get_path_string(), such as `#/{key8}/[6]/{key81}`. Steps are `{key}` for a member of
+    /// an object and `[n]` for an element of an array, while `{*}` matches any member, `[*]` any element and `*` either
+    /// of them. Unlike a json pointer, the kind of container is checked, so `{0}` never matches an element and `[0]`
+    /// never matches a member. A key runs till the `}` which ends the step, so it may hold any char but "}/". The
+    /// trailing "/" is optional. Errors tell the byte position of the offending char, starting from 0
+    /// ### Example
+    /// ```
+    /// use json_walker::path_matcher::PathMatcher;
+    ///
+    /// let m = PathMatcher::from_path_string("#/{items}/[*]/").unwrap();
+    /// assert!(m.matches_pointer("/items/0"));
+    /// assert!(!m.matches_pointer("/items/name"));
+    /// assert!(PathMatcher::from_path_string("#/[a]").is_err());
+    /// ```
+    pub fn from_path_string(path: &str) -> Result<Self, Error> {
+        let b = path.as_bytes();
+        let unexpected = |i: usize| Error::invalid_path(match b.get(i) {
+            None => format!("Unexpected end at position {i} of `{path}`"),
+            Some(_) => format!("Unexpected `{}` at position {i} of `{path}`", path[i..].chars().next().unwrap_or_default()),
+        });
+        if b.first() != Some(&b'#') {
+            return Err(unexpected(0));
+        }
+        let mut segments = vec![];
+        let mut i = 1;
+        while i < b.len() {
+            if b[i] != b'/' {
+                return Err(unexpected(i));
+            }
+            i += 1;
+            match b.get(i) {
+                // the trailing "/"
+                None => break,
+                Some(b'*') => {
+                    segments.push(Segment::Any);
+                    i += 1;
+                }
+                Some(b'{') => {
+                    let start = i + 1;
+                    let end = (start..b.len()).find(|j| b[*j] == b'}' && b.get(j + 1).is_none_or(|c| *c == b'/')).ok_or_else(|| unexpected(b.len()))?;
+                    let key = &path[start..end];
+                    segments.push(if key == "*" { Segment::AnyKey } else { Segment::Key(key.to_string()) });
+                    i = end + 1;
+                }
+                Some(b'[') => {
+                    i += 1;
+                    let start = i;
+                    if b.get(i) == Some(&b'*') {
+                        i += 1;
+                    } else {
+                        while b.get(i).is_some_and(|c| c.is_ascii_digit()) {
+                            i += 1;
+                        }
+                    }
+                    if i == start || b.get(i) != Some(&b']') {
+                        return Err(unexpected(i));
+                    }
+                    segments.push(match &path[start..i] {
+                        "*" => Segment::AnyIndex,
+                        index => Segment::Index(index.parse().map_err(|_| unexpected(start))?),
+                    });
+                    i += 1;
+                }
+                Some(_) => return Err(unexpected(i)),
+            }
+        }
+        Ok(PathMatcher { segments, normalize_keys: None })
+    }
+
+    /// Compare keys of paths with names of the pointer in the form of **normalize_keys**
+    /// ### Example
+    /// ```
+    /// use json_walker::json_walker::{JsonWalker, StringReader};
+    /// use json_walker::path_matcher::{KeyNormalization, PathMatcher};
+    ///
+    /// // "e" followed by a combining acute accent
+    /// let m = PathMatcher::new("/cafe\u{301}").unwrap().with_normalize_keys(Some(KeyNormalization::Nfc));
+    /// let mut walker = JsonWalker::new(StringReader::new(r#"{"caf\u00e9": 1}"#.to_string()), 0);
+    /// walker.next_key().unwrap();
+    /// assert!(m.matches(&walker.get_path()));
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    pub fn with_normalize_keys(mut self, normalize_keys: Option<KeyNormalization>) -> Self {
+        self.normalize_keys = normalize_keys;
+        self
+    }
+
+    pub(crate) fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// check whether **path** (the result of get_path()) points to a selected node
+    pub fn matches(&self, path: &[PathItem]) -> bool {
+        let nodes = path.iter().filter(|p| !matches!(p, PathItem::Start));
+        nodes.clone().count() == self.segments.len() && self.matches_nodes(nodes)
+    }
+
+    /// check whether **path** points to a selected node or to a node inside it
+    pub(crate) fn covers(&self, path: &[PathItem]) -> bool {
+        let nodes = path.iter().filter(|p| !matches!(p, PathItem::Start));
+        nodes.clone().count() >= self.segments.len() && self.matches_nodes(nodes)
+    }
+
+    /// check whether **path** points to an ancestor of a selected node
+    pub(crate) fn leads_to(&self, path: &[PathItem]) -> bool {
+        let nodes = path.iter().filter(|p| !matches!(p, PathItem::Start));
+        nodes.clone().count() < self.segments.len() && self.matches_nodes(nodes)
+    }
+
+    /// check whether the item at **path**, a key if **is_key**, is the first one of a selected node: the key of a member,
+    /// or the first item of an element or of the root, which is the value itself for a scalar
+    pub(crate) fn reaches(&self, path: &[PathItem], is_key: bool) -> bool {
+        let nodes: Vec<&PathItem> = path.iter().filter(|p| !matches!(p, PathItem::Start)).collect();
+        let n = self.segments.len();
+        if nodes.len() < n || !self.matches_nodes(nodes.iter().copied()) {
+            return false;
+        }
+        // a value in an object belongs to a member, which is reached by its key
+        if !is_key && matches!(nodes.last(), Some(PathItem::Object(..))) {
+            return false;
+        }
+        match n.checked_sub(1).map(|i| nodes[i]) {
+            Some(PathItem::Object(..)) => nodes.len() == n,
+            _ => nodes[n..].iter().all(|node| matches!(node, PathItem::Object(_, 0) | PathItem::Array(_, 0))),
+        }
+    }
+
+    /// the part of the pattern which is left after **path**, to match nodes relative to the node of **path**. None if
+    /// the pattern matches neither the node nor one of its descendants
+    #[cfg(feature = "deserialize")]
+    pub(crate) fn relative_to(&self, path: &[PathItem]) -> Option<PathMatcher> {
+        let nodes = path.iter().filter(|p| !matches!(p, PathItem::Start));
+        let n = nodes.clone().count();
+        if n > self.segments.len() || !self.matches_nodes(nodes) {
+            return None;
+        }
+        Some(PathMatcher { segments: self.segments[n..].to_vec(), normalize_keys: self.normalize_keys })
+    }
+
+    /// check whether a node may be selected by both patterns, or one of them may select a node inside the other's one
+    pub(crate) fn overlaps(&self, other: &PathMatcher) -> bool {
+        use Segment::*;
+        self.segments.iter().zip(&other.segments).all(|pair| match pair {
+            (Any, _) | (_, Any) => true,
+            (Key(_) | AnyKey, Index(_) | AnyIndex) | (Index(_) | AnyIndex, Key(_) | AnyKey) => false,
+            (Name(a) | Key(a), Name(b) | Key(b)) => a == b,
+            (Index(a), Index(b)) => a == b,
+            (Index(i), Name(name)) | (Name(name), Index(i)) => name.parse::<usize>() == Ok(*i),
+            _ => true,
+        })
+    }
+
+    /// zip **nodes** with segments, so only the shorter one of them is compared
+    fn matches_nodes<'a>(&self, nodes: impl Iterator<Item=&'a PathItem>) -> bool {
+        nodes.zip(&self.segments).all(|(node, segment)| match (node, segment) {
+            (PathItem::Start, _) => false,
+            (_, Segment::Any) | (PathItem::Object(..), Segment::AnyKey) | (PathItem::Array(..), Segment::AnyIndex) => true,
+            (PathItem::Object(key, _), Segment::Name(name) | Segment::Key(name)) => same_key_text(key, name, self.normalize_keys),
+            (PathItem::Array(_, i), Segment::Name(name)) => name.parse::<usize>() == Ok(*i),
+            (PathItem::Array(_, i), Segment::Index(index)) => i == index,
+            _ => false,
+        })
+    }
+
+    /// check whether **pointer**, a json pointer without "*" such as "/users/3/email", points to a selected node
+    pub fn matches_pointer(&self, pointer: &str) -> bool {
+        match split_pointer(pointer) {
+            Ok(tokens) => tokens.len() == self.segments.len() && tokens.iter().zip(&self.segments).all(|(token, segment)| match segment {
+                Segment::Any | Segment::AnyKey => true,
+                Segment::Name(name) | Segment::Key(name) => token == name,
+                Segment::Index(i) => *token == i.to_string(),
+                Segment::AnyIndex => token.parse::<usize>().is_ok(),
+            }),
+            Err(_) => false,
+        }
+    }
+}
+
+/// compare a decoded key with **name**, after normalizing both of them if it is asked
+pub(crate) fn same_key_text(key: &str, name: &str, normalize_keys: Option<KeyNormalization>) -> bool {
+    if key == name {
+        return true;
+    }
+    match normalize_keys {
+        #[cfg(feature = "unicode-normalization")]
+        Some(KeyNormalization::Nfc) => {
+            use unicode_normalization::UnicodeNormalization;
+            key.nfc().eq(name.nfc())
+        }
+        _ => false,
+    }
+}
+
+/// split a json pointer (RFC 6901) into its unescaped reference tokens. The empty pointer has no token
+pub(crate) fn split_pointer(pointer: &str) -> Result<Vec<String>, Error> {
+    if pointer.is_empty() {
+        return Ok(vec![]);
+    }
+    if !pointer.starts_with('/') {
+        return Err(Error::invalid_path(format!("`{pointer}` must start with '/'")));
+    }
+    Ok(pointer[1..].split('/').map(|s| s.replace("~1", "/").replace("~0", "~")).collect())
+}
+
+/// escape a key to be used as a token of json pointer
+pub(crate) fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod path_matcher_tests {
+    use std::rc::Rc;
+
+    use crate::ErrorKind;
+    use crate::parser_core::PathItem;
+    use crate::path_matcher::PathMatcher;
+
+    fn path(items: &[(&str, Option<usize>)]) -> Vec<PathItem> {
+        let mut v = vec![PathItem::Start];
+        for (key, i) in items {
+            v.push(match i {
+                Some(i) => PathItem::Array(Rc::new(key.to_string()), *i),
+                None => PathItem::Object(Rc::new(key.to_string()), 0),
+            });
+        }
+        v
+    }
+
+    #[test]
+    fn test_matches() {
+        let p = path(&[("users", None), ("users", Some(3)), ("email", None)]);
+        assert!(PathMatcher::new("/users/*/email").unwrap().matches(&p));
+        assert!(PathMatcher::new("/users/3/email").unwrap().matches(&p));
+        assert!(PathMatcher::new("/*/*/*").unwrap().matches(&p));
+        assert!(!PathMatcher::new("/users/2/email").unwrap().matches(&p));
+        assert!(!PathMatcher::new("/users/*").unwrap().matches(&p));
+        assert!(!PathMatcher::new("/users/*/email/x").unwrap().matches(&p));
+        assert!(PathMatcher::new("").unwrap().matches(&path(&[])));
+        assert!(PathMatcher::new("/a~1b~0").unwrap().matches(&path(&[("a/b~", None)])));
+        assert_eq!(PathMatcher::new("users").unwrap_err().kind, ErrorKind::InvalidPath);
+    }
+
+    #[test]
+    fn test_from_query() {
+        let q = |expr: &str| PathMatcher::from_query(expr);
+        assert_eq!(q(".").unwrap(), PathMatcher::new("").unwrap());
+        assert_eq!(q(".key8").unwrap(), PathMatcher::new("/key8").unwrap());
+        assert_eq!(q(".key7.key78").unwrap(), PathMatcher::new("/key7/key78").unwrap());
+        assert_eq!(q(".items[2]").unwrap(), PathMatcher::new("/items/2").unwrap());
+        assert_eq!(q(".items[]").unwrap(), PathMatcher::new("/items/*").unwrap());
+        assert_eq!(q(".[][0]._a1").unwrap(), PathMatcher::new("/*/0/_a1").unwrap());
+
+        let msg = |expr: &str| q(expr).unwrap_err().msg;
+        assert_eq!(msg(".a|.b"), "`|` is not supported at position 2 of `.a|.b`");
+        assert_eq!(msg(".a .b"), "` ` is not supported at position 2 of `.a .b`");
+        assert_eq!(msg("a"), "Unexpected `a` at position 0 of `a`");
+        assert_eq!(msg(""), "Unexpected end at position 0 of ``");
+        assert_eq!(msg(".a[-1]"), "`-` is not supported at position 3 of `.a[-1]`");
+        assert_eq!(msg(".a[1:2]"), "`:` is not supported at position 4 of `.a[1:2]`");
+        assert_eq!(msg(".a[\"b\"]"), "Unexpected `\"` at position 3 of `.a[\"b\"]`");
+        assert_eq!(msg(".a[1"), "Unexpected end at position 4 of `.a[1`");
+        assert_eq!(msg(".a."), "Unexpected end at position 3 of `.a.`");
+        assert_eq!(msg(".a..b"), "`..` is not supported at position 2 of `.a..b`");
+        assert_eq!(msg(".1a"), "Unexpected `1` at position 1 of `.1a`");
+        assert_eq!(msg(".a?"), "`?` is not supported at position 2 of `.a?`");
+        assert_eq!(q("..").unwrap_err().kind, ErrorKind::InvalidPath);
+    }
+
+    #[test]
+    fn test_from_path_string() {
+        let p = |path: &str| PathMatcher::from_path_string(path);
+        assert_eq!(p("#").unwrap(), PathMatcher::new("").unwrap());
+        assert_eq!(p("#/").unwrap(), PathMatcher::new("").unwrap());
+        let matches = |path: &str, items: &[(&str, Option<usize>)]| p(path).unwrap().matches(&self::path(items));
+        assert!(matches("#/{key8}/[6]/{key81}", &[("key8", None), ("key8", Some(6)), ("key81", None)]));
+        assert!(matches("#/{key8}/[*]/*/", &[("key8", None), ("key8", Some(0)), ("a", None)]));
+        assert!(matches("#/{key8}/[*]/*/", &[("key8", None), ("key8", Some(0)), ("key8", Some(3))]));
+        assert!(matches("#/{*}/{a/b}/{{x}}/{}", &[("k", None), ("a/b", None), ("{x}", None), ("", None)]));
+
+        // the kind of container is checked, while a json pointer does not
+        assert!(!matches("#/[0]", &[("0", None)]));
+        assert!(!matches("#/{0}", &[("", Some(0))]));
+        assert!(!matches("#/[*]", &[("a", None)]));
+        assert!(!matches("#/{*}", &[("", Some(0))]));
+        assert!(matches("#/*", &[("a", None)]) && matches("#/*", &[("", Some(0))]));
+        assert!(PathMatcher::new("/0").unwrap().matches(&path(&[("0", None)])));
+        assert!(PathMatcher::new("/0").unwrap().matches(&path(&[("", Some(0))])));
+        assert!(!p("#/[0]").unwrap().overlaps(&p("#/{0}").unwrap()));
+        assert!(p("#/[0]").unwrap().overlaps(&PathMatcher::new("/0").unwrap()));
+        assert!(!p("#/{*}").unwrap().overlaps(&p("#/[1]").unwrap()));
+
+        let msg = |path: &str| p(path).unwrap_err().msg;
+        assert_eq!(msg(""), "Unexpected end at position 0 of ``");
+        assert_eq!(msg("/{a}"), "Unexpected `/` at position 0 of `/{a}`");
+        assert_eq!(msg("#{a}"), "Unexpected `{` at position 1 of `#{a}`");
+        assert_eq!(msg("#/a"), "Unexpected `a` at position 2 of `#/a`");
+        assert_eq!(msg("#/{a"), "Unexpected end at position 4 of `#/{a`");
+        assert_eq!(msg("#/[]"), "Unexpected `]` at position 3 of `#/[]`");
+        assert_eq!(msg("#/[1a]"), "Unexpected `a` at position 4 of `#/[1a]`");
+        assert_eq!(msg("#/[1]x"), "Unexpected `x` at position 5 of `#/[1]x`");
+        assert_eq!(msg("#/[99999999999999999999]"), "Unexpected `9` at position 3 of `#/[99999999999999999999]`");
+    }
+
+    #[test]
+    fn test_matches_pointer() {
+        let m = PathMatcher::new("/users/*/email").unwrap();
+        assert!(m.matches_pointer("/users/3/email"));
+        assert!(!m.matches_pointer("/users/3"));
+        assert!(!m.matches_pointer("/users/3/name"));
+        assert!(PathMatcher::new("").unwrap().matches_pointer(""));
+        assert!(PathMatcher::new("/a~1b").unwrap().matches_pointer("/a~1b"));
+    }
+}
@@ -1,17 +1,38 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind as IoErrorKind, Read};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::{Error, ErrorKind};
+
+/// A reader which owns the json. The bytes are shared by its forks, so cloning it is cheap, and it is Send
+#[derive(Clone)]
 pub struct StringReader {
-    bytes: Vec<u8>,
+    bytes: Arc<[u8]>,
     pos: usize,
     len: usize,
 }
 
 impl StringReader {
     pub fn new(json_text: String) -> Box<Self> {
-        let bytes = json_text.into_bytes();
+        let bytes: Arc<[u8]> = json_text.into_bytes().into();
         let len = bytes.len();
         Box::new(StringReader { bytes, pos: 0, len })
     }
 }
 
+impl ForkableReader for StringReader {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn remaining(&self) -> usize {
+        self.len - self.pos
+    }
+}
+
 impl Iterator for StringReader {
     type Item = u8;
 
@@ -24,4 +45,978 @@ impl Iterator for StringReader {
             r
         }
     }
-}
\ No newline at end of file
+}
+
+/// Read json from borrowed bytes, such as a &str or a part of a larger buffer, without copying them. The walker then
+/// borrows the bytes as well, so it can not outlive them
+/// ### Example
+/// ```
+/// use json_walker::json_walker::{JsonWalker, SliceReader};
+///
+/// let json = r#"{"key": "value"}"#;
+/// let mut walker = JsonWalker::new(SliceReader::new(json.as_bytes()), 0);
+/// assert_eq!(walker.next_key().unwrap().1, "key");
+/// ```
+#[derive(Clone)]
+pub struct SliceReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SliceReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Box<Self> {
+        Box::new(SliceReader { bytes, pos: 0 })
+    }
+}
+
+impl SeekableReader for StringReader {
+    fn offset(&self) -> u64 {
+        self.pos as u64
+    }
+
+    fn seek_to(&mut self, offset: u64) -> std::io::Result<()> {
+        self.pos = (offset as usize).min(self.len);
+        Ok(())
+    }
+}
+
+impl SeekableReader for SliceReader<'_> {
+    fn offset(&self) -> u64 {
+        self.pos as u64
+    }
+
+    fn seek_to(&mut self, offset: u64) -> std::io::Result<()> {
+        self.pos = (offset as usize).min(self.bytes.len());
+        Ok(())
+    }
+}
+
+impl ForkableReader for SliceReader<'_> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+}
+
+impl Iterator for SliceReader<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let b = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+}
+
+/// A reader of json which is in memory, so a copy of it goes on from the same byte independently, check out
+/// JsonWalker::fork()
+pub trait ForkableReader: Iterator<Item=u8> + Clone {
+    /// bytes which are read so far
+    fn position(&self) -> usize;
+
+    /// bytes which are left to read
+    fn remaining(&self) -> usize;
+}
+
+impl<T: ForkableReader> ForkableReader for Box<T> {
+    fn position(&self) -> usize {
+        self.as_ref().position()
+    }
+
+    fn remaining(&self) -> usize {
+        self.as_ref().remaining()
+    }
+}
+
+/// A reader which can go back to a byte which it has read, check out JsonWalker::checkpoint()
+pub trait SeekableReader: Iterator<Item=u8> {
+    /// bytes which are read so far
+    fn offset(&self) -> u64;
+
+    /// go to **offset**, so the next byte is the one at it
+    fn seek_to(&mut self, offset: u64) -> std::io::Result<()>;
+}
+
+impl<T: SeekableReader + ?Sized> SeekableReader for Box<T> {
+    fn offset(&self) -> u64 {
+        self.as_ref().offset()
+    }
+
+    fn seek_to(&mut self, offset: u64) -> std::io::Result<()> {
+        self.as_mut().seek_to(offset)
+    }
+}
+
+/// Read json which arrives in chunks, such as messages of a queue, split at any byte. Only the current chunk is kept.
+/// A chunk may be anything which is AsRef<[u8]>, e.g. Vec<u8> or bytes::Bytes, and empty chunks are passed over
+/// ### Example
+/// ```
+/// use json_walker::json_walker::{ChunkedReader, JsonWalker};
+///
+/// let chunks = vec![br#"{"ke"#.to_vec(), br#"y": tr"#.to_vec(), b"ue}".to_vec()];
+/// let mut walker = JsonWalker::new(ChunkedReader::new(chunks.into_iter()), 0);
+/// assert_eq!(walker.next_item().unwrap().1, "key");
+/// assert_eq!(walker.next_item().unwrap().1, "true");
+/// ```
+pub struct ChunkedReader<I: Iterator> {
+    chunks: I,
+    current: Option<I::Item>,
+    pos: usize,
+}
+
+impl<I> ChunkedReader<I> where I: Iterator, I::Item: AsRef<[u8]> {
+    pub fn new(chunks: I) -> Box<Self> {
+        Box::new(ChunkedReader { chunks, current: None, pos: 0 })
+    }
+}
+
+impl<I> Iterator for ChunkedReader<I> where I: Iterator, I::Item: AsRef<[u8]> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(&b) = self.current.as_ref().and_then(|c| c.as_ref().get(self.pos)) {
+                self.pos += 1;
+                return Some(b);
+            }
+            self.current = Some(self.chunks.next()?);
+            self.pos = 0;
+        }
+    }
+}
+
+/// the default size of the buffer of FileReader and IoReader
+const FILE_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Read json from a file through a buffer, so a large file is walked without being loaded into memory.
+/// An io error, other than Interrupted, finishes the stream just as the end of file does
+/// ### Example
+/// ```no_run
+/// use json_walker::json_walker::{FileReader, JsonWalker};
+///
+/// let mut walker = JsonWalker::new(FileReader::new("dump.json").unwrap(), 0);
+/// let first_key = walker.next_key().unwrap();
+/// ```
+pub struct FileReader {
+    inner: BufReader<File>,
+    /// bytes which are read so far, check out SeekableReader
+    pos: u64,
+}
+
+impl FileReader {
+    pub fn new(path: impl AsRef<Path>) -> std::io::Result<Box<Self>> {
+        Self::with_capacity(path, FILE_BUFFER_SIZE)
+    }
+
+    /// the same as new(), but the buffer holds **capacity** bytes
+    pub fn with_capacity(path: impl AsRef<Path>, capacity: usize) -> std::io::Result<Box<Self>> {
+        let file = File::open(path)?;
+        Ok(Box::new(FileReader { inner: BufReader::with_capacity(capacity.max(1), file), pos: 0 }))
+    }
+}
+
+impl Iterator for FileReader {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.fill_buf() {
+                Ok([]) => return None,
+                Ok(buf) => {
+                    let b = buf[0];
+                    self.inner.consume(1);
+                    self.pos += 1;
+                    return Some(b);
+                }
+                Err(e) if e.kind() == IoErrorKind::Interrupted => {}
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+impl SeekableReader for FileReader {
+    fn offset(&self) -> u64 {
+        self.pos
+    }
+
+    fn seek_to(&mut self, offset: u64) -> std::io::Result<()> {
+        // seek_relative() keeps the buffer when the offset is in it
+        self.inner.seek_relative(offset as i64 - self.pos as i64)?;
+        self.pos = offset;
+        Ok(())
+    }
+}
+
+/// Read json from any std::io::Read, such as a TcpStream or a decompressor, through a buffer. A read which is
+/// Interrupted is tried again. Another io error finishes the stream just as the end of it does, while the error is kept
+/// for the handle of error_handle(), so the caller can tell a broken stream from a finished one
+/// ### Example
+/// ```
+/// use std::io::Cursor;
+/// use json_walker::json_walker::{IoReader, JsonWalker};
+///
+/// let reader = IoReader::new(Cursor::new(br#"{"a": 1}"#.to_vec()));
+/// let error = reader.error_handle();
+/// let mut walker = JsonWalker::new(reader, 0);
+/// assert_eq!(walker.next_key().unwrap().1, "a");
+/// assert!(error.borrow().is_none());
+/// ```
+pub struct IoReader<R: Read> {
+    inner: R,
+    buffer: Vec<u8>,
+    pos: usize,
+    len: usize,
+    error: Rc<RefCell<Option<std::io::Error>>>,
+}
+
+impl<R: Read> IoReader<R> {
+    pub fn new(inner: R) -> Box<Self> {
+        Self::with_capacity(inner, FILE_BUFFER_SIZE)
+    }
+
+    /// the same as new(), but the buffer holds **capacity** bytes
+    pub fn with_capacity(inner: R, capacity: usize) -> Box<Self> {
+        Box::new(IoReader { inner, buffer: vec![0; capacity.max(1)], pos: 0, len: 0, error: Rc::new(RefCell::new(None)) })
+    }
+
+    /// the io error which has finished the stream, if any
+    pub fn error_handle(&self) -> Rc<RefCell<Option<std::io::Error>>> {
+        self.error.clone()
+    }
+}
+
+impl<R: Read> Iterator for IoReader<R> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos == self.len {
+            if self.error.borrow().is_some() {
+                return None;
+            }
+            match self.inner.read(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(n) => {
+                    self.pos = 0;
+                    self.len = n;
+                }
+                Err(e) if e.kind() == IoErrorKind::Interrupted => {}
+                Err(e) => {
+                    *self.error.borrow_mut() = Some(e);
+                    return None;
+                }
+            }
+        }
+        self.pos += 1;
+        Some(self.buffer[self.pos - 1])
+    }
+}
+
+/// What a SignalReader returns
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ReadSignal {
+    Byte(u8),
+    /// no data is available yet, but the stream is not finished
+    Pending,
+    Eof,
+}
+
+/// A reader which may have no data yet, such as a non-blocking socket. Check out JsonWalker::new_nonblocking()
+pub trait SignalReader {
+    fn read_signal(&mut self) -> ReadSignal;
+}
+
+/// Encodings which DetectingReader recognizes
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+/// Read json in any encoding of RFC 4627 and hand it to the parser as UTF-8. The encoding is chosen by the BOM if there
+/// is one, otherwise by the pattern of null bytes in the first 4 bytes, as the first two chars of json are ASCII.
+/// Invalid code units are replaced by U+FFFD
+/// ### Example
+/// ```
+/// use json_walker::json_walker::{DetectingReader, Encoding, JsonWalker};
+///
+/// let utf16: Vec<u8> = r#"{"a": "b"}"#.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+/// let reader = DetectingReader::new(Box::new(utf16.into_iter())).unwrap();
+/// assert_eq!(reader.encoding(), Encoding::Utf16Le);
+/// let mut walker = JsonWalker::new(reader, 0);
+/// assert_eq!(walker.next_key().unwrap().1, "a");
+/// ```
+pub struct DetectingReader {
+    decoder: UnitDecoder<Box<dyn Iterator<Item=u8>>>,
+}
+
+impl DetectingReader {
+    /// read the first 4 bytes of **inner** to detect the encoding. A pattern of null bytes which matches no encoding
+    /// is an UnsupportedEncoding error
+    pub fn new(inner: Box<dyn Iterator<Item=u8>>) -> Result<Box<Self>, Error> {
+        let mut decoder = UnitDecoder::new(inner, Encoding::Utf8, false);
+        let (encoding, bom) = detect_encoding(decoder.peek_head(4))?;
+        decoder.encoding = encoding;
+        decoder.skip_head(bom);
+        Ok(Box::new(DetectingReader { decoder }))
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.decoder.encoding
+    }
+}
+
+impl Iterator for DetectingReader {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // a lossy decoder has no error
+        self.decoder.next()?.ok()
+    }
+}
+
+/// Decode UTF-16 or UTF-32 into UTF-8 for DetectingReader and Utf16Reader, while UTF-8 is passed as it is. Invalid
+/// input, which is a lone surrogate or an incomplete code unit at the end, is replaced by U+FFFD unless the decoder is
+/// strict, then it finishes the stream with an InvalidData io error
+struct UnitDecoder<I: Iterator<Item=u8>> {
+    inner: I,
+    encoding: Encoding,
+    strict: bool,
+    /// bytes which are read ahead to detect the encoding, but not decoded yet
+    head: [u8; 4],
+    head_pos: usize,
+    head_len: usize,
+    /// bytes which are read so far, to locate errors
+    offset: u64,
+    /// a unit which is read after a high surrogate, but does not complete it
+    pending_unit: Option<u32>,
+    /// utf-8 bytes of the last decoded char
+    out: [u8; 4],
+    out_pos: usize,
+    out_len: usize,
+    /// an error finishes the stream
+    failed: bool,
+}
+
+impl<I: Iterator<Item=u8>> UnitDecoder<I> {
+    fn new(inner: I, encoding: Encoding, strict: bool) -> Self {
+        UnitDecoder {
+            inner,
+            encoding,
+            strict,
+            head: [0; 4],
+            head_pos: 0,
+            head_len: 0,
+            offset: 0,
+            pending_unit: None,
+            out: [0; 4],
+            out_pos: 0,
+            out_len: 0,
+            failed: false,
+        }
+    }
+
+    /// read up to **n** bytes ahead, which are decoded later unless skip_head() drops them
+    fn peek_head(&mut self, n: usize) -> &[u8] {
+        while self.head_len < n {
+            match self.inner.next() {
+                Some(b) => self.head[self.head_len] = b,
+                None => break,
+            }
+            self.head_len += 1;
+        }
+        &self.head[..self.head_len]
+    }
+
+    /// drop the first **n** bytes of the head, which is a BOM
+    fn skip_head(&mut self, n: usize) {
+        self.head_pos = n;
+        self.offset = n as u64;
+    }
+
+    fn next_raw(&mut self) -> Option<u8> {
+        let b = if self.head_pos < self.head_len {
+            self.head_pos += 1;
+            self.head[self.head_pos - 1]
+        } else {
+            self.inner.next()?
+        };
+        self.offset += 1;
+        Some(b)
+    }
+
+    fn unit_size(&self) -> usize {
+        match self.encoding {
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+            _ => 4,
+        }
+    }
+
+    /// read a code unit. An incomplete unit at the end is returned as u32::MAX, which is no char
+    fn next_unit(&mut self) -> Option<u32> {
+        let mut bytes = [0u8; 4];
+        bytes[0] = self.next_raw()?;
+        for b in bytes.iter_mut().take(self.unit_size()).skip(1) {
+            *b = match self.next_raw() {
+                Some(b) => b,
+                None => return Some(u32::MAX),
+            };
+        }
+        Some(match self.encoding {
+            Encoding::Utf16Le => u16::from_le_bytes([bytes[0], bytes[1]]) as u32,
+            Encoding::Utf16Be => u16::from_be_bytes([bytes[0], bytes[1]]) as u32,
+            Encoding::Utf32Le => u32::from_le_bytes(bytes),
+            _ => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn next_char(&mut self) -> Option<std::io::Result<char>> {
+        let unit = match self.pending_unit.take() {
+            Some(u) => u,
+            None => self.next_unit()?,
+        };
+        let at = self.offset.saturating_sub(self.unit_size() as u64);
+        let c = match unit {
+            0xD800..=0xDBFF if self.unit_size() == 2 => match self.next_unit() {
+                Some(low) if (0xDC00..0xE000).contains(&low) => char::from_u32(0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)),
+                Some(u32::MAX) if self.strict => return Some(Err(self.incomplete())),
+                low => {
+                    self.pending_unit = low;
+                    None
+                }
+            },
+            u32::MAX if self.strict => return Some(Err(self.incomplete())),
+            _ => char::from_u32(unit),
+        };
+        match c {
+            Some(c) => Some(Ok(c)),
+            None if self.strict => Some(Err(self.invalid(match self.unit_size() {
+                2 => format!("A lone surrogate {unit:04X} in UTF-16 at byte {at}"),
+                _ => format!("An invalid code point {unit:08X} in UTF-32 at byte {at}"),
+            }))),
+            None => Some(Ok(char::REPLACEMENT_CHARACTER)),
+        }
+    }
+
+    fn incomplete(&self) -> std::io::Error {
+        self.invalid(match self.unit_size() {
+            2 => format!("An odd count of bytes, {}, in UTF-16", self.offset),
+            _ => format!("A count of bytes, {}, which is not a multiple of 4 in UTF-32", self.offset),
+        })
+    }
+
+    fn invalid(&self, msg: String) -> std::io::Error {
+        std::io::Error::new(IoErrorKind::InvalidData, msg)
+    }
+}
+
+impl<I: Iterator<Item=u8>> Iterator for UnitDecoder<I> {
+    type Item = std::io::Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.encoding == Encoding::Utf8 {
+            return self.next_raw().map(Ok);
+        }
+        if self.out_pos == self.out_len {
+            if self.failed {
+                return None;
+            }
+            match self.next_char()? {
+                Ok(c) => self.out_len = c.encode_utf8(&mut self.out).len(),
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+            self.out_pos = 0;
+        }
+        self.out_pos += 1;
+        Some(Ok(self.out[self.out_pos - 1]))
+    }
+}
+
+/// return the encoding of json which starts with **head** and the length of its BOM
+fn detect_encoding(head: &[u8]) -> Result<(Encoding, usize), Error> {
+    match head {
+        [0xEF, 0xBB, 0xBF, ..] => return Ok((Encoding::Utf8, 3)),
+        [0, 0, 0xFE, 0xFF] => return Ok((Encoding::Utf32Be, 4)),
+        [0xFF, 0xFE, 0, 0] => return Ok((Encoding::Utf32Le, 4)),
+        [0xFE, 0xFF, ..] => return Ok((Encoding::Utf16Be, 2)),
+        [0xFF, 0xFE, ..] => return Ok((Encoding::Utf16Le, 2)),
+        _ => {}
+    }
+    // a short json may have a single char, so only the available bytes are checked
+    let zeros: Vec<bool> = head.iter().map(|b| *b == 0).collect();
+    let encoding = match zeros.as_slice() {
+        [false, false, false, false] | [false, false, false] | [false, false] | [false] | [] => Encoding::Utf8,
+        [true, true, true, false] => Encoding::Utf32Be,
+        [false, true, true, true] => Encoding::Utf32Le,
+        [true, false, true, false] | [true, false] => Encoding::Utf16Be,
+        [false, true, false, true] | [false, true] => Encoding::Utf16Le,
+        _ => {
+            let pattern: Vec<String> = head.iter().map(|b| format!("{b:02X}")).collect();
+            return Err(Error::unsupported_encoding(format!("The encoding of json which starts with {} is not supported", pattern.join(" "))));
+        }
+    };
+    Ok((encoding, 0))
+}
+
+/// Byte order of Utf16Reader
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// Read json in UTF-16 and hand it to a walker of new_fallible() as UTF-8. Unlike DetectingReader, invalid UTF-16,
+/// which is a lone surrogate or an odd count of bytes, is not replaced: it finishes the stream with an InvalidData io
+/// error, so the walker returns Io error
+/// ### Example
+/// ```
+/// use json_walker::json_walker::{JsonWalker, Utf16Reader};
+///
+/// let utf16: Vec<u8> = "\u{FEFF}{\"a\": \"\u{1F600}\"}".encode_utf16().flat_map(|u| u.to_be_bytes()).collect();
+/// let mut walker = JsonWalker::new_fallible(Utf16Reader::new(utf16.into_iter()), 0);
+/// walker.next_key().unwrap();
+/// assert_eq!(walker.next_item().unwrap().1, "\u{1F600}");
+/// ```
+pub struct Utf16Reader<I: Iterator<Item=u8>> {
+    decoder: UnitDecoder<I>,
+    /// it is None till the first unit is read, unless it is given by with_endianness()
+    endianness: Option<Endianness>,
+}
+
+impl<I: Iterator<Item=u8>> Utf16Reader<I> {
+    /// the byte order is taken from the BOM, which is dropped. Without a BOM, it is taken from the first char, which
+    /// is ASCII in json
+    pub fn new(inner: I) -> Box<Self> {
+        Box::new(Utf16Reader { decoder: UnitDecoder::new(inner, Encoding::Utf16Le, true), endianness: None })
+    }
+
+    /// A BOM is decoded as any other char, then the walker skips it as a UTF-8 BOM
+    pub fn with_endianness(inner: I, endianness: Endianness) -> Box<Self> {
+        let mut reader = Utf16Reader::new(inner);
+        reader.set_endianness(endianness);
+        reader
+    }
+
+    fn set_endianness(&mut self, endianness: Endianness) {
+        self.endianness = Some(endianness);
+        self.decoder.encoding = match endianness {
+            Endianness::Little => Encoding::Utf16Le,
+            Endianness::Big => Encoding::Utf16Be,
+        };
+    }
+
+    fn detect_endianness(&mut self) {
+        let (endianness, bom) = match self.decoder.peek_head(2) {
+            [0xFF, 0xFE] => (Endianness::Little, 2),
+            [0xFE, 0xFF] => (Endianness::Big, 2),
+            [0, ..] => (Endianness::Big, 0),
+            _ => (Endianness::Little, 0),
+        };
+        self.set_endianness(endianness);
+        self.decoder.skip_head(bom);
+    }
+}
+
+impl<I: Iterator<Item=u8>> Iterator for Utf16Reader<I> {
+    type Item = std::io::Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.endianness.is_none() {
+            self.detect_endianness();
+        }
+        self.decoder.next()
+    }
+}
+
+/// Split a stream of frames, each one a 4-byte big-endian length followed by that many bytes of json, as sent by
+/// socket protocols. Each frame is a reader for a walker, which can be reused for the next frame by reset()
+/// ### Example
+/// ```
+/// use json_walker::json_walker::{FramedReader, JsonWalker, StringReader};
+///
+/// let mut stream = vec![];
+/// for json in [r#"{"id": 1}"#, r#"{"id": 2}"#] {
+///     stream.extend((json.len() as u32).to_be_bytes());
+///     stream.extend(json.as_bytes());
+/// }
+/// let mut frames = FramedReader::new(stream.as_slice(), 1024);
+/// let mut walker = JsonWalker::new(StringReader::new(String::new()), 0);
+/// let mut ids = vec![];
+/// while let Some(frame) = frames.next_frame().unwrap() {
+///     walker.reset(frame);
+///     walker.next_key_by_name("id").unwrap();
+///     ids.push(walker.next_item().unwrap().1);
+/// }
+/// assert_eq!(ids, vec!["1", "2"]);
+/// ```
+pub struct FramedReader<R: Read> {
+    inner: R,
+    max_frame: usize,
+    /// count of frames read so far, to locate errors
+    frames: u64,
+}
+
+impl<R: Read> FramedReader<R> {
+    pub fn new(inner: R, max_frame: usize) -> Self {
+        FramedReader { inner, max_frame, frames: 0 }
+    }
+
+    /// Read the next frame. None means the stream is finished right at a frame boundary. A frame which is cut short is
+    /// Io error. An empty frame is InvalidStructure error and a frame larger than max_frame is LimitExceeded error;
+    /// both are passed over, so the next call reads the frame after them
+    pub fn next_frame(&mut self) -> Result<Option<Box<dyn Iterator<Item=u8>>>, Error> {
+        let mut header = [0u8; 4];
+        let n = self.read_full(&mut header)?;
+        if n == 0 {
+            return Ok(None);
+        }
+        self.frames += 1;
+        if n < header.len() {
+            return Err(self.cut_short("header", n, header.len()));
+        }
+        let len = u32::from_be_bytes(header) as usize;
+        if len == 0 {
+            return Err(Error::invalid_structure(format!("Frame {} is empty", self.frames)));
+        }
+        if len > self.max_frame {
+            let skipped = std::io::copy(&mut self.inner.by_ref().take(len as u64), &mut std::io::sink())? as usize;
+            if skipped < len {
+                return Err(self.cut_short("payload", skipped, len));
+            }
+            return Err(Error::limit_exceeded(format!("Frame {} has {len} bytes, which is more than {} bytes", self.frames, self.max_frame)));
+        }
+        let mut payload = vec![0u8; len];
+        let n = self.read_full(&mut payload)?;
+        if n < len {
+            return Err(self.cut_short("payload", n, len));
+        }
+        Ok(Some(Box::new(payload.into_iter())))
+    }
+
+    /// fill **buf** unless the stream finishes, then return the count of read bytes
+    fn read_full(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut n = 0;
+        while n < buf.len() {
+            match self.inner.read(&mut buf[n..]) {
+                Ok(0) => break,
+                Ok(m) => n += m,
+                Err(e) if e.kind() == IoErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(n)
+    }
+
+    fn cut_short(&self, part: &str, read: usize, expected: usize) -> Error {
+        Error::new(ErrorKind::Io(std::io::ErrorKind::UnexpectedEof), format!("The {part} of frame {} is cut short: {read} of {expected} bytes", self.frames))
+    }
+}
+
+#[cfg(test)]
+mod readers_tests {
+    use crate::{Error, ErrorKind};
+    use crate::json_walker::JsonWalker;
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::parser_core::{Item, ValueType};
+    use crate::readers::{ChunkedReader, DetectingReader, Encoding, Endianness, FileReader, ForkableReader, FramedReader, IoReader, SeekableReader, SliceReader, StringReader, Utf16Reader};
+
+    fn encode(text: &str, encoding: Encoding, bom: bool) -> Vec<u8> {
+        let text = if bom { format!("\u{FEFF}{text}") } else { text.to_string() };
+        match encoding {
+            Encoding::Utf8 => text.into_bytes(),
+            Encoding::Utf16Le => text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect(),
+            Encoding::Utf16Be => text.encode_utf16().flat_map(|u| u.to_be_bytes()).collect(),
+            Encoding::Utf32Le => text.chars().flat_map(|c| (c as u32).to_le_bytes()).collect(),
+            Encoding::Utf32Be => text.chars().flat_map(|c| (c as u32).to_be_bytes()).collect(),
+        }
+    }
+
+    fn items(walker: &mut JsonWalker) -> Vec<Item> {
+        let mut items = vec![];
+        while let Ok(item) = walker.next_item() {
+            items.push(item);
+        }
+        items
+    }
+
+    fn detect(bytes: Vec<u8>) -> Result<Box<DetectingReader>, Error> {
+        DetectingReader::new(Box::new(bytes.into_iter()))
+    }
+
+    #[test]
+    fn test_encodings() {
+        let json = CORRECT_JSON.replace("str1", "str1 \u{e9}\u{1F600}");
+        let expected = items(&mut JsonWalker::new(StringReader::new(json.clone()), 0));
+        for encoding in [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be, Encoding::Utf32Le, Encoding::Utf32Be] {
+            for bom in [false, true] {
+                // the leading white-space of CORRECT_JSON is trimmed, so the first char is "{"
+                let reader = detect(encode(json.trim_start(), encoding, bom)).unwrap();
+                assert_eq!(reader.encoding(), encoding, "bom: {bom}");
+                assert_eq!(items(&mut JsonWalker::new(reader, 50)), expected, "{encoding:?}, bom: {bom}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_short_and_invalid() {
+        let value = |bytes: Vec<u8>| items(&mut JsonWalker::new(detect(bytes).unwrap(), 0));
+        assert_eq!(value(encode("1", Encoding::Utf16Le, false)), vec![(ValueType::UInt, "1".to_string())]);
+        assert_eq!(detect(encode("1", Encoding::Utf16Be, false)).unwrap().encoding(), Encoding::Utf16Be);
+        assert_eq!(detect(encode("1", Encoding::Utf32Le, false)).unwrap().encoding(), Encoding::Utf32Le);
+        assert_eq!(detect(vec![]).unwrap().encoding(), Encoding::Utf8);
+
+        // a lone surrogate
+        let mut bytes = encode(r#"["a"#, Encoding::Utf16Le, false);
+        bytes.extend([0x00, 0xD8]);
+        bytes.extend(encode(r#"b"]"#, Encoding::Utf16Le, false));
+        assert_eq!(value(bytes)[0].1, "a\u{FFFD}b");
+
+        let e = detect(vec![0, 0, b'{', 0]).err().unwrap();
+        assert_eq!(e.kind, ErrorKind::UnsupportedEncoding);
+        assert_eq!(e.msg, "The encoding of json which starts with 00 00 7B 00 is not supported");
+        assert_eq!(detect(vec![b'{', 0, 0]).err().unwrap().kind, ErrorKind::UnsupportedEncoding);
+    }
+
+    fn frame(json: &str) -> Vec<u8> {
+        let mut bytes = (json.len() as u32).to_be_bytes().to_vec();
+        bytes.extend(json.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_frames() {
+        let mut stream = [frame(r#"{"a": 1}"#), frame(""), frame(r#"[true, "x"]"#), frame(r#"{"b": "cut short"}"#)].concat();
+        stream.truncate(stream.len() - 5);
+        let mut frames = FramedReader::new(stream.as_slice(), 100);
+
+        let mut walker = JsonWalker::new(frames.next_frame().unwrap().unwrap(), 10);
+        assert_eq!(items(&mut walker), vec![(ValueType::Str, "a".to_string()), (ValueType::UInt, "1".to_string())]);
+        let e = frames.next_frame().err().unwrap();
+        assert_eq!((e.kind, e.msg), (ErrorKind::InvalidStructure, "Frame 2 is empty".to_string()));
+        walker.reset(frames.next_frame().unwrap().unwrap());
+        assert_eq!(walker.stats().bytes_consumed, 1);
+        assert_eq!(walker.get_recent_piece_window(10), "[");
+        assert_eq!(items(&mut walker), vec![(ValueType::Bool, "true".to_string()), (ValueType::Str, "x".to_string())]);
+        let e = frames.next_frame().err().unwrap();
+        assert_eq!((e.kind, e.msg), (ErrorKind::Io(std::io::ErrorKind::UnexpectedEof), "The payload of frame 4 is cut short: 13 of 18 bytes".to_string()));
+        assert!(frames.next_frame().unwrap().is_none());
+
+        // a large frame is passed over
+        let stream = [frame(&"1".repeat(101)), frame("2"), vec![0, 0]].concat();
+        let mut frames = FramedReader::new(stream.as_slice(), 100);
+        assert_eq!(frames.next_frame().err().unwrap().kind, ErrorKind::LimitExceeded);
+        walker.reset(frames.next_frame().unwrap().unwrap());
+        assert_eq!(walker.next_item(), Ok((ValueType::UInt, "2".to_string())));
+        assert_eq!(frames.next_frame().err().unwrap().msg, "The header of frame 3 is cut short: 2 of 4 bytes");
+    }
+
+    /// write **text** to a new file of the temp directory and return its path
+    fn temp_file(name: &str, text: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("json_walker_{}_{name}.json", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_file_reader() {
+        let json = format!("[{}]", vec![CORRECT_JSON; 100].join(","));
+        assert!(json.len() > 8 * 1024);
+        let expected = items(&mut JsonWalker::new(StringReader::new(json.clone()), 0));
+        let path = temp_file("large", &json);
+        assert_eq!(items(&mut JsonWalker::new(FileReader::new(&path).unwrap(), 0)), expected);
+        assert_eq!(items(&mut JsonWalker::new(FileReader::with_capacity(&path, 7).unwrap(), 0)), expected);
+        std::fs::remove_file(&path).unwrap();
+
+        // the file ends in the middle of a token
+        let path = temp_file("cut", r#"{"key1": 123, "key2": ["ab"#);
+        let mut walker = JsonWalker::new(FileReader::with_capacity(&path, 4).unwrap(), 0);
+        let e = loop {
+            if let Err(e) = walker.next_item() {
+                break e;
+            }
+        };
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(e.kind, ErrorKind::UnexpectedEos);
+        assert!(e.msg.starts_with("Unexpected end of stream in a string at byte 26 (1:27)"), "{}", e.msg);
+
+        assert_eq!(FileReader::new("/nonexistent/json_walker.json").err().unwrap().kind(), std::io::ErrorKind::NotFound);
+    }
+
+    /// pass one byte per read and fail after **fail_at** bytes, while every other read is Interrupted
+    struct Dribble {
+        bytes: Vec<u8>,
+        pos: usize,
+        fail_at: usize,
+        interrupt: bool,
+    }
+
+    impl std::io::Read for Dribble {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(std::io::ErrorKind::Interrupted.into());
+            }
+            if self.pos == self.fail_at {
+                return Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset"));
+            }
+            if self.pos == self.bytes.len() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.bytes[self.pos];
+            self.pos += 1;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_io_reader() {
+        let expected = items(&mut JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0));
+        let cursor = std::io::Cursor::new(CORRECT_JSON.as_bytes().to_vec());
+        assert_eq!(items(&mut JsonWalker::new(IoReader::new(cursor.clone()), 0)), expected);
+        assert_eq!(items(&mut JsonWalker::new(IoReader::with_capacity(cursor, 3), 0)), expected);
+
+        let bytes = CORRECT_JSON.as_bytes().to_vec();
+        let reader = IoReader::new(Dribble { bytes: bytes.clone(), pos: 0, fail_at: usize::MAX, interrupt: false });
+        let error = reader.error_handle();
+        assert_eq!(items(&mut JsonWalker::new(reader, 0)), expected);
+        assert!(error.borrow().is_none());
+
+        // the stream is finished by an error after the root value
+        let mut bytes = br#"[1, "a"]"#.to_vec();
+        let fail_at = bytes.len();
+        bytes.extend(b"  ");
+        let reader = IoReader::new(Dribble { bytes, pos: 0, fail_at, interrupt: false });
+        let error = reader.error_handle();
+        assert_eq!(items(&mut JsonWalker::new(reader, 0)).len(), 2);
+        assert_eq!(error.borrow().as_ref().map(|e| e.kind()), Some(std::io::ErrorKind::ConnectionReset));
+    }
+
+    #[test]
+    fn test_slice_reader() {
+        let expected = items(&mut JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0));
+        assert_eq!(items(&mut JsonWalker::new(SliceReader::new(CORRECT_JSON.as_bytes()), 0)), expected);
+
+        // a buffer on the stack, of which only a part is json
+        let buffer: [u8; 16] = *b"xx[1, [true]]yyy";
+        let other = String::from(r#"{"a": null}"#);
+        let mut walker = JsonWalker::new(SliceReader::new(&buffer[2..13]), 20);
+        assert_eq!(walker.next_item(), Ok((ValueType::UInt, "1".to_string())));
+        assert_eq!(walker.next_item(), Ok((ValueType::Bool, "true".to_string())));
+        assert_eq!(walker.next_item().map_err(|e| e.kind().clone()), Err(ErrorKind::EOS));
+        assert_eq!(walker.get_recent_piece(), "[1, [true]]");
+
+        // reset() can take other bytes, as long as they outlive the walker
+        walker.reset(SliceReader::new(other.as_bytes()));
+        assert_eq!(walker.next_key().unwrap().1, "a");
+        assert_eq!(walker.next_item(), Ok((ValueType::Null, "null".to_string())));
+    }
+
+    #[test]
+    fn test_forkable_reader() {
+        let mut reader = StringReader::new("[1, 2]".to_string());
+        reader.nth(2);
+        let mut fork = reader.clone();
+        assert_eq!((reader.position(), reader.remaining()), (3, 3));
+        assert_eq!(reader.collect::<Vec<_>>(), b" 2]");
+        assert_eq!((fork.position(), fork.remaining()), (3, 3));
+        assert_eq!(fork.next(), Some(b' '));
+
+        let mut reader = SliceReader::new(b"[true]");
+        assert_eq!((reader.position(), reader.remaining()), (0, 6));
+        reader.next();
+        let fork = reader.clone();
+        assert_eq!(reader.by_ref().count(), 5);
+        assert_eq!((reader.position(), reader.remaining()), (6, 0));
+        assert_eq!((fork.position(), fork.remaining()), (1, 5));
+
+        // a fork may be read on another thread
+        let fork = StringReader::new("[1, 2]".to_string()).clone();
+        assert_eq!(std::thread::spawn(move || fork.count()).join().unwrap(), 6);
+    }
+
+    #[test]
+    fn test_seekable_reader() {
+        let json = format!("[{}]", [CORRECT_JSON; 10].join(","));
+        let path = temp_file("seek", &json);
+        let mut readers: [Box<dyn SeekableReader>; 3] = [
+            StringReader::new(json.clone()),
+            SliceReader::new(json.as_bytes()),
+            FileReader::with_capacity(&path, 16).unwrap(),
+        ];
+        for reader in readers.iter_mut() {
+            reader.nth(99);
+            assert_eq!(reader.offset(), 100);
+            // inside the buffer of FileReader, then before and after it
+            for offset in [95, 3, 2000, 100] {
+                reader.seek_to(offset).unwrap();
+                assert_eq!(reader.offset(), offset);
+                assert_eq!(reader.next(), Some(json.as_bytes()[offset as usize]));
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_chunked_reader() {
+        // a value per string, number and literal, so every split inside them is covered
+        let expected = items(&mut JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0));
+        let bytes = CORRECT_JSON.as_bytes();
+        for i in 0..=bytes.len() {
+            for j in i..=bytes.len() {
+                let chunks = [&bytes[..i], &bytes[i..j], &bytes[j..]];
+                assert_eq!(items(&mut JsonWalker::new(ChunkedReader::new(chunks.into_iter()), 0)), expected, "split at {i} and {j}");
+            }
+        }
+
+        let chunks: Vec<Vec<u8>> = vec![vec![], b"[\"a\\".to_vec(), vec![], b"u0041\", -1.5e".to_vec(), b"3, nu".to_vec(), b"ll]".to_vec(), vec![]];
+        let mut walker = JsonWalker::new(ChunkedReader::new(chunks.into_iter()), 0);
+        assert_eq!(items(&mut walker), vec![
+            (ValueType::Str, "aA".to_string()),
+            (ValueType::Float, "-1.5e3".to_string()),
+            (ValueType::Null, "null".to_string()),
+        ]);
+        assert!(ChunkedReader::new(Vec::<Vec<u8>>::new().into_iter()).next().is_none());
+    }
+
+    #[test]
+    fn test_utf16_reader() {
+        let expected = items(&mut JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0));
+        for encoding in [Encoding::Utf16Le, Encoding::Utf16Be] {
+            let endianness = if encoding == Encoding::Utf16Le { Endianness::Little } else { Endianness::Big };
+            for bom in [false, true] {
+                let bytes = encode(CORRECT_JSON, encoding, bom);
+                assert_eq!(items(&mut JsonWalker::new_fallible(Utf16Reader::new(bytes.clone().into_iter()), 0)), expected);
+                assert_eq!(items(&mut JsonWalker::new_fallible(Utf16Reader::with_endianness(bytes.into_iter(), endianness), 0)), expected);
+            }
+            let json = "[\"\u{e9}\u{10437}\u{FFFD}\", \"\u{1F600}\"]";
+            let mut walker = JsonWalker::new_fallible(Utf16Reader::new(encode(json, encoding, false).into_iter()), 0);
+            assert_eq!(items(&mut walker), vec![(ValueType::Str, "\u{e9}\u{10437}\u{FFFD}".to_string()), (ValueType::Str, "\u{1F600}".to_string())]);
+        }
+
+        let invalid = ErrorKind::Io(std::io::ErrorKind::InvalidData);
+        let le = |units: &[u16]| -> Vec<u8> { units.iter().flat_map(|u| u.to_le_bytes()).collect() };
+        let cases: [(Vec<u8>, &str); 4] = [
+            (le(&[b'[' as u16, b'"' as u16, 0xD801, b'"' as u16, b']' as u16]), "A lone surrogate D801 in UTF-16 at byte 4"),
+            (le(&[b'[' as u16, b'"' as u16, 0xDC37, b'"' as u16, b']' as u16]), "A lone surrogate DC37 in UTF-16 at byte 4"),
+            (le(&[b'[' as u16, b'"' as u16, 0xD801]), "A lone surrogate D801 in UTF-16 at byte 4"),
+            ([le(&[b'[' as u16, b'1' as u16]), vec![b']']].concat(), "An odd count of bytes, 5, in UTF-16"),
+        ];
+        for (bytes, msg) in cases {
+            let mut walker = JsonWalker::new_fallible(Utf16Reader::new(bytes.into_iter()), 0);
+            let e = walker.current_value_content().unwrap_err();
+            assert_eq!((e.kind(), e.msg.as_str()), (&invalid, msg));
+        }
+        let mut reader = Utf16Reader::new(vec![b'1'].into_iter());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,445 @@
+#[cfg(feature = "serialize")]
+pub mod serialize_mod {
+    use std::fmt::Display;
+    use std::io::Write;
+
+    use serde::ser::{self, Impossible, Serialize};
+
+    use crate::Error;
+    use crate::writer::JsonWriter;
+
+    //region error
+    impl ser::Error for Error {
+        fn custom<T>(msg: T) -> Self where T: Display {
+            Error::serde(msg.to_string())
+        }
+    }
+    //endregion
+
+    //region api
+    /// serialize **value** as compact json into **writer**
+    /// ### Example
+    /// ```
+    /// let mut out = Vec::new();
+    /// json_walker::to_writer(&mut out, &(1, "a", [true])).unwrap();
+    /// assert_eq!(out, br#"[1,"a",[true]]"#);
+    /// ```
+    pub fn to_writer<W: Write, T: ?Sized + Serialize>(writer: W, value: &T) -> Result<(), Error> {
+        let mut ser = Serializer::new(JsonWriter::new(writer));
+        value.serialize(&mut ser)?;
+        ser.into_inner().finish()?;
+        Ok(())
+    }
+
+    /// serialize **value** as compact json string
+    pub fn to_string<T: ?Sized + Serialize>(value: &T) -> Result<String, Error> {
+        let mut out = Vec::with_capacity(128);
+        to_writer(&mut out, value)?;
+        // the writer only produces valid utf-8
+        Ok(String::from_utf8(out).unwrap())
+    }
+    //endregion
+
+    /// serde::Serializer on top of JsonWriter. Enums are written in externally tagged form, like {"Variant":value}
+    pub struct Serializer<W: Write> {
+        writer: JsonWriter<W>,
+    }
+
+    impl<W: Write> Serializer<W> {
+        pub fn new(writer: JsonWriter<W>) -> Self {
+            Serializer { writer }
+        }
+
+        pub fn into_inner(self) -> JsonWriter<W> {
+            self.writer
+        }
+    }
+
+    /// an open array or object. If it is the content of an enum variant, the wrapper object is closed too
+    pub struct Compound<'a, W: Write> {
+        ser: &'a mut Serializer<W>,
+        variant: bool,
+    }
+
+    impl<'a, W: Write> Compound<'a, W> {
+        fn end(self) -> Result<(), Error> {
+            self.ser.writer.end()?;
+            if self.variant {
+                self.ser.writer.end()?;
+            }
+            Ok(())
+        }
+    }
+
+    impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = Compound<'a, W>;
+        type SerializeTuple = Compound<'a, W>;
+        type SerializeTupleStruct = Compound<'a, W>;
+        type SerializeTupleVariant = Compound<'a, W>;
+        type SerializeMap = Compound<'a, W>;
+        type SerializeStruct = Compound<'a, W>;
+        type SerializeStructVariant = Compound<'a, W>;
+
+        fn serialize_bool(self, v: bool) -> Result<(), Error> {
+            self.writer.value_bool(v)
+        }
+
+        fn serialize_i8(self, v: i8) -> Result<(), Error> {
+            self.writer.value_i64(v as i64)
+        }
+
+        fn serialize_i16(self, v: i16) -> Result<(), Error> {
+            self.writer.value_i64(v as i64)
+        }
+
+        fn serialize_i32(self, v: i32) -> Result<(), Error> {
+            self.writer.value_i64(v as i64)
+        }
+
+        fn serialize_i64(self, v: i64) -> Result<(), Error> {
+            self.writer.value_i64(v)
+        }
+
+        fn serialize_u8(self, v: u8) -> Result<(), Error> {
+            self.writer.value_u64(v as u64)
+        }
+
+        fn serialize_u16(self, v: u16) -> Result<(), Error> {
+            self.writer.value_u64(v as u64)
+        }
+
+        fn serialize_u32(self, v: u32) -> Result<(), Error> {
+            self.writer.value_u64(v as u64)
+        }
+
+        fn serialize_u64(self, v: u64) -> Result<(), Error> {
+            self.writer.value_u64(v)
+        }
+
+        fn serialize_f32(self, v: f32) -> Result<(), Error> {
+            self.writer.value_f32(v)
+        }
+
+        fn serialize_f64(self, v: f64) -> Result<(), Error> {
+            self.writer.value_f64(v)
+        }
+
+        fn serialize_char(self, v: char) -> Result<(), Error> {
+            self.writer.value_str(v.encode_utf8(&mut [0; 4]))
+        }
+
+        fn serialize_str(self, v: &str) -> Result<(), Error> {
+            self.writer.value_str(v)
+        }
+
+        fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+            self.writer.begin_array()?;
+            for b in v {
+                self.writer.value_u64(*b as u64)?;
+            }
+            self.writer.end()
+        }
+
+        fn serialize_none(self) -> Result<(), Error> {
+            self.writer.value_null()
+        }
+
+        fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_unit(self) -> Result<(), Error> {
+            self.writer.value_null()
+        }
+
+        fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+            self.writer.value_null()
+        }
+
+        fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<(), Error> {
+            self.writer.value_str(variant)
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<(), Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, variant: &'static str, value: &T) -> Result<(), Error> {
+            self.writer.begin_object()?;
+            self.writer.key(variant)?;
+            value.serialize(&mut *self)?;
+            self.writer.end()
+        }
+
+        fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+            self.writer.begin_array()?;
+            Ok(Compound { ser: self, variant: false })
+        }
+
+        fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Error> {
+            self.serialize_seq(Some(len))
+        }
+
+        fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, Error> {
+            self.serialize_seq(Some(len))
+        }
+
+        fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, Error> {
+            self.writer.begin_object()?;
+            self.writer.key(variant)?;
+            self.writer.begin_array()?;
+            Ok(Compound { ser: self, variant: true })
+        }
+
+        fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+            self.writer.begin_object()?;
+            Ok(Compound { ser: self, variant: false })
+        }
+
+        fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct, Error> {
+            self.serialize_map(Some(len))
+        }
+
+        fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, Error> {
+            self.writer.begin_object()?;
+            self.writer.key(variant)?;
+            self.writer.begin_object()?;
+            Ok(Compound { ser: self, variant: true })
+        }
+    }
+
+    //region compounds
+    impl<'a, W: Write> ser::SerializeSeq for Compound<'a, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            value.serialize(&mut *self.ser)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            Compound::end(self)
+        }
+    }
+
+    impl<'a, W: Write> ser::SerializeTuple for Compound<'a, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            value.serialize(&mut *self.ser)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            Compound::end(self)
+        }
+    }
+
+    impl<'a, W: Write> ser::SerializeTupleStruct for Compound<'a, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            value.serialize(&mut *self.ser)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            Compound::end(self)
+        }
+    }
+
+    impl<'a, W: Write> ser::SerializeTupleVariant for Compound<'a, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            value.serialize(&mut *self.ser)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            Compound::end(self)
+        }
+    }
+
+    impl<'a, W: Write> ser::SerializeMap for Compound<'a, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+            key.serialize(MapKeySerializer { writer: &mut self.ser.writer })
+        }
+
+        fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            value.serialize(&mut *self.ser)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            Compound::end(self)
+        }
+    }
+
+    impl<'a, W: Write> ser::SerializeStruct for Compound<'a, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+            self.ser.writer.key(key)?;
+            value.serialize(&mut *self.ser)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            Compound::end(self)
+        }
+    }
+
+    impl<'a, W: Write> ser::SerializeStructVariant for Compound<'a, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+            self.ser.writer.key(key)?;
+            value.serialize(&mut *self.ser)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            Compound::end(self)
+        }
+    }
+    //endregion
+
+    //region map key
+    /// json keys are strings, so only str, char and unit variants are accepted
+    struct MapKeySerializer<'a, W: Write> {
+        writer: &'a mut JsonWriter<W>,
+    }
+
+    fn key_error() -> Error {
+        Error::wrong_data_type("Map keys must be strings")
+    }
+
+    impl<'a, W: Write> ser::Serializer for MapKeySerializer<'a, W> {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = Impossible<(), Error>;
+        type SerializeTuple = Impossible<(), Error>;
+        type SerializeTupleStruct = Impossible<(), Error>;
+        type SerializeTupleVariant = Impossible<(), Error>;
+        type SerializeMap = Impossible<(), Error>;
+        type SerializeStruct = Impossible<(), Error>;
+        type SerializeStructVariant = Impossible<(), Error>;
+
+        fn serialize_str(self, v: &str) -> Result<(), Error> {
+            self.writer.key(v)
+        }
+
+        fn serialize_char(self, v: char) -> Result<(), Error> {
+            self.writer.key(v.encode_utf8(&mut [0; 4]))
+        }
+
+        fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<(), Error> {
+            self.writer.key(variant)
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<(), Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_bool(self, _v: bool) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_i8(self, _v: i8) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_i16(self, _v: i16) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_i32(self, _v: i32) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_i64(self, _v: i64) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_u8(self, _v: u8) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_u16(self, _v: u16) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_u32(self, _v: u32) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_u64(self, _v: u64) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_f32(self, _v: f32) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_f64(self, _v: f64) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_bytes(self, _v: &[u8]) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_none(self) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_unit(self) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> { Err(key_error()) }
+
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<(), Error> {
+            Err(key_error())
+        }
+
+        fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> { Err(key_error()) }
+
+        fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> { Err(key_error()) }
+
+        fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, Error> {
+            Err(key_error())
+        }
+
+        fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> { Err(key_error()) }
+
+        fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Error> { Err(key_error()) }
+
+        fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, Error> {
+            Err(key_error())
+        }
+    }
+    //endregion
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "serialize", feature = "deserialize"))]
+mod serializer_tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use crate::{ErrorKind, to_string};
+    use crate::json_walker::JsonWalker;
+    use crate::json_walker::walker_test_de::data1::{create_data, MixedDataTypes};
+    use crate::json_walker::walker_test_de::data2;
+    use crate::readers::StringReader;
+
+    #[test]
+    fn test_round_trip_data1() {
+        let data = create_data();
+        let json = to_string(&data).unwrap();
+        assert_eq!(json, serde_json::to_string(&data).unwrap());
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        assert_eq!(walker.current_value::<MixedDataTypes>().unwrap(), data);
+    }
+
+    #[test]
+    fn test_round_trip_data2() {
+        let data = data2::create_data();
+        let json = to_string(&data).unwrap();
+        assert_eq!(json, serde_json::to_string(&data).unwrap());
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        assert_eq!(walker.current_value::<Vec<data2::Person>>().unwrap(), data);
+    }
+
+    #[test]
+    fn test_map_keys() {
+        let map: BTreeMap<char, Option<()>> = [('a', None), ('"', Some(()))].into_iter().collect();
+        assert_eq!(to_string(&map).unwrap(), r#"{"\"":null,"a":null}"#);
+        let map: HashMap<i32, i32> = [(1, 2)].into_iter().collect();
+        assert_eq!(to_string(&map).unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(to_string(&f64::NAN).unwrap_err().kind, ErrorKind::WrongDataType);
+    }
+}
@@ -0,0 +1,90 @@
+use std::io::Write;
+
+use crate::Error;
+use crate::parser_core::{Content, TextItem, ValueType};
+use crate::writer::JsonWriter;
+
+mod aggregate;
+mod canonical;
+mod compare;
+mod concat_arrays;
+mod csv;
+mod extract;
+mod key_profile;
+mod merge_patch;
+mod minify;
+pub(crate) mod ndjson;
+mod normalize;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod patch;
+mod reformat;
+mod redact;
+mod roundtrip;
+mod schema;
+mod split_array;
+mod stream_validator;
+pub(crate) mod structural_hash;
+mod transform_keys;
+mod unwrap_ndjson;
+
+pub use aggregate::{AggKind, aggregate, Aggregate, NonNumericPolicy};
+pub use canonical::canonicalize_stream;
+pub use compare::{compare, CompareOptions, ComparisonResult, Difference};
+pub use concat_arrays::concat_arrays;
+pub use csv::{CsvOptions, NestedPolicy, to_csv};
+pub use extract::{extract_multi, extract_to, RouteCount};
+pub use key_profile::{KeyProfile, KeyStats, profile_keys};
+pub use merge_patch::merge_patch_stream;
+pub use minify::minify;
+pub use ndjson::{array_to_ndjson, ndjson_to_array};
+pub use normalize::{LenientOptions, normalize};
+#[cfg(feature = "rayon")]
+pub use parallel::{element_ranges, par_elements};
+pub use patch::{apply_patch, diff_patch, PatchOp};
+pub use redact::{redact, Redaction};
+pub use roundtrip::{Divergence, RoundTripPolicy, verify_roundtrip};
+pub use schema::{infer_schema, SchemaNode, SchemaReport};
+pub use reformat::{Indent, IndentStyle, NewLine, reformat};
+pub use split_array::split_array;
+pub use stream_validator::{Check, StreamValidator, Violation};
+pub use structural_hash::{HashOptions, structural_hash};
+pub use transform_keys::transform_keys;
+pub use unwrap_ndjson::unwrap_to_ndjson;
+
+/// pass an item of walk_forward() to the writer. The parser must keep escapes, so strings are copied as they are.
+/// "," and ":" are handled by the writer itself
+fn write_text_item<W: Write>(item: TextItem, w: &mut JsonWriter<W>) -> Result<(), Error> {
+    match item {
+        TextItem::Key(k) => w.raw_key(&k.1),
+        TextItem::Value(v) if v.0 == ValueType::Str => w.raw_str(&v.1),
+        TextItem::Value(v) => w.raw(&v.1),
+        TextItem::None(b'{') => w.begin_object(),
+        TextItem::None(b'[') => w.begin_array(),
+        TextItem::None(b'}') | TextItem::None(b']') => w.end(),
+        TextItem::None(_) => Ok(()),
+    }
+}
+
+/// write the whole content. Strings of Content are not escaped, so they are escaped here
+pub(crate) fn write_content<W: Write>(content: &Content, w: &mut JsonWriter<W>) -> Result<(), Error> {
+    match content {
+        Content::Simple((ValueType::Str, s)) => w.value_str(s),
+        Content::Simple((_, s)) => w.raw(s),
+        Content::Array(a) => {
+            w.begin_array()?;
+            for c in a {
+                write_content(c, w)?;
+            }
+            w.end()
+        }
+        Content::Object(m) => {
+            w.begin_object()?;
+            for (k, c) in m {
+                w.key(k)?;
+                write_content(c, w)?;
+            }
+            w.end()
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{Error, NIL};
+use crate::parser_core::{get_current_status, get_path, Parser, set_keep_escapes, skip_value, TextItem, ValueType, walk_forward};
+use crate::path_matcher::PathMatcher;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggKind {
+    Sum,
+    Min,
+    Max,
+    Count,
+    Mean,
+}
+
+/// What to do with a matched value which is not a number, such as null, a string or a container
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NonNumericPolicy {
+    Skip,
+    /// WrongDataType error is returned
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aggregate {
+    /// None when there is no number to aggregate, except for Sum and Count which are 0
+    pub value: Option<f64>,
+    /// count of the numbers which are aggregated
+    pub count: u64,
+}
+
+/// Aggregate the numbers selected by **pointer** (such as "/items/*/amount") in one pass. Numbers are parsed right from
+/// the json text as f64 and nothing else is built.
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::{aggregate, AggKind, NonNumericPolicy};
+///
+/// let json = r#"{"items": [{"amount": 2.5}, {"amount": null}, {"amount": 4}]}"#;
+/// let sum = aggregate(StringReader::new(json.to_string()), "/items/*/amount", AggKind::Sum, NonNumericPolicy::Skip).unwrap();
+/// assert_eq!((sum.value, sum.count), (Some(6.5), 2));
+/// ```
+pub fn aggregate(reader: Box<dyn Iterator<Item=u8>>, pointer: &str, kind: AggKind, non_numeric: NonNumericPolicy) -> Result<Aggregate, Error> {
+    let matcher = PathMatcher::new(pointer)?;
+    let mut parser = Parser::new(reader, 0);
+    set_keep_escapes(&mut parser, true);
+
+    let mut acc: Option<f64> = None;
+    let mut count = 0u64;
+    let mut before_value = parser.next_byte != NIL;
+    loop {
+        if before_value && matcher.matches(&get_path(&mut parser)) {
+            match next_number(&mut parser)? {
+                Some(n) => {
+                    count += 1;
+                    acc = Some(match (kind, acc) {
+                        (_, None) => n,
+                        (AggKind::Min, Some(a)) => a.min(n),
+                        (AggKind::Max, Some(a)) => a.max(n),
+                        (_, Some(a)) => a + n,
+                    });
+                }
+                None if non_numeric == NonNumericPolicy::Error => {
+                    return Err(Error::wrong_data_type(format!("Expecting a number.{}", get_current_status(&mut parser))));
+                }
+                None => {}
+            }
+        }
+        if parser.next_byte == NIL {
+            break;
+        }
+        before_value = match walk_forward(&mut parser) {
+            TextItem::None(b':') => true,
+            TextItem::None(b'[') | TextItem::None(b',') => parser.stack.last().is_some_and(|s| s.symbol == '[') && parser.next_byte != b']',
+            _ => false,
+        };
+    }
+
+    let value = match kind {
+        AggKind::Sum => Some(acc.unwrap_or(0.0)),
+        AggKind::Count => Some(count as f64),
+        AggKind::Mean => acc.map(|a| a / count as f64),
+        AggKind::Min | AggKind::Max => acc,
+    };
+    Ok(Aggregate { value, count })
+}
+
+/// consume the value in front of the cursor. None means it is not a number
+fn next_number(parser: &mut Parser) -> Result<Option<f64>, Error> {
+    if parser.next_byte == b'{' || parser.next_byte == b'[' {
+        skip_value(parser)?;
+        return Ok(None);
+    }
+    match walk_forward(parser) {
+        TextItem::Value((ValueType::UInt | ValueType::Int | ValueType::Float, t)) => t.parse::<f64>()
+            .map(Some)
+            .map_err(|_| Error::parse_float(format!("`{t}` is not a number"))),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod aggregate_tests {
+    use crate::ErrorKind;
+    use crate::readers::StringReader;
+    use crate::tools::{aggregate, AggKind, NonNumericPolicy};
+
+    fn agg(json: &str, pointer: &str, kind: AggKind) -> (Option<f64>, u64) {
+        let a = aggregate(StringReader::new(json.to_string()), pointer, kind, NonNumericPolicy::Skip).unwrap();
+        (a.value, a.count)
+    }
+
+    #[test]
+    fn test_large_array() {
+        let items: Vec<String> = (0..100_000)
+            .map(|i| format!(r#"{{"id": {i}, "amount": {}, "meta": {{"amount": "x"}}}}"#, if i % 2 == 0 { format!("{}", i % 977) } else { format!("{}.25", -(i % 1013)) }))
+            .collect();
+        let json = format!(r#"{{"items": [{}], "amount": 1}}"#, items.join(","));
+
+        let v: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let amounts: Vec<f64> = v["items"].as_array().unwrap().iter().map(|i| i["amount"].as_f64().unwrap()).collect();
+        let sum: f64 = amounts.iter().sum();
+        let min = amounts.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = amounts.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+        let pointer = "/items/*/amount";
+        assert_eq!(agg(&json, pointer, AggKind::Sum), (Some(sum), 100_000));
+        assert_eq!(agg(&json, pointer, AggKind::Min), (Some(min), 100_000));
+        assert_eq!(agg(&json, pointer, AggKind::Max), (Some(max), 100_000));
+        assert_eq!(agg(&json, pointer, AggKind::Count), (Some(100_000.0), 100_000));
+        assert_eq!(agg(&json, pointer, AggKind::Mean), (Some(sum / 100_000.0), 100_000));
+        assert_eq!(agg(&json, "/items/7/amount", AggKind::Sum), (Some(-7.25), 1));
+        assert_eq!(agg(&json, "/amount", AggKind::Max), (Some(1.0), 1));
+    }
+
+    #[test]
+    fn test_non_numeric() {
+        let json = r#"[{"a": 1}, {"a": null}, {"a": "2"}, {"a": [3]}, {"a": {"a": 4}}, {"b": 5}, {"a": -1.5}]"#;
+        assert_eq!(agg(json, "/*/a", AggKind::Sum), (Some(-0.5), 2));
+        assert_eq!(agg(json, "/*/b", AggKind::Mean), (Some(5.0), 1));
+        assert_eq!(agg(json, "/*/c", AggKind::Mean), (None, 0));
+        assert_eq!(agg(json, "/*/c", AggKind::Sum), (Some(0.0), 0));
+        assert_eq!(agg("[]", "/*", AggKind::Count), (Some(0.0), 0));
+        let result = aggregate(StringReader::new(json.to_string()), "/*/a", AggKind::Sum, NonNumericPolicy::Error);
+        assert_eq!(result.unwrap_err().kind, ErrorKind::WrongDataType);
+    }
+}
@@ -0,0 +1,296 @@
+use std::io::Write;
+
+use crate::{Error, NIL};
+use crate::escape::unescape;
+use crate::parser_core::{begin_value, Content, copy_value, end_of_stream, finish, get_current_status, get_stats, is_poisoned, Parser, set_keep_escapes, Stats, TextItem, ValueType, walk_forward};
+use crate::writer::JsonWriter;
+
+impl Content {
+    /// the canonical form of JSON Canonicalization Scheme (RFC 8785): no white-space, members sorted by the UTF-16 code
+    /// units of their keys, numbers in ECMAScript form and strings with minimal escaping
+    pub fn to_canonical_json(&self) -> Result<String, Error> {
+        let mut w = JsonWriter::new(Vec::new());
+        write_canonical_content(self, &mut w)?;
+        // the writer only produces valid utf-8
+        Ok(String::from_utf8(w.finish()?).unwrap())
+    }
+}
+
+fn write_canonical_content(content: &Content, w: &mut JsonWriter<Vec<u8>>) -> Result<(), Error> {
+    match content {
+        Content::Simple(item) => write_scalar(item.0, &item.1, false, w),
+        Content::Array(a) => {
+            w.begin_array()?;
+            for c in a {
+                write_canonical_content(c, w)?;
+            }
+            w.end()
+        }
+        Content::Object(m) => {
+            let mut members: Vec<_> = m.iter().collect();
+            members.sort_by_cached_key(|(k, _)| k.encode_utf16().collect::<Vec<u16>>());
+            w.begin_object()?;
+            for (k, c) in members {
+                w.key(k)?;
+                write_canonical_content(c, w)?;
+            }
+            w.end()
+        }
+    }
+}
+
+/// Write the canonical form (RFC 8785, check Content::to_canonical_json()) of the json of **reader** to **writer**.
+/// Members of an object must be sorted before they are written, so each object is held as its keys plus the raw text of
+/// its values, then every value is canonicalized in turn. Arrays are streamed element by element.
+/// So only arrays keep memory low: an object is held whole with everything nested in it, and a document whose root is
+/// an object is held whole as well.
+/// The result is the stats of the parser. Data after the root value fails with TrailingCharacters error, like
+/// JsonWalker::finish().
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::canonicalize_stream;
+///
+/// let mut out = Vec::new();
+/// canonicalize_stream(StringReader::new(r#"{ "b" : [ 1.50, "A" ], "a" : 100000000000000000000000 }"#.to_string()), &mut out).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), r#"{"a":1e+23,"b":[1.5,"A"]}"#);
+/// ```
+pub fn canonicalize_stream<W: Write>(reader: Box<dyn Iterator<Item=u8>>, writer: W) -> Result<Stats, Error> {
+    let mut parser = Parser::new(reader, 0);
+    set_keep_escapes(&mut parser, true);
+    let mut w = JsonWriter::new(writer);
+    canonicalize_value(&mut parser, &mut w)?;
+    finish(&mut parser)?;
+    w.finish()?;
+    Ok(get_stats(&parser).clone())
+}
+
+/// the cursor stands before a value
+fn canonicalize_value<W: Write>(parser: &mut Parser, w: &mut JsonWriter<W>) -> Result<(), Error> {
+    begin_value(parser);
+    match parser.next_byte {
+        b'{' => {
+            walk_forward(parser);
+            let mut members = vec![];
+            while parser.next_byte != b'}' {
+                if parser.next_byte == b',' {
+                    walk_forward(parser);
+                }
+                let key = match walk_forward(parser) {
+                    TextItem::Key(k) => unescape(&k.1)?,
+                    _ => return Err(unexpected(parser)),
+                };
+                let mut raw = Vec::new();
+                copy_value(parser, &mut raw)?;
+                members.push((key.encode_utf16().collect::<Vec<u16>>(), key, raw));
+            }
+            walk_forward(parser);
+            members.sort_by(|a, b| a.0.cmp(&b.0));
+            w.begin_object()?;
+            for (_, key, raw) in members {
+                w.key(&key)?;
+                let mut member = Parser::new(Box::new(raw.into_iter()), 0);
+                set_keep_escapes(&mut member, true);
+                canonicalize_value(&mut member, w)?;
+            }
+            w.end()
+        }
+        b'[' => {
+            walk_forward(parser);
+            w.begin_array()?;
+            while parser.next_byte != b']' {
+                if parser.next_byte == b',' {
+                    walk_forward(parser);
+                }
+                canonicalize_value(parser, w)?;
+            }
+            walk_forward(parser);
+            w.end()
+        }
+        _ => match walk_forward(parser) {
+            TextItem::Value(t) => write_scalar(t.0, &t.1, true, w),
+            _ => Err(unexpected(parser)),
+        },
+    }
+}
+
+/// a malformed token or the end of stream stops the walk, then its error is returned rather than the token which is not there
+fn unexpected(parser: &mut Parser) -> Error {
+    if is_poisoned(parser) || parser.next_byte == NIL {
+        return end_of_stream(parser);
+    }
+    Error::invalid_structure(format!("Unexpected token.{}", get_current_status(parser)))
+}
+
+/// **escaped** tells whether strings are still in their json form
+fn write_scalar<W: Write>(value_type: ValueType, txt: &str, escaped: bool, w: &mut JsonWriter<W>) -> Result<(), Error> {
+    match value_type {
+        ValueType::Str if escaped => w.value_str(&unescape(txt)?),
+        ValueType::Str => w.value_str(txt),
+        ValueType::UInt | ValueType::Int | ValueType::Float => w.raw(&canonical_number(txt)?),
+        _ => w.raw(txt),
+    }
+}
+
+/// format the number like ECMAScript Number.prototype.toString(), as RFC 8785 requires
+pub(crate) fn canonical_number(txt: &str) -> Result<String, Error> {
+    let f: f64 = txt.parse().map_err(|_| Error::parse_float(format!("`{txt}` is not a number")))?;
+    if !f.is_finite() {
+        return Err(Error::parse_float(format!("`{txt}` is out of range")));
+    }
+    if f == 0.0 {
+        return Ok("0".to_string());
+    }
+    // shortest round-trip digits and the decimal exponent, e.g. "1.25e-7"
+    let sci = format!("{:e}", f.abs());
+    let (mantissa, exp) = sci.split_once('e').unwrap();
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    Ok(layout(f < 0.0, &digits, exp.parse::<i64>().unwrap() + 1))
+}
+
+/// the same form as canonical_number(), but the digits are taken from **txt** as they are rather than through f64, so
+/// two numbers get the same text only if they are equal, e.g. 9007199254740993 and 9007199254740993.0, while
+/// 9007199254740992 differs from both
+pub(crate) fn exact_number(txt: &str) -> Result<String, Error> {
+    let not_a_number = || Error::parse_float(format!("`{txt}` is not a number"));
+    let (negative, unsigned) = match txt.as_bytes().first() {
+        Some(b'-') => (true, &txt[1..]),
+        Some(b'+') => (false, &txt[1..]),
+        _ => (false, txt),
+    };
+    let (mantissa, exp) = unsigned.split_once(['e', 'E']).unwrap_or((unsigned, "0"));
+    let exp: i64 = exp.parse().map_err(|_| not_a_number())?;
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int_part.is_empty() && frac_part.is_empty() || !int_part.bytes().chain(frac_part.bytes()).all(|b| b.is_ascii_digit()) {
+        return Err(not_a_number());
+    }
+    let digits = format!("{int_part}{frac_part}");
+    let digits = digits.trim_start_matches('0');
+    let significant = digits.trim_end_matches('0');
+    if significant.is_empty() {
+        return Ok("0".to_string());
+    }
+    // the value is 0.digits × 10^n
+    let n = exp.checked_add(digits.len() as i64 - frac_part.len() as i64).ok_or_else(not_a_number)?;
+    Ok(layout(negative, significant, n))
+}
+
+/// the ECMAScript form of 0.**digits** × 10^**n**, where **digits** has no leading or trailing zero
+fn layout(negative: bool, digits: &str, n: i64) -> String {
+    let k = digits.len() as i64;
+    let mut out = String::with_capacity(32);
+    if negative {
+        out.push('-');
+    }
+    if k <= n && n <= 21 {
+        out.push_str(digits);
+        out.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.push_str(&"0".repeat(-n as usize));
+        out.push_str(digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push(if n - 1 < 0 { '-' } else { '+' });
+        out.push_str(&(n - 1).abs().to_string());
+    }
+    out
+}
+
+#[cfg(test)]
+mod canonical_tests {
+    use crate::ErrorKind;
+    use crate::json_walker::{Content, JsonWalker};
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::readers::StringReader;
+    use crate::tools::canonical::{canonical_number, exact_number};
+    use crate::tools::canonicalize_stream;
+
+    fn canonicalize(json: &str) -> String {
+        let mut out = Vec::new();
+        canonicalize_stream(StringReader::new(json.to_string()), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn content(json: &str) -> Content {
+        JsonWalker::new(StringReader::new(json.to_string()), 0).current_value_content().unwrap()
+    }
+
+    #[test]
+    fn test_numbers() {
+        let cases = [
+            ("0", "0"), ("-0", "0"), ("1", "1"), ("-1.50", "-1.5"), ("100", "100"), ("0.000001", "0.000001"), ("0.0000001", "1e-7"),
+            ("123456789012345678901", "123456789012345680000"), ("1234567890123456789012", "1.2345678901234568e+21"),
+            ("0.1", "0.1"), ("333.333", "333.333"), ("9007199254740993", "9007199254740992"), ("-0.00000123", "-0.00000123"),
+        ];
+        for (txt, expected) in cases {
+            assert_eq!(canonical_number(txt).unwrap(), expected, "{txt}");
+        }
+    }
+
+    #[test]
+    fn test_exact_numbers() {
+        let cases = [
+            ("0", "0"), ("-0", "0"), ("0.000", "0"), ("1", "1"), ("-1.50", "-1.5"), ("1.0", "1"), ("1e2", "100"), ("+7", "7"),
+            ("0.0000001", "1e-7"), ("10.0E-1", "1"), ("123456789012345678901", "123456789012345678901"),
+            ("1234567890123456789012", "1.234567890123456789012e+21"), ("9007199254740993", "9007199254740993"),
+            ("9007199254740993.0", "9007199254740993"), ("18446744073709551616", "18446744073709551616"), ("1e400", "1e+400"),
+        ];
+        for (txt, expected) in cases {
+            assert_eq!(exact_number(txt).unwrap(), expected, "{txt}");
+        }
+        // the digits of f64 are kept when there are no more of them
+        assert_eq!(exact_number("333.333").unwrap(), canonical_number("333.333").unwrap());
+        assert!(exact_number("1.2.3").is_err());
+        assert!(exact_number("NaN").is_err());
+    }
+
+    #[test]
+    fn test_stream_matches_content() {
+        let docs = [
+            CORRECT_JSON,
+            r#"{"b": 1, "a": {"d": [3, {"z": 1, "y": 2}], "c": "x\"y\\z"}, "é": 1.0, "e": [], "😀": 2, "｡": 3}"#,
+            r#"[{"b":[]},{"a":{}}, 10.50, "s"]"#,
+        ];
+        for doc in docs {
+            assert_eq!(canonicalize(doc), content(doc).to_canonical_json().unwrap());
+        }
+        assert_eq!(canonicalize(docs[1]), r#"{"a":{"c":"x\"y\\z","d":[3,{"y":2,"z":1}]},"b":1,"e":[],"é":1,"😀":2,"｡":3}"#);
+    }
+
+    #[test]
+    fn test_escapes_are_normalized() {
+        assert_eq!(canonicalize(r#"{"b":"€\/\t\u001f😀"}"#), "{\"b\":\"€/\\t\\u001f😀\"}");
+    }
+
+    #[test]
+    fn test_wide_object() {
+        let members: Vec<String> = (0..20000).rev().map(|i| format!(r#""k{i:05}": [{i}, {{"v": "{i}"}}]"#)).collect();
+        let doc = format!("{{{}}}", members.join(","));
+        let out = canonicalize(&doc);
+        assert!(out.starts_with(r#"{"k00000":[0,{"v":"0"}],"k00001":[1,{"v":"1"}],"#));
+        // three white-spaces per member are dropped
+        assert_eq!(out.len(), doc.len() - 3 * 20000);
+        assert_eq!(out, content(&doc).to_canonical_json().unwrap());
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        let err = |json: &str| canonicalize_stream(StringReader::new(json.to_string()), Vec::new()).unwrap_err();
+        assert_eq!(err(r#"{"a":1} x"#).kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(err("[1] [2]").kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(err("tru").kind(), &ErrorKind::UnexpectedEos);
+        assert_eq!(err("[1, x]").kind(), &ErrorKind::UnexpectedChar);
+        assert_eq!(err("[1").kind(), &ErrorKind::UnexpectedEos);
+        assert_eq!(err(r#"{"b":1.,"a":2}"#).kind(), &ErrorKind::InvalidNumber);
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{Error, NIL};
+use crate::escape::unescape;
+use crate::parser_core::{begin_value, copy_value, end_of_stream, finish, get_current_status, is_poisoned, Item, Parser, set_keep_escapes, TextItem, ValueType, walk_forward};
+use crate::path_matcher::escape_token;
+use crate::tools::canonical::exact_number;
+
+/// Options of compare()
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompareOptions {
+    /// objects with the same members in a different order are equal. Members of each object of the second document are
+    /// buffered as raw text till the object of the first document is finished
+    pub ignore_key_order: bool,
+    /// numbers are compared by value, so 1, 1.0 and 1.00 are equal. Digits are compared as they are written rather than
+    /// through f64, so integers which round to the same f64 are still different
+    pub numeric_equivalence: bool,
+}
+
+impl CompareOptions {
+    pub fn with_ignore_key_order(mut self, ignore_key_order: bool) -> Self {
+        self.ignore_key_order = ignore_key_order;
+        self
+    }
+
+    pub fn with_numeric_equivalence(mut self, numeric_equivalence: bool) -> Self {
+        self.numeric_equivalence = numeric_equivalence;
+        self
+    }
+}
+
+/// The first difference which is found. Values are in json form and containers are abbreviated as "{...}" and "[...]".
+/// A missing value means the path does not exist in that document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// json pointer of the different value
+    pub path: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComparisonResult {
+    Equal,
+    Different(Difference),
+}
+
+/// Compare the json of **reader_a** and **reader_b** by walking both in lockstep. White-spaces and escape forms of
+/// strings do not matter. Reading stops at the first difference, which is reported with its path. Equal documents must
+/// be followed by white-spaces only, and malformed json fails with the error of the parser.
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::{compare, CompareOptions, ComparisonResult, Difference};
+///
+/// let a = StringReader::new(r#"{"a": [1, {"b": "x"}]}"#.to_string());
+/// let b = StringReader::new(r#"{"a":[1,{"b":"y"}]}"#.to_string());
+/// assert_eq!(compare(a, b, &CompareOptions::default()).unwrap(), ComparisonResult::Different(Difference {
+///     path: "/a/1/b".to_string(),
+///     left: Some(r#""x""#.to_string()),
+///     right: Some(r#""y""#.to_string()),
+/// }));
+/// ```
+pub fn compare(reader_a: Box<dyn Iterator<Item=u8>>, reader_b: Box<dyn Iterator<Item=u8>>, options: &CompareOptions) -> Result<ComparisonResult, Error> {
+    let mut a = Parser::new(reader_a, 0);
+    let mut b = Parser::new(reader_b, 0);
+    set_keep_escapes(&mut a, true);
+    set_keep_escapes(&mut b, true);
+    Ok(match compare_value(&mut a, &mut b, "", options)? {
+        None => {
+            // equal documents must be over on both sides
+            finish(&mut a)?;
+            finish(&mut b)?;
+            ComparisonResult::Equal
+        }
+        Some(d) => ComparisonResult::Different(d),
+    })
+}
+
+/// both cursors stand before a value. If they are equal, both cursors stand right after them at the end
+fn compare_value(a: &mut Parser, b: &mut Parser, path: &str, options: &CompareOptions) -> Result<Option<Difference>, Error> {
+    begin_value(a);
+    begin_value(b);
+    if a.next_byte == NIL || b.next_byte == NIL {
+        return Err(if a.next_byte == NIL { end_of_stream(a) } else { end_of_stream(b) });
+    }
+    match (a.next_byte, b.next_byte) {
+        (b'{', b'{') if options.ignore_key_order => compare_unordered_objects(a, b, path, options),
+        (b'{', b'{') => compare_objects(a, b, path, options),
+        (b'[', b'[') => compare_arrays(a, b, path, options),
+        (x, y) if x != b'{' && x != b'[' && y != b'{' && y != b'[' => {
+            let x = next_scalar(a)?;
+            let y = next_scalar(b)?;
+            Ok(if same_scalar(&x, &y, options)? { None } else { different(path, Some(scalar_text(&x)), Some(scalar_text(&y))) })
+        }
+        _ => Ok(different(path, Some(describe(a)?), Some(describe(b)?))),
+    }
+}
+
+fn compare_objects(a: &mut Parser, b: &mut Parser, path: &str, options: &CompareOptions) -> Result<Option<Difference>, Error> {
+    walk_forward(a);
+    walk_forward(b);
+    loop {
+        let key_a = next_key(a)?;
+        let key_b = next_key(b)?;
+        match (key_a, key_b) {
+            (None, None) => return Ok(None),
+            (Some(k), None) => return Ok(different(&member_path(path, &k), Some(describe(a)?), None)),
+            (None, Some(k)) => return Ok(different(&member_path(path, &k), None, Some(describe(b)?))),
+            (Some(x), Some(y)) if x != y => return Ok(different(&member_path(path, &x), Some(describe(a)?), None)),
+            (Some(k), Some(_)) => {
+                if let Some(d) = compare_value(a, b, &member_path(path, &k), options)? {
+                    return Ok(Some(d));
+                }
+            }
+        }
+    }
+}
+
+/// members of b are buffered, then members of a are looked up among them
+fn compare_unordered_objects(a: &mut Parser, b: &mut Parser, path: &str, options: &CompareOptions) -> Result<Option<Difference>, Error> {
+    walk_forward(b);
+    let mut members = vec![];
+    while let Some(k) = next_key(b)? {
+        let mut raw = Vec::new();
+        copy_value(b, &mut raw)?;
+        members.push((k, raw));
+    }
+
+    walk_forward(a);
+    while let Some(k) = next_key(a)? {
+        let p = member_path(path, &k);
+        match members.iter().position(|m| m.0 == k) {
+            None => return Ok(different(&p, Some(describe(a)?), None)),
+            Some(i) => {
+                let mut member = Parser::new(Box::new(members.swap_remove(i).1.into_iter()), 0);
+                set_keep_escapes(&mut member, true);
+                if let Some(d) = compare_value(a, &mut member, &p, options)? {
+                    return Ok(Some(d));
+                }
+            }
+        }
+    }
+    Ok(match members.into_iter().next() {
+        None => None,
+        Some((k, raw)) => {
+            let mut member = Parser::new(Box::new(raw.into_iter()), 0);
+            different(&member_path(path, &k), None, Some(describe(&mut member)?))
+        }
+    })
+}
+
+fn compare_arrays(a: &mut Parser, b: &mut Parser, path: &str, options: &CompareOptions) -> Result<Option<Difference>, Error> {
+    walk_forward(a);
+    walk_forward(b);
+    let mut index = 0;
+    loop {
+        let end_a = at_array_end(a);
+        let end_b = at_array_end(b);
+        let p = format!("{path}/{index}");
+        match (end_a, end_b) {
+            (true, true) => return Ok(None),
+            (false, true) => return Ok(different(&p, Some(describe(a)?), None)),
+            (true, false) => return Ok(different(&p, None, Some(describe(b)?))),
+            (false, false) => {
+                if let Some(d) = compare_value(a, b, &p, options)? {
+                    return Ok(Some(d));
+                }
+            }
+        }
+        index += 1;
+    }
+}
+
+/// the cursor stands inside an object. The key is returned unescaped, None means the object is closed
+fn next_key(parser: &mut Parser) -> Result<Option<String>, Error> {
+    if parser.next_byte == b',' {
+        walk_forward(parser);
+    }
+    if parser.next_byte == b'}' {
+        walk_forward(parser);
+        return Ok(None);
+    }
+    match walk_forward(parser) {
+        TextItem::Key(k) => Ok(Some(unescape(&k.1)?)),
+        _ => Err(unexpected(parser)),
+    }
+}
+
+/// the cursor stands inside an array. If the array is closed, it is consumed
+fn at_array_end(parser: &mut Parser) -> bool {
+    if parser.next_byte == b',' {
+        walk_forward(parser);
+    }
+    if parser.next_byte == b']' {
+        walk_forward(parser);
+        return true;
+    }
+    false
+}
+
+fn next_scalar(parser: &mut Parser) -> Result<Item, Error> {
+    match walk_forward(parser) {
+        TextItem::Value(t) => Ok(t),
+        _ => Err(unexpected(parser)),
+    }
+}
+
+fn same_scalar(x: &Item, y: &Item, options: &CompareOptions) -> Result<bool, Error> {
+    let is_number = |t: ValueType| matches!(t, ValueType::UInt | ValueType::Int | ValueType::Float);
+    if options.numeric_equivalence && is_number(x.0) && is_number(y.0) {
+        return Ok(x.1 == y.1 || exact_number(&x.1)? == exact_number(&y.1)?);
+    }
+    if x.0 != y.0 {
+        return Ok(false);
+    }
+    Ok(match x.0 {
+        ValueType::Str => x.1 == y.1 || unescape(&x.1)? == unescape(&y.1)?,
+        _ => x.1 == y.1,
+    })
+}
+
+fn scalar_text(item: &Item) -> String {
+    match item.0 {
+        ValueType::Str => format!("\"{}\"", item.1),
+        _ => item.1.clone(),
+    }
+}
+
+/// the value in front of the cursor in short form
+fn describe(parser: &mut Parser) -> Result<String, Error> {
+    begin_value(parser);
+    match parser.next_byte {
+        b'{' => Ok("{...}".to_string()),
+        b'[' => Ok("[...]".to_string()),
+        NIL => Err(end_of_stream(parser)),
+        _ => Ok(scalar_text(&next_scalar(parser)?)),
+    }
+}
+
+fn member_path(path: &str, key: &str) -> String {
+    format!("{path}/{}", escape_token(key))
+}
+
+fn different(path: &str, left: Option<String>, right: Option<String>) -> Option<Difference> {
+    Some(Difference { path: path.to_string(), left, right })
+}
+
+/// a malformed token stops the walk, then its error is returned rather than the token which is not there
+fn unexpected(parser: &mut Parser) -> Error {
+    if is_poisoned(parser) {
+        return end_of_stream(parser);
+    }
+    Error::invalid_structure(format!("Unexpected token.{}", get_current_status(parser)))
+}
+
+#[cfg(test)]
+mod compare_tests {
+    use crate::ErrorKind;
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::readers::StringReader;
+    use crate::tools::{compare, CompareOptions, ComparisonResult, Difference, Indent, reformat};
+
+    fn cmp(a: &str, b: &str, options: &CompareOptions) -> ComparisonResult {
+        compare(StringReader::new(a.to_string()), StringReader::new(b.to_string()), options).unwrap()
+    }
+
+    fn diff(path: &str, left: Option<&str>, right: Option<&str>) -> ComparisonResult {
+        ComparisonResult::Different(Difference { path: path.to_string(), left: left.map(|s| s.to_string()), right: right.map(|s| s.to_string()) })
+    }
+
+    #[test]
+    fn test_formatting_does_not_matter() {
+        let mut pretty = Vec::new();
+        reformat(StringReader::new(CORRECT_JSON.to_string()), &mut pretty, Indent::tabs(1)).unwrap();
+        let pretty = String::from_utf8(pretty).unwrap();
+        for options in [CompareOptions::default(), CompareOptions::default().with_ignore_key_order(true).with_numeric_equivalence(true)] {
+            assert_eq!(cmp(CORRECT_JSON, &pretty, &options), ComparisonResult::Equal);
+            assert_eq!(cmp(r#"["a\/b", "A"]"#, r#"["a/b","A"]"#, &options), ComparisonResult::Equal);
+            assert_eq!(
+                cmp(CORRECT_JSON, &pretty.replace("555.555", "555.5"), &options),
+                diff("/key8/6/key85", Some("555.555"), Some("555.5"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_differences() {
+        let options = CompareOptions::default();
+        assert_eq!(cmp(r#"{"a":[1,2]}"#, r#"{"a":[1,2,3]}"#, &options), diff("/a/2", None, Some("3")));
+        assert_eq!(cmp(r#"{"a":[1,{}]}"#, r#"{"a":[1]}"#, &options), diff("/a/1", Some("{...}"), None));
+        assert_eq!(cmp(r#"{"a":1,"b/c":2}"#, r#"{"a":1}"#, &options), diff("/b~1c", Some("2"), None));
+        assert_eq!(cmp(r#"{"a":1}"#, r#"{"a":1,"b":"x"}"#, &options), diff("/b", None, Some(r#""x""#)));
+        assert_eq!(cmp(r#"{"a":{"b":[]}}"#, r#"{"a":{"b":null}}"#, &options), diff("/a/b", Some("[...]"), Some("null")));
+        assert_eq!(cmp(r#"{"a":1,"b":2}"#, r#"{"b":2,"a":1}"#, &options), diff("/a", Some("1"), None));
+        assert_eq!(cmp("[1.0]", "[1]", &options), diff("/0", Some("1.0"), Some("1")));
+        assert_eq!(cmp(r#""1""#, "1", &options), diff("", Some(r#""1""#), Some("1")));
+    }
+
+    #[test]
+    fn test_options() {
+        let options = CompareOptions::default().with_ignore_key_order(true);
+        assert_eq!(cmp(r#"{"a":1,"b":{"x":[{"p":1,"q":2}],"y":2}}"#, r#"{"b":{"y":2,"x":[{"q":2,"p":1}]},"a":1}"#, &options), ComparisonResult::Equal);
+        assert_eq!(cmp(r#"{"a":1,"b":{"y":2}}"#, r#"{"b":{"y":3},"a":1}"#, &options), diff("/b/y", Some("2"), Some("3")));
+        assert_eq!(cmp(r#"{"a":1}"#, r#"{"b":[1],"a":1}"#, &options), diff("/b", None, Some("[...]")));
+        assert_eq!(cmp(r#"{"a":1}"#, r#"{"b":1}"#, &options), diff("/a", Some("1"), None));
+
+        let options = CompareOptions::default().with_numeric_equivalence(true);
+        assert_eq!(cmp("[1, 1.00, -0.5, 100]", "[1.0, 1, -0.50, 100.0]", &options), ComparisonResult::Equal);
+        assert_eq!(cmp("[1]", "[1.01]", &options), diff("/0", Some("1"), Some("1.01")));
+        assert_eq!(cmp(r#"["1"]"#, "[1]", &options), diff("/0", Some(r#""1""#), Some("1")));
+        // they are the same f64
+        assert_eq!(cmp("[9007199254740993]", "[9007199254740992]", &options), diff("/0", Some("9007199254740993"), Some("9007199254740992")));
+        assert_eq!(cmp("[-18446744073709551617]", "[-18446744073709551616]", &options), diff("/0", Some("-18446744073709551617"), Some("-18446744073709551616")));
+        assert_eq!(cmp("[9007199254740993, 1e400]", "[9007199254740993.0, 10e399]", &options), ComparisonResult::Equal);
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        let err = |a: &str, b: &str| compare(StringReader::new(a.to_string()), StringReader::new(b.to_string()), &CompareOptions::default()).unwrap_err();
+        assert_eq!(err("1 garbage", "1").kind(), &ErrorKind::InvalidToken);
+        assert_eq!(err("1", "1 garbage").kind(), &ErrorKind::InvalidToken);
+        assert_eq!(err(r#"{"a":1}x"#, r#"{"a":1}"#).kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(err("[1]", "[1] [2]").kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(err("[tru]", "[true]").kind(), &ErrorKind::UnexpectedChar);
+        assert_eq!(err("true", "tru").kind(), &ErrorKind::UnexpectedEos);
+        assert_eq!(err(r#"{"a":1.}"#, r#"{"a":1}"#).kind(), &ErrorKind::InvalidNumber);
+    }
+}
@@ -0,0 +1,92 @@
+use std::io::Write;
+
+use crate::{Error, ErrorKind, NIL};
+use crate::parser_core::{copy_value, get_current_status, Parser, walk_forward};
+use crate::writer::JsonWriter;
+
+/// Write the elements of the root arrays of all **readers** into one array, in order. Elements are copied byte by byte
+/// and empty arrays contribute nothing. The result is the total count of elements.
+/// If an input fails, the error message starts with its index, e.g. "Input 2: The root is not an array"
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::concat_arrays;
+///
+/// let readers = vec![StringReader::new("[1, {\"a\": 2}]".to_string()), StringReader::new("[]".to_string()), StringReader::new("[3]".to_string())];
+/// let mut out = Vec::new();
+/// assert_eq!(concat_arrays(readers, &mut out).unwrap(), 3);
+/// assert_eq!(out, b"[1,{\"a\": 2},3]");
+/// ```
+pub fn concat_arrays<R: Iterator<Item=u8> + 'static, W: Write>(readers: Vec<R>, writer: W) -> Result<usize, Error> {
+    let mut w = JsonWriter::new(writer);
+    w.begin_array()?;
+    let mut count = 0;
+    for (i, reader) in readers.into_iter().enumerate() {
+        count += copy_elements(Box::new(reader), &mut w).map_err(|e| Error::new(e.kind, format!("Input {i}: {}", e.msg)))?;
+    }
+    w.end()?;
+    w.finish()?;
+    Ok(count)
+}
+
+fn copy_elements<W: Write>(reader: Box<dyn Iterator<Item=u8>>, w: &mut JsonWriter<W>) -> Result<usize, Error> {
+    let mut parser = Parser::new(reader, 0);
+    if parser.next_byte != b'[' {
+        return Err(Error::wrong_data_type("The root is not an array"));
+    }
+    walk_forward(&mut parser);
+    let mut count = 0;
+    while parser.next_byte != b']' {
+        if parser.next_byte == b',' {
+            walk_forward(&mut parser);
+        }
+        if parser.next_byte == NIL {
+            return Err(Error::new(ErrorKind::EOS, format!("The array is not closed.{}", get_current_status(&mut parser))));
+        }
+        copy_value(&mut parser, w.raw_writer()?)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod concat_arrays_tests {
+    use crate::ErrorKind;
+    use crate::readers::StringReader;
+    use crate::tools::concat_arrays;
+
+    fn concat(inputs: &[&str]) -> Result<Vec<u8>, crate::Error> {
+        let mut out = Vec::new();
+        concat_arrays(inputs.iter().map(|s| StringReader::new(s.to_string())).collect(), &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_three_inputs() {
+        let day1 = r#" [ {"id": 1, "tags": ["a", "b"]}, {"id": 2, "s": "x,]\"y"} , [3] ] "#;
+        let mut out = Vec::new();
+        let readers = vec![StringReader::new(day1.to_string()), StringReader::new(" [ ] ".to_string()), StringReader::new("[null]".to_string())];
+        assert_eq!(concat_arrays(readers, &mut out).unwrap(), 4);
+
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let mut expected: serde_json::Value = serde_json::from_str(day1).unwrap();
+        expected.as_array_mut().unwrap().push(serde_json::Value::Null);
+        assert_eq!(v, expected);
+        assert_eq!(v.as_array().unwrap().len(), 4);
+
+        assert_eq!(concat(&["[]", "[1]", "[]"]).unwrap(), b"[1]");
+        assert_eq!(concat(&["[]", "[]"]).unwrap(), b"[]");
+        assert_eq!(concat(&[]).unwrap(), b"[]");
+    }
+
+    #[test]
+    fn test_errors() {
+        let e = concat(&["[1]", "[2]", r#"{"a": 1}"#]).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::WrongDataType);
+        assert!(e.msg.starts_with("Input 2: "), "{}", e.msg);
+
+        let e = concat(&["[1]", "[2, 3"]).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::EOS);
+        assert!(e.msg.starts_with("Input 1: "), "{}", e.msg);
+    }
+}
@@ -0,0 +1,259 @@
+use std::io::Write;
+
+use crate::Error;
+use crate::escape::unescape;
+use crate::parser_core::{begin_value, copy_value, get_current_status, Parser, seek_pointer, set_keep_escapes, TextItem, ValueType, walk_forward};
+use crate::path_matcher::PathMatcher;
+
+/// What to do with an array inside a record
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NestedPolicy {
+    /// the cell gets the json text of the array
+    AsJson,
+    /// WrongDataType error is returned
+    Error,
+}
+
+/// Options of to_csv(). The default selects the root array, derives columns from the first 100 records, writes arrays
+/// as json text and separates cells by ","
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    /// json pointer of the array
+    pub pointer: String,
+    /// explicit columns. If it is None, columns are the union of keys of the first **sample_size** records
+    pub columns: Option<Vec<String>>,
+    pub sample_size: usize,
+    pub nested: NestedPolicy,
+    pub delimiter: char,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions { pointer: String::new(), columns: None, sample_size: 100, nested: NestedPolicy::AsJson, delimiter: ',' }
+    }
+}
+
+impl CsvOptions {
+    pub fn with_pointer(mut self, pointer: &str) -> Self {
+        self.pointer = pointer.to_string();
+        self
+    }
+
+    pub fn with_columns(mut self, columns: &[&str]) -> Self {
+        self.columns = Some(columns.iter().map(|c| c.to_string()).collect());
+        self
+    }
+
+    pub fn with_sample_size(mut self, sample_size: usize) -> Self {
+        self.sample_size = sample_size;
+        self
+    }
+
+    pub fn with_nested(mut self, nested: NestedPolicy) -> Self {
+        self.nested = nested;
+        self
+    }
+
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+}
+
+type Record = Vec<(String, String)>;
+
+/// Write the array of objects selected by **options** as CSV with a header line. Nested objects are flattened,
+/// so {"address":{"city":"x"}} goes to column "address.city". Missing fields and null values become empty cells and
+/// fields which are not among the columns are ignored. Cells are quoted only when it is necessary and lines end with "\n".
+/// Only the records which are sampled for columns are held in memory, the rest are streamed.
+/// The result is the count of written records
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::{CsvOptions, to_csv};
+///
+/// let json = r#"[{"name":"a","age":1},{"name":"b, c","tags":[1]}]"#;
+/// let mut out = Vec::new();
+/// to_csv(StringReader::new(json.to_string()), &mut out, &CsvOptions::default()).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "name,age,tags\na,1,\n\"b, c\",,[1]\n");
+/// ```
+pub fn to_csv<W: Write>(reader: Box<dyn Iterator<Item=u8>>, mut writer: W, options: &CsvOptions) -> Result<usize, Error> {
+    let mut parser = Parser::new(reader, 0);
+    set_keep_escapes(&mut parser, true);
+    seek_pointer(&mut parser, &PathMatcher::new(&options.pointer)?)?;
+    if parser.next_byte != b'[' {
+        return Err(Error::wrong_data_type(format!("The value of `{}` is not an array", options.pointer)));
+    }
+    walk_forward(&mut parser);
+
+    let mut sample = vec![];
+    let mut finished = false;
+    let columns = match &options.columns {
+        Some(c) => c.clone(),
+        None => {
+            let mut columns: Vec<String> = vec![];
+            while sample.len() < options.sample_size {
+                match next_record(&mut parser, options)? {
+                    Some(r) => {
+                        for (k, _) in &r {
+                            if !columns.contains(k) {
+                                columns.push(k.clone());
+                            }
+                        }
+                        sample.push(r);
+                    }
+                    None => {
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+            columns
+        }
+    };
+
+    write_row(&mut writer, columns.iter().map(|c| c.as_str()), options.delimiter)?;
+    let mut count = 0;
+    for r in sample {
+        write_record(&mut writer, &columns, &r, options.delimiter)?;
+        count += 1;
+    }
+    while !finished {
+        match next_record(&mut parser, options)? {
+            Some(r) => {
+                write_record(&mut writer, &columns, &r, options.delimiter)?;
+                count += 1;
+            }
+            None => finished = true,
+        }
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// read the next element of the array as flattened cells. None means the array is finished
+fn next_record(parser: &mut Parser, options: &CsvOptions) -> Result<Option<Record>, Error> {
+    if parser.next_byte == b',' {
+        walk_forward(parser);
+    }
+    if parser.next_byte == b']' {
+        walk_forward(parser);
+        return Ok(None);
+    }
+    begin_value(parser);
+    if parser.next_byte != b'{' {
+        return Err(Error::wrong_data_type(format!("Records must be objects.{}", get_current_status(parser))));
+    }
+    walk_forward(parser);
+    let mut record = vec![];
+    flatten_object(parser, "", options, &mut record)?;
+    Ok(Some(record))
+}
+
+/// the cursor stands right after "{"
+fn flatten_object(parser: &mut Parser, prefix: &str, options: &CsvOptions, record: &mut Record) -> Result<(), Error> {
+    while parser.next_byte != b'}' {
+        if parser.next_byte == b',' {
+            walk_forward(parser);
+        }
+        let name = match walk_forward(parser) {
+            TextItem::Key(k) => format!("{prefix}{}", unescape(&k.1)?),
+            _ => return Err(Error::invalid_structure(format!("Expecting a key.{}", get_current_status(parser)))),
+        };
+        begin_value(parser);
+        match parser.next_byte {
+            b'{' => {
+                walk_forward(parser);
+                flatten_object(parser, &format!("{name}."), options, record)?;
+            }
+            b'[' => {
+                if options.nested == NestedPolicy::Error {
+                    return Err(Error::wrong_data_type(format!("`{name}` is an array")));
+                }
+                let mut raw = Vec::new();
+                copy_value(parser, &mut raw)?;
+                record.push((name, String::from_utf8_lossy(&raw).to_string()));
+            }
+            _ => {
+                let cell = match walk_forward(parser) {
+                    TextItem::Value((ValueType::Null, _)) => String::new(),
+                    TextItem::Value((ValueType::Str, s)) => unescape(&s)?,
+                    TextItem::Value((_, s)) => s,
+                    _ => return Err(Error::invalid_structure(format!("Expecting a value.{}", get_current_status(parser)))),
+                };
+                record.push((name, cell));
+            }
+        }
+    }
+    walk_forward(parser);
+    Ok(())
+}
+
+fn write_record<W: Write>(writer: &mut W, columns: &[String], record: &Record, delimiter: char) -> Result<(), Error> {
+    let cells = columns.iter().map(|c| record.iter().find(|(k, _)| k == c).map(|(_, v)| v.as_str()).unwrap_or(""));
+    write_row(writer, cells, delimiter)
+}
+
+fn write_row<'a, W: Write>(writer: &mut W, cells: impl Iterator<Item=&'a str>, delimiter: char) -> Result<(), Error> {
+    let mut line = String::with_capacity(128);
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            line.push(delimiter);
+        }
+        if cell.contains([delimiter, '"', '\n', '\r']) {
+            line.push('"');
+            line.push_str(&cell.replace('"', "\"\""));
+            line.push('"');
+        } else {
+            line.push_str(cell);
+        }
+    }
+    line.push('\n');
+    writer.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod csv_tests {
+    use crate::ErrorKind;
+    use crate::readers::StringReader;
+    use crate::tools::{CsvOptions, NestedPolicy, to_csv};
+
+    const PEOPLE: &str = r#"{"people": [
+        {"name": "John Doe", "age": -30, "height": 1.75, "address": {"street": "123 Main St", "city": "New York"}},
+        {"name": "Alice \"Al\"", "height": null, "address": {"street": "1, Elm\nSt", "city": "Laas"}, "hobbies": ["reading", "hiking"]},
+        {"name": "Bob", "age": 32, "address": {"city": "goh", "street": "shit"}, "married": true}
+    ]}"#;
+
+    fn csv(json: &str, options: &CsvOptions) -> Result<String, crate::Error> {
+        let mut out = Vec::new();
+        to_csv(StringReader::new(json.to_string()), &mut out, options)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_people_to_csv() {
+        let out = csv(PEOPLE, &CsvOptions::default().with_pointer("/people")).unwrap();
+        let expected = "name,age,height,address.street,address.city,hobbies,married\n\
+            John Doe,-30,1.75,123 Main St,New York,,\n\
+            \"Alice \"\"Al\"\"\",,,\"1, Elm\nSt\",Laas,\"[\"\"reading\"\", \"\"hiking\"\"]\",\n\
+            Bob,32,,shit,goh,,true\n";
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_options() {
+        let options = CsvOptions::default().with_pointer("/people").with_columns(&["address.city", "name", "x"]).with_delimiter(';');
+        assert_eq!(csv(PEOPLE, &options).unwrap(), "address.city;name;x\nNew York;John Doe;\nLaas;\"Alice \"\"Al\"\"\";\ngoh;Bob;\n");
+
+        // columns of later records are ignored
+        let options = CsvOptions::default().with_pointer("/people").with_sample_size(1);
+        assert!(csv(PEOPLE, &options).unwrap().starts_with("name,age,height,address.street,address.city\n"));
+
+        let options = CsvOptions::default().with_pointer("/people").with_nested(NestedPolicy::Error);
+        assert_eq!(csv(PEOPLE, &options).unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(csv(PEOPLE, &CsvOptions::default()).unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(csv("[1]", &CsvOptions::default()).unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(csv("[]", &CsvOptions::default()).unwrap(), "\n");
+    }
+}
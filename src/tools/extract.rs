@@ -0,0 +1,182 @@
+use std::io::Write;
+
+use crate::{Error, NIL};
+use crate::parser_core::{copy_value, get_path, Parser, seek_pointer, skip_value, TextItem, walk_forward};
+use crate::path_matcher::PathMatcher;
+use crate::tools::ndjson::SingleLine;
+
+/// Write the value at **pointer** (such as "/data/items/0") to **writer**, byte by byte as it is in json text.
+/// Nothing is built on the way and reading stops as soon as the value ends.
+/// The result is the count of written bytes. If the pointer does not exist, KeyNotFound error names the failing segment
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::extract_to;
+///
+/// let mut out = Vec::new();
+/// let n = extract_to(StringReader::new(r#"{"data":{"items":[1, {"a": 2}]}}"#.to_string()), "/data/items/1", &mut out).unwrap();
+/// assert_eq!(n, 8);
+/// assert_eq!(out, br#"{"a": 2}"#);
+/// ```
+pub fn extract_to<W: Write>(reader: Box<dyn Iterator<Item=u8>>, pointer: &str, writer: W) -> Result<u64, Error> {
+    let pointer = PathMatcher::new(pointer)?;
+    let mut parser = Parser::new(reader, 0);
+    seek_pointer(&mut parser, &pointer)?;
+    let mut w = CountingWriter { writer, count: 0 };
+    copy_value(&mut parser, &mut w)?;
+    w.flush()?;
+    Ok(w.count)
+}
+
+/// What extract_multi() wrote to the writer of a route
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RouteCount {
+    /// count of matched values
+    pub matches: u64,
+    /// count of written bytes, new lines included
+    pub bytes: u64,
+}
+
+/// Write the values selected by the matcher of each route to the writer of that route in one pass, byte by byte as
+/// they are in json text except that new lines become spaces. Each value is followed by a new line, so a matcher which selects many values, such as the
+/// elements of an array, produces NDJSON. Regions which no matcher leads to are skipped without being built.
+/// Routes whose matchers may select the same value or values inside each other are rejected before reading by
+/// InvalidPath error. The result holds the counts of each route in the order of **routes**
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::path_matcher::PathMatcher;
+/// use json_walker::tools::extract_multi;
+///
+/// let json = r#"{"users": [{"id": 1}, {"id": 2}], "meta": {"v": 3}}"#;
+/// let (mut users, mut meta) = (Vec::new(), Vec::new());
+/// let counts = extract_multi(StringReader::new(json.to_string()), vec![
+///     (PathMatcher::new("/users/*").unwrap(), Box::new(&mut users)),
+///     (PathMatcher::new("/meta").unwrap(), Box::new(&mut meta)),
+/// ]).unwrap();
+/// assert_eq!(users, b"{\"id\": 1}\n{\"id\": 2}\n");
+/// assert_eq!(meta, b"{\"v\": 3}\n");
+/// assert_eq!((counts[0].matches, counts[1].bytes), (2, 9));
+/// ```
+pub fn extract_multi<'a>(reader: Box<dyn Iterator<Item=u8>>, routes: Vec<(PathMatcher, Box<dyn Write + 'a>)>) -> Result<Vec<RouteCount>, Error> {
+    for (i, (m, _)) in routes.iter().enumerate() {
+        if let Some(j) = routes[..i].iter().position(|(other, _)| other.overlaps(m)) {
+            return Err(Error::invalid_path(format!("The matchers of routes {j} and {i} overlap")));
+        }
+    }
+    let (matchers, writers): (Vec<PathMatcher>, Vec<_>) = routes.into_iter().unzip();
+    let mut writers: Vec<_> = writers.into_iter().map(|writer| CountingWriter { writer, count: 0 }).collect();
+    let mut matches = vec![0; matchers.len()];
+    let mut parser = Parser::new(reader, 0);
+    let mut before_value = parser.next_byte != NIL;
+    loop {
+        if before_value {
+            let path = get_path(&mut parser);
+            if let Some(i) = matchers.iter().position(|m| m.matches(&path)) {
+                copy_value(&mut parser, &mut SingleLine(&mut writers[i]))?;
+                writers[i].write_all(b"\n")?;
+                matches[i] += 1;
+            } else if !matchers.iter().any(|m| m.leads_to(&path)) {
+                skip_value(&mut parser)?;
+            }
+        }
+        if parser.next_byte == NIL {
+            break;
+        }
+        before_value = match walk_forward(&mut parser) {
+            TextItem::None(b':') => true,
+            TextItem::None(b'[') | TextItem::None(b',') => parser.stack.last().is_some_and(|s| s.symbol == '[') && parser.next_byte != b']',
+            _ => false,
+        };
+    }
+    let mut counts = Vec::with_capacity(writers.len());
+    for (w, matches) in writers.iter_mut().zip(matches) {
+        w.flush()?;
+        counts.push(RouteCount { matches, bytes: w.count });
+    }
+    Ok(counts)
+}
+
+struct CountingWriter<W: Write> {
+    writer: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.writer.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod extract_tests {
+    use crate::ErrorKind;
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::readers::StringReader;
+    use crate::path_matcher::PathMatcher;
+    use crate::tools::{extract_multi, extract_to};
+
+    fn extract(json: &str, pointer: &str) -> Result<String, crate::Error> {
+        let mut out = Vec::new();
+        let n = extract_to(StringReader::new(json.to_string()), pointer, &mut out)?;
+        assert_eq!(n as usize, out.len());
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_extract_key8() {
+        let out = extract(CORRECT_JSON, "/key8").unwrap();
+        let expected: serde_json::Value = serde_json::from_str(CORRECT_JSON).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&out).unwrap(), expected["key8"]);
+        assert!(out.starts_with('[') && out.ends_with(']'));
+
+        assert_eq!(extract(CORRECT_JSON, "/key8/6/key81").unwrap(), "null");
+        assert_eq!(extract(CORRECT_JSON, "/key7/key78/5").unwrap(), expected["key7"]["key78"][5].to_string());
+        assert_eq!(extract(CORRECT_JSON, "").unwrap().len(), CORRECT_JSON.trim().len());
+    }
+
+    #[test]
+    fn test_extract_missing_pointer() {
+        for (pointer, segment) in [("/key8/6/nothing", "Segment 3 (`nothing`)"), ("/key8/100", "Segment 2 (`100`)"), ("/key1/a", "Segment 2 (`a`)"), ("/x", "Segment 1 (`x`)")] {
+            let err = extract(CORRECT_JSON, pointer).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::KeyNotFound);
+            assert!(err.msg.starts_with(segment), "{}", err.msg);
+        }
+        assert_eq!(extract(CORRECT_JSON, "key8").unwrap_err().kind, ErrorKind::InvalidPath);
+    }
+
+    #[test]
+    fn test_extract_multi() {
+        let (mut members, mut elements) = (Vec::new(), Vec::new());
+        let counts = extract_multi(StringReader::new(CORRECT_JSON.to_string()), vec![
+            (PathMatcher::new("/key7/*").unwrap(), Box::new(&mut members)),
+            (PathMatcher::new("/key8/*").unwrap(), Box::new(&mut elements)),
+        ]).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(CORRECT_JSON).unwrap();
+        for (out, count, values) in [(&members, counts[0], expected["key7"].as_object().unwrap().values().cloned().collect::<Vec<_>>()), (&elements, counts[1], expected["key8"].as_array().unwrap().clone())] {
+            let lines: Vec<serde_json::Value> = String::from_utf8(out.clone()).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+            // keys of serde_json objects are sorted
+            let sorted = |v: &Vec<serde_json::Value>| {
+                let mut v: Vec<String> = v.iter().map(|v| v.to_string()).collect();
+                v.sort();
+                v
+            };
+            assert_eq!(sorted(&lines), sorted(&values));
+            assert_eq!((count.matches as usize, count.bytes as usize), (values.len(), out.len()));
+        }
+
+        for overlapping in ["/key7/key78", "/*", "", "/key7"] {
+            let err = extract_multi(StringReader::new(CORRECT_JSON.to_string()), vec![
+                (PathMatcher::new("/key7/*").unwrap(), Box::new(Vec::new())),
+                (PathMatcher::new(overlapping).unwrap(), Box::new(Vec::new())),
+            ]).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::InvalidPath);
+        }
+    }
+}
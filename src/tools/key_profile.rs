@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use crate::{Error, NIL};
+use crate::escape::unescape;
+use crate::parser_core::{end_of_stream, finish, Parser, set_keep_escapes, TextItem, walk_forward};
+use crate::path_matcher::escape_token;
+use crate::writer::JsonWriter;
+
+/// How often a key is seen at one path
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyStats {
+    /// json pointer of the key where array indices are replaced by "[]", such as "/items/[]/id"
+    pub path: String,
+    /// every occurrence of the key, duplicates included
+    pub count: u64,
+    /// count of objects which have the key
+    pub parents: u64,
+    /// whether the key appears more than once in a single object
+    pub duplicated: bool,
+}
+
+/// Result of profile_keys(). Keys are in the order they are first seen
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyProfile {
+    pub keys: Vec<KeyStats>,
+}
+
+impl KeyProfile {
+    /// find the stats of a path such as "/items/[]/id"
+    pub fn get(&self, path: &str) -> Option<&KeyStats> {
+        self.keys.iter().find(|k| k.path == path)
+    }
+
+    /// keys which appear more than once in a single object
+    pub fn duplicates(&self) -> impl Iterator<Item=&KeyStats> {
+        self.keys.iter().filter(|k| k.duplicated)
+    }
+
+    /// the profile as json text: {"keys":[{"path":"/id","count":2,"parents":2,"duplicated":false}]}
+    pub fn to_json(&self) -> Result<String, Error> {
+        let mut w = JsonWriter::new(Vec::new());
+        w.begin_object()?;
+        w.key("keys")?;
+        w.begin_array()?;
+        for k in &self.keys {
+            w.begin_object()?;
+            w.key("path")?;
+            w.value_str(&k.path)?;
+            w.key("count")?;
+            w.raw(&k.count.to_string())?;
+            w.key("parents")?;
+            w.raw(&k.parents.to_string())?;
+            w.key("duplicated")?;
+            w.raw(if k.duplicated { "true" } else { "false" })?;
+            w.end()?;
+        }
+        w.end()?;
+        w.end()?;
+        // the writer only produces valid utf-8
+        Ok(String::from_utf8(w.finish()?).unwrap())
+    }
+}
+
+/// open container: its path, the latest key and the keys seen so far if it is an object
+struct Frame {
+    path: String,
+    is_array: bool,
+    key: String,
+    seen: Vec<usize>,
+}
+
+/// Count which keys appear in the json of **reader** and how often, in one streaming pass. Array indices are collapsed to
+/// "[]", so the keys of all elements share their paths. Memory is proportional to the count of distinct paths.
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::profile_keys;
+///
+/// let profile = profile_keys(StringReader::new(r#"[{"id": 1, "tag": "a"}, {"id": 2, "id": 3}]"#.to_string())).unwrap();
+/// let id = profile.get("/[]/id").unwrap();
+/// assert_eq!((id.count, id.parents, id.duplicated), (3, 2, true));
+/// assert_eq!(profile.get("/[]/tag").unwrap().count, 1);
+/// ```
+pub fn profile_keys(reader: Box<dyn Iterator<Item=u8>>) -> Result<KeyProfile, Error> {
+    let mut parser = Parser::new(reader, 0);
+    set_keep_escapes(&mut parser, true);
+
+    let mut keys: Vec<KeyStats> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut stack: Vec<Frame> = vec![];
+    loop {
+        if parser.next_byte == NIL {
+            if !stack.is_empty() {
+                return Err(end_of_stream(&parser));
+            }
+            break;
+        }
+        match walk_forward(&mut parser) {
+            TextItem::Key(k) => {
+                let top = match stack.last_mut() {
+                    Some(top) => top,
+                    None => continue,
+                };
+                top.key = escape_token(&unescape(&k.1)?);
+                let path = format!("{}/{}", top.path, top.key);
+                let i = match index.get(&path) {
+                    Some(i) => *i,
+                    None => {
+                        keys.push(KeyStats { path: path.clone(), count: 0, parents: 0, duplicated: false });
+                        index.insert(path, keys.len() - 1);
+                        keys.len() - 1
+                    }
+                };
+                keys[i].count += 1;
+                if top.seen.contains(&i) {
+                    keys[i].duplicated = true;
+                } else {
+                    top.seen.push(i);
+                    keys[i].parents += 1;
+                }
+            }
+            TextItem::None(c @ (b'{' | b'[')) => {
+                let path = match stack.last() {
+                    None => String::new(),
+                    Some(top) if top.is_array => format!("{}/[]", top.path),
+                    Some(top) => format!("{}/{}", top.path, top.key),
+                };
+                stack.push(Frame { path, is_array: c == b'[', key: String::new(), seen: vec![] });
+            }
+            TextItem::None(b'}' | b']') => {
+                stack.pop();
+                if stack.is_empty() {
+                    break;
+                }
+            }
+            TextItem::Value(_) if stack.is_empty() => break,
+            _ => {}
+        }
+    }
+    // a malformed root scalar or trailing data stops the walk early
+    finish(&mut parser)?;
+    Ok(KeyProfile { keys })
+}
+
+#[cfg(test)]
+mod key_profile_tests {
+    use crate::ErrorKind;
+    use crate::readers::StringReader;
+    use crate::tools::profile_keys;
+
+    #[test]
+    fn test_feed() {
+        let records: Vec<String> = (0..100)
+            .map(|i| {
+                let email = if i % 4 == 0 { format!(r#", "email": "u{i}@x.y""#) } else { String::new() };
+                let tags = if i % 10 == 0 { r#", "tags": [{"name": "a"}, {"name": "b", "score": 1}]"# } else { "" };
+                format!(r#"{{"id": {i}{email}{tags}, "meta": {{"a/b": null}}}}"#)
+            })
+            .collect();
+        let mut json = format!(r#"{{"feed": [{}], "total": 100}}"#, records.join(","));
+        json = json.replacen(r#""id": 7,"#, r#""id": 7, "id": 7,"#, 1);
+
+        let profile = profile_keys(StringReader::new(json)).unwrap();
+        let stats = |path: &str| {
+            let k = profile.get(path).unwrap();
+            (k.count, k.parents, k.duplicated)
+        };
+        assert_eq!(stats("/feed"), (1, 1, false));
+        assert_eq!(stats("/feed/[]/id"), (101, 100, true));
+        assert_eq!(stats("/feed/[]/email"), (25, 25, false));
+        assert_eq!(stats("/feed/[]/tags/[]/name"), (20, 20, false));
+        assert_eq!(stats("/feed/[]/tags/[]/score"), (10, 10, false));
+        assert_eq!(stats("/feed/[]/meta/a~1b"), (100, 100, false));
+        assert_eq!(stats("/total"), (1, 1, false));
+        assert_eq!(profile.keys.len(), 9);
+        assert_eq!(profile.keys[1].path, "/feed/[]/id");
+        assert_eq!(profile.duplicates().map(|k| k.path.as_str()).collect::<Vec<_>>(), vec!["/feed/[]/id"]);
+
+        let v: serde_json::Value = serde_json::from_str(&profile.to_json().unwrap()).unwrap();
+        assert_eq!(v["keys"][1]["path"], "/feed/[]/id");
+        assert_eq!(v["keys"][1]["count"], 101);
+        assert_eq!(v["keys"][1]["duplicated"], true);
+    }
+
+    #[test]
+    fn test_scalar_and_nested_arrays() {
+        assert!(profile_keys(StringReader::new("12".to_string())).unwrap().keys.is_empty());
+        let profile = profile_keys(StringReader::new(r#"[[{"a": 1}], [{"a": 2, "b": {"a": 3}}]]"#.to_string())).unwrap();
+        let paths: Vec<(&str, u64)> = profile.keys.iter().map(|k| (k.path.as_str(), k.count)).collect();
+        assert_eq!(paths, vec![("/[]/[]/a", 2), ("/[]/[]/b", 1), ("/[]/[]/b/a", 1)]);
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        for (json, kind) in [
+            ("tru", ErrorKind::UnexpectedEos),
+            ("1.", ErrorKind::InvalidNumber),
+            ("[{\"a\": 1}, {\"a\" 2}]", ErrorKind::UnexpectedChar),
+            ("{\"a\": 1} x", ErrorKind::TrailingCharacters),
+            ("12 13", ErrorKind::InvalidToken),
+        ] {
+            assert_eq!(profile_keys(StringReader::new(json.to_string())).unwrap_err().kind(), &kind, "{json}");
+        }
+    }
+}
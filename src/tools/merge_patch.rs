@@ -0,0 +1,176 @@
+use std::collections::BTreeSet;
+use std::io::Write;
+
+use crate::{Error, NIL};
+use crate::escape::unescape;
+use crate::parser_core::{begin_value, Content, copy_value, get_current_status, get_stats, Parser, set_keep_escapes, skip_value, Stats, TextItem, ValueType, walk_forward};
+use crate::tools::write_content;
+use crate::writer::JsonWriter;
+
+/// Apply JSON Merge Patch (RFC 7386) **patch** to the json of **target_reader** while copying it to **writer** in compact form.
+/// The target is never built: members which the patch does not touch are copied byte by byte, replaced members are skipped.
+/// Members which exist only in the patch are appended right before the enclosing object closes.
+/// The result is the stats of the parser.
+/// ### Example
+/// ```
+/// use json_walker::json_walker::{JsonWalker, StringReader};
+/// use json_walker::tools::merge_patch_stream;
+///
+/// let patch = JsonWalker::new(StringReader::new(r#"{"a":{"b":null},"c":1}"#.to_string()), 0).current_value_content().unwrap();
+/// let mut out = Vec::new();
+/// merge_patch_stream(StringReader::new(r#"{"a": {"b": 0, "d": [ 2 ]}}"#.to_string()), &patch, &mut out).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), r#"{"a":{"d":[ 2 ]},"c":1}"#);
+/// ```
+pub fn merge_patch_stream<W: Write>(target_reader: Box<dyn Iterator<Item=u8>>, patch: &Content, writer: W) -> Result<Stats, Error> {
+    let mut parser = Parser::new(target_reader, 0);
+    set_keep_escapes(&mut parser, true);
+    let mut w = JsonWriter::new(writer);
+    merge_value(&mut parser, patch, &mut w)?;
+    w.finish()?;
+    Ok(get_stats(&parser).clone())
+}
+
+/// the cursor stands before the target value
+fn merge_value<W: Write>(parser: &mut Parser, patch: &Content, w: &mut JsonWriter<W>) -> Result<(), Error> {
+    let members = match patch {
+        Content::Object(m) => m,
+        _ => {
+            skip_value(parser)?;
+            return write_content(patch, w);
+        }
+    };
+    begin_value(parser);
+    if parser.next_byte != b'{' {
+        // the target is not an object, so the patch is merged into an empty object
+        skip_value(parser)?;
+        return write_content(&without_nulls(patch), w);
+    }
+    walk_forward(parser);
+    w.begin_object()?;
+    let mut seen = BTreeSet::new();
+    while parser.next_byte != b'}' && parser.next_byte != NIL {
+        if parser.next_byte == b',' {
+            walk_forward(parser);
+        }
+        let key = match walk_forward(parser) {
+            TextItem::Key(k) => k.1,
+            _ => return Err(Error::invalid_structure(format!("Expecting a key.{}", get_current_status(parser)))),
+        };
+        // keys of the patch are decoded, while the one of the target is kept as it is written
+        let decoded = unescape(&key)?;
+        let member = members.iter().find(|(k, _)| **k == decoded);
+        match member {
+            None => {
+                w.raw_key(&key)?;
+                copy_value(parser, w.raw_writer()?)?;
+            }
+            Some((k, v)) => {
+                seen.insert(k);
+                if is_null(v) {
+                    skip_value(parser)?;
+                } else {
+                    w.raw_key(&key)?;
+                    merge_value(parser, v, w)?;
+                }
+            }
+        }
+    }
+    for (k, v) in members {
+        if !seen.contains(k) && !is_null(v) {
+            w.key(k)?;
+            write_content(&without_nulls(v), w)?;
+        }
+    }
+    walk_forward(parser);
+    w.end()
+}
+
+fn is_null(c: &Content) -> bool {
+    matches!(c, Content::Simple((ValueType::Null, _)))
+}
+
+/// merging a patch into an empty object leaves the patch without its null members
+fn without_nulls(patch: &Content) -> Content {
+    match patch {
+        Content::Object(m) => Content::Object(m.iter().filter(|(_, v)| !is_null(v)).map(|(k, v)| (k.clone(), without_nulls(v))).collect()),
+        _ => patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod merge_patch_tests {
+    use crate::json_walker::{Content, JsonWalker};
+    use crate::readers::StringReader;
+    use crate::tools::merge_patch_stream;
+
+    fn content(json: &str) -> Content {
+        JsonWalker::new(StringReader::new(json.to_string()), 0).current_value_content().unwrap()
+    }
+
+    fn merge(target: &str, patch: &str) -> serde_json::Value {
+        let mut out = Vec::new();
+        merge_patch_stream(StringReader::new(target.to_string()), &content(patch), &mut out).unwrap();
+        serde_json::from_slice(&out).unwrap()
+    }
+
+    fn value(json: &str) -> serde_json::Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    const RFC_TARGET: &str = r#"{"title":"Goodbye!","author":{"givenName":"John","familyName":"Doe"},"tags":["example","sample"],"content":"This will be unchanged"}"#;
+    const RFC_PATCH: &str = r#"{"title":"Hello!","phoneNumber":"+01-555-555-5555","author":{"familyName":null},"tags":["example"]}"#;
+    const RFC_RESULT: &str = r#"{"title":"Hello!","author":{"givenName":"John"},"tags":["example"],"content":"This will be unchanged","phoneNumber":"+01-555-555-5555"}"#;
+
+    #[test]
+    fn test_rfc_example_in_large_document() {
+        let records: Vec<String> = (0..2000).map(|i| format!(r#"{{"id": {i}, "doc": {{"title": "t{i}", "tags": [ "x" ]}}}}"#)).collect();
+        let target = format!(r#"{{"records": [{}], "doc": {RFC_TARGET}, "tail": {{"doc": null}}}}"#, records.join(", "));
+        let out = merge(&target, &format!(r#"{{"doc": {RFC_PATCH}, "added": {{"a": null, "b": {{"c": null}}}}}}"#));
+
+        let mut expected = value(&target);
+        expected["doc"] = value(RFC_RESULT);
+        expected["added"] = value(r#"{"b":{}}"#);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_rfc_appendix_cases() {
+        let cases = [
+            (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+            (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+            (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+            (r#"{"a":{"b":"c"}}"#, r#"{"a":{"b":"d","c":null}}"#, r#"{"a":{"b":"d"}}"#),
+            (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+            (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+            (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+            (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+            (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+            (r#"{}"#, r#"{"a":{"bb":{"ccc":null}}}"#, r#"{"a":{"bb":{}}}"#),
+        ];
+        for (target, patch, result) in cases {
+            assert_eq!(merge(target, patch), value(result), "{target} + {patch}");
+        }
+    }
+
+    #[test]
+    fn test_untouched_regions_are_raw_copied() {
+        let mut out = Vec::new();
+        let target = r#"{ "keep" : [ 1 ,  "a\"b" ], "x" : 1 }"#;
+        merge_patch_stream(StringReader::new(target.to_string()), &content(r#"{"x":2}"#), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), r#"{"keep":[ 1 ,  "a\"b" ],"x":2}"#);
+    }
+
+    #[test]
+    fn test_escaped_keys_of_target() {
+        // keys are compared in their decoded form, however they are escaped
+        assert_eq!(merge(r#"{"\u0061":1,"b\/c":2,"\u00e9":3}"#, r#"{"a":null,"b/c":4,"é":5}"#), value(r#"{"b/c":4,"é":5}"#));
+        let mut out = Vec::new();
+        merge_patch_stream(StringReader::new(r#"{"\u0061":1}"#.to_string()), &content(r#"{"a":2}"#), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), r#"{"\u0061":2}"#);
+    }
+}
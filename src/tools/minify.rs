@@ -0,0 +1,105 @@
+use std::io::Write;
+
+use crate::{Error, NIL};
+use crate::parser_core::{finish, get_stats, Parser, set_keep_escapes, Stats, walk_forward};
+use crate::tools::write_text_item;
+use crate::writer::JsonWriter;
+
+/// Read json from **reader** and write it to **writer** without any white-space between tokens.
+/// Strings and numbers are copied byte by byte, so escape sequences are kept untouched.
+/// Only one token is kept in memory at a time, so the size of json does not matter.
+/// The result is the stats of the parser. Malformed json or data after the root value fails with the error of the parser.
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::minify;
+///
+/// let mut out = Vec::new();
+/// minify(StringReader::new(r#"{ "a" : [ 1 , "A b" ] }"#.to_string()), &mut out).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), r#"{"a":[1,"A b"]}"#);
+/// ```
+pub fn minify<W: Write>(reader: Box<dyn Iterator<Item=u8>>, writer: W) -> Result<Stats, Error> {
+    let mut parser = Parser::new(reader, 0);
+    set_keep_escapes(&mut parser, true);
+    let mut w = JsonWriter::new(writer);
+    while parser.next_byte != NIL {
+        write_text_item(walk_forward(&mut parser), &mut w)?;
+    }
+    // a malformed token stops the walk early, and trailing data must not be dropped silently
+    finish(&mut parser)?;
+    w.finish()?;
+    Ok(get_stats(&parser).clone())
+}
+
+#[cfg(test)]
+mod minify_tests {
+    use crate::ErrorKind;
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::readers::StringReader;
+    use crate::tools::minify;
+
+    fn minify_str(json: &str) -> String {
+        let mut out = Vec::new();
+        minify(StringReader::new(json.to_string()), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// drop white-spaces outside of strings
+    fn reference_minify(json: &str) -> String {
+        let mut out = String::new();
+        let mut in_string = false;
+        let mut escaped = false;
+        for c in json.chars() {
+            if in_string {
+                in_string = escaped || c != '"';
+                escaped = !escaped && c == '\\';
+            } else if c == '"' {
+                in_string = true;
+            } else if c.is_ascii_whitespace() {
+                continue;
+            }
+            out.push(c);
+        }
+        out
+    }
+
+    #[test]
+    fn test_minify_correct_json() {
+        let minified = minify_str(CORRECT_JSON);
+        assert_eq!(minified, reference_minify(CORRECT_JSON));
+        let expected: serde_json::Value = serde_json::from_str(CORRECT_JSON).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&minified).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_minify_keeps_strings() {
+        let json = " [ \"a  b\\n\\u0041\\\"\" , { \"k\\\\ \" : \"  \" } , -1.5 ] ";
+        assert_eq!(minify_str(json), "[\"a  b\\n\\u0041\\\"\",{\"k\\\\ \":\"  \"},-1.5]");
+    }
+
+    #[test]
+    fn test_minify_is_idempotent() {
+        let docs = [CORRECT_JSON, r#"{"a" : { "b" : [ [ ] , { } , "x y" ] } }"#, " \"root\" ", "[ 1 , 2 ]"];
+        for doc in docs {
+            let once = minify_str(doc);
+            assert_eq!(minify_str(&once), once);
+        }
+    }
+
+    #[test]
+    fn test_minify_malformed_json() {
+        let cases = [
+            ("tru", ErrorKind::UnexpectedEos),
+            ("\"abc", ErrorKind::UnexpectedEos),
+            ("[1.", ErrorKind::InvalidNumber),
+            ("[1, x]", ErrorKind::UnexpectedChar),
+            ("{\"a\":1} 2", ErrorKind::TrailingCharacters),
+            ("[1]x", ErrorKind::TrailingCharacters),
+        ];
+        for (json, kind) in cases {
+            let err = minify(StringReader::new(json.to_string()), Vec::new()).unwrap_err();
+            assert_eq!(err.kind(), &kind, "{json}");
+            assert!(err.position().is_some(), "{json}");
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::io;
+use std::io::Write;
+
+use crate::{Error, ErrorKind, NIL};
+use crate::parser_core::{copy_value, get_current_status, is_poisoned, Parser, walk_forward};
+use crate::writer::JsonWriter;
+
+/// Write each element of the root array of **reader** as one line (NDJSON). Elements are copied byte by byte, except line
+/// breaks between their tokens which become spaces, so every element stays on its own line.
+/// The writer is flushed after each line. The result is the count of lines and errors name the failing element index
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::array_to_ndjson;
+///
+/// let mut out = Vec::new();
+/// assert_eq!(array_to_ndjson(StringReader::new("[{\"a\": 1},\n 2]".to_string()), &mut out).unwrap(), 2);
+/// assert_eq!(out, b"{\"a\": 1}\n2\n");
+/// ```
+pub fn array_to_ndjson<W: Write>(reader: Box<dyn Iterator<Item=u8>>, mut writer: W) -> Result<usize, Error> {
+    let mut parser = Parser::new(reader, 0);
+    if parser.next_byte != b'[' {
+        return Err(Error::wrong_data_type("The root is not an array"));
+    }
+    walk_forward(&mut parser);
+
+    let mut count = 0;
+    while parser.next_byte != b']' {
+        if parser.next_byte == b',' {
+            walk_forward(&mut parser);
+        }
+        if parser.next_byte == NIL {
+            return Err(Error::new(ErrorKind::EOS, format!("Element {count}: the array is not closed.{}", get_current_status(&mut parser))));
+        }
+        copy_value(&mut parser, &mut SingleLine(&mut writer)).map_err(|e| Error::new(e.kind, format!("Element {count}: {}", e.msg)))?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Wrap the lines of **reader** (NDJSON) into one json array. Each line is parsed on its own and copied byte by byte,
+/// blank lines are ignored. Only one line is kept in memory at a time.
+/// The result is the count of elements and errors name the failing line number, starting from 1
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::ndjson_to_array;
+///
+/// let mut out = Vec::new();
+/// assert_eq!(ndjson_to_array(StringReader::new("{\"a\": 1}\n\n[2]\n".to_string()), &mut out).unwrap(), 2);
+/// assert_eq!(out, b"[{\"a\": 1},[2]]");
+/// ```
+pub fn ndjson_to_array<W: Write>(mut reader: Box<dyn Iterator<Item=u8>>, writer: W) -> Result<usize, Error> {
+    let mut w = JsonWriter::new(writer);
+    w.begin_array()?;
+    let mut count = 0;
+    let mut line_number = 0;
+    let mut finished = false;
+    while !finished {
+        let mut line = Vec::new();
+        loop {
+            match reader.next() {
+                None => {
+                    finished = true;
+                    break;
+                }
+                Some(b'\n') => break,
+                Some(b) => line.push(b),
+            }
+        }
+        line_number += 1;
+        count += copy_line(line, line_number, &mut w)?;
+    }
+    w.end()?;
+    w.finish()?;
+    Ok(count)
+}
+
+/// the result is the count of copied values, zero for a blank line
+fn copy_line<W: Write>(line: Vec<u8>, line_number: usize, w: &mut JsonWriter<W>) -> Result<usize, Error> {
+    let mut parser = Parser::new(Box::new(line.into_iter()), 0);
+    if parser.next_byte == NIL {
+        return Ok(0);
+    }
+    let with_line = |e: Error| Error::new(e.kind, format!("Line {line_number}: {}", e.msg));
+    copy_value(&mut parser, w.raw_writer()?).map_err(with_line)?;
+    if parser.next_byte != NIL || is_poisoned(&parser) {
+        return Err(with_line(Error::invalid_structure("Extra content after the value")));
+    }
+    Ok(1)
+}
+
+/// replace line breaks with spaces. They can only be white-spaces between tokens, since strings hold them escaped.
+/// extract_multi() writes values on one line by it as well
+pub(crate) struct SingleLine<'a, W: Write>(pub(crate) &'a mut W);
+
+impl<W: Write> Write for SingleLine<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.contains(&b'\n') || buf.contains(&b'\r') {
+            let line: Vec<u8> = buf.iter().map(|b| if *b == b'\n' || *b == b'\r' { b' ' } else { *b }).collect();
+            self.0.write_all(&line)?;
+            Ok(buf.len())
+        } else {
+            self.0.write(buf)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod ndjson_tests {
+    use crate::ErrorKind;
+    use crate::readers::StringReader;
+    use crate::tools::{array_to_ndjson, ndjson_to_array};
+
+    const RECORDS: &str = r#"{"id":1,"name":"a \"q\" \n b","tags":["x","y"]}
+{"id": 2, "score": 1.50, "nested": {"deep": [1, [2, {}]]}}
+[1,2,3]
+"just a string"
+{"id":5,"unicode":"é€"}
+"#;
+
+    fn to_array(ndjson: &str) -> Result<Vec<u8>, crate::Error> {
+        let mut out = Vec::new();
+        ndjson_to_array(StringReader::new(ndjson.to_string()), &mut out)?;
+        Ok(out)
+    }
+
+    fn to_ndjson(json: &[u8]) -> Result<Vec<u8>, crate::Error> {
+        let mut out = Vec::new();
+        array_to_ndjson(StringReader::new(String::from_utf8(json.to_vec()).unwrap()), &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let array = to_array(RECORDS).unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&array).unwrap();
+        assert_eq!(v.as_array().unwrap().len(), 5);
+        assert_eq!(v[1]["nested"]["deep"][1][0], 2);
+
+        // element contents are kept byte by byte in both directions
+        let ndjson = to_ndjson(&array).unwrap();
+        assert_eq!(String::from_utf8(ndjson.clone()).unwrap(), RECORDS);
+        assert_eq!(to_array(&String::from_utf8(ndjson).unwrap()).unwrap(), array);
+    }
+
+    #[test]
+    fn test_blank_lines_and_line_breaks() {
+        assert_eq!(to_array("\n\n1\n\n  \n2").unwrap(), b"[1,2]");
+        assert_eq!(to_array("").unwrap(), b"[]");
+        assert_eq!(to_ndjson(b"[]").unwrap(), b"");
+        assert_eq!(to_ndjson(b"[\n  {\n    \"a\": 1\n  },\r\n  2\n]").unwrap(), b"{     \"a\": 1   }\n2\n");
+    }
+
+    #[test]
+    fn test_errors() {
+        let e = to_array("1\n2\n\n[3] 4\n").unwrap_err();
+        assert_eq!(e.kind, ErrorKind::InvalidStructure);
+        assert!(e.msg.starts_with("Line 4: "), "{}", e.msg);
+
+        let e = to_ndjson(b"{\"a\": 1}").unwrap_err();
+        assert_eq!(e.kind, ErrorKind::WrongDataType);
+        let e = to_ndjson(b"[1, 2").unwrap_err();
+        assert!(e.msg.starts_with("Element 2: "), "{}", e.msg);
+    }
+}
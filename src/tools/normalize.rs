@@ -0,0 +1,383 @@
+use std::io::Write;
+use std::iter::Peekable;
+
+use crate::{Error, ErrorKind};
+use crate::escape::unescape;
+use crate::parser_core::MAX_DEPTH;
+use crate::writer::JsonWriter;
+
+/// Relaxations of RFC 8259 which normalize() accepts. Each one is off by default
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LenientOptions {
+    /// `// line` and `/* block */` comments wherever white-spaces are allowed
+    pub comments: bool,
+    /// a "," right before "}" or "]"
+    pub trailing_commas: bool,
+    /// strings delimited by ' in which \' stands for '
+    pub single_quotes: bool,
+    /// keys which are identifiers such as `name` or `$id`, without quotation marks
+    pub unquoted_keys: bool,
+}
+
+impl LenientOptions {
+    /// all relaxations are on
+    pub fn all() -> Self {
+        LenientOptions { comments: true, trailing_commas: true, single_quotes: true, unquoted_keys: true }
+    }
+
+    pub fn with_comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    pub fn with_trailing_commas(mut self, trailing_commas: bool) -> Self {
+        self.trailing_commas = trailing_commas;
+        self
+    }
+
+    pub fn with_single_quotes(mut self, single_quotes: bool) -> Self {
+        self.single_quotes = single_quotes;
+        self
+    }
+
+    pub fn with_unquoted_keys(mut self, unquoted_keys: bool) -> Self {
+        self.unquoted_keys = unquoted_keys;
+        self
+    }
+}
+
+/// Read json which may use the relaxations of **options** (JSONC or a JSON5 subset) and write it to **writer** as strict
+/// compact json. Comments are dropped, keys get quotation marks and strings are escaped again, so ' delimited strings
+/// become regular ones. Only one token is kept in memory at a time.
+/// Errors tell the line and column of the offending char. Containers may be nested as deep as the walker allows by
+/// default, otherwise MaxDepthExceeded error is returned
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::{LenientOptions, normalize};
+///
+/// let config = "{name: 'it\\'s', /* port */ port: 80, tags: ['a',],} // end";
+/// let mut out = Vec::new();
+/// normalize(StringReader::new(config.to_string()), &mut out, &LenientOptions::all()).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), r#"{"name":"it's","port":80,"tags":["a"]}"#);
+/// ```
+pub fn normalize<W: Write>(reader: Box<dyn Iterator<Item=u8>>, writer: W, options: &LenientOptions) -> Result<(), Error> {
+    let mut lexer = Lexer { reader: reader.peekable(), options, line: 1, column: 0, depth: 0 };
+    let mut w = JsonWriter::new(writer);
+    let first = lexer.next_token()?;
+    write_value(&mut lexer, first, &mut w)?;
+    match lexer.next_token() {
+        // a string or comment which is cut short after the root value is not a clean end
+        Err(e) if e == Error::new_eos() => {}
+        Err(e) if e.kind == ErrorKind::EOS => return Err(lexer.error(&e.msg)),
+        Err(e) => return Err(e),
+        Ok(_) => return Err(lexer.error("Extra content after the root value")),
+    }
+    w.finish()?;
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Symbol(u8),
+    Str(String),
+    /// numbers, true, false, null and identifiers
+    Word(String),
+}
+
+fn write_value<W: Write>(lexer: &mut Lexer, token: Token, w: &mut JsonWriter<W>) -> Result<(), Error> {
+    match token {
+        Token::Symbol(b'{') => {
+            lexer.enter()?;
+            w.begin_object()?;
+            let mut token = lexer.next_token()?;
+            loop {
+                if token == Token::Symbol(b'}') {
+                    break;
+                }
+                match token {
+                    Token::Str(key) => w.key(&key)?,
+                    Token::Word(key) if lexer.options.unquoted_keys && is_identifier(&key) => w.key(&key)?,
+                    _ => return Err(lexer.error("Expecting a key")),
+                }
+                if lexer.next_token()? != Token::Symbol(b':') {
+                    return Err(lexer.error("Expecting `:`"));
+                }
+                let value = lexer.next_token()?;
+                write_value(lexer, value, w)?;
+                token = match lexer.next_token()? {
+                    Token::Symbol(b'}') => break,
+                    Token::Symbol(b',') => lexer.after_comma(b'}')?,
+                    _ => return Err(lexer.error("Expecting `,` or `}`")),
+                };
+            }
+            lexer.depth -= 1;
+            w.end()
+        }
+        Token::Symbol(b'[') => {
+            lexer.enter()?;
+            w.begin_array()?;
+            let mut token = lexer.next_token()?;
+            loop {
+                if token == Token::Symbol(b']') {
+                    break;
+                }
+                write_value(lexer, token, w)?;
+                token = match lexer.next_token()? {
+                    Token::Symbol(b']') => break,
+                    Token::Symbol(b',') => lexer.after_comma(b']')?,
+                    _ => return Err(lexer.error("Expecting `,` or `]`")),
+                };
+            }
+            lexer.depth -= 1;
+            w.end()
+        }
+        Token::Str(s) => w.value_str(&s),
+        Token::Word(word) if word == "true" || word == "false" || word == "null" || is_number(&word) => w.raw(&word),
+        Token::Word(word) => Err(lexer.error(&format!("`{word}` is not a value"))),
+        Token::Symbol(_) => Err(lexer.error("Expecting a value")),
+    }
+}
+
+struct Lexer<'a> {
+    reader: Peekable<Box<dyn Iterator<Item=u8>>>,
+    options: &'a LenientOptions,
+    line: usize,
+    column: usize,
+    /// containers which are open, check out enter()
+    depth: usize,
+}
+
+impl Lexer<'_> {
+    fn next_byte(&mut self) -> Option<u8> {
+        let b = self.reader.next();
+        if b == Some(b'\n') {
+            self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+        b
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        Error::invalid_structure(format!("{msg} at line {}, column {}", self.line, self.column))
+    }
+
+    /// a container is opened. It may not be deeper than MAX_DEPTH, so write_value() does not overflow the stack
+    fn enter(&mut self) -> Result<(), Error> {
+        if self.depth == MAX_DEPTH {
+            let msg = format!("More than {MAX_DEPTH} nested containers at line {}, column {}", self.line, self.column);
+            return Err(Error::max_depth_exceeded(msg));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// the token after a ",". A closing **bracket** is only accepted when trailing commas are allowed
+    fn after_comma(&mut self, bracket: u8) -> Result<Token, Error> {
+        let token = self.next_token()?;
+        if token == Token::Symbol(bracket) && !self.options.trailing_commas {
+            return Err(self.error("Trailing comma"));
+        }
+        Ok(token)
+    }
+
+    fn next_token(&mut self) -> Result<Token, Error> {
+        self.skip_white_spaces()?;
+        let b = match self.next_byte() {
+            None => return Err(Error::new_eos()),
+            Some(b) => b,
+        };
+        match b {
+            b'{' | b'}' | b'[' | b']' | b':' | b',' => Ok(Token::Symbol(b)),
+            b'"' => self.string(b'"'),
+            b'\'' if self.options.single_quotes => self.string(b'\''),
+            _ if b.is_ascii_alphanumeric() || b"_$+-.".contains(&b) => {
+                let mut word = vec![b];
+                while let Some(&c) = self.reader.peek() {
+                    if !(c.is_ascii_alphanumeric() || b"_$+-.".contains(&c)) {
+                        break;
+                    }
+                    word.push(c);
+                    self.next_byte();
+                }
+                Ok(Token::Word(String::from_utf8(word).unwrap()))
+            }
+            _ => Err(self.error(&format!("Unexpected char `{}`", b as char))),
+        }
+    }
+
+    fn skip_white_spaces(&mut self) -> Result<(), Error> {
+        while let Some(&b) = self.reader.peek() {
+            if b.is_ascii_whitespace() {
+                self.next_byte();
+            } else if b == b'/' && self.options.comments {
+                self.next_byte();
+                match self.next_byte() {
+                    Some(b'/') => while !matches!(self.next_byte(), Some(b'\n') | None) {},
+                    Some(b'*') => {
+                        let mut last = 0;
+                        loop {
+                            match self.next_byte() {
+                                None => return Err(Error::new(ErrorKind::EOS, "The block comment is not closed")),
+                                Some(b'/') if last == b'*' => break,
+                                Some(c) => last = c,
+                            }
+                        }
+                    }
+                    _ => return Err(self.error("Unexpected char `/`")),
+                }
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// the opening **quote** is consumed. The result is the decoded string
+    fn string(&mut self, quote: u8) -> Result<Token, Error> {
+        // the text is turned into the form of a regular json string, then decoded
+        let mut raw = Vec::with_capacity(32);
+        loop {
+            match self.next_byte() {
+                None => return Err(Error::new(ErrorKind::EOS, "The string is not closed")),
+                Some(b'\\') => match self.next_byte() {
+                    Some(b'\'') if quote == b'\'' => raw.push(b'\''),
+                    Some(c) => raw.extend_from_slice(&[b'\\', c]),
+                    None => return Err(Error::new(ErrorKind::EOS, "The string is not closed")),
+                },
+                Some(c) if c == quote => break,
+                Some(b'"') => raw.extend_from_slice(b"\\\""),
+                Some(c) => raw.push(c),
+            }
+        }
+        let raw = String::from_utf8(raw).map_err(|_| self.error("The string is not utf8"))?;
+        unescape(&raw).map(Token::Str).map_err(|e| self.error(&e.msg))
+    }
+}
+
+fn is_identifier(word: &str) -> bool {
+    let mut bytes = word.bytes();
+    bytes.next().is_some_and(|b| b.is_ascii_alphabetic() || b == b'_' || b == b'$') && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'$')
+}
+
+/// the number grammar of RFC 8259
+fn is_number(word: &str) -> bool {
+    let s = word.strip_prefix('-').unwrap_or(word);
+    let (int, rest) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    if int.is_empty() || (int.len() > 1 && int.starts_with('0')) {
+        return false;
+    }
+    let rest = match rest.strip_prefix('.') {
+        Some(r) => {
+            let digits = r.find(|c: char| !c.is_ascii_digit()).unwrap_or(r.len());
+            if digits == 0 {
+                return false;
+            }
+            &r[digits..]
+        }
+        None => rest,
+    };
+    match rest.strip_prefix(['e', 'E']) {
+        Some(e) => {
+            let e = e.strip_prefix(['+', '-']).unwrap_or(e);
+            !e.is_empty() && e.bytes().all(|b| b.is_ascii_digit())
+        }
+        None => rest.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod normalize_tests {
+    use crate::ErrorKind;
+    use crate::json_walker::{Content, JsonWalker};
+    use crate::readers::StringReader;
+    use crate::tools::{LenientOptions, normalize};
+    use crate::tools::normalize::is_number;
+
+    const CONFIG: &str = r#"
+// service configuration
+{
+    name: 'walker "svc"',          // single quotes with a double quote inside
+    'quoted-key': 'it\'s \u00e9',
+    "version": 3,
+    /* block comment
+       over lines */
+    servers: [
+        {host: "a.example", port: 8080, $weight: 0.5,},
+        {host: 'b.example', port: 8081, tags: ['x', "y",],},
+    ],
+    limits: {max_body: -1024, ratio: 12.25, enabled: true, proxy: null,},
+    path: "c:\\temp\/logs", /* trailing */
+}
+// end"#;
+
+    const STRICT: &str = r#"{"name": "walker \"svc\"", "quoted-key": "it's é", "version": 3,
+        "servers": [{"host": "a.example", "port": 8080, "$weight": 0.5}, {"host": "b.example", "port": 8081, "tags": ["x", "y"]}],
+        "limits": {"max_body": -1024, "ratio": 12.25, "enabled": true, "proxy": null}, "path": "c:\\temp/logs"}"#;
+
+    fn run(json: &str, options: &LenientOptions) -> Result<String, crate::Error> {
+        let mut out = Vec::new();
+        normalize(StringReader::new(json.to_string()), &mut out, options)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn content(json: &str) -> Content {
+        JsonWalker::new(StringReader::new(json.to_string()), 0).current_value_content().unwrap()
+    }
+
+    #[test]
+    fn test_config_with_all_relaxations() {
+        let out = run(CONFIG, &LenientOptions::all()).unwrap();
+        let strict: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(strict, serde_json::from_str::<serde_json::Value>(STRICT).unwrap());
+        assert_eq!(content(&out), content(STRICT));
+        // strict json passes as it is, apart from white-spaces
+        assert_eq!(run(STRICT, &LenientOptions::default()).unwrap(), out);
+    }
+
+    #[test]
+    fn test_each_relaxation_is_opt_in() {
+        let cases = [
+            ("[1] // c", LenientOptions::default().with_comments(true)),
+            ("[1,]", LenientOptions::default().with_trailing_commas(true)),
+            ("{\"a\":1,}", LenientOptions::default().with_trailing_commas(true)),
+            ("['a']", LenientOptions::default().with_single_quotes(true)),
+            ("{a:1}", LenientOptions::default().with_unquoted_keys(true)),
+        ];
+        for (json, options) in cases {
+            assert!(run(json, &options).is_ok(), "{json}");
+            assert_eq!(run(json, &LenientOptions::default()).unwrap_err().kind, ErrorKind::InvalidStructure, "{json}");
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let options = LenientOptions::all();
+        assert_eq!(run("{\n  a: tru\n}", &options).unwrap_err().msg, "`tru` is not a value at line 2, column 8");
+        assert_eq!(run("[1, /* open", &options).unwrap_err().kind, ErrorKind::EOS);
+        assert_eq!(run("[1,,]", &options).unwrap_err().kind, ErrorKind::InvalidStructure);
+        assert_eq!(run("{1a: 1}", &options).unwrap_err().kind, ErrorKind::InvalidStructure);
+        assert_eq!(run("[1] 2", &options).unwrap_err().kind, ErrorKind::InvalidStructure);
+        assert_eq!(run("[1", &options).unwrap_err().kind, ErrorKind::EOS);
+        assert_eq!(run("123\"", &options).unwrap_err().kind, ErrorKind::InvalidStructure);
+        assert_eq!(run("1 /* open", &options).unwrap_err().kind, ErrorKind::InvalidStructure);
+        // nesting is limited, so deep json does not overflow the stack
+        assert_eq!(run(&"[".repeat(1_000_000), &options).unwrap_err().kind, ErrorKind::MaxDepthExceeded);
+        let deep = format!("{}{}", "[".repeat(128), "]".repeat(128));
+        assert_eq!(run(&deep, &options).unwrap(), deep);
+        let too_deep = format!("{{a: {}1{}}}", "[".repeat(128), "]".repeat(128));
+        assert_eq!(run(&too_deep, &options).unwrap_err().msg, "More than 128 nested containers at line 1, column 132");
+    }
+
+    #[test]
+    fn test_numbers() {
+        for n in ["0", "-0", "12", "1.5", "-0.25e+10", "3E2"] {
+            assert!(is_number(n), "{n}");
+        }
+        for n in ["01", "+1", ".5", "1.", "1e", "0x10", "-", "1.2.3", "Infinity"] {
+            assert!(!is_number(n), "{n}");
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::ops::Range;
+
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+
+use crate::{Error, NIL};
+use crate::parser_core::{end_of_stream, get_stats, Parser, seek_pointer, skip_value, walk_forward};
+use crate::path_matcher::PathMatcher;
+use crate::readers::SliceReader;
+
+/// Byte ranges of the elements of the array at **pointer** in **bytes**. Elements are passed by the fast path of
+/// skip_current_value(), so nothing is built. A range may end with the white-spaces after its element
+/// ### Example
+/// ```
+/// use json_walker::tools::element_ranges;
+///
+/// assert_eq!(element_ranges(br#"{"a": [1, {"b": 2}]}"#, "/a").unwrap(), vec![7..8, 10..18]);
+/// ```
+pub fn element_ranges(bytes: &[u8], pointer: &str) -> Result<Vec<Range<usize>>, Error> {
+    let mut parser = Parser::new(SliceReader::new(bytes), 0);
+    seek_pointer(&mut parser, &PathMatcher::new(pointer)?)?;
+    if parser.next_byte != b'[' {
+        return Err(Error::wrong_data_type(format!("The value of `{pointer}` is not an array")));
+    }
+    walk_forward(&mut parser);
+    let mut ranges = vec![];
+    loop {
+        match parser.next_byte {
+            b']' => return Ok(ranges),
+            b',' => {
+                walk_forward(&mut parser);
+            }
+            NIL => return Err(end_of_stream(&parser)),
+            _ => {
+                // the first byte of the element is already read as the look-ahead, so is the byte after it
+                let start = get_stats(&parser).bytes_consumed - 1;
+                skip_value(&mut parser)?;
+                ranges.push(start as usize..get_stats(&parser).bytes_consumed as usize - 1);
+            }
+        }
+    }
+}
+
+/// Deserialize the elements of the array at **pointer** in parallel. The array is scanned once by element_ranges(),
+/// then each worker of rayon walks its own elements over a SliceReader. So **bytes** must be in memory as a whole, a
+/// stream can not be split this way. Results are in the order of elements, and a failed element does not affect others
+/// ### Example
+/// ```
+/// use rayon::prelude::*;
+/// use json_walker::tools::par_elements;
+///
+/// let ids: Vec<u32> = par_elements::<u32>(br#"{"ids": [1, 2, 3]}"#, "/ids").unwrap().map(|id| id.unwrap() * 10).collect();
+/// assert_eq!(ids, vec![10, 20, 30]);
+/// ```
+pub fn par_elements<'a, T>(bytes: &'a [u8], pointer: &str) -> Result<impl IndexedParallelIterator<Item=Result<T, Error>> + 'a, Error>
+    where T: DeserializeOwned + Send {
+    let ranges = element_ranges(bytes, pointer)?;
+    Ok(ranges.into_par_iter().map(move |r| Parser::new(SliceReader::new(&bytes[r]), 0).current_value::<T>()))
+}
+
+#[cfg(test)]
+mod parallel_tests {
+    use rayon::prelude::*;
+    use serde::Deserialize;
+
+    use crate::ErrorKind;
+    use crate::json_walker::{JsonWalker, SliceReader};
+    use crate::tools::{element_ranges, par_elements};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Item {
+        id: u32,
+        name: String,
+        tags: Vec<String>,
+        score: Option<f64>,
+    }
+
+    fn items_json(n: u32) -> String {
+        let items: Vec<String> = (0..n).map(|i| format!(
+            r#" {{"id": {i}, "name": "item \"{i}\"", "tags": [{}], "score": {}}}"#,
+            (0..i % 4).map(|t| format!(r#""t{t}""#)).collect::<Vec<_>>().join(", "),
+            if i % 3 == 0 { "null".to_string() } else { format!("{i}.5") },
+        )).collect();
+        format!(r#"{{"count": {n}, "items": [{}], "after": true}}"#, items.join(","))
+    }
+
+    #[test]
+    fn test_par_elements() {
+        let json = items_json(10_000);
+        let sequential: Vec<Item> = {
+            let mut walker = JsonWalker::new(SliceReader::new(json.as_bytes()), 0);
+            walker.next_key_by_name("items").unwrap();
+            walker.current_value().unwrap()
+        };
+        let parallel: Vec<Item> = par_elements(json.as_bytes(), "/items").unwrap().map(|i| i.unwrap()).collect();
+        assert_eq!(parallel.len(), 10_000);
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn test_element_ranges() {
+        let json = br#" [ 1 , "a,]" ,{"b": [2, {}]},[] ]"#;
+        let ranges = element_ranges(json, "").unwrap();
+        let elements: Vec<&str> = ranges.into_iter().map(|r| std::str::from_utf8(&json[r]).unwrap().trim_end()).collect();
+        assert_eq!(elements, vec!["1", r#""a,]""#, r#"{"b": [2, {}]}"#, "[]"]);
+        assert!(element_ranges(b"[]", "").unwrap().is_empty());
+
+        assert_eq!(element_ranges(br#"{"a": {}}"#, "/a").unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(element_ranges(br#"{"a": []}"#, "/b").unwrap_err().kind, ErrorKind::KeyNotFound);
+
+        // a failed element does not affect others
+        let results: Vec<_> = par_elements::<u8>(b"[1, 300, 3]", "").unwrap().map(|r| r.map_err(|e| e.kind)).collect();
+        assert_eq!(results, vec![Ok(1), Err(ErrorKind::ParseIntError), Ok(3)]);
+    }
+}
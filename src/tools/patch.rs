@@ -0,0 +1,304 @@
+use std::collections::BTreeMap;
+
+use crate::Error;
+use crate::parser_core::Content;
+use crate::path_matcher::{escape_token, split_pointer};
+
+/// An operation of JSON Patch (RFC 6902). Paths are json pointers (RFC 6901), such as "/a/0/b"
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOp {
+    Add { path: String, value: Content },
+    Remove { path: String },
+    Replace { path: String, value: Content },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Content },
+}
+
+/// Produce the operations which turn **a** into **b**. Only add, remove and replace are generated:
+/// - objects are compared key by key
+/// - arrays are compared index by index. Extra elements of **b** are added at the end in order and extra elements
+///   of **a** are removed from the last one, so indices stay valid while the patch is applied
+/// - anything else which is not equal (including a type change) is replaced as a whole
+/// ### Example
+/// ```
+/// use json_walker::json_walker::{JsonWalker, StringReader};
+/// use json_walker::tools::{apply_patch, diff_patch};
+///
+/// let mut a = JsonWalker::new(StringReader::new(r#"{"a":[1,2],"b":1}"#.to_string()), 0).current_value_content().unwrap();
+/// let b = JsonWalker::new(StringReader::new(r#"{"a":[1],"c":true}"#.to_string()), 0).current_value_content().unwrap();
+/// let patch = diff_patch(&a, &b);
+/// assert_eq!(patch.len(), 3);
+/// apply_patch(&mut a, &patch).unwrap();
+/// assert_eq!(a, b);
+/// ```
+pub fn diff_patch(a: &Content, b: &Content) -> Vec<PatchOp> {
+    let mut ops = vec![];
+    diff(a, b, &mut String::new(), &mut ops);
+    ops
+}
+
+fn diff(a: &Content, b: &Content, path: &mut String, ops: &mut Vec<PatchOp>) {
+    match (a, b) {
+        (Content::Object(x), Content::Object(y)) => {
+            for (k, v) in x {
+                let l = path.len();
+                path.push('/');
+                path.push_str(&escape_token(k));
+                match y.get(k) {
+                    Some(w) => diff(v, w, path, ops),
+                    None => ops.push(PatchOp::Remove { path: path.clone() }),
+                }
+                path.truncate(l);
+            }
+            for (k, w) in y {
+                if !x.contains_key(k) {
+                    ops.push(PatchOp::Add { path: format!("{path}/{}", escape_token(k)), value: w.clone() });
+                }
+            }
+        }
+        (Content::Array(x), Content::Array(y)) => {
+            for (i, (v, w)) in x.iter().zip(y).enumerate() {
+                let l = path.len();
+                path.push_str(&format!("/{i}"));
+                diff(v, w, path, ops);
+                path.truncate(l);
+            }
+            for (i, w) in y.iter().enumerate().skip(x.len()) {
+                ops.push(PatchOp::Add { path: format!("{path}/{i}"), value: w.clone() });
+            }
+            for i in (y.len()..x.len()).rev() {
+                ops.push(PatchOp::Remove { path: format!("{path}/{i}") });
+            }
+        }
+        _ if a == b => {}
+        _ => ops.push(PatchOp::Replace { path: path.clone(), value: b.clone() }),
+    }
+}
+
+/// Apply **patch** to **doc**. If an operation fails, the error is returned and **doc** is left untouched.
+/// A failed test operation returns TestFailed error, a missing path returns KeyNotFound error
+pub fn apply_patch(doc: &mut Content, patch: &[PatchOp]) -> Result<(), Error> {
+    let mut result = doc.clone();
+    for op in patch {
+        match op {
+            PatchOp::Add { path, value } => add(&mut result, path, value.clone())?,
+            PatchOp::Remove { path } => {
+                remove(&mut result, path)?;
+            }
+            PatchOp::Replace { path, value } => *get_mut(&mut result, path)? = value.clone(),
+            PatchOp::Move { from, path } => {
+                if path.starts_with(from.as_str()) && path[from.len()..].starts_with('/') {
+                    return Err(Error::invalid_path(format!("`{from}` cannot be moved into its own child `{path}`")));
+                }
+                let value = remove(&mut result, from)?;
+                add(&mut result, path, value)?;
+            }
+            PatchOp::Copy { from, path } => {
+                let value = get_mut(&mut result, from)?.clone();
+                add(&mut result, path, value)?;
+            }
+            PatchOp::Test { path, value } => {
+                if get_mut(&mut result, path)? != value {
+                    return Err(Error::test_failed(format!("The value of `{path}` is not the expected one")));
+                }
+            }
+        }
+    }
+    *doc = result;
+    Ok(())
+}
+
+fn not_found(path: &str) -> Error {
+    Error::key_not_found(format!("`{path}` does not exist"))
+}
+
+/// array index of a token. "-" (the end of array) is only accepted if **allow_end** is set
+fn index(token: &str, len: usize, allow_end: bool) -> Option<usize> {
+    if allow_end && token == "-" {
+        return Some(len);
+    }
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+    match token.parse::<usize>() {
+        Ok(i) if i < len || (allow_end && i == len) => Some(i),
+        _ => None,
+    }
+}
+
+fn child_mut<'a>(node: &'a mut Content, token: &str) -> Option<&'a mut Content> {
+    match node {
+        Content::Object(m) => m.get_mut(token),
+        Content::Array(a) => {
+            let i = index(token, a.len(), false)?;
+            a.get_mut(i)
+        }
+        Content::Simple(_) => None,
+    }
+}
+
+fn get_mut<'a>(doc: &'a mut Content, path: &str) -> Result<&'a mut Content, Error> {
+    let mut node = doc;
+    for token in split_pointer(path)? {
+        node = child_mut(node, &token).ok_or_else(|| not_found(path))?;
+    }
+    Ok(node)
+}
+
+/// the container of the target and the last token of path. None means the root
+fn parent_mut<'a>(doc: &'a mut Content, path: &str) -> Result<Option<(&'a mut Content, String)>, Error> {
+    let mut tokens = split_pointer(path)?;
+    let last = match tokens.pop() {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    let mut node = doc;
+    for token in tokens {
+        node = child_mut(node, &token).ok_or_else(|| not_found(path))?;
+    }
+    Ok(Some((node, last)))
+}
+
+fn add(doc: &mut Content, path: &str, value: Content) -> Result<(), Error> {
+    match parent_mut(doc, path)? {
+        None => *doc = value,
+        Some((Content::Object(m), key)) => {
+            m.insert(key, value);
+        }
+        Some((Content::Array(a), token)) => {
+            let i = index(&token, a.len(), true).ok_or_else(|| not_found(path))?;
+            a.insert(i, value);
+        }
+        Some(_) => return Err(not_found(path)),
+    }
+    Ok(())
+}
+
+fn remove(doc: &mut Content, path: &str) -> Result<Content, Error> {
+    match parent_mut(doc, path)? {
+        None => Ok(std::mem::replace(doc, Content::Object(BTreeMap::new()))),
+        Some((Content::Object(m), key)) => m.remove(&key).ok_or_else(|| not_found(path)),
+        Some((Content::Array(a), token)) => {
+            let i = index(&token, a.len(), false).ok_or_else(|| not_found(path))?;
+            Ok(a.remove(i))
+        }
+        Some(_) => Err(not_found(path)),
+    }
+}
+
+#[cfg(test)]
+mod patch_tests {
+    use std::collections::BTreeMap;
+
+    use crate::ErrorKind;
+    use crate::json_walker::{Content, JsonWalker, ValueType};
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::readers::StringReader;
+    use crate::tools::{apply_patch, diff_patch, PatchOp};
+    use crate::tools::PatchOp::*;
+
+    fn content(json: &str) -> Content {
+        JsonWalker::new(StringReader::new(json.to_string()), 0).current_value_content().unwrap()
+    }
+
+    fn apply(doc: &str, patch: &[PatchOp]) -> Result<Content, crate::Error> {
+        let mut doc = content(doc);
+        apply_patch(&mut doc, patch).map(|_| doc)
+    }
+
+    fn s(path: &str) -> String {
+        path.to_string()
+    }
+
+    #[test]
+    fn test_rfc_examples() {
+        // A.1 - A.7
+        assert_eq!(apply(r#"{"foo":"bar"}"#, &[Add { path: s("/baz"), value: content(r#""qux""#) }]).unwrap(), content(r#"{"baz":"qux","foo":"bar"}"#));
+        assert_eq!(apply(r#"{"foo":["bar","baz"]}"#, &[Add { path: s("/foo/1"), value: content(r#""qux""#) }]).unwrap(), content(r#"{"foo":["bar","qux","baz"]}"#));
+        assert_eq!(apply(r#"{"baz":"qux","foo":"bar"}"#, &[Remove { path: s("/baz") }]).unwrap(), content(r#"{"foo":"bar"}"#));
+        assert_eq!(apply(r#"{"foo":["bar","qux","baz"]}"#, &[Remove { path: s("/foo/1") }]).unwrap(), content(r#"{"foo":["bar","baz"]}"#));
+        assert_eq!(apply(r#"{"baz":"qux","foo":"bar"}"#, &[Replace { path: s("/baz"), value: content(r#""boo""#) }]).unwrap(), content(r#"{"baz":"boo","foo":"bar"}"#));
+        assert_eq!(
+            apply(r#"{"foo":{"bar":"baz","waldo":"fred"},"qux":{"corge":"grault"}}"#, &[Move { from: s("/foo/waldo"), path: s("/qux/thud") }]).unwrap(),
+            content(r#"{"foo":{"bar":"baz"},"qux":{"corge":"grault","thud":"fred"}}"#)
+        );
+        assert_eq!(apply(r#"{"foo":["all","grass","cows","eat"]}"#, &[Move { from: s("/foo/1"), path: s("/foo/3") }]).unwrap(), content(r#"{"foo":["all","cows","eat","grass"]}"#));
+        // A.8, A.9
+        let doc = r#"{"baz":"qux","foo":["a",2,"c"]}"#;
+        assert!(apply(doc, &[Test { path: s("/baz"), value: content(r#""qux""#) }, Test { path: s("/foo/1"), value: content("2") }]).is_ok());
+        assert_eq!(apply(r#"{"baz":"qux"}"#, &[Test { path: s("/baz"), value: content(r#""bar""#) }]).unwrap_err().kind, ErrorKind::TestFailed);
+        // A.10, A.12
+        assert_eq!(apply(r#"{"foo":"bar"}"#, &[Add { path: s("/child"), value: content(r#"{"grandchild":{}}"#) }]).unwrap(), content(r#"{"foo":"bar","child":{"grandchild":{}}}"#));
+        assert_eq!(apply(r#"{"foo":"bar"}"#, &[Add { path: s("/baz/bat"), value: content(r#""qux""#) }]).unwrap_err().kind, ErrorKind::KeyNotFound);
+        // A.14, A.15
+        assert!(apply(r#"{"/":9,"~1":10}"#, &[Test { path: s("/~01"), value: content("10") }]).is_ok());
+        assert_eq!(apply(r#"{"/":9,"~1":10}"#, &[Test { path: s("/~01"), value: content(r#""10""#) }]).unwrap_err().kind, ErrorKind::TestFailed);
+        // A.16
+        assert_eq!(apply(r#"{"foo":["bar"]}"#, &[Add { path: s("/foo/-"), value: content(r#"["abc","def"]"#) }]).unwrap(), content(r#"{"foo":["bar",["abc","def"]]}"#));
+    }
+
+    #[test]
+    fn test_failed_patch_keeps_doc() {
+        let mut doc = content(r#"{"a":[1]}"#);
+        let patch = [Remove { path: s("/a/0") }, Remove { path: s("/a/0") }];
+        assert_eq!(apply_patch(&mut doc, &patch).unwrap_err().kind, ErrorKind::KeyNotFound);
+        assert_eq!(doc, content(r#"{"a":[1]}"#));
+        assert_eq!(apply_patch(&mut doc, &[Move { from: s("/a"), path: s("/a/0") }]).unwrap_err().kind, ErrorKind::InvalidPath);
+        assert_eq!(apply_patch(&mut doc, &[Remove { path: s("/a/01") }]).unwrap_err().kind, ErrorKind::KeyNotFound);
+    }
+
+    /// tiny linear congruential generator, so the test is deterministic
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self, n: u64) -> u64 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 33) % n
+        }
+
+        fn content(&mut self, depth: u32) -> Content {
+            let scalar = |r: &mut Random| match r.next(4) {
+                0 => Content::Simple((ValueType::Null, s("null"))),
+                1 => Content::Simple((ValueType::Bool, s(if r.next(2) == 0 { "true" } else { "false" }))),
+                2 => Content::Simple((ValueType::UInt, r.next(5).to_string())),
+                _ => Content::Simple((ValueType::Str, ["a", "b/c", "~"][r.next(3) as usize].to_string())),
+            };
+            match self.next(if depth == 0 { 1 } else { 3 }) {
+                0 => scalar(self),
+                1 => Content::Array((0..self.next(4)).map(|_| self.content(depth - 1)).collect()),
+                _ => {
+                    let mut m = BTreeMap::new();
+                    for _ in 0..self.next(4) {
+                        m.insert(["x", "y", "z/", "~w"][self.next(4) as usize].to_string(), self.content(depth - 1));
+                    }
+                    Content::Object(m)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut r = Random(7);
+        for _ in 0..500 {
+            let mut a = r.content(4);
+            let b = r.content(4);
+            let patch = diff_patch(&a, &b);
+            apply_patch(&mut a, &patch).unwrap();
+            assert_eq!(a, b);
+        }
+
+        let a = content(CORRECT_JSON);
+        assert!(diff_patch(&a, &a).is_empty());
+        let b = content(&CORRECT_JSON.replace("333", "334").replace(r#""key9" : { }"#, r#""key9" : [1]"#));
+        assert_eq!(
+            diff_patch(&a, &b),
+            [
+                Replace { path: s("/key7/key78/3"), value: content("334") },
+                Replace { path: s("/key7/key78/4"), value: content("334.334") },
+                Replace { path: s("/key9"), value: content("[1]") },
+            ]
+        );
+    }
+}
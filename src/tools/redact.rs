@@ -0,0 +1,180 @@
+use std::io::Write;
+
+use crate::{Error, NIL};
+use crate::parser_core::{finish, get_path, get_stats, Parser, set_keep_escapes, skip_value, start_capture, Stats, stop_capture, TextItem, walk_forward};
+use crate::path_matcher::PathMatcher;
+use crate::sha256::sha256_hex;
+use crate::tools::write_text_item;
+use crate::writer::JsonWriter;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Redaction {
+    /// drop the value. Inside an object, its key is dropped too
+    Remove,
+    ReplaceWithNull,
+    ReplaceWithString(String),
+    /// replace the value with sha-256 hex of its original text, e.g. "\"a@b.c\"" for a string
+    Hash,
+}
+
+/// Copy json from **reader** to **writer** in compact form, while the values selected by **rules** are redacted.
+/// Values (including whole objects and arrays) are checked against the rules in order and the first match wins.
+/// A matched value is skipped without being built, then its replacement is written. Other tokens are copied with their
+/// text as is (escapes and number forms are kept), but white-space of the input is not, so the output is compact.
+/// The result is the stats of the parser. Malformed json or data after the root value fails with the error of the parser.
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::path_matcher::PathMatcher;
+/// use json_walker::tools::{redact, Redaction};
+///
+/// let json = r#"{"users":[{"name":"a","email":"a@b.c"}]}"#;
+/// let rules = [(PathMatcher::new("/users/*/email").unwrap(), Redaction::ReplaceWithString("***".to_string()))];
+/// let mut out = Vec::new();
+/// redact(StringReader::new(json.to_string()), &mut out, &rules).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), r#"{"users":[{"name":"a","email":"***"}]}"#);
+/// ```
+pub fn redact<W: Write>(reader: Box<dyn Iterator<Item=u8>>, writer: W, rules: &[(PathMatcher, Redaction)]) -> Result<Stats, Error> {
+    let mut parser = Parser::new(reader, 0);
+    set_keep_escapes(&mut parser, true);
+    let mut w = JsonWriter::new(writer);
+    // a key is written when it is clear that its value is not removed
+    let mut key: Option<String> = None;
+    let mut before_value = parser.next_byte != NIL;
+    loop {
+        if before_value {
+            let path = get_path(&mut parser);
+            match rules.iter().find(|(m, _)| m.matches(&path)).map(|(_, r)| r) {
+                Some(Redaction::Remove) => {
+                    skip_value(&mut parser)?;
+                    key = None;
+                }
+                Some(r) => {
+                    if let Some(k) = key.take() {
+                        w.raw_key(&k)?;
+                    }
+                    write_replacement(&mut parser, r, &mut w)?;
+                }
+                None => {
+                    if let Some(k) = key.take() {
+                        w.raw_key(&k)?;
+                    }
+                }
+            }
+        }
+        if parser.next_byte == NIL {
+            break;
+        }
+        before_value = match walk_forward(&mut parser) {
+            TextItem::Key(k) => {
+                key = Some(k.1);
+                false
+            }
+            TextItem::None(b':') => true,
+            item => {
+                let array_element = matches!(item, TextItem::None(b'[') | TextItem::None(b','))
+                    && parser.stack.last().is_some_and(|s| s.symbol == '[')
+                    && parser.next_byte != b']';
+                write_text_item(item, &mut w)?;
+                array_element
+            }
+        };
+    }
+    // a malformed token stops the walk early, and trailing data must not be dropped silently
+    finish(&mut parser)?;
+    w.finish()?;
+    Ok(get_stats(&parser).clone())
+}
+
+/// skip the value in front of the cursor and write the replacement
+fn write_replacement<W: Write>(parser: &mut Parser, redaction: &Redaction, w: &mut JsonWriter<W>) -> Result<(), Error> {
+    match redaction {
+        Redaction::Hash => {
+            start_capture(parser);
+            let result = skip_value(parser);
+            let raw = stop_capture(parser);
+            result?;
+            w.value_str(&sha256_hex(&raw))
+        }
+        _ => {
+            skip_value(parser)?;
+            match redaction {
+                Redaction::ReplaceWithString(s) => w.value_str(s),
+                _ => w.value_null(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod redact_tests {
+    use crate::ErrorKind;
+    use crate::path_matcher::PathMatcher;
+    use crate::readers::StringReader;
+    use crate::sha256::sha256_hex;
+    use crate::tools::{redact, Redaction};
+
+    const JSON: &str = r#"{
+        "id": 7,
+        "users": [
+            {"name": "a", "email": "a@b.c", "card": {"no": "1234", "cvv": 1}},
+            {"name": "b", "email": "x\"y@b.c", "card": null}
+        ],
+        "email": "keep@b.c"
+    }"#;
+
+    fn redact_str(json: &str, rules: &[(&str, Redaction)]) -> serde_json::Value {
+        let rules: Vec<_> = rules.iter().map(|(p, r)| (PathMatcher::new(p).unwrap(), r.clone())).collect();
+        let mut out = Vec::new();
+        redact(StringReader::new(json.to_string()), &mut out, &rules).unwrap();
+        serde_json::from_slice(&out).unwrap()
+    }
+
+    #[test]
+    fn test_redact_two_pointers() {
+        let out = redact_str(JSON, &[("/users/*/email", Redaction::ReplaceWithString("***".to_string())), ("/users/*/card", Redaction::ReplaceWithNull)]);
+        let mut expected: serde_json::Value = serde_json::from_str(JSON).unwrap();
+        for u in expected["users"].as_array_mut().unwrap() {
+            u["email"] = "***".into();
+            u["card"] = serde_json::Value::Null;
+        }
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_redact_remove_and_hash() {
+        let out = redact_str(JSON, &[("/users/0", Redaction::Remove), ("/users/*/email", Redaction::Hash), ("/id", Redaction::Remove)]);
+        let expected = serde_json::json!({
+            "users": [{"name": "b", "email": sha256_hex(br#""x\"y@b.c""#), "card": null}],
+            "email": "keep@b.c"
+        });
+        assert_eq!(out, expected);
+
+        let out = redact_str(JSON, &[("/users/0/card", Redaction::Hash)]);
+        assert_eq!(out["users"][0]["card"], sha256_hex(br#"{"no": "1234", "cvv": 1}"#));
+        assert_eq!(out["users"][1]["card"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_redact_root_and_nothing() {
+        assert_eq!(redact_str(JSON, &[]), serde_json::from_str::<serde_json::Value>(JSON).unwrap());
+        assert_eq!(redact_str(JSON, &[("", Redaction::ReplaceWithNull)]), serde_json::Value::Null);
+        assert_eq!(redact_str("[1,[2,3],4]", &[("/1/*", Redaction::Remove), ("/2", Redaction::Hash)]), serde_json::json!([1, [], sha256_hex(b"4")]));
+    }
+
+    #[test]
+    fn test_redact_malformed_json() {
+        let cases = [
+            ("tru", ErrorKind::UnexpectedEos),
+            ("{\"a\":\"abc", ErrorKind::UnexpectedEos),
+            ("{\"a\":[1.]}", ErrorKind::InvalidNumber),
+            ("{\"a\":[1, x]}", ErrorKind::UnexpectedChar),
+            ("{\"a\":1} 2", ErrorKind::TrailingCharacters),
+        ];
+        let rules = [(PathMatcher::new("/a").unwrap(), Redaction::Hash)];
+        for (json, kind) in cases {
+            let err = redact(StringReader::new(json.to_string()), Vec::new(), &rules).unwrap_err();
+            assert_eq!(err.kind(), &kind, "{json}");
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::io::Write;
+
+use crate::{Error, NIL};
+use crate::parser_core::{finish, get_stats, Parser, set_keep_escapes, Stats, TextItem, ValueType, walk_forward};
+use crate::tools::write_text_item;
+use crate::writer::JsonWriter;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndentStyle {
+    Spaces,
+    Tabs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NewLine {
+    Lf,
+    CrLf,
+}
+
+/// Layout of reformat() output. The default is 2 spaces, "\n" and no compact array
+#[derive(Debug, Clone, PartialEq)]
+pub struct Indent {
+    pub style: IndentStyle,
+    /// count of spaces or tabs per nesting level
+    pub width: usize,
+    pub new_line: NewLine,
+    /// arrays of scalars with fewer elements than this number are written in one line. Zero disables it
+    pub compact_arrays: usize,
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Indent { style: IndentStyle::Spaces, width: 2, new_line: NewLine::Lf, compact_arrays: 0 }
+    }
+}
+
+impl Indent {
+    pub fn spaces(width: usize) -> Self {
+        Indent { width, ..Indent::default() }
+    }
+
+    pub fn tabs(width: usize) -> Self {
+        Indent { style: IndentStyle::Tabs, width, ..Indent::default() }
+    }
+
+    pub fn with_new_line(mut self, new_line: NewLine) -> Self {
+        self.new_line = new_line;
+        self
+    }
+
+    pub fn with_compact_arrays(mut self, max_len: usize) -> Self {
+        self.compact_arrays = max_len;
+        self
+    }
+
+    fn unit(&self) -> String {
+        let c = match self.style {
+            IndentStyle::Spaces => " ",
+            IndentStyle::Tabs => "\t",
+        };
+        c.repeat(self.width)
+    }
+
+    fn line_break(&self) -> &'static str {
+        match self.new_line {
+            NewLine::Lf => "\n",
+            NewLine::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Read json from **reader** and write it to **writer** with the layout of **indent**.
+/// Strings and numbers are copied byte by byte, so escape sequences are kept untouched.
+/// Memory usage does not depend on the size of json; with compact arrays at most **compact_arrays** scalars are held.
+/// The result is the stats of the parser. Malformed json or data after the root value fails with the error of the parser.
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::{Indent, reformat};
+///
+/// let mut out = Vec::new();
+/// let indent = Indent::spaces(4).with_compact_arrays(3);
+/// reformat(StringReader::new(r#"{"a":[1,2],"b":{}}"#.to_string()), &mut out, indent).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "{\n    \"a\": [1, 2],\n    \"b\": {}\n}");
+/// ```
+pub fn reformat<W: Write>(reader: Box<dyn Iterator<Item=u8>>, writer: W, indent: Indent) -> Result<Stats, Error> {
+    let mut parser = Parser::new(reader, 0);
+    set_keep_escapes(&mut parser, true);
+    let mut w = JsonWriter::new_pretty(writer, &indent.unit()).with_new_line(indent.line_break());
+    // scalars of the latest array, while it is still a candidate of compact form
+    let mut pending: Option<Vec<String>> = None;
+    while parser.next_byte != NIL {
+        let item = walk_forward(&mut parser);
+        if let Some(elements) = &mut pending {
+            match item {
+                TextItem::Value(v) if elements.len() + 1 < indent.compact_arrays => {
+                    elements.push(if v.0 == ValueType::Str { format!("\"{}\"", v.1) } else { v.1 });
+                    continue;
+                }
+                TextItem::None(b',') => continue,
+                TextItem::None(b']') => {
+                    w.raw(&format!("[{}]", elements.join(", ")))?;
+                    pending = None;
+                    continue;
+                }
+                _ => flush_expanded(pending.take().unwrap(), &mut w)?,
+            }
+        }
+        match item {
+            TextItem::None(b'[') if indent.compact_arrays > 0 => pending = Some(Vec::with_capacity(indent.compact_arrays)),
+            _ => write_text_item(item, &mut w)?,
+        }
+    }
+    // a malformed token stops the walk early, and trailing data must not be dropped silently
+    finish(&mut parser)?;
+    w.finish()?;
+    Ok(get_stats(&parser).clone())
+}
+
+/// the array turned out not to be compact, so the held elements are written normally
+fn flush_expanded<W: Write>(elements: Vec<String>, w: &mut JsonWriter<W>) -> Result<(), Error> {
+    w.begin_array()?;
+    for e in elements {
+        w.raw(&e)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod reformat_tests {
+    use crate::ErrorKind;
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::readers::StringReader;
+    use crate::tools::{Indent, minify, NewLine, reformat};
+
+    fn reformat_str(json: &str, indent: Indent) -> String {
+        let mut out = Vec::new();
+        reformat(StringReader::new(json.to_string()), &mut out, indent).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn minify_str(json: &str) -> String {
+        let mut out = Vec::new();
+        minify(StringReader::new(json.to_string()), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    // keys are sorted, because serde_json::Value sorts them
+    const SORTED_JSON: &str = r#"{"a":null,"b":[1,-2.5,"x \"y\"",[],{},[true,false,{"c":[[1],{"d":"e"}]}]],"f":{"g":{},"h":"  i"}}"#;
+
+    #[test]
+    fn test_reformat_like_serde() {
+        let expected = serde_json::to_string_pretty(&serde_json::from_str::<serde_json::Value>(SORTED_JSON).unwrap()).unwrap();
+        assert_eq!(reformat_str(SORTED_JSON, Indent::default()), expected);
+        assert_eq!(reformat_str(&expected, Indent::default()), expected);
+    }
+
+    #[test]
+    fn test_reformat_options() {
+        let out = reformat_str(SORTED_JSON, Indent::tabs(1).with_new_line(NewLine::CrLf).with_compact_arrays(3));
+        assert!(out.starts_with("{\r\n\t\"a\": null,\r\n\t\"b\": [\r\n\t\t1,\r\n"));
+        assert!(out.contains("\r\n\t\t\t\t\"c\": [\r\n\t\t\t\t\t[1],\r\n"));
+        assert!(out.contains("\t\t[],\r\n\t\t{},\r\n"));
+        assert_eq!(out.matches('\n').count(), out.matches("\r\n").count());
+        assert_eq!(minify_str(&out), SORTED_JSON);
+
+        let out = reformat_str("[[1,2],[1,2,3],[[]]]", Indent::spaces(1).with_compact_arrays(3));
+        assert_eq!(out, "[\n [1, 2],\n [\n  1,\n  2,\n  3\n ],\n [\n  []\n ]\n]");
+    }
+
+    #[test]
+    fn test_reformat_is_lossless_and_idempotent() {
+        for indent in [Indent::default(), Indent::tabs(2), Indent::spaces(3).with_compact_arrays(10)] {
+            let once = reformat_str(CORRECT_JSON, indent.clone());
+            assert_eq!(minify_str(&once), minify_str(CORRECT_JSON));
+            assert_eq!(reformat_str(&once, indent.clone()), once);
+            assert_eq!(reformat_str(&minify_str(&once), indent), once);
+        }
+    }
+
+    #[test]
+    fn test_reformat_malformed_json() {
+        let cases = [
+            ("tru", ErrorKind::UnexpectedEos),
+            ("{\"a\":\"abc", ErrorKind::UnexpectedEos),
+            ("{\"a\":[1.]}", ErrorKind::InvalidNumber),
+            ("{\"a\":[1, x]}", ErrorKind::UnexpectedChar),
+            ("{\"a\":1} 2", ErrorKind::TrailingCharacters),
+        ];
+        for (json, kind) in cases {
+            let err = reformat(StringReader::new(json.to_string()), Vec::new(), Indent::spaces(2).with_compact_arrays(3)).unwrap_err();
+            assert_eq!(err.kind(), &kind, "{json}");
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{Error, NIL};
+use crate::parser_core::{end_of_stream, is_poisoned, Parser, set_keep_escapes, walk_forward};
+use crate::tools::write_text_item;
+use crate::writer::JsonWriter;
+
+/// bytes around a divergence which are reported from each side
+const CONTEXT: usize = 16;
+
+/// What verify_roundtrip() accepts as a lossless copy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundTripPolicy {
+    /// the copy must be the same as the input, byte by byte. It suits minified input only
+    ByteExact,
+    /// white-spaces between tokens of the input are ignored, any other byte must be the same
+    IgnoreWhitespace,
+}
+
+/// The first place where the copy differs from the input. Contexts are up to 16 bytes before and after the offset of
+/// each side, so they show where both sides go apart
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub input_offset: u64,
+    pub output_offset: u64,
+    pub input_context: String,
+    pub output_context: String,
+}
+
+/// Copy json of **reader** through the pass-through pipeline of minify(), which keeps escapes and number lexemes, and
+/// compare the copy with the input under **policy**. None means the copy is lossless. Both the input and the copy are
+/// kept in memory till they are compared.
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::{RoundTripPolicy, verify_roundtrip};
+///
+/// let json = r#"{ "aA": [1.50, -0E+2] }"#;
+/// assert_eq!(verify_roundtrip(StringReader::new(json.to_string()), RoundTripPolicy::IgnoreWhitespace).unwrap(), None);
+///
+/// let d = verify_roundtrip(StringReader::new(json.to_string()), RoundTripPolicy::ByteExact).unwrap().unwrap();
+/// assert_eq!(d.input_offset, 1);
+/// ```
+pub fn verify_roundtrip(reader: Box<dyn Iterator<Item=u8>>, policy: RoundTripPolicy) -> Result<Option<Divergence>, Error> {
+    let input = Rc::new(RefCell::new(Vec::new()));
+    let tee = input.clone();
+    let mut parser = Parser::new(Box::new(reader.inspect(move |b| tee.borrow_mut().push(*b))), 0);
+    set_keep_escapes(&mut parser, true);
+    let mut w = JsonWriter::new(Vec::new());
+    while parser.next_byte != NIL {
+        write_text_item(walk_forward(&mut parser), &mut w)?;
+    }
+    if is_poisoned(&parser) {
+        return Err(end_of_stream(&parser));
+    }
+    let output = w.finish()?;
+    let input = input.borrow();
+    Ok(first_divergence(&input, &output, policy))
+}
+
+fn first_divergence(input: &[u8], output: &[u8], policy: RoundTripPolicy) -> Option<Divergence> {
+    let (mut i, mut j) = (0, 0);
+    let mut in_string = false;
+    let mut escaped = false;
+    loop {
+        if policy == RoundTripPolicy::IgnoreWhitespace && !in_string {
+            while input.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+                i += 1;
+            }
+        }
+        match (input.get(i), output.get(j)) {
+            (None, None) => return None,
+            (Some(&a), Some(&b)) if a == b => {
+                if in_string {
+                    in_string = escaped || a != b'"';
+                    escaped = !escaped && a == b'\\';
+                } else {
+                    in_string = a == b'"';
+                }
+                i += 1;
+                j += 1;
+            }
+            _ => return Some(Divergence {
+                input_offset: i as u64,
+                output_offset: j as u64,
+                input_context: context(input, i),
+                output_context: context(output, j),
+            }),
+        }
+    }
+}
+
+fn context(bytes: &[u8], offset: usize) -> String {
+    let start = offset.saturating_sub(CONTEXT);
+    let end = (offset + CONTEXT).min(bytes.len());
+    String::from_utf8_lossy(&bytes[start..end]).to_string()
+}
+
+#[cfg(test)]
+mod roundtrip_tests {
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::readers::StringReader;
+    use crate::tools::{minify, RoundTripPolicy, verify_roundtrip};
+    use crate::tools::roundtrip::{Divergence, first_divergence};
+
+    fn verify(json: &str, policy: RoundTripPolicy) -> Option<Divergence> {
+        verify_roundtrip(StringReader::new(json.to_string()), policy).unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip_correct_json() {
+        assert_eq!(verify(CORRECT_JSON, RoundTripPolicy::IgnoreWhitespace), None);
+        let d = verify(CORRECT_JSON, RoundTripPolicy::ByteExact).unwrap();
+        assert_eq!((d.input_offset, d.output_offset), (0, 0));
+
+        let json = " [ \"a  b\\n\\u0041\\\"\" , { \"k\\\\ \" : \"  \" } , -1.50e+3, 0.0, 1E-0 ] \n";
+        assert_eq!(verify(json, RoundTripPolicy::IgnoreWhitespace), None);
+    }
+
+    #[test]
+    fn test_roundtrip_minified() {
+        let mut minified = Vec::new();
+        minify(StringReader::new(CORRECT_JSON.to_string()), &mut minified).unwrap();
+        let minified = String::from_utf8(minified).unwrap();
+        assert_eq!(verify(&minified, RoundTripPolicy::ByteExact), None);
+        assert_eq!(verify(r#"{"a":["é\/",1.0e5,-0,true,null,{}]}"#, RoundTripPolicy::ByteExact), None);
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let d = first_divergence(br#"{"a": "x y", "b": 1}"#, br#"{"a":"xy","b":1}"#, RoundTripPolicy::IgnoreWhitespace).unwrap();
+        assert_eq!((d.input_offset, d.output_offset), (8, 7));
+        assert_eq!(d.input_context, r#"{"a": "x y", "b": 1}"#);
+        assert_eq!(d.output_context, r#"{"a":"xy","b":1}"#);
+
+        let input = format!(r#"{{"long": "{}", "n": 1.0}}"#, "z".repeat(40));
+        let output = format!(r#"{{"long":"{}","n":1}}"#, "z".repeat(40));
+        let d = first_divergence(input.as_bytes(), output.as_bytes(), RoundTripPolicy::IgnoreWhitespace).unwrap();
+        assert_eq!((d.input_offset, d.output_offset), (59, 56));
+        assert_eq!(d.input_context, r#"zzzzzzz", "n": 1.0}"#);
+        assert_eq!(d.output_context, r#"zzzzzzzzz","n":1}"#);
+
+        // the copy is shorter than the input
+        let d = first_divergence(b"[1]  ", b"[1]", RoundTripPolicy::ByteExact).unwrap();
+        assert_eq!((d.input_offset, d.output_offset, d.output_context.as_str()), (3, 3, "[1]"));
+        assert_eq!(first_divergence(b"[1]  ", b"[1]", RoundTripPolicy::IgnoreWhitespace), None);
+    }
+}
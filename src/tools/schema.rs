@@ -0,0 +1,343 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{Error, NIL};
+use crate::escape::unescape;
+use crate::parser_core::{end_of_stream, finish, Parser, set_keep_escapes, TextItem, ValueType, walk_forward};
+use crate::path_matcher::escape_token;
+use crate::writer::JsonWriter;
+
+/// count of distinct example values which are kept for each path
+const MAX_EXAMPLES: usize = 3;
+
+/// What is observed at one path pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaNode {
+    /// json pointer where array indices are replaced by "*", such as "/items/*/id". The root is ""
+    pub path: String,
+    /// value types with the count of each, in the order they are seen
+    pub types: Vec<(ValueType, usize)>,
+    /// range of numbers
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// length of the longest string in chars
+    pub max_len: Option<usize>,
+    /// the first few distinct scalars as json text
+    pub examples: Vec<String>,
+}
+
+impl SchemaNode {
+    fn new(path: String) -> Self {
+        SchemaNode { path, types: vec![], min: None, max: None, max_len: None, examples: vec![] }
+    }
+
+    /// how many times a value is seen at this path
+    pub fn count(&self) -> usize {
+        self.types.iter().map(|t| t.1).sum()
+    }
+
+    pub fn type_count(&self, value_type: ValueType) -> usize {
+        self.types.iter().find(|t| t.0 == value_type).map(|t| t.1).unwrap_or(0)
+    }
+
+    pub fn nullable(&self) -> bool {
+        self.type_count(ValueType::Null) > 0
+    }
+
+    /// more than one type apart from null
+    pub fn is_heterogeneous(&self) -> bool {
+        self.types.iter().filter(|t| t.0 != ValueType::Null).count() > 1
+    }
+
+    fn depth(&self) -> usize {
+        self.path.matches('/').count()
+    }
+
+    fn observe(&mut self, value_type: ValueType) {
+        match self.types.iter_mut().find(|t| t.0 == value_type) {
+            Some(t) => t.1 += 1,
+            None => self.types.push((value_type, 1)),
+        }
+    }
+
+    fn observe_scalar(&mut self, value_type: ValueType, raw: &str) -> Result<(), Error> {
+        self.observe(value_type);
+        let example = match value_type {
+            ValueType::UInt | ValueType::Int | ValueType::Float => {
+                if let Ok(n) = raw.parse::<f64>() {
+                    self.min = Some(self.min.map_or(n, |m| m.min(n)));
+                    self.max = Some(self.max.map_or(n, |m| m.max(n)));
+                }
+                raw.to_string()
+            }
+            ValueType::Str => {
+                let len = unescape(raw)?.chars().count();
+                self.max_len = Some(self.max_len.map_or(len, |m| m.max(len)));
+                format!("\"{raw}\"")
+            }
+            _ => raw.to_string(),
+        };
+        if self.examples.len() < MAX_EXAMPLES && !self.examples.contains(&example) {
+            self.examples.push(example);
+        }
+        Ok(())
+    }
+}
+
+/// Result of infer_schema(). Nodes are in tree order, so each node comes after its parent and before its parent's next
+/// sibling
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaReport {
+    pub nodes: Vec<SchemaNode>,
+}
+
+impl SchemaReport {
+    /// find the node of a path pattern such as "/items/*/id"
+    pub fn get(&self, path: &str) -> Option<&SchemaNode> {
+        self.nodes.iter().find(|n| n.path == path)
+    }
+
+    /// the report as json text: {"paths":[{"path":"","count":1,"types":{"object":1},"nullable":false, ...}]}
+    pub fn to_json(&self) -> Result<String, Error> {
+        let mut w = JsonWriter::new(Vec::new());
+        w.begin_object()?;
+        w.key("paths")?;
+        w.begin_array()?;
+        for n in &self.nodes {
+            w.begin_object()?;
+            w.key("path")?;
+            w.value_str(&n.path)?;
+            w.key("count")?;
+            w.raw(&n.count().to_string())?;
+            w.key("types")?;
+            w.begin_object()?;
+            for (t, c) in &n.types {
+                w.key(type_name(*t))?;
+                w.raw(&c.to_string())?;
+            }
+            w.end()?;
+            w.key("nullable")?;
+            w.raw(if n.nullable() { "true" } else { "false" })?;
+            if let (Some(min), Some(max)) = (n.min, n.max) {
+                w.key("min")?;
+                w.raw(&min.to_string())?;
+                w.key("max")?;
+                w.raw(&max.to_string())?;
+            }
+            if let Some(len) = n.max_len {
+                w.key("max_len")?;
+                w.raw(&len.to_string())?;
+            }
+            w.key("examples")?;
+            w.begin_array()?;
+            for e in &n.examples {
+                w.raw(e)?;
+            }
+            w.end()?;
+            w.end()?;
+        }
+        w.end()?;
+        w.end()?;
+        // the writer only produces valid utf-8
+        Ok(String::from_utf8(w.finish()?).unwrap())
+    }
+}
+
+/// text tree, one line per node such as `  key5: float(1) [111.111, 111.111] e.g. 111.111`
+impl Display for SchemaReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for n in &self.nodes {
+            let name = n.path.rsplit('/').next().filter(|_| !n.path.is_empty()).unwrap_or("$");
+            let types: Vec<String> = n.types.iter().map(|(t, c)| format!("{}({c})", type_name(*t))).collect();
+            write!(f, "{}{name}: {}", "  ".repeat(n.depth()), types.join(" | "))?;
+            if let (Some(min), Some(max)) = (n.min, n.max) {
+                write!(f, " [{min}, {max}]")?;
+            }
+            if let Some(len) = n.max_len {
+                write!(f, " len<={len}")?;
+            }
+            if !n.examples.is_empty() {
+                write!(f, " e.g. {}", n.examples.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn type_name(value_type: ValueType) -> &'static str {
+    match value_type {
+        ValueType::Null => "null",
+        ValueType::Bool => "bool",
+        ValueType::UInt => "uint",
+        ValueType::Int => "int",
+        ValueType::Float => "float",
+        ValueType::Str => "string",
+        ValueType::Arr => "array",
+        ValueType::Obj => "object",
+    }
+}
+
+/// Describe the json of **reader** in one streaming pass: for each path pattern (array indices are collapsed to "*") the
+/// types of the observed values with their counts, nullability, range of numbers, the longest string and a few examples.
+/// Only the report is kept in memory, so it suits feeds of any size. Keys which are literally "*" share their pattern
+/// with array elements.
+/// ### Example
+/// ```
+/// use json_walker::json_walker::{StringReader, ValueType};
+/// use json_walker::tools::infer_schema;
+///
+/// let report = infer_schema(StringReader::new(r#"[{"id": 1}, {"id": 7, "tag": null}, {"id": "x"}]"#.to_string())).unwrap();
+/// let id = report.get("/*/id").unwrap();
+/// assert_eq!(id.types, vec![(ValueType::UInt, 2), (ValueType::Str, 1)]);
+/// assert_eq!((id.min, id.max), (Some(1.0), Some(7.0)));
+/// assert!(report.get("/*/tag").unwrap().nullable());
+/// ```
+pub fn infer_schema(reader: Box<dyn Iterator<Item=u8>>) -> Result<SchemaReport, Error> {
+    let mut parser = Parser::new(reader, 0);
+    set_keep_escapes(&mut parser, true);
+
+    // nodes are collected as a tree with children indices, then flattened in tree order
+    let mut nodes = vec![(SchemaNode::new(String::new()), Vec::<usize>::new())];
+    // open containers: node index, whether it is an array and the latest key of objects
+    let mut stack: Vec<(usize, bool, String)> = vec![];
+    let mut seen_root = false;
+    loop {
+        if parser.next_byte == NIL {
+            if !stack.is_empty() {
+                return Err(end_of_stream(&parser));
+            }
+            break;
+        }
+        let item = walk_forward(&mut parser);
+        if let TextItem::Key(k) = &item {
+            if let Some(top) = stack.last_mut() {
+                top.2 = escape_token(&unescape(&k.1)?);
+            }
+            continue;
+        }
+        if let TextItem::None(b'}') | TextItem::None(b']') = item {
+            stack.pop();
+            continue;
+        }
+        let value_type = match &item {
+            TextItem::Value(v) => v.0,
+            TextItem::None(b'{') => ValueType::Obj,
+            TextItem::None(b'[') => ValueType::Arr,
+            _ => continue,
+        };
+
+        let index = match stack.last() {
+            None if seen_root => break,
+            None => 0,
+            Some((parent, is_array, key)) => {
+                let segment = if *is_array { "*" } else { key.as_str() };
+                let path = format!("{}/{segment}", nodes[*parent].0.path);
+                match nodes[*parent].1.iter().find(|c| nodes[**c].0.path == path) {
+                    Some(c) => *c,
+                    None => {
+                        let parent = *parent;
+                        nodes.push((SchemaNode::new(path), vec![]));
+                        let c = nodes.len() - 1;
+                        nodes[parent].1.push(c);
+                        c
+                    }
+                }
+            }
+        };
+        seen_root = true;
+        match item {
+            TextItem::Value(v) => nodes[index].0.observe_scalar(v.0, &v.1)?,
+            _ => {
+                nodes[index].0.observe(value_type);
+                stack.push((index, value_type == ValueType::Arr, String::new()));
+            }
+        }
+    }
+    // a malformed root scalar or trailing data stops the walk early
+    finish(&mut parser)?;
+
+    let mut order = vec![];
+    let mut pending = vec![0];
+    while let Some(i) = pending.pop() {
+        order.push(i);
+        pending.extend(nodes[i].1.iter().rev());
+    }
+    let mut nodes: Vec<Option<SchemaNode>> = nodes.into_iter().map(|n| Some(n.0)).collect();
+    Ok(SchemaReport { nodes: order.into_iter().filter_map(|i| nodes[i].take()).collect() })
+}
+
+#[cfg(test)]
+mod schema_tests {
+    use crate::ErrorKind;
+    use crate::json_walker::ValueType;
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::readers::StringReader;
+    use crate::tools::infer_schema;
+
+    #[test]
+    fn test_correct_json() {
+        let report = infer_schema(StringReader::new(CORRECT_JSON.to_string())).unwrap();
+        assert_eq!(report.nodes[0].path, "");
+
+        let key1 = report.get("/key1").unwrap();
+        assert!(key1.nullable());
+        assert_eq!(key1.count(), 1);
+
+        let key5 = report.get("/key5").unwrap();
+        assert_eq!(key5.types, vec![(ValueType::Float, 1)]);
+        assert_eq!((key5.min, key5.max), (Some(111.111), Some(111.111)));
+
+        let key78 = report.get("/key7/key78/*").unwrap();
+        assert!(key78.is_heterogeneous());
+        assert_eq!(key78.count(), 8);
+        assert_eq!(key78.types.len(), 7);
+        assert_eq!(key78.examples, vec!["null", "true", "false"]);
+
+        assert_eq!(report.get("/key6").unwrap().max_len, Some(12));
+        assert_eq!(report.get("/key8/*/key84").unwrap().examples, vec!["555"]);
+        assert!(report.get("/key9/x").is_none());
+
+        // parents always come before their children
+        for (i, n) in report.nodes.iter().enumerate().skip(1) {
+            let parent = &n.path[..n.path.rfind('/').unwrap()];
+            assert!(report.nodes[..i].iter().any(|p| p.path == parent), "{}", n.path);
+        }
+    }
+
+    #[test]
+    fn test_tree_order_and_rendering() {
+        let json = r#"{"a": [{"x": 1}, {"y": "s"}], "b": -2.5, "a/b": [[1, 2.5]]}"#;
+        let report = infer_schema(StringReader::new(json.to_string())).unwrap();
+        let paths: Vec<&str> = report.nodes.iter().map(|n| n.path.as_str()).collect();
+        assert_eq!(paths, vec!["", "/a", "/a/*", "/a/*/x", "/a/*/y", "/b", "/a~1b", "/a~1b/*", "/a~1b/*/*"]);
+        assert_eq!(report.to_string(), "$: object(1)
+  a: array(1)
+    *: object(2)
+      x: uint(1) [1, 1] e.g. 1
+      y: string(1) len<=1 e.g. \"s\"
+  b: float(1) [-2.5, -2.5] e.g. -2.5
+  a~1b: array(1)
+    *: array(1)
+      *: uint(1) | float(1) [1, 2.5] e.g. 1, 2.5
+");
+
+        let v: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(v["paths"][3]["path"], "/a/*/x");
+        assert_eq!(v["paths"][8]["types"]["float"], 1);
+        assert_eq!(v["paths"][8]["max"], 2.5);
+        assert_eq!(v["paths"][4]["examples"][0], "s");
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        for (json, kind) in [
+            ("tru", ErrorKind::UnexpectedEos),
+            ("1.", ErrorKind::InvalidNumber),
+            ("\"abc", ErrorKind::UnexpectedEos),
+            ("{\"a\":[1, x]}", ErrorKind::UnexpectedChar),
+            ("{\"a\":1} 2", ErrorKind::TrailingCharacters),
+        ] {
+            assert_eq!(infer_schema(StringReader::new(json.to_string())).unwrap_err().kind(), &kind, "{json}");
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::io;
+use std::io::Write;
+
+use crate::Error;
+use crate::parser_core::{copy_value, Parser, walk_forward};
+
+/// Split the root array of **reader** into arrays of at most **chunk_size** elements. **sink_factory** is called with the
+/// index of each chunk (starting from zero) and the chunk is written into the returned writer.
+/// Elements are copied byte by byte, so not even a whole element is kept in memory.
+/// The result is the element count of each chunk. If the root is not an array, WrongDataType error is returned
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::split_array;
+///
+/// let mut chunks = [Vec::new(), Vec::new()];
+/// let mut sinks = chunks.iter_mut();
+/// let counts = split_array(StringReader::new("[1, 2, {\"a\": 3}]".to_string()), 2, |_| Ok(sinks.next().unwrap())).unwrap();
+/// assert_eq!(counts, [2, 1]);
+/// assert_eq!(chunks, [b"[1,2]".to_vec(), b"[{\"a\": 3}]".to_vec()]);
+/// ```
+pub fn split_array<W: Write>(
+    reader: Box<dyn Iterator<Item=u8>>,
+    chunk_size: usize,
+    mut sink_factory: impl FnMut(usize) -> io::Result<W>,
+) -> Result<Vec<usize>, Error> {
+    if chunk_size == 0 {
+        return Err(Error::wrong_data_type("chunk_size must be greater than zero"));
+    }
+    let mut parser = Parser::new(reader, 0);
+    if parser.next_byte != b'[' {
+        return Err(Error::wrong_data_type("The root is not an array"));
+    }
+    walk_forward(&mut parser);
+
+    let mut counts = vec![];
+    let mut sink: Option<W> = None;
+    while parser.next_byte != b']' {
+        if parser.next_byte == b',' {
+            walk_forward(&mut parser);
+        }
+        let w = match &mut sink {
+            Some(w) => {
+                w.write_all(b",")?;
+                w
+            }
+            None => {
+                let mut w = sink_factory(counts.len())?;
+                w.write_all(b"[")?;
+                counts.push(0);
+                sink.insert(w)
+            }
+        };
+        copy_value(&mut parser, w)?;
+        let n = counts.last_mut().unwrap();
+        *n += 1;
+        if *n == chunk_size {
+            close_chunk(sink.take().unwrap())?;
+        }
+    }
+    if let Some(w) = sink {
+        close_chunk(w)?;
+    }
+    walk_forward(&mut parser);
+    Ok(counts)
+}
+
+fn close_chunk<W: Write>(mut w: W) -> Result<(), Error> {
+    w.write_all(b"]")?;
+    w.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod split_array_tests {
+    use crate::ErrorKind;
+    use crate::readers::StringReader;
+    use crate::tools::split_array;
+
+    fn split(json: &str, chunk_size: usize) -> (Vec<usize>, Vec<String>) {
+        let mut chunks = vec![Vec::new(); 10];
+        let mut sinks = chunks.iter_mut();
+        let counts = split_array(StringReader::new(json.to_string()), chunk_size, |_| Ok(sinks.next().unwrap())).unwrap();
+        let chunks = chunks.into_iter().take(counts.len()).map(|c| String::from_utf8(c).unwrap()).collect();
+        (counts, chunks)
+    }
+
+    #[test]
+    fn test_split_ten_elements_by_three() {
+        let json = r#" [ 0, "1 ]", {"a": [2, {"b": "}"}]}, [3, [[]]], null, 5.5, true, {}, "8\"]", [ 9 ] ] "#;
+        let expected: serde_json::Value = serde_json::from_str(json).unwrap();
+        let (counts, chunks) = split(json, 3);
+        assert_eq!(counts, [3, 3, 3, 1]);
+        assert_eq!(chunks[1], r#"[[3, [[]]],null,5.5]"#);
+        let mut elements = vec![];
+        for c in chunks {
+            let v: serde_json::Value = serde_json::from_str(&c).unwrap();
+            elements.extend(v.as_array().unwrap().iter().cloned());
+        }
+        assert_eq!(&elements, expected.as_array().unwrap());
+    }
+
+    #[test]
+    fn test_split_edge_cases() {
+        assert_eq!(split("[]", 3), (vec![], vec![]));
+        assert_eq!(split("[1,2,3]", 3), (vec![3], vec!["[1,2,3]".to_string()]));
+        let err = split_array(StringReader::new(r#"{"a":[1]}"#.to_string()), 3, |_| Ok(Vec::new())).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::WrongDataType);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::Error;
+use crate::parser_core::{TextItem, ValueType};
+use crate::path_matcher::{escape_token, PathMatcher, split_pointer};
+use crate::tools::schema::type_name;
+
+/// A rule of StreamValidator. Each one applies to the nodes selected by its pointer, in which "*" matches any key or index
+#[derive(Debug, Clone, PartialEq)]
+pub enum Check {
+    /// the member must exist in each object which holds it, e.g. "/*/address/city" is checked in every "/*/address"
+    Required,
+    /// the value must have one of these types
+    Types(Vec<ValueType>),
+    /// the value, if it is an array, must only have elements of these types
+    ElementTypes(Vec<ValueType>),
+    /// the number must be in this inclusive range. Values which are not numbers are left to Types
+    Range(Option<f64>, Option<f64>),
+    /// the count of chars of the string must not exceed it
+    MaxLen(usize),
+}
+
+/// A broken rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// json pointer of the node with real indices, such as "/1/address/city"
+    pub path: String,
+    pub rule: Check,
+    /// what is found instead: "missing", the type name, the number or the length of the string
+    pub found: String,
+}
+
+struct Rule {
+    matcher: PathMatcher,
+    check: Check,
+    /// the member name of Required rules, then matcher selects its parent
+    key: String,
+}
+
+/// open container: the required rules which apply to it with whether their member is seen, and the element rules which
+/// apply to it
+struct Frame {
+    required: Vec<(usize, bool)>,
+    elements: Vec<usize>,
+}
+
+/// Validate json against a small schema while it is walked, so bad records can be rejected before they are deserialized.
+/// Feed it with JsonWalker::walk_with(), alone or along with your own processing in the same pass. All violations are
+/// collected, not just the first one
+/// ### Example
+/// ```
+/// use json_walker::json_walker::{JsonWalker, StringReader, ValueType};
+/// use json_walker::tools::{Check, StreamValidator};
+///
+/// let mut validator = StreamValidator::new(&[
+///     ("/*/id", Check::Required),
+///     ("/*/id", Check::Types(vec![ValueType::UInt])),
+///     ("/*/score", Check::Range(Some(0.0), Some(10.0))),
+/// ]).unwrap();
+/// let mut walker = JsonWalker::new(StringReader::new(r#"[{"id": 1, "score": 12}, {"score": 3}]"#.to_string()), 0);
+/// walker.walk_with(&mut |pointer, item| validator.observe(pointer, item)).unwrap();
+/// let violations = validator.finish();
+/// assert_eq!(violations.iter().map(|v| v.path.as_str()).collect::<Vec<_>>(), vec!["/0/score", "/1/id"]);
+/// ```
+pub struct StreamValidator {
+    rules: Vec<Rule>,
+    frames: Vec<Frame>,
+    violations: Vec<Violation>,
+}
+
+impl StreamValidator {
+    /// **schema** is a list of json pointers with the rule which applies to them
+    pub fn new(schema: &[(&str, Check)]) -> Result<Self, Error> {
+        let mut rules = Vec::with_capacity(schema.len());
+        for (pointer, check) in schema {
+            let rule = if *check == Check::Required {
+                let mut tokens = split_pointer(pointer)?;
+                let key = match tokens.pop() {
+                    Some(key) => key,
+                    None => return Err(Error::invalid_path("The root can not be required")),
+                };
+                let parent: String = tokens.iter().map(|t| format!("/{}", escape_token(t))).collect();
+                Rule { matcher: PathMatcher::new(&parent)?, check: check.clone(), key }
+            } else {
+                Rule { matcher: PathMatcher::new(pointer)?, check: check.clone(), key: String::new() }
+            };
+            rules.push(rule);
+        }
+        Ok(StreamValidator { rules, frames: vec![], violations: vec![] })
+    }
+
+    /// check an item of JsonWalker::walk_with(). It never fails, so it can be passed to walk_with() as it is
+    pub fn observe(&mut self, pointer: &str, item: &TextItem) -> Result<(), Error> {
+        let value_type = match item {
+            TextItem::Key(k) => {
+                if let Some(frame) = self.frames.last_mut() {
+                    for (i, seen) in frame.required.iter_mut() {
+                        if self.rules[*i].key == k.1 {
+                            *seen = true;
+                        }
+                    }
+                }
+                return Ok(());
+            }
+            TextItem::None(b'}') | TextItem::None(b']') => {
+                if let Some(frame) = self.frames.pop() {
+                    for (i, seen) in frame.required {
+                        if !seen {
+                            let path = format!("{pointer}/{}", escape_token(&self.rules[i].key));
+                            self.violate(path, i, "missing".to_string());
+                        }
+                    }
+                }
+                return Ok(());
+            }
+            TextItem::Value(v) => v.0,
+            TextItem::None(b'{') => ValueType::Obj,
+            TextItem::None(b'[') => ValueType::Arr,
+            TextItem::None(_) => return Ok(()),
+        };
+
+        if let Some(frame) = self.frames.last() {
+            for i in frame.elements.clone() {
+                if let Check::ElementTypes(types) = &self.rules[i].check {
+                    if !types.contains(&value_type) {
+                        self.violate(pointer.to_string(), i, type_name(value_type).to_string());
+                    }
+                }
+            }
+        }
+
+        for i in 0..self.rules.len() {
+            let rule = &self.rules[i];
+            if rule.check == Check::Required || !rule.matcher.matches_pointer(pointer) {
+                continue;
+            }
+            let found = match (&rule.check, item) {
+                (Check::Types(types), _) if !types.contains(&value_type) => type_name(value_type).to_string(),
+                (Check::Range(min, max), TextItem::Value(v)) if matches!(value_type, ValueType::UInt | ValueType::Int | ValueType::Float) => {
+                    match v.1.parse::<f64>() {
+                        Ok(n) if min.is_some_and(|m| n < m) || max.is_some_and(|m| n > m) => v.1.clone(),
+                        _ => continue,
+                    }
+                }
+                (Check::MaxLen(max), TextItem::Value(v)) if value_type == ValueType::Str && v.1.chars().count() > *max => {
+                    v.1.chars().count().to_string()
+                }
+                _ => continue,
+            };
+            self.violate(pointer.to_string(), i, found);
+        }
+
+        if value_type == ValueType::Obj || value_type == ValueType::Arr {
+            let required = self.rules.iter().enumerate()
+                .filter(|(_, r)| value_type == ValueType::Obj && r.check == Check::Required && r.matcher.matches_pointer(pointer))
+                .map(|(i, _)| (i, false))
+                .collect();
+            let elements = self.rules.iter().enumerate()
+                .filter(|(_, r)| value_type == ValueType::Arr && matches!(r.check, Check::ElementTypes(_)) && r.matcher.matches_pointer(pointer))
+                .map(|(i, _)| i)
+                .collect();
+            self.frames.push(Frame { required, elements });
+        }
+        Ok(())
+    }
+
+    /// violations found so far, in the order they are found
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// return all violations. Objects which are not closed yet are not checked for their required members
+    pub fn finish(self) -> Vec<Violation> {
+        self.violations
+    }
+
+    fn violate(&mut self, path: String, rule: usize, found: String) {
+        self.violations.push(Violation { path, rule: self.rules[rule].check.clone(), found });
+    }
+}
+
+#[cfg(test)]
+mod stream_validator_tests {
+    use crate::ErrorKind;
+    use crate::json_walker::{JsonWalker, ValueType};
+    use crate::readers::StringReader;
+    use crate::tools::{Check, StreamValidator};
+
+    fn validate(json: &str, validator: &mut StreamValidator) {
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        walker.walk_with(&mut |pointer, item| validator.observe(pointer, item)).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "deserialize")]
+    fn test_person() {
+        use crate::json_walker::walker_test_de::data2;
+
+        let mut people = serde_json::to_value(data2::create_data()).unwrap();
+        people[0]["age"] = serde_json::Value::from("thirty");
+        people[1]["address"].as_object_mut().unwrap().remove("city");
+        people[1]["weight"] = serde_json::Value::from(-500.5);
+        let json = people.to_string();
+
+        let mut validator = StreamValidator::new(&[
+            ("/*/name", Check::Required),
+            ("/*/name", Check::MaxLen(20)),
+            ("/*/age", Check::Types(vec![ValueType::UInt, ValueType::Int])),
+            ("/*/address/city", Check::Required),
+            ("/*/weight", Check::Range(Some(-200.0), Some(200.0))),
+            ("/*/hobbies", Check::ElementTypes(vec![ValueType::Str])),
+            ("/*/friends", Check::Types(vec![ValueType::Arr, ValueType::Null])),
+        ]).unwrap();
+
+        // the caller's own processing shares the same pass
+        let mut names = vec![];
+        let mut walker = JsonWalker::new(StringReader::new(json.clone()), 0);
+        walker.walk_with(&mut |pointer, item| {
+            validator.observe(pointer, item)?;
+            if let (crate::json_walker::TextItem::Value(v), true) = (item, pointer.ends_with("/name")) {
+                names.push(v.1.clone());
+            }
+            Ok(())
+        }).unwrap();
+        // serde_json::Value sorts the keys, so "friends" comes before "name"
+        assert_eq!(names, vec!["Alice", "Bob", "John Doe", "Arash"]);
+
+        let violations = validator.finish();
+        let found: Vec<(&str, &str)> = violations.iter().map(|v| (v.path.as_str(), v.found.as_str())).collect();
+        assert_eq!(found, vec![("/0/age", "string"), ("/1/address/city", "missing"), ("/1/weight", "-500.5")]);
+        assert_eq!(violations[0].rule, Check::Types(vec![ValueType::UInt, ValueType::Int]));
+        assert_eq!(violations[1].rule, Check::Required);
+
+        // the original data is valid
+        let mut validator = StreamValidator::new(&[("/*/address/city", Check::Required), ("/*/age", Check::Types(vec![ValueType::UInt, ValueType::Int]))]).unwrap();
+        validate(&serde_json::to_string(&data2::create_data()).unwrap(), &mut validator);
+        assert!(validator.violations().is_empty());
+    }
+
+    #[test]
+    fn test_checks() {
+        let mut validator = StreamValidator::new(&[
+            ("/tags", Check::ElementTypes(vec![ValueType::Str])),
+            ("/tags/*", Check::MaxLen(3)),
+            ("/a~1b", Check::Required),
+            ("/n", Check::Range(None, Some(1.0))),
+        ]).unwrap();
+        validate(r#"{"tags": ["ab", 1, "abcd", {"x": 1}], "n": "big", "a~1b": 0}"#, &mut validator);
+        let found: Vec<(&str, &str)> = validator.violations().iter().map(|v| (v.path.as_str(), v.found.as_str())).collect();
+        assert_eq!(found, vec![("/tags/1", "uint"), ("/tags/2", "4"), ("/tags/3", "object"), ("/a~1b", "missing")]);
+
+        assert_eq!(StreamValidator::new(&[("", Check::Required)]).err().unwrap().kind, ErrorKind::InvalidPath);
+        assert_eq!(StreamValidator::new(&[("x", Check::MaxLen(1))]).err().unwrap().kind, ErrorKind::InvalidPath);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{Error, NIL};
+use crate::escape::unescape;
+use crate::parser_core::{end_of_stream, finish, get_keep_escapes, is_poisoned, Parser, set_keep_escapes, TextItem, ValueType, walk_forward};
+use crate::sha256::Sha256;
+use crate::tools::canonical::exact_number;
+
+/// Options of structural_hash() and JsonWalker::hash_current_value()
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HashOptions {
+    /// when it is set, objects with the same members in a different order get the same digest
+    pub ignore_key_order: bool,
+}
+
+impl HashOptions {
+    pub fn with_ignore_key_order(mut self, ignore_key_order: bool) -> Self {
+        self.ignore_key_order = ignore_key_order;
+        self
+    }
+}
+
+/// Sha-256 digest of the structure of the json of **reader**, computed in a single pass without building anything.
+/// White-spaces, escape forms of strings and number forms (1.0 vs 1) do not matter. Numbers are compared by their
+/// digits rather than through f64, so big integers which round to the same f64 get different digests, key order matters unless
+/// **options** says otherwise. Data after the root value fails with TrailingCharacters error.
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::{HashOptions, structural_hash};
+///
+/// let a = structural_hash(StringReader::new(r#"{"a": [1.0, "A"], "b": null}"#.to_string()), &HashOptions::default()).unwrap();
+/// let b = structural_hash(StringReader::new(r#"{"a":[1,"A"],"b":null}"#.to_string()), &HashOptions::default()).unwrap();
+/// assert_eq!(a, b);
+/// ```
+pub fn structural_hash(reader: Box<dyn Iterator<Item=u8>>, options: &HashOptions) -> Result<[u8; 32], Error> {
+    let mut parser = Parser::new(reader, 0);
+    let digest = hash_value(&mut parser, options)?;
+    finish(&mut parser)?;
+    Ok(digest)
+}
+
+enum Frame {
+    Hasher(Sha256),
+    /// digests of the members of an object, which are sorted when the object closes
+    Members(Vec<[u8; 32]>),
+}
+
+/// hash the value in front of the cursor, then the cursor stands right after it.
+/// Each event is encoded by a tag byte: n, t, f, d (number) and s (string) for scalars, k for keys and brackets for
+/// containers. Strings and keys are prefixed by their length, so no two documents share an encoding.
+/// When key order is ignored, each member is hashed on its own and the sorted digests stand for the object
+pub(crate) fn hash_value<R: Iterator<Item=u8>>(parser: &mut Parser<'_, R>, options: &HashOptions) -> Result<[u8; 32], Error> {
+    let keep_escapes = get_keep_escapes(parser);
+    set_keep_escapes(parser, true);
+    let result = hash_items(parser, options);
+    set_keep_escapes(parser, keep_escapes);
+    result
+}
+
+fn hash_items<R: Iterator<Item=u8>>(parser: &mut Parser<'_, R>, options: &HashOptions) -> Result<[u8; 32], Error> {
+    let mut frames = vec![Frame::Hasher(Sha256::new())];
+    let mut depth = 0;
+    loop {
+        if parser.next_byte == NIL {
+            return Err(end_of_stream(parser));
+        }
+        match walk_forward(parser) {
+            TextItem::Key(k) => {
+                if options.ignore_key_order {
+                    frames.push(Frame::Hasher(Sha256::new()));
+                }
+                emit_str(&mut frames, b'k', &unescape(&k.1)?);
+                continue;
+            }
+            TextItem::Value((ValueType::Null, _)) => emit(&mut frames, b"n"),
+            TextItem::Value((ValueType::Bool, b)) => emit(&mut frames, if b == "true" { b"t" } else { b"f" }),
+            TextItem::Value((ValueType::Str, s)) => emit_str(&mut frames, b's', &unescape(&s)?),
+            TextItem::Value((_, n)) => {
+                emit(&mut frames, b"d");
+                emit(&mut frames, exact_number(&n)?.as_bytes());
+                emit(&mut frames, b";");
+            }
+            TextItem::None(b'{') => {
+                depth += 1;
+                emit(&mut frames, b"{");
+                if options.ignore_key_order {
+                    frames.push(Frame::Members(vec![]));
+                }
+                continue;
+            }
+            TextItem::None(b'[') => {
+                depth += 1;
+                emit(&mut frames, b"[");
+                continue;
+            }
+            TextItem::None(b'}') => {
+                depth -= 1;
+                if options.ignore_key_order {
+                    if let Some(Frame::Members(mut members)) = frames.pop() {
+                        members.sort();
+                        members.iter().for_each(|m| emit(&mut frames, m));
+                    }
+                }
+                emit(&mut frames, b"}");
+            }
+            TextItem::None(b']') => {
+                depth -= 1;
+                emit(&mut frames, b"]");
+            }
+            TextItem::None(_) => continue,
+        }
+
+        // a value is complete. If it is the value of a member, the member is complete too
+        if frames.len() > 1 && matches!(frames[frames.len() - 2], Frame::Members(_)) {
+            if let Some(Frame::Hasher(h)) = frames.pop() {
+                if let Some(Frame::Members(members)) = frames.last_mut() {
+                    members.push(h.finish());
+                }
+            }
+        }
+        if depth == 0 {
+            break;
+        }
+    }
+    // a malformed scalar is returned before the walk stops
+    if is_poisoned(parser) {
+        return Err(end_of_stream(parser));
+    }
+    match frames.pop() {
+        Some(Frame::Hasher(h)) => Ok(h.finish()),
+        _ => Err(Error::new_eos()),
+    }
+}
+
+fn emit(frames: &mut [Frame], bytes: &[u8]) {
+    if let Some(Frame::Hasher(h)) = frames.iter_mut().rev().find(|f| matches!(f, Frame::Hasher(_))) {
+        h.update(bytes);
+    }
+}
+
+fn emit_str(frames: &mut [Frame], tag: u8, s: &str) {
+    emit(frames, &[tag]);
+    emit(frames, &(s.len() as u64).to_be_bytes());
+    emit(frames, s.as_bytes());
+}
+
+#[cfg(test)]
+mod structural_hash_tests {
+    use crate::ErrorKind;
+    use crate::json_walker::{JsonWalker, ValueType};
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::readers::StringReader;
+    use crate::tools::{HashOptions, Indent, minify, reformat, structural_hash};
+
+    fn hash(json: &str, options: &HashOptions) -> [u8; 32] {
+        structural_hash(StringReader::new(json.to_string()), options).unwrap()
+    }
+
+    #[test]
+    fn test_formatting_does_not_matter() {
+        let mut pretty = Vec::new();
+        reformat(StringReader::new(CORRECT_JSON.to_string()), &mut pretty, Indent::spaces(4)).unwrap();
+        let mut compact = Vec::new();
+        minify(StringReader::new(CORRECT_JSON.to_string()), &mut compact).unwrap();
+        let options = HashOptions::default();
+        let h = hash(CORRECT_JSON, &options);
+        assert_eq!(h, hash(&String::from_utf8(pretty).unwrap(), &options));
+        assert_eq!(h, hash(&String::from_utf8(compact).unwrap(), &options));
+
+        // one nested value differs
+        assert_ne!(h, hash(&CORRECT_JSON.replace("555.555", "555.556"), &options));
+        assert_ne!(h, hash(&CORRECT_JSON.replace("\"key84\"", "\"key85\""), &options));
+        // type and structure matter, not only the text
+        assert_ne!(hash(r#"["1"]"#, &options), hash("[1]", &options));
+        assert_ne!(hash(r#"[["a"],[]]"#, &options), hash(r#"[["a",[]]]"#, &options));
+        assert_ne!(hash(r#"{"a":"b"}"#, &options), hash(r#"["a","b"]"#, &options));
+    }
+
+    #[test]
+    fn test_big_integers() {
+        let options = HashOptions::default();
+        // they are the same f64
+        assert_ne!(hash("[9007199254740993]", &options), hash("[9007199254740992]", &options));
+        assert_ne!(hash("[18446744073709551617]", &options), hash("[18446744073709551616]", &options));
+        assert_ne!(hash("[-9223372036854775809]", &options), hash("[-9223372036854775808]", &options));
+        // while number forms of one value still do not matter
+        assert_eq!(hash("[9007199254740993, 100]", &options), hash("[9007199254740993.0, 1e2]", &options));
+    }
+
+    #[test]
+    fn test_key_order() {
+        let a = r#"{"x": 1, "y": {"p": [1, {"m": null, "n": true}], "q": "s"}}"#;
+        let b = r#"{"y": {"q": "s", "p": [1, {"n": true, "m": null}]}, "x": 1.0}"#;
+        let sensitive = HashOptions::default();
+        let insensitive = HashOptions::default().with_ignore_key_order(true);
+        assert_ne!(hash(a, &sensitive), hash(b, &sensitive));
+        assert_eq!(hash(a, &insensitive), hash(b, &insensitive));
+        // order of array elements still matters
+        assert_ne!(hash("[1,2]", &insensitive), hash("[2,1]", &insensitive));
+        // keys are bound to their values
+        assert_ne!(hash(r#"{"a":1,"b":2}"#, &insensitive), hash(r#"{"a":2,"b":1}"#, &insensitive));
+    }
+
+    #[test]
+    fn test_hash_current_value() {
+        let options = HashOptions::default().with_ignore_key_order(true);
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.next_key_by_name("key7").unwrap();
+        let h = walker.hash_current_value(&options).unwrap();
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "key8".to_string())));
+
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        let mut source = Vec::new();
+        crate::tools::extract_to(StringReader::new(CORRECT_JSON.to_string()), "/key7", &mut source).unwrap();
+        assert_eq!(h, hash(&String::from_utf8(source).unwrap(), &options));
+
+        // escapes are decoded even though the walker drops them
+        walker.next_key_by_name("key6").unwrap();
+        assert_eq!(walker.hash_current_value(&options).unwrap(), hash(r#""str1 \":{}[],""#, &options));
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "key7".to_string())));
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        let err = |json: &str| structural_hash(StringReader::new(json.to_string()), &HashOptions::default()).unwrap_err();
+        assert_eq!(err(r#"{"a":1}xyz"#).kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(err("[1] [2]").kind(), &ErrorKind::TrailingCharacters);
+        assert_eq!(err("tru").kind(), &ErrorKind::UnexpectedEos);
+        assert_eq!(err("1.").kind(), &ErrorKind::InvalidNumber);
+        assert_eq!(err(r#"{"a":[1, x]}"#).kind(), &ErrorKind::UnexpectedChar);
+        assert!(structural_hash(StringReader::new(" [1] \n".to_string()), &HashOptions::default()).is_ok());
+    }
+}
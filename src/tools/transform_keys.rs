@@ -0,0 +1,106 @@
+use std::io::Write;
+
+use crate::{Error, NIL};
+use crate::parser_core::{finish, get_path, get_stats, Parser, Path, set_keep_escapes, Stats, TextItem, walk_forward};
+use crate::tools::write_text_item;
+use crate::writer::JsonWriter;
+
+/// Copy json from **reader** to **writer** in compact form, while object keys are renamed by **mapper**.
+/// The mapper receives the path of the key (the last item is the key itself) and the key as it is written in json, escapes untouched.
+/// If it returns None, the original key is kept. Values are copied byte by byte.
+/// Only one document is processed, so for NDJSON call it per line.
+/// The result is the stats of the parser. Malformed json or data after the root value fails with the error of the parser.
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::transform_keys;
+///
+/// let mut out = Vec::new();
+/// transform_keys(StringReader::new(r#"{"userId":1,"tags":[{"userId":2}]}"#.to_string()), &mut out, |path, key| {
+///     // only the top level key
+///     (path.len() == 2 && key == "userId").then(|| "user_id".to_string())
+/// }).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), r#"{"user_id":1,"tags":[{"userId":2}]}"#);
+/// ```
+pub fn transform_keys<W: Write>(reader: Box<dyn Iterator<Item=u8>>, writer: W, mapper: impl Fn(&Path, &str) -> Option<String>) -> Result<Stats, Error> {
+    let mut parser = Parser::new(reader, 0);
+    set_keep_escapes(&mut parser, true);
+    let mut w = JsonWriter::new(writer);
+    while parser.next_byte != NIL {
+        match walk_forward(&mut parser) {
+            TextItem::Key(k) => match mapper(&get_path(&mut parser), &k.1) {
+                Some(name) => w.key(&name)?,
+                None => w.raw_key(&k.1)?,
+            },
+            item => write_text_item(item, &mut w)?,
+        }
+    }
+    // a malformed token stops the walk early, and trailing data must not be dropped silently
+    finish(&mut parser)?;
+    w.finish()?;
+    Ok(get_stats(&parser).clone())
+}
+
+#[cfg(test)]
+mod transform_keys_tests {
+    use crate::ErrorKind;
+    use crate::json_walker::{PathItem, StringReader};
+    use crate::tools::transform_keys;
+
+    const JSON: &str = r#"{
+        "userId": 1,
+        "profile": {"userId": "x", "firstName": "a \"b\"", "tags": [{"firstName": null}]},
+        "items": [{"userId": 2.5, "list": [1, {"a": []}]}]
+    }"#;
+
+    fn transform(mapper: impl Fn(&[PathItem], &str) -> Option<String>) -> serde_json::Value {
+        let mut out = Vec::new();
+        transform_keys(StringReader::new(JSON.to_string()), &mut out, mapper).unwrap();
+        serde_json::from_slice(&out).unwrap()
+    }
+
+    #[test]
+    fn test_transform_keys_at_two_depths() {
+        let out = transform(|path, key| match (path.len(), key) {
+            (2, "userId") => Some("user_id".to_string()),
+            (3, "firstName") => Some("first name/\"".to_string()),
+            _ => None,
+        });
+        let expected = serde_json::json!({
+            "user_id": 1,
+            "profile": {"userId": "x", "first name/\"": "a \"b\"", "tags": [{"firstName": null}]},
+            "items": [{"userId": 2.5, "list": [1, {"a": []}]}]
+        });
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_transform_keys_scoped_by_path() {
+        let out = transform(|path, key| match path.get(1) {
+            Some(PathItem::Object(k, _)) if k.as_str() == "items" && key == "userId" => Some("owner".to_string()),
+            _ => None,
+        });
+        let mut expected: serde_json::Value = serde_json::from_str(JSON).unwrap();
+        let item = expected["items"][0].as_object_mut().unwrap();
+        let v = item.remove("userId").unwrap();
+        item.insert("owner".to_string(), v);
+        assert_eq!(out, expected);
+
+        assert_eq!(transform(|_, _| None), serde_json::from_str::<serde_json::Value>(JSON).unwrap());
+    }
+
+    #[test]
+    fn test_transform_keys_malformed_json() {
+        let cases = [
+            ("tru", ErrorKind::UnexpectedEos),
+            ("{\"a\":\"abc", ErrorKind::UnexpectedEos),
+            ("{\"a\":[1.]}", ErrorKind::InvalidNumber),
+            ("{\"a\":[1, x]}", ErrorKind::UnexpectedChar),
+            ("{\"a\":1} 2", ErrorKind::TrailingCharacters),
+        ];
+        for (json, kind) in cases {
+            let err = transform_keys(StringReader::new(json.to_string()), Vec::new(), |_, k| Some(k.to_uppercase())).unwrap_err();
+            assert_eq!(err.kind(), &kind, "{json}");
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::io::Write;
+
+use crate::{Error, ErrorKind, NIL};
+use crate::parser_core::{get_current_status, Parser, seek_pointer, set_keep_escapes, TextItem, walk_forward};
+use crate::path_matcher::PathMatcher;
+use crate::tools::write_text_item;
+use crate::writer::JsonWriter;
+
+/// Write each element of the array at **pointer** as one compact line (NDJSON). Everything before the array is skipped
+/// without being built, and elements are re-emitted token by token, so only one token is kept in memory at a time.
+/// The writer is flushed after each line, so consumers can start right away. Reading stops when the array is closed.
+/// The result is the count of written lines
+/// ### Example
+/// ```
+/// use json_walker::json_walker::StringReader;
+/// use json_walker::tools::unwrap_to_ndjson;
+///
+/// let json = r#"{"meta": {"page": 1}, "data": [ {"a": 1}, [ 2 ] ]}"#;
+/// let mut out = Vec::new();
+/// assert_eq!(unwrap_to_ndjson(StringReader::new(json.to_string()), "/data", &mut out).unwrap(), 2);
+/// assert_eq!(String::from_utf8(out).unwrap(), "{\"a\":1}\n[2]\n");
+/// ```
+pub fn unwrap_to_ndjson<W: Write>(reader: Box<dyn Iterator<Item=u8>>, pointer: &str, mut writer: W) -> Result<usize, Error> {
+    let mut parser = Parser::new(reader, 0);
+    set_keep_escapes(&mut parser, true);
+    seek_pointer(&mut parser, &PathMatcher::new(pointer)?)?;
+    if parser.next_byte != b'[' {
+        return Err(Error::wrong_data_type(format!("The value of `{pointer}` is not an array")));
+    }
+    walk_forward(&mut parser);
+
+    let mut count = 0;
+    while parser.next_byte != b']' {
+        if parser.next_byte == b',' {
+            walk_forward(&mut parser);
+        }
+        if parser.next_byte == NIL {
+            return Err(Error::new(ErrorKind::EOS, format!("The array is not closed.{}", get_current_status(&mut parser))));
+        }
+        let mut w = JsonWriter::new(&mut writer);
+        let mut depth = 0;
+        loop {
+            let item = walk_forward(&mut parser);
+            match item {
+                TextItem::None(b'{') | TextItem::None(b'[') => depth += 1,
+                TextItem::None(b'}') | TextItem::None(b']') => depth -= 1,
+                _ => {}
+            }
+            write_text_item(item, &mut w)?;
+            if depth == 0 {
+                break;
+            }
+        }
+        w.finish()?.write_all(b"\n")?;
+        writer.flush()?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod unwrap_ndjson_tests {
+    use crate::ErrorKind;
+    use crate::readers::StringReader;
+    use crate::tools::unwrap_to_ndjson;
+
+    #[test]
+    fn test_wrapped_array() {
+        let meta: Vec<String> = (0..20000).map(|i| format!(r#"{{"k{i}": [{i}, "v"]}}"#)).collect();
+        let data = r#"[ {"id": 1, "name": "a \"b\""} , [1, [ ]], "s" , {"nested": {"x": null}} ]"#;
+        let json = format!(r#"{{"meta": [{}], "envelope": {{"data": {data}}}, "tail": 1}}"#, meta.join(","));
+
+        let mut out = Vec::new();
+        assert_eq!(unwrap_to_ndjson(StringReader::new(json), "/envelope/data", &mut out).unwrap(), 4);
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines, vec![r#"{"id":1,"name":"a \"b\""}"#, "[1,[]]", r#""s""#, r#"{"nested":{"x":null}}"#]);
+
+        let expected: serde_json::Value = serde_json::from_str(data).unwrap();
+        for (i, line) in lines.iter().enumerate() {
+            assert_eq!(serde_json::from_str::<serde_json::Value>(line).unwrap(), expected[i]);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let json = r#"{"meta": {}, "data": {"a": []}}"#;
+        let run = |pointer: &str| unwrap_to_ndjson(StringReader::new(json.to_string()), pointer, Vec::new());
+        assert_eq!(run("/data").unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(run("/items").unwrap_err().kind, ErrorKind::KeyNotFound);
+        assert_eq!(run("/data/a"), Ok(0));
+    }
+}
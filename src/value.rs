@@ -0,0 +1,150 @@
+use serde_json::{Map, Number, Value};
+
+use crate::{Error, ErrorKind, NIL};
+use crate::parser_core::{begin_value, DuplicatePolicy, end_of_stream, end_value, get_current_status, Item, on_duplicate_key, Parser, spend_content_bytes, TextItem, ValueType, walk_forward};
+
+/// build serde_json::Value for the value in front of the cursor, directly from walk_forward() items.
+/// Only the value itself is kept in memory and the cursor stands right after it at the end, even if max_content_bytes
+/// is exceeded
+pub fn build_value<R: Iterator<Item=u8>>(parser: &mut Parser<'_, R>) -> Result<Value, Error> {
+    let stack_size = begin_value(parser);
+    let mut used = 0;
+    let value = build(parser, &mut used);
+    if value.as_ref().is_err_and(|e| e.kind == ErrorKind::LimitExceeded) {
+        end_value(parser, stack_size);
+    }
+    value
+}
+
+fn build<R: Iterator<Item=u8>>(parser: &mut Parser<'_, R>, used: &mut usize) -> Result<Value, Error> {
+    // open containers with the latest key of objects
+    let mut containers: Vec<(Value, Option<String>)> = Vec::new();
+    let first_wins = on_duplicate_key(parser) == DuplicatePolicy::FirstWins;
+    loop {
+        if parser.next_byte == NIL {
+            return Err(end_of_stream(parser));
+        }
+        let item = walk_forward(parser);
+        match &item {
+            TextItem::Key(t) | TextItem::Value(t) => spend_content_bytes(parser, used, &t.1)?,
+            TextItem::None(b'{') | TextItem::None(b'[') => spend_content_bytes(parser, used, "")?,
+            _ => {}
+        }
+        let value = match item {
+            TextItem::Key(k) => {
+                if let Some(c) = containers.last_mut() {
+                    c.1 = Some(k.1);
+                }
+                continue;
+            }
+            TextItem::Value(t) => scalar(t)?,
+            TextItem::None(b'{') => {
+                containers.push((Value::Object(Map::new()), None));
+                continue;
+            }
+            TextItem::None(b'[') => {
+                containers.push((Value::Array(Vec::new()), None));
+                continue;
+            }
+            TextItem::None(b'}') | TextItem::None(b']') => match containers.pop() {
+                Some(c) => c.0,
+                None => return Err(Error::wrong_data_type(format!("Expecting a value.{}", get_current_status(parser)))),
+            },
+            TextItem::None(_) => continue,
+        };
+        match containers.last_mut() {
+            None => return Ok(value),
+            Some((Value::Object(m), key)) => {
+                let key = key.take().unwrap_or_default();
+                if first_wins {
+                    m.entry(key).or_insert(value);
+                } else {
+                    m.insert(key, value);
+                }
+            }
+            Some((Value::Array(a), _)) => a.push(value),
+            Some(_) => {}
+        }
+    }
+}
+
+/// numbers get the narrowest type: u64 or i64 by their sign, then f64. With "arbitrary_precision" feature they keep their
+/// text instead
+fn scalar(item: Item) -> Result<Value, Error> {
+    let (value_type, txt) = item;
+    // the text is kept as it is, unless it is not a json number, such as "+1"
+    #[cfg(feature = "arbitrary_precision")]
+    if matches!(value_type, ValueType::UInt | ValueType::Int | ValueType::Float) {
+        if let Ok(n) = txt.parse::<Number>() {
+            return Ok(Value::Number(n));
+        }
+    }
+    Ok(match value_type {
+        ValueType::Null => Value::Null,
+        ValueType::Bool => Value::Bool(txt == "true"),
+        ValueType::UInt => match txt.parse::<u64>() {
+            Ok(u) => Value::from(u),
+            Err(_) => float(&txt)?,
+        },
+        ValueType::Int => match txt.parse::<i64>() {
+            Ok(i) => Value::from(i),
+            Err(_) => float(&txt)?,
+        },
+        ValueType::Float => float(&txt)?,
+        _ => Value::String(txt),
+    })
+}
+
+fn float(txt: &str) -> Result<Value, Error> {
+    let f: f64 = txt.parse().map_err(|_| Error::parse_float(format!("`{txt}` is not a number")))?;
+    Number::from_f64(f)
+        .map(Value::Number)
+        .ok_or_else(|| Error::parse_float(format!("`{txt}` is not a finite number")))
+}
+
+#[cfg(test)]
+mod value_tests {
+    use crate::json_walker::{JsonWalker, ValueType};
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::readers::StringReader;
+    use crate::tools::extract_to;
+
+    #[test]
+    fn test_to_value_of_key7() {
+        let mut source = Vec::new();
+        extract_to(StringReader::new(CORRECT_JSON.to_string()), "/key7", &mut source).unwrap();
+        let expected: serde_json::Value = serde_json::from_slice(&source).unwrap();
+
+        let mut walker = JsonWalker::new(StringReader::new(CORRECT_JSON.to_string()), 0);
+        walker.next_key_by_name("key7").unwrap();
+        assert_eq!(walker.to_value().unwrap(), expected);
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "key8".to_string())));
+    }
+
+    #[test]
+    fn test_to_value_max_content_bytes() {
+        let items: Vec<String> = (0..200_000).map(|i| format!(r#"{{"id": {i}, "text": "{}"}}"#, "x".repeat(30))).collect();
+        let json = format!(r#"{{"items": [{}], "last": {{"a": 1}}}}"#, items.join(","));
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        walker.set_max_content_bytes(Some(1 << 20));
+        walker.next_key_by_name("items").unwrap();
+        assert_eq!(walker.to_value().unwrap_err().kind, crate::ErrorKind::LimitExceeded);
+        assert_eq!(walker.next_key(), Ok((ValueType::Str, "last".to_string())));
+        assert_eq!(walker.to_value().unwrap(), serde_json::json!({"a": 1}));
+    }
+
+    #[test]
+    fn test_to_value_numbers() {
+        let json = r#"{"a": [-1, 18446744073709551615, 1.5, 184467440737095516150, "x", true, null, {}]}"#;
+        let mut walker = JsonWalker::new(StringReader::new(json.to_string()), 0);
+        let v = walker.to_value().unwrap();
+        let a = &v["a"];
+        assert_eq!(a[0].as_i64(), Some(-1));
+        assert_eq!(a[1].as_u64(), Some(u64::MAX));
+        assert_eq!(a[2].as_f64(), Some(1.5));
+        // it is kept as an integer with its text by arbitrary_precision
+        assert_eq!(a[3].is_f64(), !cfg!(feature = "arbitrary_precision"));
+        assert_eq!(v, serde_json::from_str::<serde_json::Value>(json).unwrap());
+        assert!(walker.to_value().is_err());
+    }
+}
@@ -0,0 +1,377 @@
+use std::io::Write;
+
+use crate::Error;
+use crate::escape::escape_into;
+
+enum Scope {
+    Object { has_items: bool, after_key: bool },
+    Array { has_items: bool },
+}
+
+/// Produce json as a stream. The writer keeps only the nesting of open containers, so the output can be as big as you wish.
+/// Structural validity is checked on each call, for example a value without key inside an object or a mismatched end() returns
+/// InvalidStructure error.
+/// ### Example
+/// ```
+/// use json_walker::writer::JsonWriter;
+///
+/// let mut w = JsonWriter::new(Vec::new());
+/// w.begin_object().unwrap();
+/// w.key("key1").unwrap();
+/// w.value_i64(-1).unwrap();
+/// w.key("key2").unwrap();
+/// w.begin_array().unwrap();
+/// w.value_str("a \"b\"").unwrap();
+/// w.value_null().unwrap();
+/// w.end().unwrap();
+/// w.end().unwrap();
+/// let out = w.finish().unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), r#"{"key1":-1,"key2":["a \"b\"",null]}"#);
+/// ```
+pub struct JsonWriter<W: Write> {
+    writer: W,
+    stack: Vec<Scope>,
+    indent: Option<String>,
+    line_break: String,
+    root_written: bool,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> JsonWriter<W> {
+    /// compact output without any white-space
+    pub fn new(writer: W) -> Self {
+        JsonWriter { writer, stack: Vec::with_capacity(30), indent: None, line_break: "\n".to_string(), root_written: false, buf: Vec::with_capacity(64) }
+    }
+
+    /// pretty output. Each element of a container gets its own line and **indent** is repeated per nesting level
+    pub fn new_pretty(writer: W, indent: &str) -> Self {
+        let mut w = JsonWriter::new(writer);
+        w.indent = Some(indent.to_string());
+        w
+    }
+
+    /// line separator of pretty output, "\n" by default. It has no effect on compact output
+    pub fn with_new_line(mut self, new_line: &str) -> Self {
+        self.line_break = new_line.to_string();
+        self
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), Error> {
+        self.before_value()?;
+        self.stack.push(Scope::Object { has_items: false, after_key: false });
+        self.write(b"{")
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), Error> {
+        self.before_value()?;
+        self.stack.push(Scope::Array { has_items: false });
+        self.write(b"[")
+    }
+
+    /// close the latest open object or array
+    pub fn end(&mut self) -> Result<(), Error> {
+        let has_items = match self.stack.pop() {
+            None => return Err(structure_error("There is no open object or array to end")),
+            Some(Scope::Object { after_key: true, .. }) => return Err(structure_error("The latest key has no value")),
+            Some(Scope::Object { has_items, .. }) => {
+                self.close(has_items)?;
+                return self.write(b"}");
+            }
+            Some(Scope::Array { has_items }) => has_items,
+        };
+        self.close(has_items)?;
+        self.write(b"]")
+    }
+
+    pub fn key(&mut self, key: &str) -> Result<(), Error> {
+        self.buf.clear();
+        escape_into(key, &mut self.buf);
+        self.write_key()
+    }
+
+    /// write a key which is already escaped, without the surrounding quotation marks
+    pub fn raw_key(&mut self, escaped_key: &str) -> Result<(), Error> {
+        self.buf.clear();
+        self.buf.push(b'"');
+        self.buf.extend_from_slice(escaped_key.as_bytes());
+        self.buf.push(b'"');
+        self.write_key()
+    }
+
+    /// write the key in buf
+    fn write_key(&mut self) -> Result<(), Error> {
+        match self.stack.last_mut() {
+            Some(Scope::Object { has_items, after_key }) if !*after_key => {
+                let had_items = *has_items;
+                *has_items = true;
+                *after_key = true;
+                if had_items {
+                    self.write(b",")?;
+                }
+                self.new_line()?;
+                self.writer.write_all(&self.buf)?;
+                if self.indent.is_some() { self.write(b": ") } else { self.write(b":") }
+            }
+            _ => Err(structure_error("A key is only allowed inside an object, before its value")),
+        }
+    }
+
+    pub fn value_str(&mut self, value: &str) -> Result<(), Error> {
+        self.before_value()?;
+        self.buf.clear();
+        escape_into(value, &mut self.buf);
+        self.writer.write_all(&self.buf)?;
+        Ok(())
+    }
+
+    pub fn value_i64(&mut self, value: i64) -> Result<(), Error> {
+        self.before_value()?;
+        write!(self.writer, "{value}")?;
+        Ok(())
+    }
+
+    pub fn value_u64(&mut self, value: u64) -> Result<(), Error> {
+        self.before_value()?;
+        write!(self.writer, "{value}")?;
+        Ok(())
+    }
+
+    /// NaN and infinity are not allowed in json, so they return WrongDataType error
+    pub fn value_f32(&mut self, value: f32) -> Result<(), Error> {
+        if !value.is_finite() {
+            return Err(Error::wrong_data_type(format!("{value} is not a valid json number")));
+        }
+        self.before_value()?;
+        write!(self.writer, "{value:?}")?;
+        Ok(())
+    }
+
+    /// NaN and infinity are not allowed in json, so they return WrongDataType error
+    pub fn value_f64(&mut self, value: f64) -> Result<(), Error> {
+        if !value.is_finite() {
+            return Err(Error::wrong_data_type(format!("{value} is not a valid json number")));
+        }
+        self.before_value()?;
+        // debug format keeps the fraction part of round numbers such as 1.0
+        write!(self.writer, "{value:?}")?;
+        Ok(())
+    }
+
+    pub fn value_bool(&mut self, value: bool) -> Result<(), Error> {
+        self.before_value()?;
+        self.write(if value { b"true" } else { b"false" })
+    }
+
+    pub fn value_null(&mut self) -> Result<(), Error> {
+        self.before_value()?;
+        self.write(b"null")
+    }
+
+    /// write a value which is already in json form, such as a number with its original text.
+    /// The text is written as is, so it is up to you to keep it valid
+    pub fn raw(&mut self, json: &str) -> Result<(), Error> {
+        self.before_value()?;
+        self.write(json.as_bytes())
+    }
+
+    /// prepare the position of a value (separator and indentation) and return the inner writer, so a value which is
+    /// already in json form can be written piece by piece. Exactly one value must be written before the next call
+    pub fn raw_writer(&mut self) -> Result<&mut W, Error> {
+        self.before_value()?;
+        Ok(&mut self.writer)
+    }
+
+    /// write a string value which is already escaped, without the surrounding quotation marks
+    pub fn raw_str(&mut self, escaped: &str) -> Result<(), Error> {
+        self.before_value()?;
+        self.write(b"\"")?;
+        self.write(escaped.as_bytes())?;
+        self.write(b"\"")
+    }
+
+    /// nesting level of the cursor. Zero means no container is open
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// flush the output and return the inner writer. All containers must be closed
+    pub fn finish(mut self) -> Result<W, Error> {
+        if !self.stack.is_empty() {
+            return Err(structure_error("Some objects or arrays are not closed"));
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn before_value(&mut self) -> Result<(), Error> {
+        match self.stack.last_mut() {
+            None => {
+                if self.root_written {
+                    return Err(structure_error("Only one root value is allowed"));
+                }
+                self.root_written = true;
+                Ok(())
+            }
+            Some(Scope::Object { after_key, .. }) => {
+                if !*after_key {
+                    return Err(structure_error("A value inside an object needs a key"));
+                }
+                *after_key = false;
+                Ok(())
+            }
+            Some(Scope::Array { has_items }) => {
+                let had_items = *has_items;
+                *has_items = true;
+                if had_items {
+                    self.write(b",")?;
+                }
+                self.new_line()
+            }
+        }
+    }
+
+    /// before the closing byte of a container
+    fn close(&mut self, has_items: bool) -> Result<(), Error> {
+        if has_items { self.new_line() } else { Ok(()) }
+    }
+
+    fn new_line(&mut self) -> Result<(), Error> {
+        if let Some(indent) = &self.indent {
+            self.writer.write_all(self.line_break.as_bytes())?;
+            for _ in 0..self.stack.len() {
+                self.writer.write_all(indent.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.writer.write_all(bytes)?;
+        Ok(())
+    }
+}
+
+fn structure_error(msg: &str) -> Error {
+    Error::invalid_structure(msg.to_string())
+}
+
+#[cfg(test)]
+mod writer_tests {
+    use crate::ErrorKind;
+    use crate::json_walker::JsonWalker;
+    use crate::json_walker::walker_tests::CORRECT_JSON;
+    use crate::readers::StringReader;
+    use crate::writer::JsonWriter;
+
+    fn write_correct_json(w: &mut JsonWriter<Vec<u8>>) {
+        let scalars = |w: &mut JsonWriter<Vec<u8>>, keys: Option<[&str; 6]>, i: i64, f: f64, s: &str| {
+            let key = |w: &mut JsonWriter<Vec<u8>>, n: usize| {
+                if let Some(k) = keys { w.key(k[n]).unwrap(); }
+            };
+            key(w, 0);
+            w.value_null().unwrap();
+            key(w, 1);
+            w.value_bool(true).unwrap();
+            key(w, 2);
+            w.value_bool(false).unwrap();
+            key(w, 3);
+            w.value_i64(i).unwrap();
+            key(w, 4);
+            w.value_f64(f).unwrap();
+            key(w, 5);
+            w.value_str(s).unwrap();
+        };
+        w.begin_object().unwrap();
+        scalars(w, Some(["key1", "key2", "key3", "key4", "key5", "key6"]), 111, 111.111, "str1 \":{}[],");
+        w.key("key7").unwrap();
+        w.begin_object().unwrap();
+        scalars(w, Some(["key71", "key72", "key73", "key74", "key75", "key76"]), 222, 222.222, "str2 \":{}[],");
+        w.key("key78").unwrap();
+        w.begin_array().unwrap();
+        scalars(w, None, 333, 333.333, "str3 \":{}[],");
+        w.begin_object().unwrap();
+        w.end().unwrap();
+        w.begin_array().unwrap();
+        w.end().unwrap();
+        w.end().unwrap();
+        w.key("key79").unwrap();
+        w.begin_object().unwrap();
+        w.end().unwrap();
+        w.key("key710").unwrap();
+        w.begin_array().unwrap();
+        w.end().unwrap();
+        w.end().unwrap();
+        w.key("key8").unwrap();
+        w.begin_array().unwrap();
+        scalars(w, None, 444, 444.444, "str4 \":{}[],");
+        w.begin_object().unwrap();
+        scalars(w, Some(["key81", "key82", "key83", "key84", "key85", "key86"]), 555, 555.555, "str5 \":{}[],");
+        w.key("key89").unwrap();
+        w.begin_object().unwrap();
+        w.end().unwrap();
+        w.key("key810").unwrap();
+        w.raw("[]").unwrap();
+        w.end().unwrap();
+        w.begin_object().unwrap();
+        w.end().unwrap();
+        w.begin_array().unwrap();
+        w.end().unwrap();
+        w.end().unwrap();
+        w.key("key9").unwrap();
+        w.begin_object().unwrap();
+        w.end().unwrap();
+        w.key("key10").unwrap();
+        w.begin_array().unwrap();
+        w.end().unwrap();
+        w.end().unwrap();
+    }
+
+    fn items(json: String) -> Vec<(crate::json_walker::ValueType, String)> {
+        let mut walker = JsonWalker::new(StringReader::new(json), 0);
+        let mut v = vec![];
+        while let Ok(t) = walker.next_item() {
+            v.push(t);
+        }
+        v
+    }
+
+    #[test]
+    fn test_write_and_parse_compact() {
+        let mut w = JsonWriter::new(Vec::new());
+        write_correct_json(&mut w);
+        let out = String::from_utf8(w.finish().unwrap()).unwrap();
+        assert!(out.starts_with(r#"{"key1":null,"key2":true,"key3":false,"key4":111,"key5":111.111,"key6":"str1 \":{}[],","key7":{"#));
+        assert_eq!(items(out), items(CORRECT_JSON.to_string()));
+    }
+
+    #[test]
+    fn test_write_and_parse_pretty() {
+        let mut w = JsonWriter::new_pretty(Vec::new(), "  ");
+        write_correct_json(&mut w);
+        let out = String::from_utf8(w.finish().unwrap()).unwrap();
+        assert!(out.starts_with("{\n  \"key1\": null,\n  \"key2\": true,"));
+        assert_eq!(items(out), items(CORRECT_JSON.to_string()));
+    }
+
+    #[test]
+    fn test_structure_errors() {
+        let mut w = JsonWriter::new(Vec::new());
+        w.begin_object().unwrap();
+        assert_eq!(w.value_i64(1).unwrap_err().kind, ErrorKind::InvalidStructure);
+        w.key("a").unwrap();
+        assert_eq!(w.key("b").unwrap_err().kind, ErrorKind::InvalidStructure);
+        assert_eq!(w.end().unwrap_err().kind, ErrorKind::InvalidStructure);
+
+        let mut w = JsonWriter::new(Vec::new());
+        w.begin_array().unwrap();
+        assert_eq!(w.key("a").unwrap_err().kind, ErrorKind::InvalidStructure);
+        w.end().unwrap();
+        assert_eq!(w.end().unwrap_err().kind, ErrorKind::InvalidStructure);
+        assert_eq!(w.value_null().unwrap_err().kind, ErrorKind::InvalidStructure);
+
+        let mut w = JsonWriter::new(Vec::new());
+        w.begin_array().unwrap();
+        assert_eq!(w.value_f64(f64::NAN).unwrap_err().kind, ErrorKind::WrongDataType);
+        assert_eq!(w.finish().unwrap_err().kind, ErrorKind::InvalidStructure);
+    }
+}